@@ -0,0 +1,162 @@
+//! Criterion benches of the channel variants, for every payload size in `sizes!`:
+//!
+//! - `write`: a write nobody reads, overwriting the previous value
+//! - `read_hit`: a write followed by the read returning it, compare with `write` for the read alone
+//! - `read_miss`: a read finding nothing new
+//! - `read_contended`: a read while another thread writes as fast as it can, hit or miss
+//!
+//! The `padding` group isolates the cache line padding of `atomic_spsc` and `blocking_spsc`: the
+//! reader bumps its own counter while another thread bumps the writer's, either on the same cache
+//! line (`unpadded`) or on separate ones (`padded`). The gap only shows with both threads running
+//! on their own core, so the group is skipped on a machine with a single one. Numbers quoted for it
+//! should name the CPU and its core count.
+//!
+//! Criterion compares every run with the previous one and reports whether the change is
+//! significant. To compare a branch with `master`, save a baseline first:
+//!
+//! ```text
+//! git checkout master && cargo bench -- --save-baseline master
+//! git checkout my-branch && cargo bench -- --baseline master
+//! ```
+//!
+//! Arguments after `--` filter the benches, e.g. `cargo bench -- atomic_spsc/read_miss`. The HTML
+//! reports end up in `target/criterion/report/index.html`. For tail latencies, see `latency.rs`.
+
+use std::hint::black_box;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, BenchmarkGroup, BenchmarkId, Criterion};
+use criterion::{measurement::WallTime, Throughput};
+
+use benchmark_ops::Payload;
+
+#[cfg(feature = "epoch")]
+use rustedrazors::epoch_spsc;
+#[cfg(any(target_os = "linux", windows))]
+use rustedrazors::futex_spsc;
+use rustedrazors::{
+    arc_spsc, atomic_spsc, blocking_spsc, left_right, mutex_spsc, seqlock_spsc, ticket_spsc,
+};
+use rustedrazors::{Channel, Reader, Writer};
+
+/// Runs every bench on fresh channels of `C` carrying `Payload<N>`.
+fn bench_payload<C, const N: usize>(group: &mut BenchmarkGroup<'_, WallTime>)
+where
+    C: Channel<Payload<N>>,
+    C::Reader: Send,
+    C::Writer: Send,
+{
+    let value = Payload::<N>::default();
+    group.throughput(Throughput::Bytes(N as u64));
+
+    let (_r, mut w) = C::create();
+    group.bench_function(BenchmarkId::new("write", N), |b| {
+        b.iter(|| w.write(black_box(value)))
+    });
+
+    let (mut r, mut w) = C::create();
+    group.bench_function(BenchmarkId::new("read_hit", N), |b| {
+        b.iter(|| {
+            w.write(black_box(value));
+            black_box(r.read().is_ok())
+        })
+    });
+
+    let (mut r, mut w) = C::create();
+    w.write(value);
+    while r.read().is_ok() {}
+    group.bench_function(BenchmarkId::new("read_miss", N), |b| {
+        b.iter(|| black_box(r.read().is_ok()))
+    });
+
+    let (mut r, mut w) = C::create();
+    let stop = &AtomicBool::new(false);
+    thread::scope(|s| {
+        s.spawn(move || {
+            while !stop.load(Ordering::Relaxed) {
+                w.write(black_box(value));
+            }
+        });
+        group.bench_function(BenchmarkId::new("read_contended", N), |b| {
+            b.iter(|| black_box(r.read().is_ok()))
+        });
+        stop.store(true, Ordering::Relaxed);
+    });
+}
+
+/// A counter aligned to its own cache line, like the padded fields of the pooled channels.
+#[repr(align(64))]
+struct Padded(AtomicU64);
+
+/// Bumps `reader` while another thread bumps `writer` as fast as it can, see `padding` in the
+/// module docs.
+fn bench_sharing(
+    group: &mut BenchmarkGroup<'_, WallTime>,
+    name: &str,
+    writer: &AtomicU64,
+    reader: &AtomicU64,
+) {
+    let stop = &AtomicBool::new(false);
+    thread::scope(|s| {
+        s.spawn(move || {
+            while !stop.load(Ordering::Relaxed) {
+                writer.fetch_add(1, Ordering::Release);
+            }
+        });
+        group.bench_function(name, |b| {
+            b.iter(|| black_box(reader.fetch_add(1, Ordering::AcqRel)))
+        });
+        stop.store(true, Ordering::Relaxed);
+    });
+}
+
+fn padding(c: &mut Criterion) {
+    if thread::available_parallelism().map_or(1, usize::from) < 2 {
+        eprintln!("skipping padding: both threads need a core of their own");
+        return;
+    }
+    let mut group = c.benchmark_group("padding");
+    let unpadded = [AtomicU64::new(0), AtomicU64::new(0)];
+    bench_sharing(&mut group, "unpadded", &unpadded[0], &unpadded[1]);
+    let padded = [Padded(AtomicU64::new(0)), Padded(AtomicU64::new(0))];
+    bench_sharing(&mut group, "padded", &padded[0].0, &padded[1].0);
+    group.finish();
+}
+
+/// Benches the channels of `$channel` in a group named `$name`, once per payload size.
+macro_rules! sizes {
+    ($c:expr, $name:literal, $channel:ty) => {{
+        let mut group = $c.benchmark_group($name);
+        bench_payload::<$channel, 8>(&mut group);
+        bench_payload::<$channel, 64>(&mut group);
+        bench_payload::<$channel, 1024>(&mut group);
+        bench_payload::<$channel, 4096>(&mut group);
+        group.finish();
+    }};
+}
+
+fn channels(c: &mut Criterion) {
+    sizes!(c, "arc_spsc", arc_spsc::Channel);
+    sizes!(c, "atomic_spsc", atomic_spsc::Channel);
+    sizes!(c, "blocking_spsc", blocking_spsc::Channel);
+    #[cfg(any(target_os = "linux", windows))]
+    sizes!(c, "futex_spsc", futex_spsc::Channel);
+    #[cfg(feature = "epoch")]
+    sizes!(c, "epoch_spsc", epoch_spsc::Channel);
+    sizes!(c, "left_right", left_right::Channel);
+    sizes!(c, "mutex_spsc", mutex_spsc::Channel);
+    sizes!(c, "seqlock_spsc", seqlock_spsc::Channel);
+    sizes!(c, "ticket_spsc", ticket_spsc::Channel);
+}
+
+criterion_group! {
+    name = benches;
+    // 9 variants, 4 sizes and 4 cases add up: keep a full run within a few minutes
+    config = Criterion::default()
+        .warm_up_time(Duration::from_millis(500))
+        .measurement_time(Duration::from_secs(1));
+    targets = channels, padding
+}
+criterion_main!(benches);
@@ -0,0 +1,165 @@
+//! Latency percentiles of every operation of the channel variants, measured one by one while the
+//! other side runs flat out on another thread.
+//!
+//! Criterion, in `channels.rs`, estimates the typical cost of an operation; the tail is what a
+//! real-time reader or writer has to budget for. Each operation is timed and recorded in an HDR
+//! histogram, and the table printed reports p50, p99, p99.9 and the maximum, in nanoseconds, of:
+//!
+//! - `read_hit`: reads returning a value the writer published meanwhile
+//! - `read_miss`: reads finding nothing new
+//! - `write`: writes, while the reader polls
+//!
+//! ```text
+//! cargo bench --bench latency
+//! cargo bench --bench latency -- atomic_spsc mutex_spsc
+//! ```
+//!
+//! Arguments after `--` keep only the variants whose name contains one of them.
+
+use std::hint::black_box;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Instant;
+
+use hdrhistogram::Histogram;
+
+use benchmark_ops::Payload;
+#[cfg(feature = "epoch")]
+use rustedrazors::epoch_spsc;
+#[cfg(any(target_os = "linux", windows))]
+use rustedrazors::futex_spsc;
+use rustedrazors::{
+    arc_spsc, atomic_spsc, blocking_spsc, left_right, mutex_spsc, seqlock_spsc, ticket_spsc,
+};
+use rustedrazors::{Channel, Reader, Writer};
+
+const PAYLOAD_SIZE: usize = 1024;
+const ITERS: usize = 1000000;
+
+/// Histogram of latencies in nanoseconds, from 1 ns to 1 s with 3 significant digits.
+fn histogram() -> Histogram<u64> {
+    Histogram::new_with_bounds(1, 1_000_000_000, 3).unwrap()
+}
+
+/// Times `ITERS` reads while another thread keeps writing, split by whether they returned a value.
+fn read_ops<R, W>(mut r: R, mut w: W) -> (Histogram<u64>, Histogram<u64>)
+where
+    R: Reader<Item = Payload<PAYLOAD_SIZE>>,
+    W: Writer<Item = Payload<PAYLOAD_SIZE>> + Send,
+{
+    let stop = &AtomicBool::new(false);
+    thread::scope(|s| {
+        s.spawn(move || {
+            let value = Payload::default();
+            while !stop.load(Ordering::Relaxed) {
+                w.write(black_box(value));
+            }
+        });
+
+        let mut hit = histogram();
+        let mut miss = histogram();
+        for _ in 0..ITERS {
+            let start = Instant::now();
+            let ok = black_box(r.read()).is_ok();
+            let ns = start.elapsed().as_nanos() as u64;
+            if ok {
+                hit.saturating_record(ns);
+            } else {
+                miss.saturating_record(ns);
+            }
+        }
+        stop.store(true, Ordering::Relaxed);
+        (hit, miss)
+    })
+}
+
+/// Times `ITERS` writes while another thread keeps reading.
+fn write_ops<R, W>(mut r: R, mut w: W) -> Histogram<u64>
+where
+    R: Reader<Item = Payload<PAYLOAD_SIZE>> + Send,
+    W: Writer<Item = Payload<PAYLOAD_SIZE>>,
+{
+    let stop = &AtomicBool::new(false);
+    thread::scope(|s| {
+        s.spawn(move || {
+            while !stop.load(Ordering::Relaxed) {
+                let _ = black_box(r.read());
+            }
+        });
+
+        let value = Payload::default();
+        let mut writes = histogram();
+        for _ in 0..ITERS {
+            let start = Instant::now();
+            w.write(black_box(value));
+            writes.saturating_record(start.elapsed().as_nanos() as u64);
+        }
+        stop.store(true, Ordering::Relaxed);
+        writes
+    })
+}
+
+/// Prints a row of percentiles of `hist`, or nothing if no operation was recorded in it.
+fn summary(name: &str, op: &str, hist: &Histogram<u64>) {
+    if hist.is_empty() {
+        return;
+    }
+    println!(
+        "{:<16} {:<10} {:>10} {:>10} {:>10} {:>10} {:>10}",
+        name,
+        op,
+        hist.len(),
+        hist.value_at_quantile(0.5),
+        hist.value_at_quantile(0.99),
+        hist.value_at_quantile(0.999),
+        hist.max(),
+    );
+}
+
+/// Runs both benchmarks on fresh channels of `C`, unless `filters` leave out `name`.
+fn bench_function<C>(name: &str, filters: &[String])
+where
+    C: Channel<Payload<PAYLOAD_SIZE>>,
+    C::Reader: Send,
+    C::Writer: Send,
+{
+    if !filters.is_empty() && !filters.iter().any(|filter| name.contains(filter.as_str())) {
+        return;
+    }
+
+    let (r, w) = C::create();
+    let (hit, miss) = read_ops(r, w);
+    let (r, w) = C::create();
+    let writes = write_ops(r, w);
+
+    summary(name, "read_hit", &hit);
+    summary(name, "read_miss", &miss);
+    summary(name, "write", &writes);
+}
+
+fn main() {
+    // `cargo bench` passes `--bench` to every target, on top of the arguments after `--`
+    let filters: Vec<String> = std::env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with("--"))
+        .collect();
+
+    if thread::available_parallelism().map_or(true, |n| n.get() < 2) {
+        eprintln!("warning: a single CPU runs one side at a time, the percentiles are meaningless");
+    }
+    println!(
+        "{:<16} {:<10} {:>10} {:>10} {:>10} {:>10} {:>10}",
+        "variant", "op", "count", "p50 ns", "p99 ns", "p999 ns", "max ns"
+    );
+    bench_function::<arc_spsc::Channel>("arc_spsc", &filters);
+    bench_function::<atomic_spsc::Channel>("atomic_spsc", &filters);
+    bench_function::<blocking_spsc::Channel>("blocking_spsc", &filters);
+    #[cfg(any(target_os = "linux", windows))]
+    bench_function::<futex_spsc::Channel>("futex_spsc", &filters);
+    #[cfg(feature = "epoch")]
+    bench_function::<epoch_spsc::Channel>("epoch_spsc", &filters);
+    bench_function::<left_right::Channel>("left_right", &filters);
+    bench_function::<mutex_spsc::Channel>("mutex_spsc", &filters);
+    bench_function::<seqlock_spsc::Channel>("seqlock_spsc", &filters);
+    bench_function::<ticket_spsc::Channel>("ticket_spsc", &filters);
+}
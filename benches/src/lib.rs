@@ -0,0 +1,18 @@
+//! Payload shared by the benches under `benches/`.
+
+use rustedrazors::seqlock_spsc;
+
+/// `N` bytes moved through the channels, plain enough for every variant.
+#[derive(Clone, Copy)]
+pub struct Payload<const N: usize> {
+    _p: [u8; N],
+}
+
+// Safety: a byte array has no padding
+unsafe impl<const N: usize> seqlock_spsc::Plain for Payload<N> {}
+
+impl<const N: usize> Default for Payload<N> {
+    fn default() -> Self {
+        Payload { _p: [0; N] }
+    }
+}
@@ -0,0 +1,122 @@
+#![no_main]
+//! Runs a writer and a reader on two threads, each following a script decoded from the input, and
+//! checks that values are read in order, without tearing, and that the last one is never lost.
+//!
+//! The first byte picks the variant and the second one where the writer's script ends: the rest
+//! of the input is the reader's script.
+
+use libfuzzer_sys::fuzz_target;
+
+use rustedrazors::{
+    arc_spsc, atomic_spsc, blocking_spsc, box_spsc, double_buffer, left_right, mpmc_latest,
+    mpsc_latest, mutex_spsc, seqlock_spsc, ticket_spsc, triple_buffer, watch,
+};
+use rustedrazors::{Reader, TryReadError, Writer};
+
+use std::thread;
+
+/// Sequence number repeated over the whole value, so that a torn read shows.
+type Payload = [u64; 4];
+
+/// Script bytes from this one on inject a pause instead of an operation.
+const PAUSE: u8 = 0xc0;
+
+/// Yields the thread on even bytes, spins for as many rounds as the byte otherwise.
+fn pause(op: u8) {
+    if op.is_multiple_of(2) {
+        thread::yield_now();
+    } else {
+        for _ in 0..op {
+            std::hint::spin_loop();
+        }
+    }
+}
+
+fn run<R, W>((mut r, mut w): (R, W), writes: &[u8], reads: &[u8])
+where
+    R: Reader<Item = Payload>,
+    W: Writer<Item = Payload> + Send,
+{
+    let written = writes.iter().filter(|&&op| op < PAUSE).count() as u64;
+    let mut last = 0;
+    let mut check = |value: &Payload| {
+        assert!(value.iter().all(|&v| v == value[0]), "torn value {value:?}");
+        assert!(value[0] > last, "read {} after {last}", value[0]);
+        assert!(value[0] <= written, "{} was never written", value[0]);
+        last = value[0];
+    };
+
+    thread::scope(|s| {
+        // joined explicitly, unlike at the end of the scope this waits for the thread to exit
+        let writer = s.spawn(move || {
+            let mut seq = 0;
+            for &op in writes {
+                match op {
+                    PAUSE.. => pause(op),
+                    _ if op.is_multiple_of(2) => {
+                        seq += 1;
+                        w.write([seq; 4]);
+                    }
+                    _ => {
+                        seq += 1;
+                        w.write_with(|value| *value = [seq; 4]);
+                    }
+                }
+            }
+            // dropping `w` disconnects the reader
+        });
+
+        for &op in reads {
+            match op {
+                PAUSE.. => pause(op),
+                _ if op.is_multiple_of(2) => {
+                    if let Ok(value) = r.read() {
+                        check(&value);
+                    }
+                }
+                _ => match r.read() {
+                    Ok(value) => check(&value),
+                    Err(TryReadError::Empty | TryReadError::Disconnected) => {}
+                    Err(err) => panic!("unexpected {err:?}"),
+                },
+            }
+        }
+
+        // whatever the schedule, the last value must still be readable once the writer is gone
+        loop {
+            match r.read() {
+                Ok(value) => check(&value),
+                Err(TryReadError::Empty) => thread::yield_now(),
+                Err(TryReadError::Disconnected) => break,
+                Err(err) => panic!("unexpected {err:?}"),
+            }
+        }
+        writer.join().unwrap();
+    });
+    assert_eq!(last, written, "the last value was lost");
+}
+
+fuzz_target!(|data: &[u8]| {
+    let [variant, split, script @ ..] = data else {
+        return;
+    };
+    let (writes, reads) = script.split_at((*split as usize).min(script.len()));
+    match variant % 14 {
+        0 => run(arc_spsc::new(), writes, reads),
+        1 => run(atomic_spsc::new(), writes, reads),
+        2 => run(blocking_spsc::new(), writes, reads),
+        3 => run(box_spsc::new(), writes, reads),
+        4 => run(double_buffer::new(), writes, reads),
+        #[cfg(any(target_os = "linux", windows))]
+        5 => run(rustedrazors::futex_spsc::new(), writes, reads),
+        6 => run(left_right::new(), writes, reads),
+        7 => run(mpmc_latest::new(), writes, reads),
+        8 => run(mpsc_latest::new(), writes, reads),
+        9 => run(mutex_spsc::new([0; 4]), writes, reads),
+        10 => run(seqlock_spsc::new(), writes, reads),
+        11 => run(ticket_spsc::new([0; 4]), writes, reads),
+        12 => run(triple_buffer::new(), writes, reads),
+        13 => run(watch::new(), writes, reads),
+        _ => {}
+    }
+});
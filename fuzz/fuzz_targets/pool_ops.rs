@@ -0,0 +1,126 @@
+#![no_main]
+//! Drives the extra operations of `atomic_spsc` from two threads, each following a script decoded
+//! from the input: the reader keeps a guard across operations and swaps values out, the writer
+//! mixes in-place writes and updates.
+//!
+//! Besides ordering and tearing, this checks that a leased slot is never written under the
+//! reader and that versions count the published values. The first byte is where the writer's
+//! script ends, the rest of the input is the reader's script.
+
+use libfuzzer_sys::fuzz_target;
+
+use rustedrazors::atomic_spsc;
+use rustedrazors::{Reader, TryReadError, Writer};
+
+use std::thread;
+
+/// Sequence number repeated over the whole value, so that a torn read shows.
+type Payload = [u64; 4];
+
+/// Script bytes from this one on inject a pause instead of an operation.
+const PAUSE: u8 = 0xc0;
+
+/// Yields the thread on even bytes, spins for as many rounds as the byte otherwise.
+fn pause(op: u8) {
+    if op.is_multiple_of(2) {
+        thread::yield_now();
+    } else {
+        for _ in 0..op {
+            std::hint::spin_loop();
+        }
+    }
+}
+
+fn untorn(value: &Payload) -> u64 {
+    assert!(value.iter().all(|&v| v == value[0]), "torn value {value:?}");
+    value[0]
+}
+
+fuzz_target!(|data: &[u8]| {
+    let [split, script @ ..] = data else {
+        return;
+    };
+    let (writes, reads) = script.split_at((*split as usize).min(script.len()));
+    let (mut r, mut w) = atomic_spsc::new::<Payload>();
+
+    thread::scope(|s| {
+        // joined explicitly, unlike at the end of the scope this waits for the thread to exit
+        let writer = s.spawn(move || {
+            let mut seq = 0;
+            for &op in writes {
+                match op % 4 {
+                    _ if op >= PAUSE => pause(op),
+                    0 => {
+                        seq += 1;
+                        w.write([seq; 4]);
+                    }
+                    1 => {
+                        seq += 1;
+                        w.write_with(|value| *value = [seq; 4]);
+                    }
+                    2 => {
+                        seq += 1;
+                        w.update(|value| {
+                            // the reference may have been swapped out by the reader, but not torn
+                            untorn(value);
+                            *value = [seq; 4];
+                        });
+                    }
+                    _ => {
+                        seq += 1;
+                        let mut guard = w.begin_write();
+                        *guard = [seq; 4];
+                        guard.commit();
+                    }
+                }
+                assert_eq!(w.published(), seq);
+            }
+        });
+
+        let mut last = 0;
+        let mut check = |version: u64, value: &Payload| {
+            let seq = untorn(value);
+            assert_eq!(version, seq, "version does not match the value");
+            assert!(seq > last, "read {seq} after {last}");
+            last = seq;
+        };
+        let mut scratch = [0; 4];
+        let mut ops = reads.iter();
+        while let Some(&op) = ops.next() {
+            match op % 4 {
+                _ if op >= PAUSE => pause(op),
+                0 => {
+                    if let Ok((version, guard)) = r.read_versioned() {
+                        check(version, &guard);
+                        // kept up to the next release, the handle cannot read anything else before
+                        for &op in ops.by_ref() {
+                            if op >= PAUSE {
+                                pause(op);
+                            } else if op % 4 == 1 {
+                                break;
+                            }
+                        }
+                        assert_eq!(
+                            untorn(&guard),
+                            version,
+                            "leased slot written under the reader"
+                        );
+                    }
+                }
+                1 => {}
+                2 => {
+                    if r.read_swap(&mut scratch).is_ok() {
+                        let seq = untorn(&scratch);
+                        check(seq, &scratch);
+                    }
+                }
+                _ => match r.read() {
+                    Ok(guard) => check(guard.version(), &guard),
+                    Err(TryReadError::Empty | TryReadError::Disconnected) => {}
+                    Err(err) => panic!("unexpected {err:?}"),
+                },
+            }
+        }
+        writer.join().unwrap();
+    });
+});
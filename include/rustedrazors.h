@@ -0,0 +1,76 @@
+#ifndef RUSTEDRAZORS_H
+#define RUSTEDRAZORS_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Outcome of [`rr_try_read`], see [`TryReadError`] for the failures.
+ */
+typedef enum rr_status {
+  /**
+   * A new value was copied out.
+   */
+  RR_STATUS_OK = 0,
+  RR_STATUS_EMPTY = 1,
+  RR_STATUS_DISCONNECTED = 2,
+  RR_STATUS_CLOSED = 3,
+  RR_STATUS_POISONED = 4,
+} rr_status;
+
+/**
+ * Opaque channel holding both ends, created by [`rr_channel_new`] and destroyed by [`rr_free`].
+ */
+typedef struct rr_channel rr_channel;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Creates a channel of `value_size`-byte values. Reads fail with [`Status::Empty`] until the
+ * first write.
+ */
+rr_channel *rr_channel_new(size_t value_size);
+
+/**
+ * Publishes the `value_size` bytes at `value`, replacing any value that was not read yet.
+ *
+ * # Safety
+ *
+ * `channel` must come from [`rr_channel_new`] and not be freed, `value` must point to
+ * `value_size` readable bytes, and no other thread may be writing to `channel`.
+ */
+void rr_write(rr_channel *channel, const uint8_t *value);
+
+/**
+ * Copies the last published value to the `value_size` bytes at `out`, if it was not read yet.
+ *
+ * Returns [`Status::Ok`] on success, leaving `out` untouched otherwise.
+ *
+ * # Safety
+ *
+ * `channel` must come from [`rr_channel_new`] and not be freed, `out` must point to `value_size`
+ * writable bytes, and no other thread may be reading from `channel`.
+ */
+rr_status rr_try_read(rr_channel *channel, uint8_t *out);
+
+/**
+ * Destroys a channel. Does nothing if `channel` is null.
+ *
+ * # Safety
+ *
+ * `channel` must come from [`rr_channel_new`], not be freed already, and not be in use by any
+ * other thread.
+ */
+void rr_free(rr_channel *channel);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* RUSTEDRAZORS_H */
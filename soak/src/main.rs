@@ -0,0 +1,212 @@
+//! Soak test: runs a reader/writer pair per variant, all at once, until the given number of hours
+//! has elapsed, checking every read and printing progress along the way.
+//!
+//! ```text
+//! cargo run --release -- [--hours H] [--report SECONDS] [VARIANT...]
+//! ```
+//!
+//! Values are stamped with their sequence number in every word, so that reads can be checked for
+//! tearing and ordering. On top of that, each report checks that every reader made progress, and
+//! the run ends by checking that every reader gets the last written value. The process exits with
+//! a non-zero status on the first failure.
+
+use std::env;
+use std::process;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "epoch")]
+use rustedrazors::epoch_spsc;
+#[cfg(any(target_os = "linux", windows))]
+use rustedrazors::futex_spsc;
+use rustedrazors::{
+    arc_spsc, atomic_spsc, blocking_spsc, box_spsc, double_buffer, isr_spsc, left_right,
+    mpmc_latest, mpsc_latest, mutex_spsc, seqlock_spsc, ticket_spsc, triple_buffer, watch,
+};
+use rustedrazors::{Reader, TryReadError, Writer};
+
+const PAYLOAD_WORDS: usize = 16;
+// writers yield now and then so that readers keep running when there are more threads than cores
+const YIELD_EVERY: u64 = 1024;
+
+/// Sequence number repeated over the whole value, so that a torn read shows.
+type Payload = [u64; PAYLOAD_WORDS];
+
+/// Progress of a pair, shared with the reporting thread.
+#[derive(Default)]
+struct Stats {
+    writes: AtomicU64,
+    reads: AtomicU64,
+}
+
+/// Reports a failure of the pair `name` and exits right away, so that an overnight run stops on the
+/// first one.
+fn fail(name: &str, msg: &str) -> ! {
+    eprintln!("FAILED {name}: {msg}");
+    process::exit(1);
+}
+
+/// Writes values stamped with consecutive sequence numbers until `stop` is set, then drops the
+/// writer, while reading and checking every value on the current thread.
+fn soak<R, W>(name: &str, (mut r, mut w): (R, W), stats: &Stats, stop: &AtomicBool)
+where
+    R: Reader<Item = Payload>,
+    W: Writer<Item = Payload> + Send,
+{
+    thread::scope(|s| {
+        s.spawn(move || {
+            let mut seq = 0;
+            while !stop.load(Ordering::Relaxed) {
+                seq += 1;
+                w.write([seq; PAYLOAD_WORDS]);
+                stats.writes.store(seq, Ordering::Relaxed);
+                if seq % YIELD_EVERY == 0 {
+                    thread::yield_now();
+                }
+            }
+        });
+
+        let mut last = 0;
+        loop {
+            match r.read() {
+                Ok(guard) => {
+                    let value: &Payload = &guard;
+                    let seq = value[0];
+                    if value.iter().any(|&v| v != seq) {
+                        fail(name, &format!("torn value {value:?}"));
+                    }
+                    if seq <= last {
+                        fail(name, &format!("read {seq} after {last}"));
+                    }
+                    last = seq;
+                    stats.reads.fetch_add(1, Ordering::Relaxed);
+                }
+                Err(TryReadError::Empty) => thread::yield_now(),
+                Err(TryReadError::Disconnected) => break,
+                Err(err) => fail(name, &format!("unexpected {err:?}")),
+            }
+        }
+        let written = stats.writes.load(Ordering::Relaxed);
+        if last != written {
+            fail(
+                name,
+                &format!("last read {last}, but {written} were written"),
+            );
+        }
+    });
+}
+
+type Pair = fn(&str, &Stats, &AtomicBool);
+
+macro_rules! pair {
+    ($name:expr, $factory:ident $(, $init:expr)?) => {
+        (
+            $name,
+            (|name, stats, stop| soak(name, $factory::new::<Payload>($($init)?), stats, stop))
+                as Pair,
+        )
+    };
+}
+
+fn pairs() -> Vec<(&'static str, Pair)> {
+    vec![
+        pair!("arc_spsc", arc_spsc),
+        pair!("atomic_spsc", atomic_spsc),
+        pair!("blocking_spsc", blocking_spsc),
+        pair!("box_spsc", box_spsc),
+        pair!("double_buffer", double_buffer),
+        #[cfg(feature = "epoch")]
+        pair!("epoch_spsc", epoch_spsc),
+        #[cfg(any(target_os = "linux", windows))]
+        pair!("futex_spsc", futex_spsc),
+        (
+            "isr_spsc",
+            (|name, stats, stop| {
+                let storage = Box::leak(Box::new(isr_spsc::Storage::<Payload>::new()));
+                soak(name, storage.split().unwrap(), stats, stop)
+            }) as Pair,
+        ),
+        pair!("left_right", left_right),
+        pair!("mpmc_latest", mpmc_latest),
+        pair!("mpsc_latest", mpsc_latest),
+        pair!("mutex_spsc", mutex_spsc, [0; PAYLOAD_WORDS]),
+        pair!("seqlock_spsc", seqlock_spsc),
+        pair!("ticket_spsc", ticket_spsc, [0; PAYLOAD_WORDS]),
+        pair!("triple_buffer", triple_buffer),
+        pair!("watch", watch),
+    ]
+}
+
+fn usage() -> ! {
+    let names: Vec<_> = pairs().into_iter().map(|(name, _)| name).collect();
+    eprintln!("usage: soak [--hours H] [--report SECONDS] [VARIANT...]");
+    eprintln!("variants: {}", names.join(" "));
+    process::exit(2);
+}
+
+fn main() {
+    let mut hours = 8.0;
+    let mut report = 60;
+    let mut selected = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--hours" => {
+                hours = args
+                    .next()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or_else(|| usage())
+            }
+            "--report" => {
+                report = args
+                    .next()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or_else(|| usage())
+            }
+            _ => selected.push(arg),
+        }
+    }
+    let mut pairs = pairs();
+    if !selected.is_empty() {
+        if let Some(name) = selected
+            .iter()
+            .find(|name| !pairs.iter().any(|p| p.0 == *name))
+        {
+            eprintln!("unknown variant {name}");
+            usage();
+        }
+        pairs.retain(|(name, _)| selected.iter().any(|s| s == name));
+    }
+
+    let duration = Duration::from_secs_f64(hours * 3600.0);
+    let report = Duration::from_secs(report);
+    let stats: Vec<Stats> = pairs.iter().map(|_| Stats::default()).collect();
+    let stop = AtomicBool::new(false);
+    println!("soaking {} pairs for {hours} h", pairs.len());
+
+    thread::scope(|s| {
+        for ((name, pair), stats) in pairs.iter().zip(&stats) {
+            let stop = &stop;
+            s.spawn(move || pair(name, stats, stop));
+        }
+
+        let start = Instant::now();
+        let mut reads: Vec<u64> = vec![0; pairs.len()];
+        while start.elapsed() < duration {
+            thread::sleep(report.min(duration.saturating_sub(start.elapsed())));
+            println!("--- {:.2} h", start.elapsed().as_secs_f64() / 3600.0);
+            for (((name, _), stats), last) in pairs.iter().zip(&stats).zip(&mut reads) {
+                let writes = stats.writes.load(Ordering::Relaxed);
+                let now = stats.reads.load(Ordering::Relaxed);
+                println!("{name:<16} writes {writes:>14} reads {now:>14}");
+                if now == *last {
+                    fail(name, "the reader made no progress since the last report");
+                }
+                *last = now;
+            }
+        }
+        stop.store(true, Ordering::Relaxed);
+    });
+    println!("done, no failure");
+}
@@ -0,0 +1,569 @@
+//! Wrappers around the handles of any variant, changing what is read or written through them.
+//!
+//! They are built from the provided methods of the traits, such as [`Reader::map`], or by the
+//! functions of this module combining several readers, and implement the same trait as the handles
+//! they wrap, so they fit wherever the handles did.
+
+use crate::{Reader, TryReadError, WriteError, Writer};
+
+#[cfg(all(feature = "std", not(target_family = "wasm")))]
+use crate::stamped::Stamped;
+use core::marker::PhantomData;
+use core::ops::Deref;
+#[cfg(all(feature = "std", not(target_family = "wasm")))]
+use std::time::{Duration, Instant};
+
+/// Reader returning `f` applied to the values read through the wrapped reader, built by
+/// [`Reader::map`].
+pub struct MappedReader<R, F> {
+    inner: R,
+    f: F,
+}
+
+/// Value returned by the reads of the adapters that build or keep their own copy of the values,
+/// such as [`MappedReader`] and [`Zip`], owning it.
+///
+/// The leases on the values of the wrapped readers end as soon as they are mapped or copied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MappedGuard<U>(U);
+
+impl<U> MappedGuard<U> {
+    /// Returns the result of the mapping.
+    pub fn into_inner(self) -> U {
+        self.0
+    }
+}
+
+impl<U> Deref for MappedGuard<U> {
+    type Target = U;
+
+    fn deref(&self) -> &U {
+        &self.0
+    }
+}
+
+/// Writer taking values of another type, converted by `f` before being written to the wrapped
+/// writer, built by [`Writer::with_map`].
+///
+/// The converted value cannot be handed back in a [`WriteError`], so [`Writer::try_write`] only
+/// refuses values once the reader is gone, and otherwise writes like [`Writer::write`].
+pub struct MappedWriter<W, F, U> {
+    inner: W,
+    f: F,
+    _item: PhantomData<fn(U)>,
+}
+
+/// Writer dropping the values rejected by `predicate` instead of writing them to the wrapped
+/// writer, built by [`Writer::with_filter`].
+pub struct FilteredWriter<W, P> {
+    inner: W,
+    predicate: P,
+}
+
+#[cfg(all(feature = "std", not(target_family = "wasm")))]
+/// Reader returning the newest value of either of two stamped readers, built by [`merge`].
+pub struct Merge<R1, R2> {
+    first: R1,
+    second: R2,
+    // stamp of the last value returned, older values are skipped
+    last: Option<Instant>,
+}
+
+/// Value returned by the reads of a [`Merge`], holding the lease of the reader it came from.
+#[cfg(all(feature = "std", not(target_family = "wasm")))]
+#[derive(Debug)]
+pub enum MergedGuard<G1, G2> {
+    /// Lease on a value of the first reader
+    First(G1),
+    /// Lease on a value of the second reader
+    Second(G2),
+}
+
+#[cfg(all(feature = "std", not(target_family = "wasm")))]
+impl<T, G1, G2> Deref for MergedGuard<G1, G2>
+where
+    G1: Deref<Target = T>,
+    G2: Deref<Target = T>,
+{
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        match self {
+            MergedGuard::First(guard) => guard,
+            MergedGuard::Second(guard) => guard,
+        }
+    }
+}
+
+/// Reader returning the values of two readers as pairs, built by [`zip`].
+pub struct Zip<R1: Reader, R2: Reader> {
+    first: R1,
+    second: R2,
+    // new values read from either side while waiting for one from the other
+    first_pending: Option<R1::Item>,
+    second_pending: Option<R2::Item>,
+}
+
+/// Reader returning at most one value per interval, built by [`Reader::throttle`].
+#[cfg(all(feature = "std", not(target_family = "wasm")))]
+pub struct Throttled<R> {
+    inner: R,
+    interval: Duration,
+    // instant of the last value returned
+    last: Option<Instant>,
+}
+
+/// Reader returning a value only once no newer one was written for a while, built by
+/// [`Reader::debounce`].
+#[cfg(all(feature = "std", not(target_family = "wasm")))]
+pub struct Debounced<R: Reader> {
+    inner: R,
+    quiet: Duration,
+    // newest value read and the instant it was, until it is returned
+    pending: Option<(Instant, R::Item)>,
+}
+
+impl<R, F> MappedReader<R, F> {
+    pub(crate) fn new(inner: R, f: F) -> Self {
+        MappedReader { inner, f }
+    }
+
+    /// Returns the wrapped reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R, F, U> Reader for MappedReader<R, F>
+where
+    R: Reader,
+    F: Fn(&R::Item) -> U,
+{
+    type Item = U;
+    type Guard<'a>
+        = MappedGuard<U>
+    where
+        Self: 'a;
+
+    fn read(&mut self) -> Result<Self::Guard<'_>, TryReadError> {
+        self.inner.read().map(|guard| MappedGuard((self.f)(&guard)))
+    }
+
+    fn read_with<V>(&mut self, f: impl FnOnce(&U) -> V) -> Result<V, TryReadError> {
+        self.inner.read_with(|value| f(&(self.f)(value)))
+    }
+}
+
+impl<W, F, U> MappedWriter<W, F, U> {
+    pub(crate) fn new(inner: W, f: F) -> Self {
+        MappedWriter {
+            inner,
+            f,
+            _item: PhantomData,
+        }
+    }
+
+    /// Returns the wrapped writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W, F, U> Writer for MappedWriter<W, F, U>
+where
+    W: Writer,
+    F: Fn(U) -> W::Item,
+{
+    type Item = U;
+
+    fn write(&mut self, value: U) {
+        self.inner.write((self.f)(value));
+    }
+
+    /// Publishes a value built by `f`, converted before being written.
+    ///
+    /// The wrapped channel holds converted values, so `f` gets a fresh `U::default()` instead of a
+    /// slot of the channel.
+    fn write_with(&mut self, f: impl FnOnce(&mut U))
+    where
+        U: Default,
+    {
+        let mut value = U::default();
+        f(&mut value);
+        self.write(value);
+    }
+
+    fn close(self) {
+        self.inner.close();
+    }
+
+    fn is_reader_alive(&self) -> bool {
+        self.inner.is_reader_alive()
+    }
+}
+
+impl<W, P> FilteredWriter<W, P> {
+    pub(crate) fn new(inner: W, predicate: P) -> Self {
+        FilteredWriter { inner, predicate }
+    }
+
+    /// Returns the wrapped writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W, P> Writer for FilteredWriter<W, P>
+where
+    W: Writer,
+    P: Fn(&W::Item) -> bool,
+{
+    type Item = W::Item;
+
+    fn write(&mut self, value: W::Item) {
+        if (self.predicate)(&value) {
+            self.inner.write(value);
+        }
+    }
+
+    /// Publishes a value built by `f` if `predicate` accepts it.
+    ///
+    /// A value built in a slot of the channel could not be taken back once rejected, so `f` gets a
+    /// fresh `Default::default()` instead.
+    fn write_with(&mut self, f: impl FnOnce(&mut W::Item))
+    where
+        W::Item: Default,
+    {
+        let mut value = W::Item::default();
+        f(&mut value);
+        self.write(value);
+    }
+
+    fn close(self) {
+        self.inner.close();
+    }
+
+    fn is_reader_alive(&self) -> bool {
+        self.inner.is_reader_alive()
+    }
+
+    /// Writes `value` to the wrapped writer if `predicate` accepts it, a rejected value counting as
+    /// written.
+    fn try_write(&mut self, value: W::Item) -> Result<(), WriteError<W::Item>> {
+        if !self.is_reader_alive() {
+            return Err(WriteError::Disconnected(value));
+        }
+        if (self.predicate)(&value) {
+            self.inner.try_write(value)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(all(feature = "std", not(target_family = "wasm")))]
+impl<R1, R2> Merge<R1, R2> {
+    /// Returns the wrapped readers.
+    pub fn into_inner(self) -> (R1, R2) {
+        (self.first, self.second)
+    }
+}
+
+#[cfg(all(feature = "std", not(target_family = "wasm")))]
+impl<T, R1, R2> Reader for Merge<R1, R2>
+where
+    R1: Reader<Item = Stamped<T>>,
+    R2: Reader<Item = Stamped<T>>,
+{
+    type Item = Stamped<T>;
+    type Guard<'a>
+        = MergedGuard<R1::Guard<'a>, R2::Guard<'a>>
+    where
+        Self: 'a;
+
+    fn read(&mut self) -> Result<Self::Guard<'_>, TryReadError> {
+        let newest = match (self.first.read(), self.second.read()) {
+            (Ok(first), Ok(second)) if second.0 > first.0 => MergedGuard::Second(second),
+            (Ok(first), _) => MergedGuard::First(first),
+            (_, Ok(second)) => MergedGuard::Second(second),
+            (Err(first), Err(second)) => return Err(merge_errors(first, second)),
+        };
+        if self.last.is_some_and(|last| newest.0 < last) {
+            // published after the value last returned, but written before it
+            return Err(TryReadError::Empty);
+        }
+        self.last = Some(newest.0);
+        Ok(newest)
+    }
+}
+
+/// Error of a merged read that failed on both sides: the merge is done once both sides are.
+#[cfg(all(feature = "std", not(target_family = "wasm")))]
+fn merge_errors(first: TryReadError, second: TryReadError) -> TryReadError {
+    match (first, second) {
+        (TryReadError::Empty, _) | (_, TryReadError::Empty) => TryReadError::Empty,
+        (err, _) => err,
+    }
+}
+
+impl<R1: Reader, R2: Reader> Zip<R1, R2> {
+    /// Returns the wrapped readers, dropping the values kept for the next pair.
+    pub fn into_inner(self) -> (R1, R2) {
+        (self.first, self.second)
+    }
+}
+
+impl<R1, R2> Reader for Zip<R1, R2>
+where
+    R1: Reader,
+    R2: Reader,
+    R1::Item: Clone,
+    R2::Item: Clone,
+{
+    type Item = (R1::Item, R2::Item);
+    type Guard<'a>
+        = MappedGuard<(R1::Item, R2::Item)>
+    where
+        Self: 'a;
+
+    fn read(&mut self) -> Result<Self::Guard<'_>, TryReadError> {
+        let first = refresh(&mut self.first, &mut self.first_pending);
+        let second = refresh(&mut self.second, &mut self.second_pending);
+        if let (Some(_), Some(_)) = (&self.first_pending, &self.second_pending) {
+            let pair = (
+                self.first_pending.take().unwrap(),
+                self.second_pending.take().unwrap(),
+            );
+            return Ok(MappedGuard(pair));
+        }
+        // the pairs stop for good once a side is gone with no value kept for the next one
+        let gone = |res: Result<(), TryReadError>, kept: bool| {
+            res.err().filter(|err| !kept && *err != TryReadError::Empty)
+        };
+        Err(gone(first, self.first_pending.is_some())
+            .or(gone(second, self.second_pending.is_some()))
+            .unwrap_or(TryReadError::Empty))
+    }
+}
+
+/// Keeps a copy of the new value of `r` in `pending`, if there is one.
+fn refresh<R>(r: &mut R, pending: &mut Option<R::Item>) -> Result<(), TryReadError>
+where
+    R: Reader,
+    R::Item: Clone,
+{
+    let guard = r.read()?;
+    match pending {
+        Some(value) => value.clone_from(&guard),
+        None => *pending = Some(guard.clone()),
+    }
+    Ok(())
+}
+
+#[cfg(all(feature = "std", not(target_family = "wasm")))]
+impl<R> Throttled<R> {
+    pub(crate) fn new(inner: R, interval: Duration) -> Self {
+        Throttled {
+            inner,
+            interval,
+            last: None,
+        }
+    }
+
+    /// Returns the wrapped reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+#[cfg(all(feature = "std", not(target_family = "wasm")))]
+impl<R: Reader> Reader for Throttled<R> {
+    type Item = R::Item;
+    type Guard<'a>
+        = R::Guard<'a>
+    where
+        Self: 'a;
+
+    /// Try reading the last written value, failing with [`TryReadError::Empty`] until `interval`
+    /// has passed since the last value returned.
+    ///
+    /// The wrapped reader is left alone meanwhile, so the values written in between are conflated
+    /// by the channel and the next read returns the newest.
+    fn read(&mut self) -> Result<Self::Guard<'_>, TryReadError> {
+        if let Some(last) = self.last {
+            if last.elapsed() < self.interval {
+                return Err(TryReadError::Empty);
+            }
+        }
+        let guard = self.inner.read()?;
+        self.last = Some(Instant::now());
+        Ok(guard)
+    }
+}
+
+#[cfg(all(feature = "std", not(target_family = "wasm")))]
+impl<R: Reader> Debounced<R> {
+    pub(crate) fn new(inner: R, quiet: Duration) -> Self {
+        Debounced {
+            inner,
+            quiet,
+            pending: None,
+        }
+    }
+
+    /// Returns the wrapped reader, dropping the value waiting to settle.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+#[cfg(all(feature = "std", not(target_family = "wasm")))]
+impl<R> Reader for Debounced<R>
+where
+    R: Reader,
+    R::Item: Clone,
+{
+    type Item = R::Item;
+    type Guard<'a>
+        = MappedGuard<R::Item>
+    where
+        Self: 'a;
+
+    /// Try reading the last written value, failing with [`TryReadError::Empty`] until it has gone
+    /// `quiet` without being replaced.
+    ///
+    /// The time is counted from the first read that found the value, as only a read learns about
+    /// it: polling less often than `quiet` delays the values accordingly.
+    ///
+    /// Once the writer is gone no value can replace the last one, so it is returned right away.
+    fn read(&mut self) -> Result<Self::Guard<'_>, TryReadError> {
+        let pending = &mut self.pending;
+        let res = self.inner.read().map(|guard| match pending {
+            Some((at, value)) => {
+                *at = Instant::now();
+                value.clone_from(&guard);
+            }
+            None => *pending = Some((Instant::now(), guard.clone())),
+        });
+        match (res, pending.take()) {
+            (Err(TryReadError::Empty) | Ok(()), Some((at, value))) if at.elapsed() < self.quiet => {
+                *pending = Some((at, value));
+                Err(TryReadError::Empty)
+            }
+            (_, Some((_, value))) => Ok(MappedGuard(value)),
+            (Err(err), None) => Err(err),
+            (Ok(()), None) => unreachable!("a value was just read"),
+        }
+    }
+}
+
+impl<R: core::fmt::Debug, F> core::fmt::Debug for MappedReader<R, F> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("MappedReader")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<W: core::fmt::Debug, F, U> core::fmt::Debug for MappedWriter<W, F, U> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("MappedWriter")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<W: core::fmt::Debug, P> core::fmt::Debug for FilteredWriter<W, P> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("FilteredWriter")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(all(feature = "std", not(target_family = "wasm")))]
+impl<R: core::fmt::Debug> core::fmt::Debug for Throttled<R> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Throttled")
+            .field("inner", &self.inner)
+            .field("interval", &self.interval)
+            .finish()
+    }
+}
+
+#[cfg(all(feature = "std", not(target_family = "wasm")))]
+impl<R: Reader + core::fmt::Debug> core::fmt::Debug for Debounced<R> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Debounced")
+            .field("inner", &self.inner)
+            .field("quiet", &self.quiet)
+            .field("pending", &self.pending.is_some())
+            .finish()
+    }
+}
+
+#[cfg(all(feature = "std", not(target_family = "wasm")))]
+impl<R1: core::fmt::Debug, R2: core::fmt::Debug> core::fmt::Debug for Merge<R1, R2> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Merge")
+            .field("first", &self.first)
+            .field("second", &self.second)
+            .field("last", &self.last)
+            .finish()
+    }
+}
+
+impl<R1, R2> core::fmt::Debug for Zip<R1, R2>
+where
+    R1: Reader + core::fmt::Debug,
+    R2: Reader + core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Zip")
+            .field("first", &self.first)
+            .field("second", &self.second)
+            .field("first_pending", &self.first_pending.is_some())
+            .field("second_pending", &self.second_pending.is_some())
+            .finish()
+    }
+}
+
+/// Combines two [`stamped`](crate::stamped) readers of the same item into one returning whichever
+/// value was written last.
+///
+/// The order of two values published on different channels is only known from their stamps: when
+/// both readers have a new value, the newer one is returned and the older one dropped, and a value
+/// written before the last one returned is skipped. Reads fail with [`TryReadError::Empty`] while
+/// either side may still publish.
+#[cfg(all(feature = "std", not(target_family = "wasm")))]
+pub fn merge<T, R1, R2>(first: R1, second: R2) -> Merge<R1, R2>
+where
+    R1: Reader<Item = Stamped<T>>,
+    R2: Reader<Item = Stamped<T>>,
+{
+    Merge {
+        first,
+        second,
+        last: None,
+    }
+}
+
+/// Combines two readers into one returning a pair once both have a new value.
+///
+/// A new value read from one side is copied and kept until the other side has one too, replaced by
+/// any newer value from the same side meanwhile. Reads fail for good once a side is gone with no
+/// value kept for the next pair.
+pub fn zip<R1, R2>(first: R1, second: R2) -> Zip<R1, R2>
+where
+    R1: Reader,
+    R2: Reader,
+    R1::Item: Clone,
+    R2::Item: Clone,
+{
+    Zip {
+        first,
+        second,
+        first_pending: None,
+        second_pending: None,
+    }
+}
@@ -0,0 +1,44 @@
+//! Time of the last publish of a channel, for consumers that must notice a stalled producer.
+//!
+//! With the `age` feature, the writers of `atomic_spsc`, `blocking_spsc`, `futex_spsc`,
+//! `mutex_spsc` and `ticket_spsc` read the clock on every publish, and their read handles return it
+//! from `last_update()` and `age()`, whether the value was read or not.
+
+use crate::atomic::{AtomicU64, Ordering};
+
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+// instants are stored as nanoseconds since the first one taken by any channel, so that channels
+// can still be built in `const` contexts
+static ORIGIN: OnceLock<Instant> = OnceLock::new();
+
+/// Instant of the last publish, embedded in the shared state of a channel.
+pub(crate) struct LastUpdate {
+    // nanoseconds since `ORIGIN` plus one, zero until the first publish
+    nanos: AtomicU64,
+}
+
+impl LastUpdate {
+    pub(crate) const fn new() -> Self {
+        LastUpdate {
+            nanos: AtomicU64::new(0),
+        }
+    }
+
+    /// Records that a value was published now.
+    pub(crate) fn touch(&self) {
+        let origin = *ORIGIN.get_or_init(Instant::now);
+        let nanos = u64::try_from(origin.elapsed().as_nanos()).unwrap_or(u64::MAX - 1);
+        // release so that whoever sees the stamp also sees `ORIGIN` set
+        self.nanos.store(nanos + 1, Ordering::Release);
+    }
+
+    /// Returns the instant of the last publish, or `None` if nothing was published yet.
+    pub(crate) fn get(&self) -> Option<Instant> {
+        match self.nanos.load(Ordering::Acquire) {
+            0 => None,
+            nanos => Some(*ORIGIN.get()? + Duration::from_nanos(nanos - 1)),
+        }
+    }
+}
@@ -0,0 +1,297 @@
+use crate::atomic::{AtomicBool, AtomicPtr, AtomicU64, Ordering};
+use crate::debug::DebugState;
+use crate::shared::Shared;
+use crate::{Reader, TryReadError, Writer};
+
+use std::marker::PhantomData;
+use std::ptr;
+use std::sync::Arc;
+
+/// The value waiting for the reader is an `Arc` leaked into `buffer`, or null if there is none.
+/// Writing and reading are a single pointer swap, so `T` is never cloned nor copied.
+struct Inner<T> {
+    buffer: AtomicPtr<T>,
+    // number of values replaced before being read, only modified by the writer
+    overwritten: AtomicU64,
+    writer_alive: AtomicBool,
+    // set right before `writer_alive` is cleared if the writer left through `close`
+    closed: AtomicBool,
+    reader_alive: AtomicBool,
+    // `buffer` owns an `Arc<T>`, so it is only `Send` and `Sync` if that is
+    _owns: PhantomData<Arc<T>>,
+}
+
+pub struct ReadHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+}
+
+pub struct WriteHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+}
+
+impl<T> Inner<T> {
+    const fn new() -> Self {
+        Inner {
+            buffer: AtomicPtr::new(ptr::null_mut()),
+            overwritten: AtomicU64::new(0),
+            writer_alive: AtomicBool::new(true),
+            closed: AtomicBool::new(false),
+            reader_alive: AtomicBool::new(true),
+            _owns: PhantomData,
+        }
+    }
+
+    /// Writes the provided value, dropping the one it replaces if it was not read.
+    ///
+    /// This method is wait-free.
+    fn write(&self, value: Arc<T>) {
+        let old = self
+            .buffer
+            .swap(Arc::into_raw(value).cast_mut(), Ordering::AcqRel);
+        if !old.is_null() {
+            self.overwritten.fetch_add(1, Ordering::Relaxed);
+            drop(unsafe { Arc::from_raw(old) });
+        }
+    }
+
+    /// Try reading the last written value.
+    /// The operation may fail if no new value was written since the last read.
+    ///
+    /// This method is wait-free.
+    fn read(&self) -> Option<ArcGuard<T>> {
+        if self.buffer.load(Ordering::Relaxed).is_null() {
+            return None;
+        }
+        let value = self.buffer.swap(ptr::null_mut(), Ordering::AcqRel);
+        if value.is_null() {
+            return None;
+        }
+        Some(ArcGuard(unsafe { Arc::from_raw(value) }))
+    }
+
+    /// Try reading the last written value, reporting whether the writer is gone.
+    fn try_read(&self) -> Result<ArcGuard<T>, TryReadError> {
+        if let Some(guard) = self.read() {
+            return Ok(guard);
+        }
+        if self.writer_alive.load(Ordering::Acquire) {
+            return Err(TryReadError::Empty);
+        }
+        // the writer may have published right before being dropped
+        self.read().ok_or_else(|| self.disconnected())
+    }
+
+    /// Error reported once the writer is gone and nothing is left to read.
+    fn disconnected(&self) -> TryReadError {
+        if self.closed.load(Ordering::Relaxed) {
+            TryReadError::Closed
+        } else {
+            TryReadError::Disconnected
+        }
+    }
+}
+
+impl<T> Drop for Inner<T> {
+    fn drop(&mut self) {
+        let value = *self.buffer.get_mut();
+        if !value.is_null() {
+            drop(unsafe { Arc::from_raw(value) });
+        }
+    }
+}
+
+/// Shared reference to a read value.
+///
+/// The guard owns the `Arc` taken out of the channel, so the writer is never held back and the
+/// value can outlive the guard through [`ArcGuard::into_arc`].
+#[derive(Debug)]
+pub struct ArcGuard<T>(Arc<T>);
+
+impl<T> ArcGuard<T> {
+    /// Returns the read value.
+    pub fn into_arc(self) -> Arc<T> {
+        self.0
+    }
+}
+
+impl<T> std::ops::Deref for ArcGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> ReadHandle<'_, T> {
+    /// Returns how many written values were replaced before they could be read.
+    pub fn overwritten(&self) -> u64 {
+        self.inner.overwritten.load(Ordering::Relaxed)
+    }
+}
+
+impl<T> WriteHandle<'_, T> {
+    /// Writes an already shared value, so that the writer can keep its own reference to it
+    /// without cloning `T`.
+    pub fn write_arc(&mut self, value: Arc<T>) {
+        self.inner.write(value)
+    }
+
+    /// Returns how many written values were replaced before the reader could read them.
+    pub fn overwritten(&self) -> u64 {
+        self.inner.overwritten.load(Ordering::Relaxed)
+    }
+}
+
+impl<T> Reader for ReadHandle<'_, T> {
+    type Item = T;
+    type Guard<'a>
+        = ArcGuard<T>
+    where
+        Self: 'a;
+
+    fn read(&mut self) -> Result<Self::Guard<'_>, TryReadError> {
+        self.inner.try_read()
+    }
+}
+
+impl<T> Writer for WriteHandle<'_, T> {
+    type Item = T;
+
+    fn write(&mut self, value: T) {
+        self.inner.write(Arc::new(value))
+    }
+
+    /// Writes a value built in place by `f`, starting from `T::default()`.
+    ///
+    /// Read values are owned by the reader, so there is no older value to reuse.
+    fn write_with(&mut self, f: impl FnOnce(&mut T))
+    where
+        T: Default,
+    {
+        let mut value = Arc::new(T::default());
+        // nobody else references it yet
+        f(Arc::get_mut(&mut value).unwrap());
+        self.inner.write(value)
+    }
+
+    fn close(self) {
+        self.inner.closed.store(true, Ordering::Relaxed);
+        // dropping `self` disconnects the reader
+    }
+
+    fn is_reader_alive(&self) -> bool {
+        self.inner.reader_alive.load(Ordering::Acquire)
+    }
+}
+
+impl<T> Drop for ReadHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.reader_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<T> Drop for WriteHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.writer_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<T> DebugState for Inner<T> {
+    fn debug_state(&self, d: &mut std::fmt::DebugStruct<'_, '_>) {
+        d.field("pending", &!self.buffer.load(Ordering::Relaxed).is_null())
+            .field("overwritten", &self.overwritten.load(Ordering::Relaxed))
+            .field("writer_alive", &self.writer_alive.load(Ordering::Relaxed))
+            .field("closed", &self.closed.load(Ordering::Relaxed))
+            .field("reader_alive", &self.reader_alive.load(Ordering::Relaxed));
+    }
+}
+
+impl<T> std::fmt::Debug for ReadHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "arc_spsc::ReadHandle")
+    }
+}
+
+impl<T> std::fmt::Debug for WriteHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "arc_spsc::WriteHandle")
+    }
+}
+
+/// Construct a new, empty read and write handle pair.
+///
+/// Reads fail with [`TryReadError::Empty`] until the first write.
+pub fn new<T>() -> (ReadHandle<'static, T>, WriteHandle<'static, T>) {
+    let inner = Arc::new(Inner::new());
+    let r = ReadHandle {
+        inner: Shared::Arc(Arc::clone(&inner)),
+    };
+    let w = WriteHandle {
+        inner: Shared::Arc(inner),
+    };
+    (r, w)
+}
+
+/// This variant as a [`crate::Channel`], for code generic over the variant.
+pub struct Channel;
+
+impl<T: 'static> crate::Channel<T> for Channel {
+    type Reader = ReadHandle<'static, T>;
+    type Writer = WriteHandle<'static, T>;
+
+    fn create() -> (Self::Reader, Self::Writer) {
+        new()
+    }
+}
+
+/// Storage for a channel that is not reference counted, so that no allocation is needed.
+///
+/// It can be placed in a `static` or borrowed by the threads of a [`std::thread::scope`]: the
+/// handles returned by [`Storage::split`] borrow it, and are handed out only once.
+///
+/// ```
+/// use rustedrazors::arc_spsc::Storage;
+/// use rustedrazors::{Reader, Writer};
+///
+/// let storage = Storage::new();
+/// let (mut r, mut w) = storage.split().unwrap();
+/// std::thread::scope(|s| {
+///     s.spawn(move || w.write(String::from("snapshot")));
+/// });
+/// assert_eq!(r.read().as_deref().map(String::as_str), Ok("snapshot"));
+/// ```
+pub struct Storage<T> {
+    inner: Inner<T>,
+    split: AtomicBool,
+}
+
+impl<T> Storage<T> {
+    /// Constructs a new, empty storage.
+    pub const fn new() -> Self {
+        Storage {
+            inner: Inner::new(),
+            split: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the read and write handles borrowing the storage, or `None` if they were already
+    /// taken.
+    pub fn split(&self) -> Option<(ReadHandle<'_, T>, WriteHandle<'_, T>)> {
+        if self.split.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+        let r = ReadHandle {
+            inner: Shared::Borrowed(&self.inner),
+        };
+        let w = WriteHandle {
+            inner: Shared::Borrowed(&self.inner),
+        };
+        Some((r, w))
+    }
+}
+
+impl<T> Default for Storage<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
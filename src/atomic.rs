@@ -0,0 +1,21 @@
+//! Atomics behind every channel, taken from `portable-atomic` with the feature of the same name,
+//! and always on targets without native 64-bit atomics, such as 32-bit PowerPC or MIPS, so that
+//! the counters of the channels can stay 64 bits wide everywhere.
+//!
+//! That crate emulates what the target lacks, 64-bit atomics or compare-and-swap altogether, and
+//! is the native type otherwise. `shm_spsc` and the futex words keep the `std` types, since their
+//! address is handed to another process or to the kernel: `shm_spsc` is left out on targets
+//! without native 64-bit atomics.
+//!
+//! Built with `--cfg shuttle`, they are the atomics of `shuttle` instead, which yield to its
+//! randomized scheduler on every access, see `tests/shuttle.rs`.
+
+#[cfg(not(any(feature = "portable-atomic", not(target_has_atomic = "64"), shuttle)))]
+pub(crate) use core::sync::atomic::*;
+#[cfg(all(
+    any(feature = "portable-atomic", not(target_has_atomic = "64")),
+    not(shuttle)
+))]
+pub(crate) use portable_atomic::*;
+#[cfg(shuttle)]
+pub(crate) use shuttle::sync::atomic::*;
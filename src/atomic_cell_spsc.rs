@@ -0,0 +1,366 @@
+use crate::atomic::{AtomicBool, AtomicU64, Ordering};
+use crate::debug::DebugState;
+use crate::shared::Shared;
+use crate::{Reader, TryReadError, Writer};
+
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+// set in `word` while its value was not read yet
+const FRESH: u64 = 1 << 63;
+
+/// Small `Copy` values that can be stored in an atomic word next to the freshness bit.
+///
+/// `into_bits` must leave the most significant bit clear, which is why 8 byte primitives do not
+/// qualify: use `atomic_spsc` for them.
+pub trait CellValue: Copy {
+    /// Encodes the value, the result must be lower than `1 << 63`.
+    fn into_bits(self) -> u64;
+
+    /// Decodes a value encoded by [`CellValue::into_bits`].
+    fn from_bits(bits: u64) -> Self;
+}
+
+macro_rules! cell_value {
+    ($($ty:ty => $bits:ty),* $(,)?) => {$(
+        impl CellValue for $ty {
+            fn into_bits(self) -> u64 {
+                self as $bits as u64
+            }
+
+            fn from_bits(bits: u64) -> Self {
+                bits as $bits as $ty
+            }
+        }
+    )*};
+}
+
+cell_value!(u8 => u8, u16 => u16, u32 => u32, i8 => u8, i16 => u16, i32 => u32);
+
+impl CellValue for f32 {
+    fn into_bits(self) -> u64 {
+        self.to_bits() as u64
+    }
+
+    fn from_bits(bits: u64) -> Self {
+        f32::from_bits(bits as u32)
+    }
+}
+
+impl CellValue for bool {
+    fn into_bits(self) -> u64 {
+        self as u64
+    }
+
+    fn from_bits(bits: u64) -> Self {
+        bits != 0
+    }
+}
+
+impl CellValue for char {
+    fn into_bits(self) -> u64 {
+        self as u64
+    }
+
+    fn from_bits(bits: u64) -> Self {
+        // only ever called on bits produced by `into_bits`
+        char::from_u32(bits as u32).unwrap_or_default()
+    }
+}
+
+/// The value lives directly in `word`, so reads and writes are a single atomic operation and no
+/// pool is needed.
+struct Inner<T> {
+    word: AtomicU64,
+    // set by the first write, only accessed by the writer
+    written: AtomicBool,
+    // number of values replaced before being read, only modified by the writer
+    overwritten: AtomicU64,
+    writer_alive: AtomicBool,
+    // set right before `writer_alive` is cleared if the writer left through `close`
+    closed: AtomicBool,
+    reader_alive: AtomicBool,
+    _value: PhantomData<fn() -> T>,
+}
+
+pub struct ReadHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+}
+
+pub struct WriteHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+}
+
+impl<T> Inner<T> {
+    const fn new() -> Self {
+        Inner {
+            word: AtomicU64::new(0),
+            written: AtomicBool::new(false),
+            overwritten: AtomicU64::new(0),
+            writer_alive: AtomicBool::new(true),
+            closed: AtomicBool::new(false),
+            reader_alive: AtomicBool::new(true),
+            _value: PhantomData,
+        }
+    }
+}
+
+impl<T> Inner<T>
+where
+    T: CellValue,
+{
+    fn write(&self, value: T) {
+        let bits = value.into_bits();
+        debug_assert!(
+            bits & FRESH == 0,
+            "CellValue::into_bits set the freshness bit"
+        );
+        let old = self.word.swap(bits | FRESH, Ordering::Release);
+        if old & FRESH != 0 {
+            self.overwritten.fetch_add(1, Ordering::Relaxed);
+        }
+        self.written.store(true, Ordering::Relaxed);
+    }
+
+    fn write_with(&self, f: impl FnOnce(&mut T))
+    where
+        T: Default,
+    {
+        // the reader never changes the value bits, only the freshness one
+        let mut value = if self.written.load(Ordering::Relaxed) {
+            T::from_bits(self.word.load(Ordering::Relaxed) & !FRESH)
+        } else {
+            T::default()
+        };
+        f(&mut value);
+        self.write(value);
+    }
+
+    /// Try reading the last written value.
+    /// The operation may fail if no new value was written since the last read.
+    ///
+    /// This method is wait-free, and a single load when there is nothing to read.
+    fn read(&self) -> Option<CellGuard<T>> {
+        if self.word.load(Ordering::Relaxed) & FRESH == 0 {
+            return None;
+        }
+        let word = self.word.fetch_and(!FRESH, Ordering::Acquire);
+        if word & FRESH == 0 {
+            return None;
+        }
+        Some(CellGuard(T::from_bits(word & !FRESH)))
+    }
+
+    /// Try reading the last written value, reporting whether the writer is gone.
+    fn try_read(&self) -> Result<CellGuard<T>, TryReadError> {
+        if let Some(guard) = self.read() {
+            return Ok(guard);
+        }
+        if self.writer_alive.load(Ordering::Acquire) {
+            return Err(TryReadError::Empty);
+        }
+        // the writer may have published right before being dropped
+        self.read().ok_or_else(|| self.disconnected())
+    }
+
+    /// Error reported once the writer is gone and nothing is left to read.
+    fn disconnected(&self) -> TryReadError {
+        if self.closed.load(Ordering::Relaxed) {
+            TryReadError::Closed
+        } else {
+            TryReadError::Disconnected
+        }
+    }
+}
+
+/// Copy of a read value.
+///
+/// Values are small enough that there is nothing to lease: the guard owns its copy.
+#[derive(Debug, Clone, Copy)]
+pub struct CellGuard<T>(T);
+
+impl<T> std::ops::Deref for CellGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> ReadHandle<'_, T> {
+    /// Returns how many written values were replaced before they could be read.
+    pub fn overwritten(&self) -> u64 {
+        self.inner.overwritten.load(Ordering::Relaxed)
+    }
+}
+
+impl<T> WriteHandle<'_, T> {
+    /// Returns how many written values were replaced before the reader could read them.
+    pub fn overwritten(&self) -> u64 {
+        self.inner.overwritten.load(Ordering::Relaxed)
+    }
+}
+
+impl<T> Reader for ReadHandle<'_, T>
+where
+    T: CellValue,
+{
+    type Item = T;
+    type Guard<'a>
+        = CellGuard<T>
+    where
+        Self: 'a;
+
+    fn read(&mut self) -> Result<Self::Guard<'_>, TryReadError> {
+        self.inner.try_read()
+    }
+}
+
+impl<T> Writer for WriteHandle<'_, T>
+where
+    T: CellValue,
+{
+    type Item = T;
+
+    fn write(&mut self, value: T) {
+        self.inner.write(value)
+    }
+
+    fn write_with(&mut self, f: impl FnOnce(&mut T))
+    where
+        T: Default,
+    {
+        self.inner.write_with(f)
+    }
+
+    fn close(self) {
+        self.inner.closed.store(true, Ordering::Relaxed);
+        // dropping `self` disconnects the reader
+    }
+
+    fn is_reader_alive(&self) -> bool {
+        self.inner.reader_alive.load(Ordering::Acquire)
+    }
+}
+
+impl<T> Drop for ReadHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.reader_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<T> Drop for WriteHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.writer_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<T> DebugState for Inner<T> {
+    fn debug_state(&self, d: &mut std::fmt::DebugStruct<'_, '_>) {
+        d.field("pending", &(self.word.load(Ordering::Relaxed) & FRESH != 0))
+            .field("written", &self.written.load(Ordering::Relaxed))
+            .field("overwritten", &self.overwritten.load(Ordering::Relaxed))
+            .field("writer_alive", &self.writer_alive.load(Ordering::Relaxed))
+            .field("closed", &self.closed.load(Ordering::Relaxed))
+            .field("reader_alive", &self.reader_alive.load(Ordering::Relaxed));
+    }
+}
+
+impl<T> std::fmt::Debug for ReadHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "atomic_cell_spsc::ReadHandle")
+    }
+}
+
+impl<T> std::fmt::Debug for WriteHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "atomic_cell_spsc::WriteHandle")
+    }
+}
+
+/// Construct a new, empty read and write handle pair.
+///
+/// Reads fail with [`TryReadError::Empty`] until the first write.
+pub fn new<T>() -> (ReadHandle<'static, T>, WriteHandle<'static, T>)
+where
+    T: CellValue,
+{
+    let inner = Arc::new(Inner::new());
+    let r = ReadHandle {
+        inner: Shared::Arc(Arc::clone(&inner)),
+    };
+    let w = WriteHandle {
+        inner: Shared::Arc(inner),
+    };
+    (r, w)
+}
+
+/// This variant as a [`crate::Channel`], for code generic over the variant.
+pub struct Channel;
+
+impl<T: CellValue + 'static> crate::Channel<T> for Channel {
+    type Reader = ReadHandle<'static, T>;
+    type Writer = WriteHandle<'static, T>;
+
+    fn create() -> (Self::Reader, Self::Writer) {
+        new()
+    }
+}
+
+/// Storage for a channel that is not reference counted, so that no allocation is needed.
+///
+/// It can be placed in a `static` or borrowed by the threads of a [`std::thread::scope`]: the
+/// handles returned by [`Storage::split`] borrow it, and are handed out only once.
+///
+/// ```
+/// use rustedrazors::atomic_cell_spsc::Storage;
+/// use rustedrazors::{Reader, Writer};
+///
+/// let storage = Storage::new();
+/// let (mut r, mut w) = storage.split().unwrap();
+/// std::thread::scope(|s| {
+///     s.spawn(move || w.write(42u32));
+/// });
+/// assert_eq!(r.read().as_deref(), Ok(&42));
+/// ```
+pub struct Storage<T> {
+    inner: Inner<T>,
+    split: AtomicBool,
+}
+
+impl<T> Storage<T>
+where
+    T: CellValue,
+{
+    /// Constructs a new, empty storage.
+    pub const fn new() -> Self {
+        Storage {
+            inner: Inner::new(),
+            split: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the read and write handles borrowing the storage, or `None` if they were already
+    /// taken.
+    pub fn split(&self) -> Option<(ReadHandle<'_, T>, WriteHandle<'_, T>)> {
+        if self.split.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+        let r = ReadHandle {
+            inner: Shared::Borrowed(&self.inner),
+        };
+        let w = WriteHandle {
+            inner: Shared::Borrowed(&self.inner),
+        };
+        Some((r, w))
+    }
+}
+
+impl<T> Default for Storage<T>
+where
+    T: CellValue,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,73 @@
+//! Waiting strategy of the spin loops.
+
+use crate::atomic::AtomicU32;
+
+// number of retries spent spinning before yielding to the scheduler
+const SPINS: usize = 20;
+// number of retries, spinning ones included, before a loop that can park should do so
+#[cfg(not(target_family = "wasm"))]
+pub(crate) const YIELDS: usize = 30;
+
+/// Waits a little before the `tries`-th retry of a spin loop: spins on the first few, then yields
+/// the current thread.
+///
+/// `wasm32` has no scheduler to yield to, so there a spin loop only ever spins.
+#[inline]
+pub(crate) fn snooze(tries: usize) {
+    if tries < SPINS || cfg!(target_family = "wasm") {
+        std::hint::spin_loop();
+    } else {
+        std::thread::yield_now();
+    }
+}
+
+/// Waits before the `tries`-th retry of an empty read in a thread polling a reader: the readers of
+/// the crate cannot all block, so an idle thread spins, then yields, then sleeps longer and longer
+/// up to about a millisecond.
+#[cfg(not(target_family = "wasm"))]
+pub(crate) fn idle(tries: usize) {
+    if tries < YIELDS {
+        snooze(tries);
+    } else {
+        let doublings = (tries - YIELDS).min(4) as u32;
+        std::thread::sleep(std::time::Duration::from_micros(50) * 2u32.pow(doublings));
+    }
+}
+
+/// Like [`snooze`], for loops waiting on `word` to change from `seen`.
+///
+/// On aarch64 the spinning retries sleep with `wfe` until `word` is stored to, so a blocked thread
+/// neither saturates its core nor starves its sibling hyperthread.
+#[inline]
+pub(crate) fn snooze_on(tries: usize, word: &AtomicU32, seen: u32) {
+    #[cfg(target_arch = "aarch64")]
+    if tries < SPINS {
+        return wait_for_store(word, seen);
+    }
+    #[cfg(not(target_arch = "aarch64"))]
+    let _ = (word, seen);
+    snooze(tries)
+}
+
+/// Sleeps until `word` is stored to, unless it already changed from `seen`.
+///
+/// `ldaxr` arms the exclusive monitor on `word`: a store to it from another core clears the monitor,
+/// which raises the event `wfe` waits for, even if it happened before `wfe` was reached. The
+/// periodic event stream most kernels enable bounds the sleep anyway.
+#[cfg(target_arch = "aarch64")]
+#[inline]
+fn wait_for_store(word: &AtomicU32, seen: u32) {
+    unsafe {
+        std::arch::asm!(
+            "ldaxr {value:w}, [{word}]",
+            "cmp {value:w}, {seen:w}",
+            "b.ne 2f",
+            "wfe",
+            "2:",
+            word = in(reg) word.as_ptr(),
+            seen = in(reg) seen,
+            value = out(reg) _,
+            options(nostack),
+        );
+    }
+}
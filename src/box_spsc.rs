@@ -0,0 +1,308 @@
+use crate::atomic::{AtomicBool, AtomicPtr, AtomicU64, Ordering};
+use crate::debug::DebugState;
+use crate::shared::Shared;
+use crate::{Reader, TryReadError, Writer};
+
+use std::marker::PhantomData;
+use std::ptr;
+use std::sync::Arc;
+
+/// The value waiting for the reader is a `Box` leaked into `buffer`, or null if there is none.
+/// Ownership of the box moves through the channel with a single pointer swap on each side.
+struct Inner<T> {
+    buffer: AtomicPtr<T>,
+    // number of values replaced before being read, only modified by the writer
+    overwritten: AtomicU64,
+    writer_alive: AtomicBool,
+    // set right before `writer_alive` is cleared if the writer left through `close`
+    closed: AtomicBool,
+    reader_alive: AtomicBool,
+    // `buffer` owns a `Box<T>`, so it is only `Send` and `Sync` if that is
+    _owns: PhantomData<Box<T>>,
+}
+
+pub struct ReadHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+}
+
+pub struct WriteHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+}
+
+impl<T> Inner<T> {
+    const fn new() -> Self {
+        Inner {
+            buffer: AtomicPtr::new(ptr::null_mut()),
+            overwritten: AtomicU64::new(0),
+            writer_alive: AtomicBool::new(true),
+            closed: AtomicBool::new(false),
+            reader_alive: AtomicBool::new(true),
+            _owns: PhantomData,
+        }
+    }
+
+    /// Writes the provided value, returning the one it replaces if it was not read.
+    ///
+    /// This method is wait-free.
+    fn write(&self, value: Box<T>) -> Option<Box<T>> {
+        let old = self.buffer.swap(Box::into_raw(value), Ordering::AcqRel);
+        if old.is_null() {
+            return None;
+        }
+        self.overwritten.fetch_add(1, Ordering::Relaxed);
+        Some(unsafe { Box::from_raw(old) })
+    }
+
+    /// Try reading the last written value.
+    /// The operation may fail if no new value was written since the last read.
+    ///
+    /// This method is wait-free.
+    fn read(&self) -> Option<Box<T>> {
+        if self.buffer.load(Ordering::Relaxed).is_null() {
+            return None;
+        }
+        let value = self.buffer.swap(ptr::null_mut(), Ordering::AcqRel);
+        if value.is_null() {
+            return None;
+        }
+        Some(unsafe { Box::from_raw(value) })
+    }
+
+    /// Try reading the last written value, reporting whether the writer is gone.
+    fn try_read(&self) -> Result<Box<T>, TryReadError> {
+        if let Some(guard) = self.read() {
+            return Ok(guard);
+        }
+        if self.writer_alive.load(Ordering::Acquire) {
+            return Err(TryReadError::Empty);
+        }
+        // the writer may have published right before being dropped
+        self.read().ok_or_else(|| self.disconnected())
+    }
+
+    /// Error reported once the writer is gone and nothing is left to read.
+    fn disconnected(&self) -> TryReadError {
+        if self.closed.load(Ordering::Relaxed) {
+            TryReadError::Closed
+        } else {
+            TryReadError::Disconnected
+        }
+    }
+}
+
+impl<T> Drop for Inner<T> {
+    fn drop(&mut self) {
+        let value = *self.buffer.get_mut();
+        if !value.is_null() {
+            drop(unsafe { Box::from_raw(value) });
+        }
+    }
+}
+
+/// Read value.
+///
+/// The guard owns the `Box` taken out of the channel, which can be moved out of it through
+/// [`BoxGuard::into_box`].
+#[derive(Debug)]
+pub struct BoxGuard<T>(Box<T>);
+
+impl<T> BoxGuard<T> {
+    /// Returns the box written by the writer.
+    pub fn into_box(self) -> Box<T> {
+        self.0
+    }
+}
+
+impl<T> std::ops::Deref for BoxGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> std::ops::DerefMut for BoxGuard<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T> ReadHandle<'_, T> {
+    /// Try reading the last written value, taking ownership of the box it was written in.
+    /// Fails like [`Reader::read`].
+    pub fn read_box(&mut self) -> Result<Box<T>, TryReadError> {
+        self.inner.try_read()
+    }
+
+    /// Returns how many written values were replaced before they could be read.
+    pub fn overwritten(&self) -> u64 {
+        self.inner.overwritten.load(Ordering::Relaxed)
+    }
+}
+
+impl<T> WriteHandle<'_, T> {
+    /// Writes an already boxed value, handing back the value it replaces if the reader did not
+    /// read it, so that the writer can reuse its box.
+    pub fn write_box(&mut self, value: Box<T>) -> Option<Box<T>> {
+        self.inner.write(value)
+    }
+
+    /// Returns how many written values were replaced before the reader could read them.
+    pub fn overwritten(&self) -> u64 {
+        self.inner.overwritten.load(Ordering::Relaxed)
+    }
+}
+
+impl<T> Reader for ReadHandle<'_, T> {
+    type Item = T;
+    type Guard<'a>
+        = BoxGuard<T>
+    where
+        Self: 'a;
+
+    fn read(&mut self) -> Result<Self::Guard<'_>, TryReadError> {
+        self.inner.try_read().map(BoxGuard)
+    }
+}
+
+impl<T> Writer for WriteHandle<'_, T> {
+    type Item = T;
+
+    /// Writes the provided value, dropping the one it replaces if it was not read.
+    fn write(&mut self, value: T) {
+        self.inner.write(Box::new(value));
+    }
+
+    /// Writes a value built in place by `f`, starting from `T::default()`.
+    ///
+    /// Read values are owned by the reader, so there is no older value to reuse: see
+    /// [`WriteHandle::write_box`] to recycle the replaced ones instead.
+    fn write_with(&mut self, f: impl FnOnce(&mut T))
+    where
+        T: Default,
+    {
+        let mut value = Box::<T>::default();
+        f(&mut value);
+        self.inner.write(value);
+    }
+
+    fn close(self) {
+        self.inner.closed.store(true, Ordering::Relaxed);
+        // dropping `self` disconnects the reader
+    }
+
+    fn is_reader_alive(&self) -> bool {
+        self.inner.reader_alive.load(Ordering::Acquire)
+    }
+}
+
+impl<T> Drop for ReadHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.reader_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<T> Drop for WriteHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.writer_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<T> DebugState for Inner<T> {
+    fn debug_state(&self, d: &mut std::fmt::DebugStruct<'_, '_>) {
+        d.field("pending", &!self.buffer.load(Ordering::Relaxed).is_null())
+            .field("overwritten", &self.overwritten.load(Ordering::Relaxed))
+            .field("writer_alive", &self.writer_alive.load(Ordering::Relaxed))
+            .field("closed", &self.closed.load(Ordering::Relaxed))
+            .field("reader_alive", &self.reader_alive.load(Ordering::Relaxed));
+    }
+}
+
+impl<T> std::fmt::Debug for ReadHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "box_spsc::ReadHandle")
+    }
+}
+
+impl<T> std::fmt::Debug for WriteHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "box_spsc::WriteHandle")
+    }
+}
+
+/// Construct a new, empty read and write handle pair.
+///
+/// Reads fail with [`TryReadError::Empty`] until the first write.
+pub fn new<T>() -> (ReadHandle<'static, T>, WriteHandle<'static, T>) {
+    let inner = Arc::new(Inner::new());
+    let r = ReadHandle {
+        inner: Shared::Arc(Arc::clone(&inner)),
+    };
+    let w = WriteHandle {
+        inner: Shared::Arc(inner),
+    };
+    (r, w)
+}
+
+/// This variant as a [`crate::Channel`], for code generic over the variant.
+pub struct Channel;
+
+impl<T: 'static> crate::Channel<T> for Channel {
+    type Reader = ReadHandle<'static, T>;
+    type Writer = WriteHandle<'static, T>;
+
+    fn create() -> (Self::Reader, Self::Writer) {
+        new()
+    }
+}
+
+/// Storage for a channel that is not reference counted, so that no allocation is needed.
+///
+/// It can be placed in a `static` or borrowed by the threads of a [`std::thread::scope`]: the
+/// handles returned by [`Storage::split`] borrow it, and are handed out only once.
+///
+/// ```
+/// use rustedrazors::box_spsc::Storage;
+///
+/// let storage = Storage::new();
+/// let (mut r, mut w) = storage.split().unwrap();
+/// std::thread::scope(|s| {
+///     s.spawn(move || w.write_box(Box::new(String::from("owned"))));
+/// });
+/// assert_eq!(r.read_box().as_deref().map(String::as_str), Ok("owned"));
+/// ```
+pub struct Storage<T> {
+    inner: Inner<T>,
+    split: AtomicBool,
+}
+
+impl<T> Storage<T> {
+    /// Constructs a new, empty storage.
+    pub const fn new() -> Self {
+        Storage {
+            inner: Inner::new(),
+            split: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the read and write handles borrowing the storage, or `None` if they were already
+    /// taken.
+    pub fn split(&self) -> Option<(ReadHandle<'_, T>, WriteHandle<'_, T>)> {
+        if self.split.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+        let r = ReadHandle {
+            inner: Shared::Borrowed(&self.inner),
+        };
+        let w = WriteHandle {
+            inner: Shared::Borrowed(&self.inner),
+        };
+        Some((r, w))
+    }
+}
+
+impl<T> Default for Storage<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
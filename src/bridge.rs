@@ -0,0 +1,198 @@
+//! Threads moving values between the channels of the crate and those of `std::sync::mpsc`, to move
+//! a codebase over one end at a time.
+//!
+//! ```
+//! use std::sync::mpsc;
+//!
+//! use rustedrazors::bridge::{self, Conflation};
+//! use rustedrazors::{atomic_spsc, Reader};
+//!
+//! // an existing producer, still sending on a queue
+//! let (tx, rx) = mpsc::channel();
+//! let (mut r, mut w) = atomic_spsc::new::<u32>();
+//! let bridge = bridge::from_receiver(rx, w, Conflation::Latest);
+//!
+//! tx.send(1).unwrap();
+//! assert_eq!(r.read_blocking().as_deref(), Ok(&1));
+//!
+//! // dropping every sender closes the channel
+//! drop(tx);
+//! bridge.join().unwrap();
+//! ```
+//!
+//! Towards the crate, the receiving side has to choose between writing every message or only the
+//! newest of those already queued, see [`Conflation`]. Towards `mpsc`, the reader of the crate
+//! already conflates the values the sender could not keep up with, so every value read is sent.
+//!
+//! With the `tokio` feature, [`to_watch`] and [`from_watch`] mirror a channel of the crate into a
+//! `tokio::sync::watch` and back, so that async code can observe the values of a real-time thread.
+
+use crate::backoff;
+use crate::{Reader, TryReadError, Writer};
+
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+
+/// How [`from_receiver`] writes the messages it finds queued.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Conflation {
+    /// Write every message, in order: the reader still only sees the newest one it did not miss,
+    /// but a writer with side effects (hooks, stats, a fan-out) sees them all.
+    Every,
+    /// Write only the newest of the messages queued when the thread wakes up, dropping the others
+    /// without writing them.
+    #[default]
+    Latest,
+}
+
+/// Thread of a bridge, returned by the functions of this module.
+///
+/// Dropping it detaches the thread, which keeps running until either side is gone.
+pub struct Bridge {
+    thread: JoinHandle<()>,
+}
+
+impl Bridge {
+    /// Waits for the thread to stop.
+    /// Fails with the payload of the panic that stopped it, if any.
+    pub fn join(self) -> thread::Result<()> {
+        self.thread.join()
+    }
+
+    /// Returns whether the thread has stopped.
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+}
+
+impl std::fmt::Debug for Bridge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Bridge")
+            .field("finished", &self.is_finished())
+            .finish()
+    }
+}
+
+/// Spawns a thread writing the messages of `rx` to `w`, conflated according to `conflation`.
+///
+/// Once every sender is gone `w` is closed, so the reader sees [`TryReadError::Closed`]. Once the
+/// reader is gone the thread stops at the next message, dropping `rx` so that the senders fail.
+pub fn from_receiver<T, W>(rx: mpsc::Receiver<T>, mut w: W, conflation: Conflation) -> Bridge
+where
+    T: Send + 'static,
+    W: Writer<Item = T> + Send + 'static,
+{
+    let thread = thread::spawn(move || {
+        while let Ok(mut value) = rx.recv() {
+            if !w.is_reader_alive() {
+                return;
+            }
+            match conflation {
+                Conflation::Every => {
+                    w.write(value);
+                    for value in rx.try_iter() {
+                        w.write(value);
+                    }
+                }
+                Conflation::Latest => {
+                    if let Some(newest) = rx.try_iter().last() {
+                        value = newest;
+                    }
+                    w.write(value);
+                }
+            }
+        }
+        w.close();
+    });
+    Bridge { thread }
+}
+
+/// Spawns a thread sending a clone of every value read from `r` on `tx`.
+///
+/// Once the writer of `r` is gone `tx` is dropped, so the receiver sees the end of the stream.
+/// Once the receiver is gone the thread stops at the next value.
+pub fn to_sender<R>(r: R, tx: mpsc::Sender<R::Item>) -> Bridge
+where
+    R: Reader + Send + 'static,
+    R::Item: Clone + Send + 'static,
+{
+    let thread = thread::spawn(move || pump(r, |value| tx.send(value).is_ok()));
+    Bridge { thread }
+}
+
+/// Like [`to_sender`], for a bounded channel: while the queue is full the thread blocks, and the
+/// values written meanwhile are conflated by `r`.
+pub fn to_sync_sender<R>(r: R, tx: mpsc::SyncSender<R::Item>) -> Bridge
+where
+    R: Reader + Send + 'static,
+    R::Item: Clone + Send + 'static,
+{
+    let thread = thread::spawn(move || pump(r, |value| tx.send(value).is_ok()));
+    Bridge { thread }
+}
+
+/// Spawns a thread publishing a clone of every value read from `r` to the receivers of `tx`.
+///
+/// Once the writer of `r` is gone `tx` is dropped, so that `changed()` fails on the receivers. Once
+/// every receiver is gone the thread stops at the next value.
+#[cfg(feature = "tokio")]
+pub fn to_watch<R>(r: R, tx: tokio::sync::watch::Sender<R::Item>) -> Bridge
+where
+    R: Reader + Send + 'static,
+    R::Item: Clone + Send + Sync + 'static,
+{
+    let thread = thread::spawn(move || pump(r, |value| tx.send(value).is_ok()));
+    Bridge { thread }
+}
+
+/// Writes the current value of `rx` to `w`, then a clone of every value it changes to, until the
+/// sender is gone, which closes `w`, or the reader of `w` is, which is noticed at the next change.
+///
+/// This is a future to spawn on the runtime of the sender, instead of a thread.
+#[cfg(feature = "tokio")]
+pub async fn from_watch<T, W>(mut rx: tokio::sync::watch::Receiver<T>, mut w: W)
+where
+    T: Clone,
+    W: Writer<Item = T>,
+{
+    loop {
+        if !w.is_reader_alive() {
+            return;
+        }
+        let value = rx.borrow_and_update().clone();
+        w.write(value);
+        if rx.changed().await.is_err() {
+            break;
+        }
+    }
+    w.close();
+}
+
+/// Body of the threads sending to `mpsc` or `watch`: hands a clone of every value read to `send`,
+/// until the writer is gone or `send` fails.
+fn pump<R>(mut r: R, mut send: impl FnMut(R::Item) -> bool)
+where
+    R: Reader,
+    R::Item: Clone,
+{
+    let mut idle = 0;
+    loop {
+        match r.read() {
+            Ok(guard) => {
+                idle = 0;
+                let value = (*guard).clone();
+                drop(guard);
+                if !send(value) {
+                    return;
+                }
+            }
+            Err(TryReadError::Empty) => {
+                backoff::idle(idle);
+                idle += 1;
+            }
+            Err(TryReadError::Closed | TryReadError::Disconnected | TryReadError::Poisoned) => {
+                return
+            }
+        }
+    }
+}
@@ -0,0 +1,455 @@
+use crate::atomic::{AtomicBool, AtomicUsize, Ordering};
+use crate::backoff;
+use crate::debug::DebugState;
+use crate::padded::CachePadded;
+use crate::shared::Shared;
+#[cfg(feature = "tokio")]
+use crate::waiter::Waiter;
+
+use std::cell::UnsafeCell;
+use std::io;
+use std::sync::Arc;
+
+/// Bounded byte pipe: the writer copies bytes in at `tail`, the reader copies them out at `head`,
+/// and each side only waits when there is no room or nothing to read.
+///
+/// The counters wrap around at `usize::MAX`, so the buffer is rounded up to a power of two bytes
+/// for every count to keep mapping to the same offset across the wrap; at most `capacity` of them
+/// are used at a time.
+struct Inner {
+    buffer: Box<[UnsafeCell<u8>]>,
+    // number of bytes the pipe holds when full, at most `buffer.len()`
+    capacity: usize,
+    // number of bytes read so far, only modified by the reader
+    head: CachePadded<AtomicUsize>,
+    // number of bytes written so far, only modified by the writer
+    tail: CachePadded<AtomicUsize>,
+    writer_alive: AtomicBool,
+    reader_alive: AtomicBool,
+    // wakes up a reader task waiting for bytes
+    #[cfg(feature = "tokio")]
+    readable: Waiter,
+    // wakes up a writer task waiting for room
+    #[cfg(feature = "tokio")]
+    writable: Waiter,
+}
+
+/// Safety: bytes between `head` and `tail` belong to the reader, the others to the writer, and
+/// each side only moves its own counter.
+unsafe impl Sync for Inner {}
+
+/// Consumer side of the pipe, see [`std::io::Read`].
+pub struct ReadHandle<'a> {
+    inner: Shared<'a, Inner>,
+}
+
+/// Producer side of the pipe, see [`std::io::Write`].
+pub struct WriteHandle<'a> {
+    inner: Shared<'a, Inner>,
+}
+
+impl Inner {
+    fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must not be zero");
+        Inner {
+            buffer: (0..capacity.next_power_of_two())
+                .map(|_| UnsafeCell::new(0))
+                .collect(),
+            capacity,
+            head: CachePadded::new(AtomicUsize::new(0)),
+            tail: CachePadded::new(AtomicUsize::new(0)),
+            writer_alive: AtomicBool::new(true),
+            reader_alive: AtomicBool::new(true),
+            #[cfg(feature = "tokio")]
+            readable: Waiter::new(),
+            #[cfg(feature = "tokio")]
+            writable: Waiter::new(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        let tail = self.tail.load(Ordering::Acquire);
+        tail.wrapping_sub(self.head.load(Ordering::Acquire))
+    }
+
+    /// Returns the two contiguous parts of the `len` bytes starting at `count`, wrapping around the
+    /// end of the buffer.
+    fn chunks(&self, count: usize, len: usize) -> [(*mut u8, usize); 2] {
+        let start = count & (self.buffer.len() - 1);
+        let first = len.min(self.buffer.len() - start);
+        let base = UnsafeCell::raw_get(self.buffer.as_ptr());
+        unsafe { [(base.add(start), first), (base, len - first)] }
+    }
+
+    /// Copies as many bytes of `buf` as fit, returning how many.
+    ///
+    /// This method is wait-free.
+    fn try_write(&self, buf: &[u8]) -> usize {
+        let tail = self.tail.load(Ordering::Relaxed);
+        let used = tail.wrapping_sub(self.head.load(Ordering::Acquire));
+        let n = buf.len().min(self.capacity - used);
+        let mut src = buf.as_ptr();
+        for (dst, len) in self.chunks(tail, n) {
+            unsafe {
+                std::ptr::copy_nonoverlapping(src, dst, len);
+                src = src.add(len);
+            }
+        }
+        self.tail.store(tail.wrapping_add(n), Ordering::Release);
+        #[cfg(feature = "tokio")]
+        if n > 0 {
+            self.readable.notify();
+        }
+        n
+    }
+
+    /// Copies as many pending bytes as fit in `buf`, returning how many.
+    ///
+    /// This method is wait-free.
+    fn try_read(&self, buf: &mut [u8]) -> usize {
+        let head = self.head.load(Ordering::Relaxed);
+        let pending = self.tail.load(Ordering::Acquire).wrapping_sub(head);
+        let n = buf.len().min(pending);
+        let mut dst = buf.as_mut_ptr();
+        for (src, len) in self.chunks(head, n) {
+            unsafe {
+                std::ptr::copy_nonoverlapping(src, dst, len);
+                dst = dst.add(len);
+            }
+        }
+        self.head.store(head.wrapping_add(n), Ordering::Release);
+        #[cfg(feature = "tokio")]
+        if n > 0 {
+            self.writable.notify();
+        }
+        n
+    }
+}
+
+/// Spins on `f` until it returns something, see [`backoff::snooze`].
+fn spin<R>(mut f: impl FnMut() -> Option<R>) -> R {
+    for i in 0.. {
+        if let Some(res) = f() {
+            return res;
+        }
+        backoff::snooze(i);
+    }
+    unreachable!()
+}
+
+impl ReadHandle<'_> {
+    /// Copies as many pending bytes as fit in `buf` without waiting, returning how many.
+    pub fn try_read(&mut self, buf: &mut [u8]) -> usize {
+        self.inner.try_read(buf)
+    }
+
+    /// Returns how many bytes are waiting to be read.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns whether no byte is waiting to be read.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns how many bytes the pipe can hold.
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity
+    }
+}
+
+impl WriteHandle<'_> {
+    /// Copies as many bytes of `buf` as fit without waiting, returning how many.
+    pub fn try_write(&mut self, buf: &[u8]) -> usize {
+        self.inner.try_write(buf)
+    }
+
+    /// Returns whether the reader still exists, so that producers can stop once nobody listens.
+    pub fn is_reader_alive(&self) -> bool {
+        self.inner.reader_alive.load(Ordering::Acquire)
+    }
+
+    /// Returns how many bytes the pipe can hold.
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity
+    }
+}
+
+impl io::Read for ReadHandle<'_> {
+    /// Waits for at least one byte and copies as many as fit in `buf`.
+    ///
+    /// Returns `Ok(0)` once the writer is gone and every byte was read.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        Ok(spin(|| match self.inner.try_read(buf) {
+            0 if self.inner.writer_alive.load(Ordering::Acquire) => None,
+            // the writer may have written right before being dropped
+            0 => Some(self.inner.try_read(buf)),
+            n => Some(n),
+        }))
+    }
+}
+
+impl io::Write for WriteHandle<'_> {
+    /// Waits for room for at least one byte and copies as many of `buf` as fit.
+    ///
+    /// Fails with [`io::ErrorKind::BrokenPipe`] once the reader is gone.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        spin(|| {
+            if !self.is_reader_alive() {
+                return Some(Err(io::ErrorKind::BrokenPipe.into()));
+            }
+            match self.inner.try_write(buf) {
+                0 => None,
+                n => Some(Ok(n)),
+            }
+        })
+    }
+
+    /// Written bytes are readable right away, there is nothing to flush.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Consumer side of the pipe for async code, see [`tokio::io::AsyncRead`].
+///
+/// This is a separate type so that the methods of [`std::io::Read`] and `AsyncReadExt` never
+/// clash. It derefs to the [`ReadHandle`] it wraps.
+#[cfg(feature = "tokio")]
+pub struct AsyncReadHandle<'a>(ReadHandle<'a>);
+
+/// Producer side of the pipe for async code, see [`tokio::io::AsyncWrite`].
+///
+/// It derefs to the [`WriteHandle`] it wraps.
+#[cfg(feature = "tokio")]
+pub struct AsyncWriteHandle<'a>(WriteHandle<'a>);
+
+#[cfg(feature = "tokio")]
+impl<'a> ReadHandle<'a> {
+    /// Turns the handle into one implementing [`tokio::io::AsyncRead`].
+    pub fn into_async(self) -> AsyncReadHandle<'a> {
+        AsyncReadHandle(self)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<'a> WriteHandle<'a> {
+    /// Turns the handle into one implementing [`tokio::io::AsyncWrite`].
+    pub fn into_async(self) -> AsyncWriteHandle<'a> {
+        AsyncWriteHandle(self)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<'a> AsyncReadHandle<'a> {
+    /// Turns the handle back into one implementing [`std::io::Read`].
+    pub fn into_sync(self) -> ReadHandle<'a> {
+        self.0
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<'a> AsyncWriteHandle<'a> {
+    /// Turns the handle back into one implementing [`std::io::Write`].
+    pub fn into_sync(self) -> WriteHandle<'a> {
+        self.0
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<'a> std::ops::Deref for AsyncReadHandle<'a> {
+    type Target = ReadHandle<'a>;
+
+    fn deref(&self) -> &ReadHandle<'a> {
+        &self.0
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<'a> std::ops::Deref for AsyncWriteHandle<'a> {
+    type Target = WriteHandle<'a>;
+
+    fn deref(&self) -> &WriteHandle<'a> {
+        &self.0
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl tokio::io::AsyncRead for AsyncReadHandle<'_> {
+    /// Copies as many pending bytes as fit in `buf`, waiting for at least one.
+    ///
+    /// Reads nothing once the writer is gone (or shut down) and every byte was read.
+    fn poll_read(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> std::task::Poll<io::Result<()>> {
+        if buf.remaining() == 0 {
+            return std::task::Poll::Ready(Ok(()));
+        }
+        let inner = &*self.0.inner;
+        loop {
+            let n = match inner.try_read(buf.initialize_unfilled()) {
+                0 if inner.writer_alive.load(Ordering::Acquire) => 0,
+                // the writer may have written right before leaving
+                0 => inner.try_read(buf.initialize_unfilled()),
+                n => n,
+            };
+            if n > 0 || !inner.writer_alive.load(Ordering::Acquire) {
+                buf.advance(n);
+                return std::task::Poll::Ready(Ok(()));
+            }
+            let ready = || inner.len() > 0 || !inner.writer_alive.load(Ordering::Acquire);
+            std::task::ready!(inner.readable.poll_wait(cx.waker(), ready));
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl tokio::io::AsyncWrite for AsyncWriteHandle<'_> {
+    /// Copies as many bytes of `buf` as fit, waiting for room for at least one.
+    ///
+    /// Fails with [`io::ErrorKind::BrokenPipe`] once the reader is gone, or after a shutdown.
+    fn poll_write(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> std::task::Poll<io::Result<usize>> {
+        if buf.is_empty() {
+            return std::task::Poll::Ready(Ok(0));
+        }
+        let inner = &*self.0.inner;
+        let broken = || {
+            !inner.reader_alive.load(Ordering::Acquire)
+                || !inner.writer_alive.load(Ordering::Relaxed)
+        };
+        loop {
+            if broken() {
+                return std::task::Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+            }
+            match inner.try_write(buf) {
+                0 => {}
+                n => return std::task::Poll::Ready(Ok(n)),
+            }
+            let ready = || inner.len() < inner.buffer.len() || broken();
+            std::task::ready!(inner.writable.poll_wait(cx.waker(), ready));
+        }
+    }
+
+    /// Written bytes are readable right away, there is nothing to flush.
+    fn poll_flush(
+        self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<io::Result<()>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+
+    /// Ends the stream: the reader reads what was already written, then reaches the end of the
+    /// stream as if the writer was dropped.
+    fn poll_shutdown(
+        self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<io::Result<()>> {
+        self.0.inner.writer_alive.store(false, Ordering::Release);
+        self.0.inner.readable.notify();
+        std::task::Poll::Ready(Ok(()))
+    }
+}
+
+impl Drop for ReadHandle<'_> {
+    fn drop(&mut self) {
+        self.inner.reader_alive.store(false, Ordering::Release);
+        #[cfg(feature = "tokio")]
+        self.inner.writable.notify();
+    }
+}
+
+impl Drop for WriteHandle<'_> {
+    fn drop(&mut self) {
+        self.inner.writer_alive.store(false, Ordering::Release);
+        #[cfg(feature = "tokio")]
+        self.inner.readable.notify();
+    }
+}
+
+impl DebugState for Inner {
+    fn debug_state(&self, d: &mut std::fmt::DebugStruct<'_, '_>) {
+        d.field(
+            "len",
+            &self
+                .tail
+                .load(Ordering::Relaxed)
+                .wrapping_sub(self.head.load(Ordering::Relaxed)),
+        )
+        .field("capacity", &self.capacity)
+        .field("writer_alive", &self.writer_alive.load(Ordering::Relaxed))
+        .field("reader_alive", &self.reader_alive.load(Ordering::Relaxed));
+    }
+}
+
+impl std::fmt::Debug for ReadHandle<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "byte_spsc::ReadHandle")
+    }
+}
+
+impl std::fmt::Debug for WriteHandle<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "byte_spsc::WriteHandle")
+    }
+}
+
+/// Construct a new read and write handle pair for a pipe holding up to `capacity` bytes.
+/// Room is allocated for `capacity` rounded up to a power of two.
+///
+/// Panics if `capacity` is zero.
+pub fn new(capacity: usize) -> (ReadHandle<'static>, WriteHandle<'static>) {
+    let inner = Arc::new(Inner::new(capacity));
+    let r = ReadHandle {
+        inner: Shared::Arc(Arc::clone(&inner)),
+    };
+    let w = WriteHandle {
+        inner: Shared::Arc(inner),
+    };
+    (r, w)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counter_wrap() {
+        // Test a pipe whose counters wrap around `usize::MAX`, with a capacity that does not
+        // divide the number of counts: bytes should come out as they went in
+
+        let inner = Inner::new(5);
+        inner.head.store(usize::MAX - 7, Ordering::Relaxed);
+        inner.tail.store(usize::MAX - 7, Ordering::Relaxed);
+        let input: Vec<u8> = (0..64).collect();
+        let mut output = Vec::new();
+        let mut written = 0;
+        // bytes are written 3 at a time and read 2 at a time, so that the two sides split them
+        // differently across the wrap
+        while output.len() < input.len() {
+            let end = input.len().min(written + 3);
+            written += inner.try_write(&input[written..end]);
+            let mut buf = [0; 2];
+            let n = inner.try_read(&mut buf);
+            output.extend_from_slice(&buf[..n]);
+        }
+        assert_eq!(output, input);
+
+        assert_eq!(inner.try_write(&input), 5, "Pipe should have been full");
+        let mut buf = [0; 8];
+        assert_eq!(inner.try_read(&mut buf), 5);
+        assert_eq!(buf[..5], input[..5]);
+    }
+}
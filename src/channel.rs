@@ -0,0 +1,397 @@
+#[cfg(any(target_os = "linux", windows))]
+use crate::futex_spsc;
+use crate::{
+    arc_spsc, atomic_spsc, blocking_spsc, box_spsc, disruptor, mutex_spsc, ring_spsc, ticket_spsc,
+    watch,
+};
+use crate::{Reader, TryReadError, WriteError, Writer};
+
+#[cfg(not(target_family = "wasm"))]
+use std::time::Duration;
+
+/// Variant picked by a [`ChannelBuilder`], named after the module implementing it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kind {
+    Arc,
+    Atomic,
+    Blocking,
+    Box,
+    /// Needs a [`ChannelBuilder::pool_size`], the capacity of the ring.
+    Disruptor,
+    #[cfg(any(target_os = "linux", windows))]
+    Futex,
+    Mutex,
+    /// Needs a [`ChannelBuilder::pool_size`], the capacity of the queue.
+    Ring,
+    Ticket,
+    Watch,
+}
+
+impl Kind {
+    /// Returns whether the read handles of this kind can block until a value is published.
+    pub fn has_blocking_reads(self) -> bool {
+        #[cfg(any(target_os = "linux", windows))]
+        if self == Kind::Futex {
+            return true;
+        }
+        matches!(
+            self,
+            Kind::Atomic | Kind::Blocking | Kind::Mutex | Kind::Ticket
+        )
+    }
+
+    /// Returns whether this kind takes its pool size at runtime, instead of having a fixed one.
+    pub fn has_pool_size(self) -> bool {
+        matches!(self, Kind::Disruptor | Kind::Ring)
+    }
+}
+
+/// Error returned by [`ChannelBuilder::build`] for options the chosen kind does not support.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildError {
+    /// A pool size was set for a kind with a fixed pool, or none (or zero) for a kind that needs
+    /// one, see [`Kind::has_pool_size`].
+    PoolSize(Kind),
+    /// Blocking reads were asked of a kind without them, see [`Kind::has_blocking_reads`].
+    BlockingReads(Kind),
+}
+
+impl std::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildError::PoolSize(kind) if kind.has_pool_size() => {
+                write!(f, "{kind:?} needs a pool size")
+            }
+            BuildError::PoolSize(kind) => write!(f, "{kind:?} has a fixed pool size"),
+            BuildError::BlockingReads(kind) => write!(f, "{kind:?} has no blocking reads"),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// Entry point of [`ChannelBuilder`], for code picking the variant at runtime.
+pub struct Channel;
+
+impl Channel {
+    /// Starts building a channel of [`Kind::Atomic`].
+    pub fn builder() -> ChannelBuilder {
+        ChannelBuilder {
+            kind: Kind::Atomic,
+            pool_size: None,
+            blocking_reads: false,
+        }
+    }
+}
+
+/// Options of a channel, turned into a pair of [`ReadHandle`] and [`WriteHandle`] by
+/// [`ChannelBuilder::build`].
+#[derive(Debug, Clone, Copy)]
+pub struct ChannelBuilder {
+    kind: Kind,
+    pool_size: Option<usize>,
+    blocking_reads: bool,
+}
+
+impl ChannelBuilder {
+    /// Sets the variant to build.
+    pub fn kind(mut self, kind: Kind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Sets how many values the channel holds, for the kinds that take it at runtime.
+    pub fn pool_size(mut self, pool_size: usize) -> Self {
+        self.pool_size = Some(pool_size);
+        self
+    }
+
+    /// Requires the read handle to support [`ReadHandle::read_blocking`] and
+    /// [`ReadHandle::read_timeout`].
+    pub fn blocking_reads(mut self, blocking_reads: bool) -> Self {
+        self.blocking_reads = blocking_reads;
+        self
+    }
+
+    /// Construct a new, empty read and write handle pair of the chosen kind.
+    ///
+    /// `Mutex` and `Ticket` start from `T::default()`, which is not readable.
+    pub fn build<T: Default>(
+        self,
+    ) -> Result<(ReadHandle<'static, T>, WriteHandle<'static, T>), BuildError> {
+        if self.blocking_reads && !self.kind.has_blocking_reads() {
+            return Err(BuildError::BlockingReads(self.kind));
+        }
+        let capacity = match (self.kind.has_pool_size(), self.pool_size) {
+            (true, Some(capacity)) if capacity > 0 => capacity,
+            (false, None) => 0,
+            _ => return Err(BuildError::PoolSize(self.kind)),
+        };
+        let handles = match self.kind {
+            Kind::Arc => {
+                let (r, w) = arc_spsc::new();
+                (ReadHandle::Arc(r), WriteHandle::Arc(w))
+            }
+            Kind::Atomic => {
+                let (r, w) = atomic_spsc::new();
+                (ReadHandle::Atomic(r), WriteHandle::Atomic(w))
+            }
+            Kind::Blocking => {
+                let (r, w) = blocking_spsc::new();
+                (ReadHandle::Blocking(r), WriteHandle::Blocking(w))
+            }
+            Kind::Box => {
+                let (r, w) = box_spsc::new();
+                (ReadHandle::Box(r), WriteHandle::Box(w))
+            }
+            Kind::Disruptor => {
+                let (r, w) = disruptor::new(capacity);
+                (ReadHandle::Disruptor(r), WriteHandle::Disruptor(w))
+            }
+            #[cfg(any(target_os = "linux", windows))]
+            Kind::Futex => {
+                let (r, w) = futex_spsc::new();
+                (ReadHandle::Futex(r), WriteHandle::Futex(w))
+            }
+            Kind::Mutex => {
+                let (r, w) = mutex_spsc::new(T::default());
+                (ReadHandle::Mutex(r), WriteHandle::Mutex(w))
+            }
+            Kind::Ring => {
+                let (r, w) = ring_spsc::new(capacity);
+                (ReadHandle::Ring(r), WriteHandle::Ring(w))
+            }
+            Kind::Ticket => {
+                let (r, w) = ticket_spsc::new(T::default());
+                (ReadHandle::Ticket(r), WriteHandle::Ticket(w))
+            }
+            Kind::Watch => {
+                let (r, w) = watch::new();
+                (ReadHandle::Watch(r), WriteHandle::Watch(w))
+            }
+        };
+        Ok(handles)
+    }
+}
+
+/// Consumer side of a channel built by [`ChannelBuilder`], forwarding to the handle of its kind.
+pub enum ReadHandle<'a, T> {
+    Arc(arc_spsc::ReadHandle<'a, T>),
+    Atomic(atomic_spsc::ReadHandle<'a, T>),
+    Blocking(blocking_spsc::ReadHandle<'a, T>),
+    Box(box_spsc::ReadHandle<'a, T>),
+    Disruptor(disruptor::ReadHandle<'a, T>),
+    #[cfg(any(target_os = "linux", windows))]
+    Futex(futex_spsc::ReadHandle<'a, T>),
+    Mutex(mutex_spsc::ReadHandle<'a, T>),
+    Ring(ring_spsc::ReadHandle<'a, T>),
+    Ticket(ticket_spsc::ReadHandle<'a, T>),
+    Watch(watch::ReadHandle<'a, T>),
+}
+
+/// Producer side of a channel built by [`ChannelBuilder`], forwarding to the handle of its kind.
+pub enum WriteHandle<'a, T> {
+    Arc(arc_spsc::WriteHandle<'a, T>),
+    Atomic(atomic_spsc::WriteHandle<'a, T>),
+    Blocking(blocking_spsc::WriteHandle<'a, T>),
+    Box(box_spsc::WriteHandle<'a, T>),
+    Disruptor(disruptor::WriteHandle<'a, T>),
+    #[cfg(any(target_os = "linux", windows))]
+    Futex(futex_spsc::WriteHandle<'a, T>),
+    Mutex(mutex_spsc::WriteHandle<'a, T>),
+    Ring(ring_spsc::WriteHandle<'a, T>),
+    Ticket(ticket_spsc::WriteHandle<'a, T>),
+    Watch(watch::WriteHandle<'a, T>),
+}
+
+/// Guard returned by the reads of a [`ReadHandle`], the one of its kind.
+pub enum Guard<'a, T> {
+    Arc(arc_spsc::ArcGuard<T>),
+    Atomic(atomic_spsc::AtomicGuard<'a, T>),
+    Blocking(blocking_spsc::BlockingGuard<'a, T>),
+    Box(box_spsc::BoxGuard<T>),
+    Disruptor(disruptor::DisruptorGuard<'a, T>),
+    #[cfg(any(target_os = "linux", windows))]
+    Futex(futex_spsc::FutexGuard<'a, T>),
+    Mutex(std::sync::MutexGuard<'a, T>),
+    Ring(ring_spsc::RingGuard<T>),
+    Ticket(ticket_spsc::TicketGuard<'a, T>),
+    Watch(watch::WatchGuard<T>),
+}
+
+/// Evaluates `$body` with `$handle` bound to the handle (or guard) inside `$value`, whichever
+/// variant of `$enum` it is.
+macro_rules! dispatch {
+    ($enum:ident, $value:expr, |$handle:ident| $body:expr) => {
+        match $value {
+            $enum::Arc($handle) => $body,
+            $enum::Atomic($handle) => $body,
+            $enum::Blocking($handle) => $body,
+            $enum::Box($handle) => $body,
+            $enum::Disruptor($handle) => $body,
+            #[cfg(any(target_os = "linux", windows))]
+            $enum::Futex($handle) => $body,
+            $enum::Mutex($handle) => $body,
+            $enum::Ring($handle) => $body,
+            $enum::Ticket($handle) => $body,
+            $enum::Watch($handle) => $body,
+        }
+    };
+}
+
+macro_rules! guard_from {
+    ($($(#[$attr:meta])* $variant:ident($guard:ty)),* $(,)?) => {$(
+        $(#[$attr])*
+        impl<'a, T> From<$guard> for Guard<'a, T> {
+            fn from(guard: $guard) -> Self {
+                Guard::$variant(guard)
+            }
+        }
+    )*};
+}
+
+guard_from!(
+    Arc(arc_spsc::ArcGuard<T>),
+    Atomic(atomic_spsc::AtomicGuard<'a, T>),
+    Blocking(blocking_spsc::BlockingGuard<'a, T>),
+    Box(box_spsc::BoxGuard<T>),
+    Disruptor(disruptor::DisruptorGuard<'a, T>),
+    #[cfg(any(target_os = "linux", windows))]
+    Futex(futex_spsc::FutexGuard<'a, T>),
+    Mutex(std::sync::MutexGuard<'a, T>),
+    Ring(ring_spsc::RingGuard<T>),
+    Ticket(ticket_spsc::TicketGuard<'a, T>),
+    Watch(watch::WatchGuard<T>),
+);
+
+impl<T> std::ops::Deref for Guard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        dispatch!(Guard, self, |guard| guard)
+    }
+}
+
+impl<T> ReadHandle<'_, T> {
+    /// Returns the kind of the channel.
+    pub fn kind(&self) -> Kind {
+        match self {
+            ReadHandle::Arc(_) => Kind::Arc,
+            ReadHandle::Atomic(_) => Kind::Atomic,
+            ReadHandle::Blocking(_) => Kind::Blocking,
+            ReadHandle::Box(_) => Kind::Box,
+            ReadHandle::Disruptor(_) => Kind::Disruptor,
+            #[cfg(any(target_os = "linux", windows))]
+            ReadHandle::Futex(_) => Kind::Futex,
+            ReadHandle::Mutex(_) => Kind::Mutex,
+            ReadHandle::Ring(_) => Kind::Ring,
+            ReadHandle::Ticket(_) => Kind::Ticket,
+            ReadHandle::Watch(_) => Kind::Watch,
+        }
+    }
+
+    /// Reads the next written value, blocking until the writer publishes one.
+    /// Fails with [`TryReadError::Closed`] or [`TryReadError::Disconnected`] once the writer is gone
+    /// and its last value was read.
+    ///
+    /// Panics if the kind has no blocking reads, which [`ChannelBuilder::blocking_reads`] rules out.
+    #[cfg(not(target_family = "wasm"))]
+    pub fn read_blocking(&mut self) -> Result<Guard<'_, T>, TryReadError> {
+        match self {
+            ReadHandle::Atomic(r) => r.read_blocking().map(Guard::from),
+            ReadHandle::Blocking(r) => r.read_blocking().map(Guard::from),
+            #[cfg(any(target_os = "linux", windows))]
+            ReadHandle::Futex(r) => r.read_blocking().map(Guard::from),
+            ReadHandle::Mutex(r) => r.read_blocking().map(Guard::from),
+            ReadHandle::Ticket(r) => r.read_blocking().map(Guard::from),
+            _ => panic!("{:?} has no blocking reads", self.kind()),
+        }
+    }
+
+    /// Reads the next written value, blocking for at most `timeout`.
+    /// Fails with [`TryReadError::Empty`] if nothing was published in the meantime.
+    ///
+    /// Panics if the kind has no blocking reads, which [`ChannelBuilder::blocking_reads`] rules out.
+    #[cfg(not(target_family = "wasm"))]
+    pub fn read_timeout(&mut self, timeout: Duration) -> Result<Guard<'_, T>, TryReadError> {
+        match self {
+            ReadHandle::Atomic(r) => r.read_timeout(timeout).map(Guard::from),
+            ReadHandle::Blocking(r) => r.read_timeout(timeout).map(Guard::from),
+            #[cfg(any(target_os = "linux", windows))]
+            ReadHandle::Futex(r) => r.read_timeout(timeout).map(Guard::from),
+            ReadHandle::Mutex(r) => r.read_timeout(timeout).map(Guard::from),
+            ReadHandle::Ticket(r) => r.read_timeout(timeout).map(Guard::from),
+            _ => panic!("{:?} has no blocking reads", self.kind()),
+        }
+    }
+}
+
+impl<T> WriteHandle<'_, T> {
+    /// Returns the kind of the channel.
+    pub fn kind(&self) -> Kind {
+        match self {
+            WriteHandle::Arc(_) => Kind::Arc,
+            WriteHandle::Atomic(_) => Kind::Atomic,
+            WriteHandle::Blocking(_) => Kind::Blocking,
+            WriteHandle::Box(_) => Kind::Box,
+            WriteHandle::Disruptor(_) => Kind::Disruptor,
+            #[cfg(any(target_os = "linux", windows))]
+            WriteHandle::Futex(_) => Kind::Futex,
+            WriteHandle::Mutex(_) => Kind::Mutex,
+            WriteHandle::Ring(_) => Kind::Ring,
+            WriteHandle::Ticket(_) => Kind::Ticket,
+            WriteHandle::Watch(_) => Kind::Watch,
+        }
+    }
+}
+
+impl<T> Reader for ReadHandle<'_, T> {
+    type Item = T;
+    type Guard<'a>
+        = Guard<'a, T>
+    where
+        Self: 'a;
+
+    fn read(&mut self) -> Result<Self::Guard<'_>, TryReadError> {
+        dispatch!(ReadHandle, self, |r| r.read().map(Guard::from))
+    }
+}
+
+impl<T> Writer for WriteHandle<'_, T> {
+    type Item = T;
+
+    fn write(&mut self, value: T) {
+        dispatch!(WriteHandle, self, |w| w.write(value))
+    }
+
+    fn write_with(&mut self, f: impl FnOnce(&mut T))
+    where
+        T: Default,
+    {
+        dispatch!(WriteHandle, self, |w| w.write_with(f))
+    }
+
+    fn close(self) {
+        dispatch!(WriteHandle, self, |w| w.close())
+    }
+
+    fn is_reader_alive(&self) -> bool {
+        dispatch!(WriteHandle, self, |w| w.is_reader_alive())
+    }
+
+    fn try_write(&mut self, value: T) -> Result<(), WriteError<T>> {
+        dispatch!(WriteHandle, self, |w| w.try_write(value))
+    }
+}
+
+impl<T> std::fmt::Debug for ReadHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        dispatch!(ReadHandle, self, |r| r.fmt(f))
+    }
+}
+
+impl<T> std::fmt::Debug for WriteHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        dispatch!(WriteHandle, self, |w| w.fmt(f))
+    }
+}
@@ -0,0 +1,203 @@
+use crate::atomic::{AtomicBool, AtomicU64, Ordering};
+use crate::debug::DebugState;
+use crate::shared::Shared;
+use crate::TryReadError;
+
+use std::collections::hash_map::{Drain, HashMap};
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+
+/// Latest value per key: the writer inserts updates in `pending`, replacing any value of the same
+/// key that was not read yet, and the reader swaps the whole map out with its own empty one.
+///
+/// Maps only ever change hands, so both sides keep reusing the same two allocations.
+struct Inner<K, V> {
+    pending: Mutex<HashMap<K, V>>,
+    // set while `pending` is not empty, only modified while holding it
+    dirty: AtomicBool,
+    // number of values replaced before being read, only modified by the writer
+    overwritten: AtomicU64,
+    writer_alive: AtomicBool,
+    // set right before `writer_alive` is cleared if the writer left through `close`
+    closed: AtomicBool,
+    reader_alive: AtomicBool,
+}
+
+/// Consumer side of the map.
+///
+/// This is not a [`crate::Reader`]: every read yields one value per updated key instead of a
+/// single value.
+pub struct ReadHandle<'a, K, V> {
+    inner: Shared<'a, Inner<K, V>>,
+    // empty between drains, swapped with `pending` on the next one
+    drained: HashMap<K, V>,
+}
+
+/// Producer side of the map.
+pub struct WriteHandle<'a, K, V> {
+    inner: Shared<'a, Inner<K, V>>,
+}
+
+impl<K, V> Inner<K, V> {
+    fn new() -> Self {
+        Inner {
+            pending: Mutex::new(HashMap::new()),
+            dirty: AtomicBool::new(false),
+            overwritten: AtomicU64::new(0),
+            writer_alive: AtomicBool::new(true),
+            closed: AtomicBool::new(false),
+            reader_alive: AtomicBool::new(true),
+        }
+    }
+
+    /// Error reported once the writer is gone and nothing is left to read.
+    fn disconnected(&self) -> TryReadError {
+        if self.closed.load(Ordering::Relaxed) {
+            TryReadError::Closed
+        } else {
+            TryReadError::Disconnected
+        }
+    }
+}
+
+impl<K, V> ReadHandle<'_, K, V>
+where
+    K: Eq + Hash,
+{
+    /// Takes the latest value of every key updated since the last drain, in no particular order.
+    ///
+    /// The map is only locked when something was published, for as long as it takes to swap it.
+    pub fn drain(&mut self) -> Drain<'_, K, V> {
+        if self.inner.dirty.load(Ordering::Acquire) {
+            let mut pending = self.inner.pending.lock().unwrap();
+            std::mem::swap(&mut *pending, &mut self.drained);
+            self.inner.dirty.store(false, Ordering::Relaxed);
+        }
+        self.drained.drain()
+    }
+
+    /// Takes the latest value of every key updated since the last drain, telling apart an empty
+    /// map from a dead one.
+    ///
+    /// Returns [`TryReadError::Closed`] (or [`TryReadError::Disconnected`] if the writer was dropped
+    /// without closing) once the writer is gone and every update was drained.
+    pub fn try_drain(&mut self) -> Result<Drain<'_, K, V>, TryReadError> {
+        if !self.inner.dirty.load(Ordering::Acquire) {
+            if self.inner.writer_alive.load(Ordering::Acquire) {
+                return Err(TryReadError::Empty);
+            }
+            // the writer may have published right before being dropped
+            if !self.inner.dirty.load(Ordering::Acquire) {
+                return Err(self.inner.disconnected());
+            }
+        }
+        Ok(self.drain())
+    }
+
+    /// Returns whether some key was updated since the last drain.
+    pub fn has_pending(&self) -> bool {
+        self.inner.dirty.load(Ordering::Relaxed)
+    }
+
+    /// Returns how many updates replaced a value of the same key before it could be drained.
+    pub fn overwritten(&self) -> u64 {
+        self.inner.overwritten.load(Ordering::Relaxed)
+    }
+}
+
+impl<K, V> WriteHandle<'_, K, V>
+where
+    K: Eq + Hash,
+{
+    /// Publishes `value` as the latest value of `key`, replacing the pending one if the reader did
+    /// not drain it yet.
+    pub fn publish(&self, key: K, value: V) {
+        let mut pending = self.inner.pending.lock().unwrap();
+        if pending.insert(key, value).is_some() {
+            self.inner.overwritten.fetch_add(1, Ordering::Relaxed);
+        }
+        self.inner.dirty.store(true, Ordering::Release);
+    }
+
+    /// Publishes every update of `updates` at once, locking the map a single time.
+    pub fn publish_all(&self, updates: impl IntoIterator<Item = (K, V)>) {
+        let mut pending = self.inner.pending.lock().unwrap();
+        let mut published = false;
+        for (key, value) in updates {
+            if pending.insert(key, value).is_some() {
+                self.inner.overwritten.fetch_add(1, Ordering::Relaxed);
+            }
+            published = true;
+        }
+        if published {
+            self.inner.dirty.store(true, Ordering::Release);
+        }
+    }
+
+    /// Returns how many updates replaced a value of the same key before the reader could drain it.
+    pub fn overwritten(&self) -> u64 {
+        self.inner.overwritten.load(Ordering::Relaxed)
+    }
+
+    /// Marks the map as finished: the reader can still drain pending updates, then every drain
+    /// fails with [`TryReadError::Closed`].
+    pub fn close(self) {
+        self.inner.closed.store(true, Ordering::Relaxed);
+        // dropping `self` disconnects the reader
+    }
+
+    /// Returns whether the reader still exists, so that producers can stop once nobody listens.
+    pub fn is_reader_alive(&self) -> bool {
+        self.inner.reader_alive.load(Ordering::Acquire)
+    }
+}
+
+impl<K, V> Drop for ReadHandle<'_, K, V> {
+    fn drop(&mut self) {
+        self.inner.reader_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<K, V> Drop for WriteHandle<'_, K, V> {
+    fn drop(&mut self) {
+        self.inner.writer_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<K, V> DebugState for Inner<K, V> {
+    fn debug_state(&self, d: &mut std::fmt::DebugStruct<'_, '_>) {
+        d.field("pending", &self.dirty.load(Ordering::Relaxed))
+            .field("overwritten", &self.overwritten.load(Ordering::Relaxed))
+            .field("writer_alive", &self.writer_alive.load(Ordering::Relaxed))
+            .field("closed", &self.closed.load(Ordering::Relaxed))
+            .field("reader_alive", &self.reader_alive.load(Ordering::Relaxed));
+    }
+}
+
+impl<K, V> std::fmt::Debug for ReadHandle<'_, K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "conflating_map::ReadHandle")
+    }
+}
+
+impl<K, V> std::fmt::Debug for WriteHandle<'_, K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "conflating_map::WriteHandle")
+    }
+}
+
+/// Construct a new, empty read and write handle pair.
+pub fn new<K, V>() -> (ReadHandle<'static, K, V>, WriteHandle<'static, K, V>)
+where
+    K: Eq + Hash,
+{
+    let inner = Arc::new(Inner::new());
+    let r = ReadHandle {
+        inner: Shared::Arc(Arc::clone(&inner)),
+        drained: HashMap::new(),
+    };
+    let w = WriteHandle {
+        inner: Shared::Arc(inner),
+    };
+    (r, w)
+}
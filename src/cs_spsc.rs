@@ -0,0 +1,301 @@
+use crate::debug::DebugState;
+use crate::shared::Shared;
+use crate::{Reader, TryReadError, Writer};
+
+use critical_section::Mutex;
+
+use core::cell::RefCell;
+#[cfg(feature = "std")]
+use std::sync::Arc;
+
+/// Everything the handles share, only ever accessed inside a critical section.
+struct State<T> {
+    // the last written value, until the reader takes it
+    value: Option<T>,
+    // number of values replaced before being read
+    overwritten: u64,
+    writer_alive: bool,
+    // set right before `writer_alive` is cleared if the writer left through `close`
+    closed: bool,
+    reader_alive: bool,
+}
+
+/// Single slot guarded by the critical sections of the `critical-section` crate instead of
+/// atomics: on a single-core microcontroller masking interrupts for the few instructions of a
+/// move is cheaper than any atomic read-modify-write, and works on cores that have none.
+///
+/// The value is moved in and out of the slot, so nothing ever runs inside a critical section but
+/// the move itself.
+struct Inner<T> {
+    state: Mutex<RefCell<State<T>>>,
+}
+
+pub struct ReadHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+}
+
+pub struct WriteHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+}
+
+impl<T> Inner<T> {
+    const fn new() -> Self {
+        Inner {
+            state: Mutex::new(RefCell::new(State {
+                value: None,
+                overwritten: 0,
+                writer_alive: true,
+                closed: false,
+                reader_alive: true,
+            })),
+        }
+    }
+
+    fn with<R>(&self, f: impl FnOnce(&mut State<T>) -> R) -> R {
+        critical_section::with(|cs| f(&mut self.state.borrow_ref_mut(cs)))
+    }
+
+    fn write(&self, value: T) {
+        let old = self.with(|state| {
+            let old = state.value.replace(value);
+            if old.is_some() {
+                state.overwritten += 1;
+            }
+            old
+        });
+        // dropped outside of the critical section
+        drop(old);
+    }
+
+    fn try_read(&self) -> Result<CsGuard<T>, TryReadError> {
+        self.with(|state| match state.value.take() {
+            Some(value) => Ok(CsGuard(value)),
+            None if state.writer_alive => Err(TryReadError::Empty),
+            None if state.closed => Err(TryReadError::Closed),
+            None => Err(TryReadError::Disconnected),
+        })
+    }
+}
+
+/// Read value, moved out of the channel.
+#[derive(Debug, Clone, Copy)]
+pub struct CsGuard<T>(T);
+
+impl<T> CsGuard<T> {
+    /// Returns the read value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> core::ops::Deref for CsGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> ReadHandle<'_, T> {
+    /// Returns how many written values were replaced before they could be read.
+    pub fn overwritten(&self) -> u64 {
+        self.inner.with(|state| state.overwritten)
+    }
+}
+
+impl<T> WriteHandle<'_, T> {
+    /// Returns how many written values were replaced before the reader could read them.
+    pub fn overwritten(&self) -> u64 {
+        self.inner.with(|state| state.overwritten)
+    }
+}
+
+impl<T> Reader for ReadHandle<'_, T> {
+    type Item = T;
+    type Guard<'a>
+        = CsGuard<T>
+    where
+        Self: 'a;
+
+    fn read(&mut self) -> Result<Self::Guard<'_>, TryReadError> {
+        self.inner.try_read()
+    }
+}
+
+impl<T> Writer for WriteHandle<'_, T> {
+    type Item = T;
+
+    fn write(&mut self, value: T) {
+        self.inner.write(value)
+    }
+
+    /// Publishes a value built by `f` from the value not read yet, if any, or `T::default()`.
+    ///
+    /// `f` runs outside of the critical section, on a value taken out of the slot.
+    fn write_with(&mut self, f: impl FnOnce(&mut T))
+    where
+        T: Default,
+    {
+        let unread = self.inner.with(|state| {
+            let unread = state.value.take();
+            if unread.is_some() {
+                state.overwritten += 1;
+            }
+            unread
+        });
+        let mut value = unread.unwrap_or_default();
+        f(&mut value);
+        self.inner.write(value);
+    }
+
+    fn close(self) {
+        self.inner.with(|state| state.closed = true);
+        // dropping `self` disconnects the reader
+    }
+
+    fn is_reader_alive(&self) -> bool {
+        self.inner.with(|state| state.reader_alive)
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<T> defmt::Format for ReadHandle<'_, T> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        let (overwritten, writer_alive) = self
+            .inner
+            .with(|state| (state.overwritten, state.writer_alive));
+        defmt::write!(
+            f,
+            "ReadHandle {{ overwritten: {=u64}, writer_alive: {=bool} }}",
+            overwritten,
+            writer_alive,
+        )
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<T> defmt::Format for WriteHandle<'_, T> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        let (overwritten, reader_alive) = self
+            .inner
+            .with(|state| (state.overwritten, state.reader_alive));
+        defmt::write!(
+            f,
+            "WriteHandle {{ overwritten: {=u64}, reader_alive: {=bool} }}",
+            overwritten,
+            reader_alive,
+        )
+    }
+}
+
+impl<T> Drop for ReadHandle<'_, T> {
+    fn drop(&mut self) {
+        let unread = self.inner.with(|state| {
+            state.reader_alive = false;
+            state.value.take()
+        });
+        drop(unread);
+    }
+}
+
+impl<T> Drop for WriteHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.with(|state| state.writer_alive = false);
+    }
+}
+
+impl<T> DebugState for Inner<T> {
+    fn debug_state(&self, d: &mut core::fmt::DebugStruct<'_, '_>) {
+        critical_section::with(|cs| {
+            let state = self.state.borrow_ref(cs);
+            d.field("pending", &state.value.is_some())
+                .field("overwritten", &state.overwritten)
+                .field("writer_alive", &state.writer_alive)
+                .field("closed", &state.closed)
+                .field("reader_alive", &state.reader_alive);
+        });
+    }
+}
+
+impl<T> core::fmt::Debug for ReadHandle<'_, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.inner.fmt_handle(f, "cs_spsc::ReadHandle")
+    }
+}
+
+impl<T> core::fmt::Debug for WriteHandle<'_, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.inner.fmt_handle(f, "cs_spsc::WriteHandle")
+    }
+}
+
+/// Construct a new, empty read and write handle pair.
+///
+/// Reads fail with [`TryReadError::Empty`] until the first write. Without `std`, use a [`Storage`] instead.
+#[cfg(feature = "std")]
+pub fn new<T>() -> (ReadHandle<'static, T>, WriteHandle<'static, T>) {
+    let inner = Arc::new(Inner::new());
+    let r = ReadHandle {
+        inner: Shared::Arc(Arc::clone(&inner)),
+    };
+    let w = WriteHandle {
+        inner: Shared::Arc(inner),
+    };
+    (r, w)
+}
+
+/// This variant as a [`crate::Channel`], for code generic over the variant.
+#[cfg(feature = "std")]
+pub struct Channel;
+
+#[cfg(feature = "std")]
+impl<T: 'static> crate::Channel<T> for Channel {
+    type Reader = ReadHandle<'static, T>;
+    type Writer = WriteHandle<'static, T>;
+
+    fn create() -> (Self::Reader, Self::Writer) {
+        new()
+    }
+}
+
+/// Storage for a channel that is not reference counted, so that no allocation is needed.
+///
+/// On a microcontroller it is typically placed in a `static` shared by the main loop and an
+/// interrupt handler: the handles returned by [`Storage::split`] borrow it, and are handed out
+/// only once.
+pub struct Storage<T> {
+    inner: Inner<T>,
+    split: Mutex<RefCell<bool>>,
+}
+
+impl<T> Storage<T> {
+    /// Constructs a new, empty storage.
+    pub const fn new() -> Self {
+        Storage {
+            inner: Inner::new(),
+            split: Mutex::new(RefCell::new(false)),
+        }
+    }
+
+    /// Returns the read and write handles borrowing the storage, or `None` if they were already
+    /// taken.
+    pub fn split(&self) -> Option<(ReadHandle<'_, T>, WriteHandle<'_, T>)> {
+        let split = critical_section::with(|cs| self.split.borrow(cs).replace(true));
+        if split {
+            return None;
+        }
+        let r = ReadHandle {
+            inner: Shared::Borrowed(&self.inner),
+        };
+        let w = WriteHandle {
+            inner: Shared::Borrowed(&self.inner),
+        };
+        Some((r, w))
+    }
+}
+
+impl<T> Default for Storage<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
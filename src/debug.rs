@@ -0,0 +1,19 @@
+//! `Debug` output of the channel handles.
+
+use core::fmt;
+
+/// State of a channel shown in the `Debug` output of its handles.
+///
+/// Both handles print the flags and counters of the channel they share, but never its value: it
+/// need not be `Debug`, and may be leased to the other side at that point.
+pub(crate) trait DebugState {
+    /// Adds the state of the channel to `d`.
+    fn debug_state(&self, d: &mut fmt::DebugStruct<'_, '_>);
+
+    /// Formats a handle of the channel as a struct named `name` holding its state.
+    fn fmt_handle(&self, f: &mut fmt::Formatter<'_>, name: &str) -> fmt::Result {
+        let mut d = f.debug_struct(name);
+        self.debug_state(&mut d);
+        d.finish()
+    }
+}
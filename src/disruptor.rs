@@ -0,0 +1,347 @@
+use crate::atomic::{AtomicBool, AtomicU64, Ordering};
+use crate::backoff;
+use crate::debug::DebugState;
+use crate::padded::CachePadded;
+use crate::shared::Shared;
+use crate::{Reader, TryReadError, WriteError, Writer};
+
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Bounded ring shared by one writer and any number of readers, each reading every value in order
+/// at its own pace.
+///
+/// Every reader publishes a cursor, the number of values it is done with, and the writer never
+/// overwrites a slot some reader has not moved past: the slowest reader holds the writer back.
+struct Inner<T> {
+    slots: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    // number of values written so far, only modified by the writer
+    tail: CachePadded<AtomicU64>,
+    // cursor of every live `ReadHandle`
+    cursors: Mutex<Vec<Arc<CachePadded<AtomicU64>>>>,
+    writer_alive: AtomicBool,
+    // set right before `writer_alive` is cleared if the writer left through `close`
+    closed: AtomicBool,
+}
+
+/// Safety: the writer only writes slots every reader moved past, and readers only read slots
+/// below `tail`.
+unsafe impl<T> Sync for Inner<T> where T: Send + Sync {}
+
+/// Consumer side of the ring, which can be cloned to add more consumers.
+///
+/// Every handle reads every value written after it joined, in order.
+pub struct ReadHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+    cursor: Arc<CachePadded<AtomicU64>>,
+}
+
+pub struct WriteHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+    // lowest cursor when last computed, the writer can write up to `capacity` values past it
+    gate: u64,
+}
+
+impl<T> Inner<T> {
+    fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must not be zero");
+        Inner {
+            slots: (0..capacity)
+                .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+                .collect(),
+            tail: CachePadded::new(AtomicU64::new(0)),
+            cursors: Mutex::new(Vec::new()),
+            writer_alive: AtomicBool::new(true),
+            closed: AtomicBool::new(false),
+        }
+    }
+
+    fn capacity(&self) -> u64 {
+        self.slots.len() as u64
+    }
+
+    fn slot(&self, seq: u64) -> *mut MaybeUninit<T> {
+        self.slots[(seq % self.capacity()) as usize].get()
+    }
+
+    /// Registers a cursor starting at `seq`.
+    fn join(&self, seq: u64) -> Arc<CachePadded<AtomicU64>> {
+        let cursor = Arc::new(CachePadded::new(AtomicU64::new(seq)));
+        self.cursors.lock().unwrap().push(Arc::clone(&cursor));
+        cursor
+    }
+
+    /// Returns the lowest cursor, or `tail` if there is no reader.
+    fn slowest(&self) -> u64 {
+        let cursors = self.cursors.lock().unwrap();
+        cursors
+            .iter()
+            .map(|cursor| cursor.load(Ordering::Acquire))
+            .min()
+            .unwrap_or_else(|| self.tail.load(Ordering::Relaxed))
+    }
+
+    /// Error reported once the writer is gone and nothing is left to read.
+    fn disconnected(&self) -> TryReadError {
+        if self.closed.load(Ordering::Relaxed) {
+            TryReadError::Closed
+        } else {
+            TryReadError::Disconnected
+        }
+    }
+}
+
+impl<T> Drop for Inner<T> {
+    fn drop(&mut self) {
+        let written = (*self.tail.get_mut()).min(self.capacity());
+        for slot in &mut self.slots[..written as usize] {
+            unsafe { slot.get_mut().assume_init_drop() }
+        }
+    }
+}
+
+/// Lease on the slot holding a read value.
+///
+/// The reader's cursor only moves past the slot once the guard is dropped, so the writer cannot
+/// overwrite it in the meantime: holding it for long holds the writer back.
+pub struct DisruptorGuard<'a, T> {
+    handle: &'a ReadHandle<'a, T>,
+    seq: u64,
+}
+
+impl<T> DisruptorGuard<'_, T> {
+    /// Returns the sequence number of the value, i.e. how many values had been written before it.
+    pub fn sequence(&self) -> u64 {
+        self.seq
+    }
+}
+
+impl<T> std::ops::Deref for DisruptorGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { (*self.handle.inner.slot(self.seq)).assume_init_ref() }
+    }
+}
+
+impl<T> Drop for DisruptorGuard<'_, T> {
+    fn drop(&mut self) {
+        self.handle.cursor.store(self.seq + 1, Ordering::Release);
+    }
+}
+
+impl<T> std::fmt::Debug for DisruptorGuard<'_, T>
+where
+    T: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T> ReadHandle<'_, T> {
+    /// Returns how many values are waiting to be read by this handle.
+    pub fn pending(&self) -> u64 {
+        let tail = self.inner.tail.load(Ordering::Acquire);
+        tail - self.cursor.load(Ordering::Relaxed)
+    }
+
+    /// Returns how many values the ring can hold.
+    pub fn capacity(&self) -> usize {
+        self.inner.slots.len()
+    }
+}
+
+impl<T> Clone for ReadHandle<'_, T> {
+    /// Adds a consumer, which starts with the values this handle did not read yet.
+    fn clone(&self) -> Self {
+        let seq = self.cursor.load(Ordering::Relaxed);
+        ReadHandle {
+            inner: self.inner.clone(),
+            // this handle's cursor keeps those values around while the new one joins
+            cursor: self.inner.join(seq),
+        }
+    }
+}
+
+impl<T> WriteHandle<'_, T> {
+    /// Writes `value` unless the slowest reader did not make room for it yet, in which case it is
+    /// handed back.
+    fn try_push(&mut self, value: T) -> Result<(), T> {
+        let tail = self.inner.tail.load(Ordering::Relaxed);
+        if tail - self.gate >= self.inner.capacity() {
+            self.gate = self.inner.slowest();
+            if tail - self.gate >= self.inner.capacity() {
+                return Err(value);
+            }
+        }
+        unsafe {
+            let slot = &mut *self.inner.slot(tail);
+            if tail >= self.inner.capacity() {
+                *slot.assume_init_mut() = value;
+            } else {
+                slot.write(value);
+            }
+        }
+        self.inner.tail.store(tail + 1, Ordering::Release);
+        Ok(())
+    }
+
+    /// Returns how many `ReadHandle`s are alive.
+    pub fn reader_count(&self) -> usize {
+        self.inner.cursors.lock().unwrap().len()
+    }
+
+    /// Returns how many values the ring can hold.
+    pub fn capacity(&self) -> usize {
+        self.inner.slots.len()
+    }
+}
+
+impl<T> Reader for ReadHandle<'_, T> {
+    type Item = T;
+    type Guard<'a>
+        = DisruptorGuard<'a, T>
+    where
+        Self: 'a;
+
+    /// Try reading the oldest value this handle did not read yet.
+    fn read(&mut self) -> Result<Self::Guard<'_>, TryReadError> {
+        let seq = self.cursor.load(Ordering::Relaxed);
+        if seq == self.inner.tail.load(Ordering::Acquire) {
+            if self.inner.writer_alive.load(Ordering::Acquire) {
+                return Err(TryReadError::Empty);
+            }
+            // the writer may have written right before being dropped
+            if seq == self.inner.tail.load(Ordering::Acquire) {
+                return Err(self.inner.disconnected());
+            }
+        }
+        Ok(DisruptorGuard { handle: self, seq })
+    }
+}
+
+impl<T> Writer for WriteHandle<'_, T> {
+    type Item = T;
+
+    /// Writes `value`, spinning until the slowest reader makes room for it.
+    ///
+    /// Readers that are dropped stop holding the writer back, so this never waits once there is
+    /// none left.
+    fn write(&mut self, mut value: T) {
+        for i in 0.. {
+            value = match self.try_push(value) {
+                Ok(()) => return,
+                Err(value) => value,
+            };
+            backoff::snooze(i);
+        }
+    }
+
+    /// Writes a value built by `f`, starting from `T::default()`.
+    ///
+    /// Slots may still be read by the slowest reader, so they cannot be reused.
+    fn write_with(&mut self, f: impl FnOnce(&mut T))
+    where
+        T: Default,
+    {
+        let mut value = T::default();
+        f(&mut value);
+        self.write(value)
+    }
+
+    fn close(self) {
+        self.inner.closed.store(true, Ordering::Relaxed);
+        // dropping `self` disconnects the readers
+    }
+
+    /// Returns whether any `ReadHandle` is still alive.
+    fn is_reader_alive(&self) -> bool {
+        self.reader_count() > 0
+    }
+
+    /// Writes `value` unless the slowest reader did not make room for it yet, in which case it is
+    /// handed back in [`WriteError::WouldBlock`].
+    fn try_write(&mut self, value: T) -> Result<(), WriteError<T>> {
+        if !self.is_reader_alive() {
+            return Err(WriteError::Disconnected(value));
+        }
+        self.try_push(value).map_err(WriteError::WouldBlock)
+    }
+}
+
+impl<T> Drop for ReadHandle<'_, T> {
+    fn drop(&mut self) {
+        let mut cursors = self.inner.cursors.lock().unwrap();
+        cursors.retain(|cursor| !Arc::ptr_eq(cursor, &self.cursor));
+    }
+}
+
+impl<T> Drop for WriteHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.writer_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<T> DebugState for Inner<T> {
+    fn debug_state(&self, d: &mut std::fmt::DebugStruct<'_, '_>) {
+        let readers = self
+            .cursors
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len();
+        d.field("written", &self.tail.load(Ordering::Relaxed))
+            .field("capacity", &self.slots.len())
+            .field("readers", &readers)
+            .field("writer_alive", &self.writer_alive.load(Ordering::Relaxed))
+            .field("closed", &self.closed.load(Ordering::Relaxed));
+    }
+}
+
+impl<T> std::fmt::Debug for ReadHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut d = f.debug_struct("disruptor::ReadHandle");
+        self.inner.debug_state(&mut d);
+        d.field("cursor", &self.cursor.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+impl<T> std::fmt::Debug for WriteHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut d = f.debug_struct("disruptor::WriteHandle");
+        self.inner.debug_state(&mut d);
+        d.field("gate", &self.gate).finish()
+    }
+}
+
+/// Construct a new write handle and a first read handle, which can be cloned to add more readers,
+/// for a ring holding up to `capacity` values.
+///
+/// Panics if `capacity` is zero.
+pub fn new<T>(capacity: usize) -> (ReadHandle<'static, T>, WriteHandle<'static, T>) {
+    let inner = Arc::new(Inner::new(capacity));
+    let r = ReadHandle {
+        cursor: inner.join(0),
+        inner: Shared::Arc(Arc::clone(&inner)),
+    };
+    let w = WriteHandle {
+        inner: Shared::Arc(inner),
+        gate: 0,
+    };
+    (r, w)
+}
+
+/// This variant as a [`crate::Channel`], holding up to `CAPACITY` values, for code generic over
+/// the variant.
+pub struct Channel<const CAPACITY: usize>;
+
+impl<T: 'static, const CAPACITY: usize> crate::Channel<T> for Channel<CAPACITY> {
+    type Reader = ReadHandle<'static, T>;
+    type Writer = WriteHandle<'static, T>;
+
+    fn create() -> (Self::Reader, Self::Writer) {
+        new(CAPACITY)
+    }
+}
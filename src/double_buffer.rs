@@ -0,0 +1,329 @@
+use crate::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use crate::debug::DebugState;
+use crate::shared::Shared;
+use crate::{Reader, TryReadError, WriteError, Writer};
+
+use std::cell::UnsafeCell;
+use std::sync::Arc;
+
+// layout of `state`
+const FRONT: usize = 0b001;
+const PUBLISHED: usize = 0b010;
+const WRITING: usize = 0b100;
+
+/// Two buffers: the reader owns the front one, the writer writes into the other one and flags it
+/// as published. The reader swaps them on refresh, unless the writer is busy with the back buffer.
+struct Inner<T> {
+    buffers: [UnsafeCell<T>; 2],
+    // index of the front buffer, plus the PUBLISHED and WRITING flags
+    state: AtomicUsize,
+    // number of values replaced before being read, only modified by the writer
+    overwritten: AtomicU64,
+    // set if a write panicked while holding the back buffer, see `Abort`
+    poisoned: AtomicBool,
+    writer_alive: AtomicBool,
+    // set right before `writer_alive` is cleared if the writer left through `close`
+    closed: AtomicBool,
+    reader_alive: AtomicBool,
+}
+
+/// Safety: the writer only touches the back buffer while WRITING is set, and the reader only
+/// swaps the buffers while it is not.
+unsafe impl<T> Sync for Inner<T> where T: Send {}
+
+pub struct ReadHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+}
+
+pub struct WriteHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+}
+
+impl<T> Inner<T>
+where
+    T: Default,
+{
+    fn new() -> Self {
+        Inner {
+            buffers: [(); 2].map(|_| UnsafeCell::new(T::default())),
+            state: AtomicUsize::new(0),
+            overwritten: AtomicU64::new(0),
+            poisoned: AtomicBool::new(false),
+            writer_alive: AtomicBool::new(true),
+            closed: AtomicBool::new(false),
+            reader_alive: AtomicBool::new(true),
+        }
+    }
+}
+
+impl<T> Inner<T> {
+    /// Hands the back buffer to `f` and publishes it.
+    ///
+    /// This method is wait-free: the reader never holds on to the back buffer.
+    ///
+    /// Panics if the channel was poisoned.
+    fn write_with(&self, f: impl FnOnce(&mut T)) {
+        assert!(
+            !self.is_poisoned(),
+            "channel poisoned by a panic during a previous write"
+        );
+        let state = self.state.fetch_or(WRITING, Ordering::Acquire);
+        let back = (state & FRONT) ^ 1;
+        let abort = Abort { inner: self, state };
+        unsafe { f(&mut *self.buffers[back].get()) }
+        std::mem::forget(abort);
+        // the reader does not modify `state` while WRITING is set
+        self.state
+            .store(state & FRONT | PUBLISHED, Ordering::Release);
+        if state & PUBLISHED != 0 {
+            self.overwritten.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Swaps the buffers if a value was published and the writer is not busy.
+    fn take(&self) -> bool {
+        let mut state = self.state.load(Ordering::Relaxed);
+        loop {
+            if state & PUBLISHED == 0 || state & WRITING != 0 {
+                return false;
+            }
+            match self.state.compare_exchange_weak(
+                state,
+                (state & FRONT) ^ 1,
+                Ordering::AcqRel,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return true,
+                Err(current) => state = current,
+            }
+        }
+    }
+
+    /// Writes `value` unless a panic poisoned the channel.
+    fn try_write(&self, value: T) -> Result<(), WriteError<T>> {
+        if self.is_poisoned() {
+            return Err(WriteError::Poisoned(value));
+        }
+        self.write_with(|back| *back = value);
+        Ok(())
+    }
+
+    /// Error reported once the writer is gone or poisoned the channel and nothing is left to read.
+    fn disconnected(&self) -> TryReadError {
+        if self.is_poisoned() {
+            TryReadError::Poisoned
+        } else if self.closed.load(Ordering::Relaxed) {
+            TryReadError::Closed
+        } else {
+            TryReadError::Disconnected
+        }
+    }
+
+    fn is_poisoned(&self) -> bool {
+        self.poisoned.load(Ordering::Acquire)
+    }
+}
+
+/// Poisons the channel if dropped, which only happens when a write panics halfway through, then
+/// hands the back buffer back to the reader. The value it held may be half written, so a pending
+/// value is dropped instead of published.
+struct Abort<'a, T> {
+    inner: &'a Inner<T>,
+    // `state` before WRITING was set
+    state: usize,
+}
+
+impl<T> Drop for Abort<'_, T> {
+    fn drop(&mut self) {
+        self.inner.poisoned.store(true, Ordering::Release);
+        self.inner
+            .state
+            .store(self.state & FRONT, Ordering::Release);
+    }
+}
+
+/// Lease on the front buffer of the reader.
+///
+/// The guard borrows the read handle, which cannot swap the buffers while it is alive, so the
+/// front buffer stays stable for as long as it is needed.
+pub struct DoubleGuard<'a, T> {
+    value: &'a T,
+}
+
+impl<T> std::ops::Deref for DoubleGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+impl<T> std::fmt::Debug for DoubleGuard<'_, T>
+where
+    T: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T> ReadHandle<'_, T> {
+    /// Swaps in the last published value as the front buffer.
+    /// Returns `false`, keeping the current front buffer, if nothing was published since the last
+    /// refresh or if the writer is in the middle of a write.
+    pub fn refresh(&mut self) -> bool {
+        self.inner.take()
+    }
+
+    /// Leases the front buffer, which is the last value taken by [`ReadHandle::refresh`] or
+    /// [`Reader::read`] (or `T::default()` before the first one).
+    pub fn front_buffer(&mut self) -> DoubleGuard<'_, T> {
+        // only the reader changes the front index
+        let front = self.inner.state.load(Ordering::Relaxed) & FRONT;
+        let value = unsafe { &*self.inner.buffers[front].get() };
+        DoubleGuard { value }
+    }
+
+    /// Returns how many written values were replaced before they could be read.
+    pub fn overwritten(&self) -> u64 {
+        self.inner.overwritten.load(Ordering::Relaxed)
+    }
+
+    /// Returns whether a write panicked halfway through, in which case reads fail with
+    /// [`TryReadError::Poisoned`].
+    pub fn is_poisoned(&self) -> bool {
+        self.inner.is_poisoned()
+    }
+}
+
+impl<T> WriteHandle<'_, T> {
+    /// Returns how many written values were replaced before the reader could read them.
+    pub fn overwritten(&self) -> u64 {
+        self.inner.overwritten.load(Ordering::Relaxed)
+    }
+
+    /// Returns whether a write panicked halfway through, in which case any further write panics.
+    pub fn is_poisoned(&self) -> bool {
+        self.inner.is_poisoned()
+    }
+}
+
+impl<T> Reader for ReadHandle<'_, T> {
+    type Item = T;
+    type Guard<'a>
+        = DoubleGuard<'a, T>
+    where
+        Self: 'a;
+
+    /// Try reading the last published value.
+    fn read(&mut self) -> Result<Self::Guard<'_>, TryReadError> {
+        if !self.inner.take() {
+            if self.inner.writer_alive.load(Ordering::Acquire) && !self.inner.is_poisoned() {
+                return Err(TryReadError::Empty);
+            }
+            // the writer may have published right before being dropped
+            if !self.inner.take() {
+                return Err(self.inner.disconnected());
+            }
+        }
+        Ok(self.front_buffer())
+    }
+}
+
+impl<T> Writer for WriteHandle<'_, T> {
+    type Item = T;
+
+    fn write(&mut self, value: T) {
+        self.inner.write_with(|back| *back = value)
+    }
+
+    fn write_with(&mut self, f: impl FnOnce(&mut T))
+    where
+        T: Default,
+    {
+        self.inner.write_with(f)
+    }
+
+    fn close(self) {
+        self.inner.closed.store(true, Ordering::Relaxed);
+        // dropping `self` disconnects the reader
+    }
+
+    fn is_reader_alive(&self) -> bool {
+        self.inner.reader_alive.load(Ordering::Acquire)
+    }
+
+    fn try_write(&mut self, value: T) -> Result<(), WriteError<T>> {
+        if !self.is_reader_alive() {
+            return Err(WriteError::Disconnected(value));
+        }
+        self.inner.try_write(value)
+    }
+}
+
+impl<T> Drop for ReadHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.reader_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<T> Drop for WriteHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.writer_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<T> DebugState for Inner<T> {
+    fn debug_state(&self, d: &mut std::fmt::DebugStruct<'_, '_>) {
+        let state = self.state.load(Ordering::Relaxed);
+        d.field("pending", &(state & PUBLISHED != 0))
+            .field("front", &(state & FRONT))
+            .field("writing", &(state & WRITING != 0))
+            .field("overwritten", &self.overwritten.load(Ordering::Relaxed))
+            .field("poisoned", &self.poisoned.load(Ordering::Relaxed))
+            .field("writer_alive", &self.writer_alive.load(Ordering::Relaxed))
+            .field("closed", &self.closed.load(Ordering::Relaxed))
+            .field("reader_alive", &self.reader_alive.load(Ordering::Relaxed));
+    }
+}
+
+impl<T> std::fmt::Debug for ReadHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "double_buffer::ReadHandle")
+    }
+}
+
+impl<T> std::fmt::Debug for WriteHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "double_buffer::WriteHandle")
+    }
+}
+
+/// Construct a new read and write handle pair, with both buffers set to `T::default()`.
+///
+/// Reads fail with [`TryReadError::Empty`] until the first write.
+pub fn new<T>() -> (ReadHandle<'static, T>, WriteHandle<'static, T>)
+where
+    T: Default,
+{
+    let inner = Arc::new(Inner::new());
+    let r = ReadHandle {
+        inner: Shared::Arc(Arc::clone(&inner)),
+    };
+    let w = WriteHandle {
+        inner: Shared::Arc(inner),
+    };
+    (r, w)
+}
+
+/// This variant as a [`crate::Channel`], for code generic over the variant.
+pub struct Channel;
+
+impl<T: Default + 'static> crate::Channel<T> for Channel {
+    type Reader = ReadHandle<'static, T>;
+    type Writer = WriteHandle<'static, T>;
+
+    fn create() -> (Self::Reader, Self::Writer) {
+        new()
+    }
+}
@@ -0,0 +1,291 @@
+use crate::atomic::{AtomicBool, AtomicU64, Ordering};
+use crate::debug::DebugState;
+use crate::shared::Shared;
+use crate::{Reader, TryReadError, Writer};
+
+use crossbeam_epoch::{self as epoch, Atomic, Owned};
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// Published value, along with its version so that the reader can tell it apart from the last
+/// one it read.
+struct Node<T> {
+    value: T,
+    version: u64,
+}
+
+/// Every write publishes a new heap node in `current`, and the node it replaces is destroyed once
+/// no pinned reader can still be looking at it.
+///
+/// Unlike the pool variants the reader does not take the value out of the channel: it reads the
+/// current node in place, pinned for as long as its guard is alive, and the writer never waits.
+struct Inner<T> {
+    current: Atomic<Node<T>>,
+    // number of values published so far, only modified by the writer
+    published: AtomicU64,
+    // version of the last value read, only modified by the reader
+    read_version: AtomicU64,
+    // number of values replaced before being read, only modified by the writer
+    overwritten: AtomicU64,
+    writer_alive: AtomicBool,
+    // set right before `writer_alive` is cleared if the writer left through `close`
+    closed: AtomicBool,
+    reader_alive: AtomicBool,
+}
+
+pub struct ReadHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+    // version of the last value read
+    seen: u64,
+}
+
+pub struct WriteHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+}
+
+impl<T> Inner<T> {
+    fn new() -> Self {
+        Inner {
+            current: Atomic::null(),
+            published: AtomicU64::new(0),
+            read_version: AtomicU64::new(0),
+            overwritten: AtomicU64::new(0),
+            writer_alive: AtomicBool::new(true),
+            closed: AtomicBool::new(false),
+            reader_alive: AtomicBool::new(true),
+        }
+    }
+
+    /// Publishes `value` in a new node, deferring the destruction of the one it replaces.
+    ///
+    /// The global collector destroys it on whichever thread happens to collect it, at any later
+    /// time, hence `T: Send + 'static` like `crossbeam_epoch::Guard::defer`.
+    ///
+    /// This method is lock-free, and never waits for the reader.
+    fn write(&self, value: T)
+    where
+        T: Send + 'static,
+    {
+        let version = self.published.load(Ordering::Relaxed) + 1;
+        self.published.store(version, Ordering::Relaxed);
+        let guard = epoch::pin();
+        let old = self.current.swap(
+            Owned::new(Node { value, version }),
+            Ordering::AcqRel,
+            &guard,
+        );
+        if let Some(node) = unsafe { old.as_ref() } {
+            if self.read_version.load(Ordering::Relaxed) != node.version {
+                self.overwritten.fetch_add(1, Ordering::Relaxed);
+            }
+            // the reader may still be pinned on it
+            unsafe { guard.defer_destroy(old) }
+        }
+    }
+
+    /// Error reported once the writer is gone and nothing is left to read.
+    fn disconnected(&self) -> TryReadError {
+        if self.closed.load(Ordering::Relaxed) {
+            TryReadError::Closed
+        } else {
+            TryReadError::Disconnected
+        }
+    }
+}
+
+impl<T> Drop for Inner<T> {
+    fn drop(&mut self) {
+        // no handle is left, so nobody else can reach the node
+        let guard = unsafe { epoch::unprotected() };
+        let current = self.current.load(Ordering::Relaxed, guard);
+        if !current.is_null() {
+            drop(unsafe { current.into_owned() });
+        }
+    }
+}
+
+/// Pinned reference to a read value.
+///
+/// The current thread stays pinned while the guard is alive, which keeps the node from being
+/// destroyed even if the writer replaces it. Holding it for long delays the reclamation of every
+/// node retired in the meantime, not the writer.
+pub struct EpochGuard<'a, T> {
+    node: *const Node<T>,
+    _guard: epoch::Guard,
+    _handle: PhantomData<&'a T>,
+}
+
+impl<T> EpochGuard<'_, T> {
+    /// Returns the version of the value, i.e. how many values had been published including it.
+    pub fn version(&self) -> u64 {
+        unsafe { (*self.node).version }
+    }
+}
+
+impl<T> std::ops::Deref for EpochGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &(*self.node).value }
+    }
+}
+
+impl<T> std::fmt::Debug for EpochGuard<'_, T>
+where
+    T: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T> ReadHandle<'_, T> {
+    /// Returns how many written values were replaced before they could be read.
+    pub fn overwritten(&self) -> u64 {
+        self.inner.overwritten.load(Ordering::Relaxed)
+    }
+
+    /// Pins the current node unless it was already read.
+    fn take(&mut self) -> Option<EpochGuard<'_, T>> {
+        let guard = epoch::pin();
+        let node = unsafe { self.inner.current.load(Ordering::Acquire, &guard).as_ref() }?;
+        if node.version == self.seen {
+            return None;
+        }
+        self.seen = node.version;
+        self.inner
+            .read_version
+            .store(node.version, Ordering::Relaxed);
+        let node: *const Node<T> = node;
+        Some(EpochGuard {
+            node,
+            _guard: guard,
+            _handle: PhantomData,
+        })
+    }
+}
+
+impl<T> WriteHandle<'_, T> {
+    /// Returns how many written values were replaced before the reader could read them.
+    pub fn overwritten(&self) -> u64 {
+        self.inner.overwritten.load(Ordering::Relaxed)
+    }
+}
+
+impl<T> Reader for ReadHandle<'_, T> {
+    type Item = T;
+    type Guard<'a>
+        = EpochGuard<'a, T>
+    where
+        Self: 'a;
+
+    /// Try reading the last written value.
+    ///
+    /// This method is lock-free.
+    fn read(&mut self) -> Result<Self::Guard<'_>, TryReadError> {
+        // the writer is only seen gone once its last value is published, so a read after checking
+        // is enough to catch it
+        let err = if self.inner.writer_alive.load(Ordering::Acquire) {
+            TryReadError::Empty
+        } else {
+            self.inner.disconnected()
+        };
+        self.take().ok_or(err)
+    }
+}
+
+impl<T: Send + 'static> Writer for WriteHandle<'_, T> {
+    type Item = T;
+
+    fn write(&mut self, value: T) {
+        self.inner.write(value)
+    }
+
+    /// Publishes a value built by `f`, starting from `T::default()`.
+    ///
+    /// Replaced nodes may still be read, so they cannot be reused.
+    fn write_with(&mut self, f: impl FnOnce(&mut T))
+    where
+        T: Default,
+    {
+        let mut value = T::default();
+        f(&mut value);
+        self.inner.write(value)
+    }
+
+    fn close(self) {
+        self.inner.closed.store(true, Ordering::Relaxed);
+        // dropping `self` disconnects the reader
+    }
+
+    fn is_reader_alive(&self) -> bool {
+        self.inner.reader_alive.load(Ordering::Acquire)
+    }
+}
+
+impl<T> Drop for ReadHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.reader_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<T> Drop for WriteHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.writer_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<T> DebugState for Inner<T> {
+    fn debug_state(&self, d: &mut std::fmt::DebugStruct<'_, '_>) {
+        let published = self.published.load(Ordering::Relaxed);
+        d.field(
+            "pending",
+            &(published != self.read_version.load(Ordering::Relaxed)),
+        )
+        .field("published", &published)
+        .field("overwritten", &self.overwritten.load(Ordering::Relaxed))
+        .field("writer_alive", &self.writer_alive.load(Ordering::Relaxed))
+        .field("closed", &self.closed.load(Ordering::Relaxed))
+        .field("reader_alive", &self.reader_alive.load(Ordering::Relaxed));
+    }
+}
+
+impl<T> std::fmt::Debug for ReadHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "epoch_spsc::ReadHandle")
+    }
+}
+
+impl<T> std::fmt::Debug for WriteHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "epoch_spsc::WriteHandle")
+    }
+}
+
+/// Construct a new, empty read and write handle pair.
+///
+/// Reads fail with [`TryReadError::Empty`] until the first write. Replaced values are dropped by
+/// crossbeam-epoch's global collector, on any thread, hence `T: Send + 'static`.
+pub fn new<T: Send + 'static>() -> (ReadHandle<'static, T>, WriteHandle<'static, T>) {
+    let inner = Arc::new(Inner::new());
+    let r = ReadHandle {
+        inner: Shared::Arc(Arc::clone(&inner)),
+        seen: 0,
+    };
+    let w = WriteHandle {
+        inner: Shared::Arc(inner),
+    };
+    (r, w)
+}
+
+/// This variant as a [`crate::Channel`], for code generic over the variant.
+pub struct Channel;
+
+impl<T: Send + 'static> crate::Channel<T> for Channel {
+    type Reader = ReadHandle<'static, T>;
+    type Writer = WriteHandle<'static, T>;
+
+    fn create() -> (Self::Reader, Self::Writer) {
+        new()
+    }
+}
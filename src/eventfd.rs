@@ -0,0 +1,51 @@
+//! Non-blocking `eventfd`, letting an event loop wait on a channel next to sockets.
+
+use std::io;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd};
+
+/// Counter readable through `epoll`, `poll` and friends whenever it is not zero.
+///
+/// Returned by the `attach_eventfd` method of the read handles that support it, to be registered
+/// in an event loop. Only the channel signals and resets it.
+#[derive(Debug)]
+pub struct EventFd {
+    fd: OwnedFd,
+}
+
+impl EventFd {
+    pub(crate) fn new() -> io::Result<Self> {
+        let fd = unsafe { libc::eventfd(0, libc::EFD_NONBLOCK | libc::EFD_CLOEXEC) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(EventFd {
+            fd: unsafe { OwnedFd::from_raw_fd(fd) },
+        })
+    }
+
+    /// Makes the file descriptor readable.
+    pub(crate) fn signal(&self) {
+        let one: u64 = 1;
+        // can only fail if the counter is about to overflow, in which case it is readable anyway
+        unsafe { libc::write(self.fd.as_raw_fd(), (&one as *const u64).cast(), 8) };
+    }
+
+    /// Resets the counter, so that the file descriptor is no longer readable.
+    pub(crate) fn drain(&self) {
+        let mut count: u64 = 0;
+        // fails with `EAGAIN` if the counter is already zero
+        unsafe { libc::read(self.fd.as_raw_fd(), (&mut count as *mut u64).cast(), 8) };
+    }
+}
+
+impl AsFd for EventFd {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+impl AsRawFd for EventFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
@@ -0,0 +1,68 @@
+use crate::atomic_spsc::{self, AtomicGuard};
+use crate::{Reader, TryReadError};
+
+use std::iter::Enumerate;
+
+/// Producer side of a fan-in channel: the write handle of its own `atomic_spsc` channel, one per
+/// producer.
+pub type WriteHandle<'a, T> = atomic_spsc::WriteHandle<'a, T>;
+
+/// Consumer side of a fan-in channel, reading the slot of every writer.
+///
+/// This is one `atomic_spsc` channel per writer, so each slot holds the latest value of its own
+/// producer and the producers never contend with each other.
+pub struct ReadHandle<'a, T> {
+    inputs: Vec<atomic_spsc::ReadHandle<'a, T>>,
+}
+
+/// Iterator over the slots holding a new value, returned by [`ReadHandle::iter`].
+pub struct Iter<'r, 'a, T> {
+    inputs: Enumerate<std::slice::IterMut<'r, atomic_spsc::ReadHandle<'a, T>>>,
+}
+
+impl<'a, T> ReadHandle<'a, T> {
+    /// Returns how many slots there are, one per writer.
+    pub fn slots(&self) -> usize {
+        self.inputs.len()
+    }
+
+    /// Try reading the last value written to `slot`, see [`Reader::read`].
+    ///
+    /// Panics if `slot` is out of range.
+    pub fn read(&mut self, slot: usize) -> Result<AtomicGuard<'_, T>, TryReadError> {
+        self.inputs[slot].read()
+    }
+
+    /// Reads every slot in order, returning the index and the value of those holding a new one.
+    pub fn iter(&mut self) -> Iter<'_, 'a, T> {
+        Iter {
+            inputs: self.inputs.iter_mut().enumerate(),
+        }
+    }
+}
+
+impl<'r, T> Iterator for Iter<'r, '_, T> {
+    type Item = (usize, AtomicGuard<'r, T>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inputs
+            .find_map(|(slot, input)| input.read().ok().map(|guard| (slot, guard)))
+    }
+}
+
+impl<T> std::fmt::Debug for ReadHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("fanin::ReadHandle")
+            .field("slots", &self.inputs)
+            .finish()
+    }
+}
+
+/// Construct a read handle collecting the values of `n` new write handles, one per producer.
+///
+/// Panics if `n` is zero.
+pub fn new<T>(n: usize) -> (ReadHandle<'static, T>, Vec<WriteHandle<'static, T>>) {
+    assert!(n > 0, "a fan-in needs at least one writer");
+    let (inputs, writers) = (0..n).map(|_| atomic_spsc::new()).unzip();
+    (ReadHandle { inputs }, writers)
+}
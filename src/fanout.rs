@@ -0,0 +1,104 @@
+use crate::{atomic_spsc, WriteError, Writer};
+
+/// Consumer side of a fan-out channel: the read handle of its own `atomic_spsc` channel.
+pub type ReadHandle<'a, T> = atomic_spsc::ReadHandle<'a, T>;
+
+/// Producer side of a fan-out channel, publishing every value to the channel of each reader.
+///
+/// This is one `atomic_spsc` channel per reader, so each reader sees every update (or the newest
+/// of those it missed) independently of the others, at the cost of a clone per reader and write.
+pub struct WriteHandle<'a, T> {
+    outputs: Vec<atomic_spsc::WriteHandle<'a, T>>,
+}
+
+impl<T> WriteHandle<'_, T> {
+    /// Returns how many readers still exist.
+    pub fn readers_alive(&self) -> usize {
+        self.outputs
+            .iter()
+            .filter(|output| output.is_reader_alive())
+            .count()
+    }
+}
+
+impl<T: Clone> Writer for WriteHandle<'_, T> {
+    type Item = T;
+
+    /// Publishes `value` to every reader still alive, cloning it for all but the last.
+    fn write(&mut self, value: T) {
+        let mut outputs = self
+            .outputs
+            .iter_mut()
+            .filter(|output| output.is_reader_alive());
+        let Some(mut output) = outputs.next() else {
+            return;
+        };
+        for next in outputs {
+            output.write(value.clone());
+            output = next;
+        }
+        output.write(value);
+    }
+
+    /// Publishes a value built by `f` to every reader still alive.
+    ///
+    /// The value is cloned into a slot of each channel, so `f` gets a fresh `T::default()` instead
+    /// of a slot of its own.
+    fn write_with(&mut self, f: impl FnOnce(&mut T))
+    where
+        T: Default,
+    {
+        let mut value = T::default();
+        f(&mut value);
+        self.write(value);
+    }
+
+    fn close(self) {
+        for output in self.outputs {
+            output.close();
+        }
+    }
+
+    /// Returns whether any reader still exists.
+    fn is_reader_alive(&self) -> bool {
+        self.outputs.iter().any(|output| output.is_reader_alive())
+    }
+
+    /// Publishes `value` to every reader still alive, failing with [`WriteError::Disconnected`]
+    /// once none is, or with [`WriteError::Poisoned`] if a channel refuses it, in which case the
+    /// channels before it already got the value.
+    fn try_write(&mut self, value: T) -> Result<(), WriteError<T>> {
+        if !self.is_reader_alive() {
+            return Err(WriteError::Disconnected(value));
+        }
+        for output in self
+            .outputs
+            .iter_mut()
+            .filter(|output| output.is_reader_alive())
+        {
+            // a reader dropped since the check just misses the value
+            if let Err(WriteError::Poisoned(_)) = output.try_write(value.clone()) {
+                return Err(WriteError::Poisoned(value));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T> std::fmt::Debug for WriteHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("fanout::WriteHandle")
+            .field("readers", &self.outputs.len())
+            .field("readers_alive", &self.readers_alive())
+            .finish()
+    }
+}
+
+/// Construct a write handle publishing to `n` new, empty, independent read handles.
+///
+/// Panics if `n` is zero.
+pub fn new<T>(n: usize) -> (Vec<ReadHandle<'static, T>>, WriteHandle<'static, T>) {
+    assert!(n > 0, "a fan-out needs at least one reader");
+    let (readers, outputs) = (0..n).map(|_| atomic_spsc::new()).unzip();
+    (readers, WriteHandle { outputs })
+}
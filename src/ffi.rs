@@ -0,0 +1,132 @@
+//! C interface to a latest-value channel of fixed-size byte blobs, declared in
+//! `include/rustedrazors.h` (regenerated with `cbindgen --config cbindgen.toml`).
+//!
+//! A channel is a `triple_buffer`, so writes and reads never block each other. One thread at a
+//! time may write and one thread at a time may read, possibly two different threads, for instance
+//! a C++ producer feeding a Rust consumer in the same process.
+
+use crate::triple_buffer::{self, ReadHandle, WriteHandle};
+use crate::{Reader, TryReadError, Writer};
+
+/// Opaque channel holding both ends, created by [`rr_channel_new`] and destroyed by [`rr_free`].
+///
+/// The reader and the writer may use the channel from two threads at the same time, so neither
+/// side ever borrows it whole: each one only borrows its own end, through a pointer to its field.
+pub struct Channel {
+    value_size: usize,
+    reader: ReadHandle<'static, Vec<u8>>,
+    writer: WriteHandle<'static, Vec<u8>>,
+}
+
+/// Outcome of [`rr_try_read`], see [`TryReadError`] for the failures.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Status {
+    /// A new value was copied out.
+    Ok = 0,
+    Empty = 1,
+    Disconnected = 2,
+    Closed = 3,
+    Poisoned = 4,
+}
+
+impl From<TryReadError> for Status {
+    fn from(err: TryReadError) -> Self {
+        match err {
+            TryReadError::Empty => Status::Empty,
+            TryReadError::Disconnected => Status::Disconnected,
+            TryReadError::Closed => Status::Closed,
+            TryReadError::Poisoned => Status::Poisoned,
+        }
+    }
+}
+
+impl Channel {
+    /// Returns the size of every value going through the channel.
+    ///
+    /// # Safety
+    ///
+    /// `channel` must come from [`rr_channel_new`] and not be freed.
+    pub unsafe fn value_size(channel: *const Channel) -> usize {
+        (*channel).value_size
+    }
+
+    /// Returns the read handle, for consumers on the Rust side of the interface, while C keeps
+    /// writing through [`rr_write`].
+    ///
+    /// Values are `value_size` bytes long.
+    ///
+    /// # Safety
+    ///
+    /// `channel` must come from [`rr_channel_new`] and not be freed while the handle is borrowed,
+    /// and no other thread may be reading from `channel` meanwhile, through [`rr_try_read`] or
+    /// another call to this function.
+    pub unsafe fn reader<'a>(channel: *mut Channel) -> &'a mut ReadHandle<'static, Vec<u8>> {
+        // projects the raw pointer to the field, without borrowing the whole channel
+        &mut (*channel).reader
+    }
+}
+
+/// Creates a channel of `value_size`-byte values. Reads fail with [`Status::Empty`] until the
+/// first write.
+#[no_mangle]
+pub extern "C" fn rr_channel_new(value_size: usize) -> *mut Channel {
+    let (reader, writer) = triple_buffer::new();
+    Box::into_raw(Box::new(Channel {
+        value_size,
+        reader,
+        writer,
+    }))
+}
+
+/// Publishes the `value_size` bytes at `value`, replacing any value that was not read yet.
+///
+/// # Safety
+///
+/// `channel` must come from [`rr_channel_new`] and not be freed, `value` must point to
+/// `value_size` readable bytes, and no other thread may be writing to `channel`.
+#[no_mangle]
+pub unsafe extern "C" fn rr_write(channel: *mut Channel, value: *const u8) {
+    let value = std::slice::from_raw_parts(value, Channel::value_size(channel));
+    // the reader may be using its end meanwhile, see `Channel`
+    let writer = &mut (*channel).writer;
+    // only allocates the first time each of the three buffers is written
+    writer.write_with(|buffer| {
+        buffer.clear();
+        buffer.extend_from_slice(value);
+    });
+}
+
+/// Copies the last published value to the `value_size` bytes at `out`, if it was not read yet.
+///
+/// Returns [`Status::Ok`] on success, leaving `out` untouched otherwise.
+///
+/// # Safety
+///
+/// `channel` must come from [`rr_channel_new`] and not be freed, `out` must point to `value_size`
+/// writable bytes, and no other thread may be reading from `channel`.
+#[no_mangle]
+pub unsafe extern "C" fn rr_try_read(channel: *mut Channel, out: *mut u8) -> Status {
+    let value_size = Channel::value_size(channel);
+    match Channel::reader(channel).read() {
+        Ok(value) => {
+            std::ptr::copy_nonoverlapping(value.as_ptr(), out, value_size);
+            Status::Ok
+        }
+        Err(err) => err.into(),
+    }
+}
+
+/// Destroys a channel. Does nothing if `channel` is null.
+///
+/// # Safety
+///
+/// `channel` must come from [`rr_channel_new`], not be freed already, and not be in use by any
+/// other thread.
+#[no_mangle]
+pub unsafe extern "C" fn rr_free(channel: *mut Channel) {
+    if !channel.is_null() {
+        drop(Box::from_raw(channel));
+    }
+}
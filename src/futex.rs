@@ -0,0 +1,69 @@
+//! Address-based wait/wake, backed by `futex` on Linux and `WaitOnAddress` on Windows.
+
+use std::sync::atomic::AtomicU32;
+use std::time::Duration;
+
+/// Blocks the current thread as long as `futex` holds `expected`, for at most `timeout`.
+///
+/// The comparison and the sleep happen atomically in the kernel, so a concurrent change followed
+/// by [`wake_one`] cannot be missed. Spurious wakeups are possible.
+#[cfg(target_os = "linux")]
+pub(crate) fn wait(futex: &AtomicU32, expected: u32, timeout: Option<Duration>) {
+    let timespec = timeout.map(|timeout| libc::timespec {
+        tv_sec: timeout.as_secs().min(libc::time_t::MAX as u64) as libc::time_t,
+        tv_nsec: timeout.subsec_nanos() as libc::c_long,
+    });
+    let timespec = timespec
+        .as_ref()
+        .map_or(std::ptr::null(), |t| t as *const libc::timespec);
+    unsafe {
+        libc::syscall(
+            libc::SYS_futex,
+            futex.as_ptr(),
+            libc::FUTEX_WAIT | libc::FUTEX_PRIVATE_FLAG,
+            expected,
+            timespec,
+        );
+    }
+}
+
+/// Wakes up one thread blocked in [`wait`] on `futex`.
+#[cfg(target_os = "linux")]
+pub(crate) fn wake_one(futex: &AtomicU32) {
+    unsafe {
+        libc::syscall(
+            libc::SYS_futex,
+            futex.as_ptr(),
+            libc::FUTEX_WAKE | libc::FUTEX_PRIVATE_FLAG,
+            1,
+        );
+    }
+}
+
+#[cfg(windows)]
+pub(crate) fn wait(futex: &AtomicU32, expected: u32, timeout: Option<Duration>) {
+    use windows_sys::Win32::System::Threading::{WaitOnAddress, INFINITE};
+
+    // round up so that short timeouts do not turn into a busy loop
+    let millis = timeout.map_or(INFINITE, |timeout| {
+        timeout
+            .as_nanos()
+            .div_ceil(1_000_000)
+            .min((INFINITE - 1) as u128) as u32
+    });
+    unsafe {
+        WaitOnAddress(
+            futex.as_ptr().cast(),
+            (&expected as *const u32).cast(),
+            std::mem::size_of::<u32>(),
+            millis,
+        );
+    }
+}
+
+#[cfg(windows)]
+pub(crate) fn wake_one(futex: &AtomicU32) {
+    use windows_sys::Win32::System::Threading::WakeByAddressSingle;
+
+    unsafe { WakeByAddressSingle(futex.as_ptr().cast()) }
+}
@@ -0,0 +1,779 @@
+#[cfg(feature = "age")]
+use crate::age::LastUpdate;
+use crate::atomic::{AtomicBool, AtomicU64, Ordering};
+use crate::debug::DebugState;
+use crate::futex;
+#[cfg(feature = "hooks")]
+use crate::hooks::{Callbacks, Hooks};
+use crate::shared::Shared;
+use crate::slots::{Abort, Slots};
+#[cfg(feature = "stats")]
+use crate::stats::{Counters, Stats};
+use crate::{Reader, TryReadError, WriteError, Writer};
+
+use std::marker::PhantomData;
+// the futex word is handed to the kernel by address, so it is always the native type
+use std::sync::atomic::AtomicU32;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const POOL_SIZE: usize = 3;
+
+/// Same slot pool as `atomic_spsc`, plus a 32 bit `epoch` word the reader can sleep on
+/// (`futex` on Linux, `WaitOnAddress` on Windows) while nothing is published.
+struct Inner<T> {
+    slots: Slots<T, POOL_SIZE>,
+    // set by the reader right before sleeping on `epoch`
+    sleeping: AtomicBool,
+    // bumped by the writer whenever it has to wake up the sleeping reader
+    epoch: AtomicU32,
+    // number of values replaced before being read, only modified by the writer
+    overwritten: AtomicU64,
+    #[cfg(feature = "stats")]
+    stats: Counters,
+    // callbacks given to `with_hooks`, run next to the counters
+    #[cfg(feature = "hooks")]
+    hooks: Callbacks,
+    // instant of the last publish, returned by `last_update` and `age`
+    #[cfg(feature = "age")]
+    updated: LastUpdate,
+    // label given to `named`, shown by `Debug` and `stats` and tagging the `tracing` events
+    name: Option<&'static str>,
+    writer_alive: AtomicBool,
+    // set right before `writer_alive` is cleared if the writer left through `close`
+    closed: AtomicBool,
+    reader_alive: AtomicBool,
+}
+
+/// Safety: see `atomic_spsc`, the same laws apply.
+unsafe impl<T> Sync for Inner<T> where T: Send {}
+
+pub struct ReadHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+}
+
+pub struct WriteHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+}
+
+impl<T> Inner<T> {
+    /// Constructs a new, empty [`Inner`].
+    const fn new() -> Self {
+        Inner {
+            slots: Slots::new(),
+            sleeping: AtomicBool::new(false),
+            epoch: AtomicU32::new(0),
+            overwritten: AtomicU64::new(0),
+            #[cfg(feature = "stats")]
+            stats: Counters::new(),
+            #[cfg(feature = "hooks")]
+            hooks: Callbacks::none(),
+            #[cfg(feature = "age")]
+            updated: LastUpdate::new(),
+            name: None,
+            writer_alive: AtomicBool::new(true),
+            closed: AtomicBool::new(false),
+            reader_alive: AtomicBool::new(true),
+        }
+    }
+}
+
+impl<T> Inner<T> {
+    /// Writes the provided value.
+    ///
+    /// This method is wait-free, the wake syscall is only issued when the reader is sleeping.
+    fn write(&self, value: T) {
+        let idx = self.acquire();
+        let abort = self.abort(idx);
+        self.slots.write_to(idx, value);
+        abort.disarm();
+        self.publish(idx);
+    }
+
+    /// Writes a value built in place by `f` in a free slot of the pool.
+    fn write_with(&self, f: impl FnOnce(&mut T))
+    where
+        T: Default,
+    {
+        let idx = self.acquire();
+        let abort = self.abort(idx);
+        self.slots.fill(idx);
+        unsafe { f(&mut *self.slots.slot(idx)) }
+        abort.disarm();
+        self.publish(idx);
+    }
+
+    /// Publishes the last written value (or `T::default()` before the first write) as modified
+    /// by `f`.
+    ///
+    /// The last written value is cloned into a free slot first, unless that slot is the free one.
+    /// The reader may hold it meanwhile, as in [`Inner::write_if_changed`], hence `T: Sync`.
+    fn update(&self, f: impl FnOnce(&mut T))
+    where
+        T: Clone + Default + Sync,
+    {
+        let idx = self.acquire();
+        let mut abort = self.abort(idx);
+        if self.slots.published() == 0 {
+            self.slots.fill(idx);
+        } else {
+            let last = self.lock_last();
+            abort.locked = true;
+            self.slots.clone_last(idx, last);
+            self.slots.unlock_last(last);
+            abort.locked = false;
+        }
+        unsafe { f(&mut *self.slots.slot(idx)) }
+        abort.disarm();
+        self.publish(idx);
+    }
+
+    /// Writes the provided value unless it is equal to the last written one, the first value is
+    /// always written.
+    ///
+    /// The writer never writes to the slot it last published until its next write, and
+    /// [`Inner::lock_last`] keeps `read_swap` out of it, so it is safe to compare against it even
+    /// while the reader holds it. Both sides then share `&T` across threads, hence `T: Sync`.
+    fn write_if_changed(&self, value: T) -> bool
+    where
+        T: PartialEq + Sync,
+    {
+        let changed = self.slots.published() == 0 || {
+            let last = self.lock_last();
+            let changed = *self.slots.read_from(last) != value;
+            self.slots.unlock_last(last);
+            changed
+        };
+        if changed {
+            self.write(value);
+        }
+        changed
+    }
+
+    /// Returns the slot of the last written value, see [`Slots::lock_last`].
+    fn lock_last(&self) -> usize {
+        #[cfg(feature = "stats")]
+        if self.slots.is_last_locked() {
+            // `read_swap` is moving the value out
+            return self.stats.stall(|| self.slots.lock_last());
+        }
+        self.slots.lock_last()
+    }
+
+    /// Makes the slot at the given index the last written value, see [`Slots::publish`].
+    fn publish(&self, idx: usize) {
+        let (version, overwrite) = self.slots.publish(idx);
+        if overwrite {
+            self.overwritten.fetch_add(1, Ordering::Relaxed);
+        }
+        #[cfg(feature = "age")]
+        self.updated.touch();
+        #[cfg(feature = "stats")]
+        self.stats.write(overwrite);
+        #[cfg(feature = "hooks")]
+        self.hooks.write(overwrite);
+        #[cfg(feature = "tracing")]
+        tracing::trace!(channel = self.name, version, overwrite, "write");
+        #[cfg(not(feature = "tracing"))]
+        let _ = version;
+        self.wake();
+    }
+
+    /// Wakes up the reader if it is sleeping, must follow a SeqCst store it has to observe.
+    fn wake(&self) {
+        if self.sleeping.load(Ordering::SeqCst) {
+            self.epoch.fetch_add(1, Ordering::SeqCst);
+            futex::wake_one(&self.epoch);
+        }
+    }
+
+    /// Marks the channel as poisoned after a write panicked, see [`Slots::poison`], and wakes up
+    /// the reader.
+    fn poison(&self, idx: usize) {
+        self.slots.poison(idx);
+        self.wake();
+    }
+
+    /// Poisons the channel if the write to the slot at the given index panics before being
+    /// disarmed.
+    fn abort(&self, idx: usize) -> Abort<'_, T, POOL_SIZE, impl Fn() + '_> {
+        Abort::new(&self.slots, idx, || self.wake())
+    }
+
+    /// Try reading the last written value.
+    /// The operation may fail if no new value was written since the last read.
+    ///
+    /// This method is wait-free.
+    fn read(&self) -> Option<FutexGuard<'_, T>> {
+        let idx = self.slots.take();
+        #[cfg(feature = "stats")]
+        self.stats.read(idx.is_some());
+        #[cfg(feature = "hooks")]
+        self.hooks.read(idx.is_some());
+        #[cfg(feature = "tracing")]
+        if let Some(idx) = idx {
+            let version = self.slots.version(idx);
+            tracing::trace!(channel = self.name, version, "read");
+        }
+        Some(FutexGuard {
+            inner: self,
+            idx: idx?,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Try reading the last written value, reporting whether the writer is gone.
+    fn try_read(&self) -> Result<FutexGuard<'_, T>, TryReadError> {
+        if let Some(guard) = self.read() {
+            return Ok(guard);
+        }
+        if self.writer_alive.load(Ordering::Acquire) && !self.slots.is_poisoned() {
+            return Err(TryReadError::Empty);
+        }
+        // the writer may have published right before being dropped
+        self.read().ok_or_else(|| self.disconnected())
+    }
+
+    /// Error reported once the writer is gone or poisoned the channel and nothing is left to read.
+    fn disconnected(&self) -> TryReadError {
+        if self.slots.is_poisoned() {
+            TryReadError::Poisoned
+        } else if self.closed.load(Ordering::Relaxed) {
+            TryReadError::Closed
+        } else {
+            TryReadError::Disconnected
+        }
+    }
+
+    /// Reads the next written value, sleeping on `epoch` until one is available, the writer is
+    /// dropped or `deadline` is reached.
+    fn read_deadline(&self, deadline: Option<Instant>) -> Result<FutexGuard<'_, T>, TryReadError> {
+        loop {
+            match self.try_read() {
+                Err(TryReadError::Empty) => {}
+                res => return res,
+            }
+            let timeout = match deadline {
+                None => None,
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Err(TryReadError::Empty);
+                    }
+                    Some(deadline - now)
+                }
+            };
+            let epoch = self.epoch.load(Ordering::SeqCst);
+            // pairs with the SeqCst stores and load in `wake`: either the writer sees us sleeping
+            // and bumps `epoch`, or we see what it stored and do not go to sleep
+            self.sleeping.store(true, Ordering::SeqCst);
+            if !self.slots.is_pending()
+                && self.writer_alive.load(Ordering::SeqCst)
+                && !self.slots.is_poisoned()
+            {
+                futex::wait(&self.epoch, epoch, timeout);
+            }
+            self.sleeping.store(false, Ordering::Relaxed);
+        }
+    }
+
+    /// Swaps the last written value with `scratch`, failing with [`TryReadError::Empty`] if there was
+    /// none.
+    fn read_swap(&self, scratch: &mut T) -> Result<(), TryReadError> {
+        let guard = self.try_read()?;
+        self.slots.swap_out(guard.idx, scratch);
+        Ok(())
+    }
+
+    /// Returns the index of the first available object in the pool, while marking it as in use.
+    /// At least one object is always free, since the reader leases at most one (its guard borrows
+    /// the read handle) and the waiting value takes another.
+    ///
+    /// Panics if the channel was poisoned.
+    fn acquire(&self) -> usize {
+        self.slots.acquire().expect("no free slot in the pool")
+    }
+}
+
+/// Zero-copy lease on the pool slot holding a read value.
+///
+/// The guard dereferences straight into the pool, no clone is involved. The slot stays reserved
+/// until the guard is dropped, so later writes cannot overwrite the value while it is borrowed.
+pub struct FutexGuard<'a, T> {
+    inner: &'a Inner<T>,
+    idx: usize,
+    // `Inner` is `Sync` for any `T: Send`, see `AtomicGuard`
+    _marker: PhantomData<&'a mut T>,
+}
+
+impl<T> FutexGuard<'_, T> {
+    /// Returns the version of the value, i.e. how many values had been published including it.
+    pub fn version(&self) -> u64 {
+        self.inner.slots.version(self.idx)
+    }
+}
+
+impl<T> std::ops::Deref for FutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.inner.slots.read_from(self.idx)
+    }
+}
+
+impl<T> Drop for FutexGuard<'_, T> {
+    fn drop(&mut self) {
+        self.inner.slots.release(self.idx);
+    }
+}
+
+impl<T> std::fmt::Debug for FutexGuard<'_, T>
+where
+    T: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T> ReadHandle<'_, T> {
+    /// Try reading the last written value by swapping it with `scratch` instead of cloning it.
+    /// Fails like [`Reader::read`], leaving `scratch` untouched.
+    ///
+    /// The previous content of `scratch` goes back into the pool, where the writer can reuse its
+    /// allocations through [`Writer::write_with`] or [`WriteHandle::begin_write`]. It also becomes
+    /// the reference for [`WriteHandle::update`] and [`WriteHandle::write_if_changed`] if no newer
+    /// value was written in the meantime.
+    pub fn read_swap(&mut self, scratch: &mut T) -> Result<(), TryReadError> {
+        self.inner.read_swap(scratch)
+    }
+
+    /// Returns how many written values were replaced before they could be read.
+    pub fn overwritten(&self) -> u64 {
+        self.inner.overwritten.load(Ordering::Relaxed)
+    }
+
+    /// Try reading the last written value along with its version, see [`FutexGuard::version`].
+    ///
+    /// Comparing versions of consecutive reads tells how many values were skipped.
+    pub fn read_versioned(&mut self) -> Result<(u64, FutexGuard<'_, T>), TryReadError> {
+        self.inner.try_read().map(|guard| (guard.version(), guard))
+    }
+
+    /// Returns how many values were published so far.
+    pub fn published(&self) -> u64 {
+        self.inner.slots.published()
+    }
+
+    /// Returns whether a write panicked halfway through, in which case reads fail with
+    /// [`TryReadError::Poisoned`] once the values published before are read.
+    pub fn is_poisoned(&self) -> bool {
+        self.inner.slots.is_poisoned()
+    }
+
+    /// Returns the label given to [`named`], if any.
+    pub fn name(&self) -> Option<&'static str> {
+        self.inner.name
+    }
+
+    /// Returns a snapshot of the usage counters of the channel, shared with the writer.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.inner.stats.snapshot(self.inner.name)
+    }
+
+    /// Returns when the writer last published a value, whether it was read or not, or `None` if it
+    /// never did.
+    #[cfg(feature = "age")]
+    pub fn last_update(&self) -> Option<Instant> {
+        self.inner.updated.get()
+    }
+
+    /// Returns how long ago the writer last published a value, or `None` if it never did.
+    ///
+    /// Comparing it against a deadline detects a stalled producer, which a read finding no new
+    /// value cannot tell apart from a slow one.
+    #[cfg(feature = "age")]
+    pub fn age(&self) -> Option<Duration> {
+        self.last_update().map(|instant| instant.elapsed())
+    }
+
+    /// Reads the next written value, blocking until the writer publishes one.
+    /// Fails with [`TryReadError::Closed`] or [`TryReadError::Disconnected`] once the writer is gone
+    /// and its last value was read.
+    ///
+    /// The thread sleeps in the kernel and is woken up on publish.
+    pub fn read_blocking(&mut self) -> Result<FutexGuard<'_, T>, TryReadError> {
+        self.inner.read_deadline(None)
+    }
+
+    /// Reads the next written value, blocking for at most `timeout`.
+    /// Fails with [`TryReadError::Empty`] if nothing was published in the meantime.
+    pub fn read_timeout(&mut self, timeout: Duration) -> Result<FutexGuard<'_, T>, TryReadError> {
+        self.inner.read_deadline(Some(Instant::now() + timeout))
+    }
+}
+
+impl<T> Reader for ReadHandle<'_, T> {
+    type Item = T;
+    type Guard<'a>
+        = FutexGuard<'a, T>
+    where
+        Self: 'a;
+
+    fn read(&mut self) -> Result<Self::Guard<'_>, TryReadError> {
+        self.inner.try_read()
+    }
+}
+
+/// Exclusive lease on a free pool slot, letting the writer build a value in place.
+///
+/// The slot is published when the guard is dropped or [`FutexWriteGuard::commit`] is called. It still holds
+/// whatever was last written into it (or `T::default()` if nothing was), which is not necessarily
+/// the latest value, so it is meant to be overwritten (possibly reusing its allocations) rather
+/// than updated.
+///
+/// If the guard is dropped by a panic, the slot is not published and the channel is poisoned.
+pub struct FutexWriteGuard<'a, T> {
+    inner: &'a Inner<T>,
+    idx: usize,
+    // whether the thread was already panicking when the guard was created
+    panicking: bool,
+    // the guard hands out `&mut T`, so it is only `Send` and `Sync` if that is
+    _marker: PhantomData<&'a mut T>,
+}
+
+impl<T> FutexWriteGuard<'_, T> {
+    /// Publishes the slot, same as dropping the guard.
+    pub fn commit(self) {}
+}
+
+impl<T> std::ops::Deref for FutexWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.inner.slots.read_from(self.idx)
+    }
+}
+
+impl<T> std::ops::DerefMut for FutexWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.inner.slots.slot(self.idx) }
+    }
+}
+
+impl<T> Drop for FutexWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        if !self.panicking && std::thread::panicking() {
+            self.inner.poison(self.idx);
+        } else {
+            self.inner.publish(self.idx);
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for FutexWriteGuard<'_, T>
+where
+    T: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T> WriteHandle<'_, T> {
+    /// Publishes the last written value (or `T::default()` before the first write) as modified
+    /// by `f`, so the producer does not have to keep its own copy of the current state.
+    ///
+    /// Unlike [`Writer::write_with`] this always starts from the latest value, at the cost of a
+    /// [`Clone::clone_from`] into the slot being written. The latest value may be read by both
+    /// sides at once, hence `T: Sync`.
+    pub fn update(&mut self, f: impl FnOnce(&mut T))
+    where
+        T: Clone + Default + Sync,
+    {
+        self.inner.update(f)
+    }
+
+    /// Writes the provided value unless it is equal to the last written one, sparing the reader a
+    /// redundant update. The first value is always written.
+    /// Returns whether the value was published.
+    ///
+    /// The comparison may run while the reader still holds the last value, hence `T: Sync`.
+    pub fn write_if_changed(&mut self, value: T) -> bool
+    where
+        T: PartialEq + Sync,
+    {
+        self.inner.write_if_changed(value)
+    }
+
+    /// Returns how many written values were replaced before the reader could read them.
+    pub fn overwritten(&self) -> u64 {
+        self.inner.overwritten.load(Ordering::Relaxed)
+    }
+
+    /// Returns how many values were published so far.
+    pub fn published(&self) -> u64 {
+        self.inner.slots.published()
+    }
+
+    /// Returns whether a write panicked halfway through, in which case any further write panics.
+    pub fn is_poisoned(&self) -> bool {
+        self.inner.slots.is_poisoned()
+    }
+
+    /// Returns the label given to [`named`], if any.
+    pub fn name(&self) -> Option<&'static str> {
+        self.inner.name
+    }
+
+    /// Returns a snapshot of the usage counters of the channel, shared with the reader.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.inner.stats.snapshot(self.inner.name)
+    }
+
+    /// Leases a free slot of the pool for writing in place, see [`FutexWriteGuard`].
+    ///
+    /// Taking `&mut self` guarantees no other write can happen while the lease is held.
+    pub fn begin_write(&mut self) -> FutexWriteGuard<'_, T>
+    where
+        T: Default,
+    {
+        let idx = self.inner.acquire();
+        let abort = self.inner.abort(idx);
+        self.inner.slots.fill(idx);
+        abort.disarm();
+        FutexWriteGuard {
+            inner: &self.inner,
+            idx,
+            panicking: std::thread::panicking(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Writer for WriteHandle<'_, T> {
+    type Item = T;
+
+    fn write(&mut self, value: T) {
+        self.inner.write(value)
+    }
+
+    fn write_with(&mut self, f: impl FnOnce(&mut T))
+    where
+        T: Default,
+    {
+        self.inner.write_with(f)
+    }
+
+    fn close(self) {
+        self.inner.closed.store(true, Ordering::Relaxed);
+        // dropping `self` disconnects the reader
+    }
+
+    fn is_reader_alive(&self) -> bool {
+        self.inner.reader_alive.load(Ordering::Acquire)
+    }
+
+    fn try_write(&mut self, value: T) -> Result<(), WriteError<T>> {
+        if !self.is_reader_alive() {
+            return Err(WriteError::Disconnected(value));
+        }
+        if self.inner.slots.is_poisoned() {
+            return Err(WriteError::Poisoned(value));
+        }
+        self.inner.write(value);
+        Ok(())
+    }
+}
+
+impl<'a, T> ReadHandle<'a, T> {
+    /// Turns the handle into an opaque pointer to the state of the channel, for places that cannot
+    /// hold a generic handle, such as the context of a C callback.
+    ///
+    /// The handle is not dropped: the channel still sees it alive until the pointer is turned back
+    /// into a handle by [`ReadHandle::from_raw`], which must happen exactly once to release it.
+    pub fn into_raw(self) -> *const () {
+        let this = std::mem::ManuallyDrop::new(self);
+        unsafe { std::ptr::read(&this.inner) }.into_raw()
+    }
+
+    /// Turns a pointer returned by [`ReadHandle::into_raw`] back into the handle.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must come from [`ReadHandle::into_raw`] on a handle of this module and of the same `T`,
+    /// and must be turned back only once: two handles built from it would share one side of the
+    /// channel. A handle of a [`Storage`] must not be turned back after the storage is gone.
+    pub unsafe fn from_raw(ptr: *const ()) -> Self {
+        ReadHandle {
+            inner: unsafe { Shared::from_raw(ptr) },
+        }
+    }
+}
+
+impl<T> Drop for ReadHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.reader_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<'a, T> WriteHandle<'a, T> {
+    /// Turns the handle into an opaque pointer to the state of the channel, for places that cannot
+    /// hold a generic handle, such as the context of a C callback.
+    ///
+    /// The handle is not dropped: the channel still sees it alive until the pointer is turned back
+    /// into a handle by [`WriteHandle::from_raw`], which must happen exactly once to release it.
+    pub fn into_raw(self) -> *const () {
+        let this = std::mem::ManuallyDrop::new(self);
+        unsafe { std::ptr::read(&this.inner) }.into_raw()
+    }
+
+    /// Turns a pointer returned by [`WriteHandle::into_raw`] back into the handle.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must come from [`WriteHandle::into_raw`] on a handle of this module and of the same `T`,
+    /// and must be turned back only once: two handles built from it would share one side of the
+    /// channel. A handle of a [`Storage`] must not be turned back after the storage is gone.
+    pub unsafe fn from_raw(ptr: *const ()) -> Self {
+        WriteHandle {
+            inner: unsafe { Shared::from_raw(ptr) },
+        }
+    }
+}
+
+impl<T> Drop for WriteHandle<'_, T> {
+    /// Drops the values of the free slots right away, the others are dropped along with the
+    /// channel.
+    fn drop(&mut self) {
+        self.inner.slots.discard_free();
+        self.inner.writer_alive.store(false, Ordering::SeqCst);
+        self.inner.wake();
+    }
+}
+
+impl<T> DebugState for Inner<T> {
+    fn debug_state(&self, d: &mut std::fmt::DebugStruct<'_, '_>) {
+        if let Some(name) = self.name {
+            d.field("name", &name);
+        }
+        self.slots.debug_state(d);
+        d.field("overwritten", &self.overwritten.load(Ordering::Relaxed))
+            .field("writer_alive", &self.writer_alive.load(Ordering::Relaxed))
+            .field("closed", &self.closed.load(Ordering::Relaxed))
+            .field("reader_alive", &self.reader_alive.load(Ordering::Relaxed));
+    }
+}
+
+impl<T> std::fmt::Debug for ReadHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "futex_spsc::ReadHandle")
+    }
+}
+
+impl<T> std::fmt::Debug for WriteHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "futex_spsc::WriteHandle")
+    }
+}
+
+/// Construct a new, empty read and write handle pair.
+///
+/// Reads fail with [`TryReadError::Empty`] until the first write.
+pub fn new<T>() -> (ReadHandle<'static, T>, WriteHandle<'static, T>) {
+    from_inner(Inner::new())
+}
+
+/// This variant as a [`crate::Channel`], for code generic over the variant.
+pub struct Channel;
+
+impl<T: 'static> crate::Channel<T> for Channel {
+    type Reader = ReadHandle<'static, T>;
+    type Writer = WriteHandle<'static, T>;
+
+    fn create() -> (Self::Reader, Self::Writer) {
+        new()
+    }
+}
+
+/// Construct a new, empty read and write handle pair labelled `name`, which shows up in `Debug`,
+/// `stats()` and the `tracing` events of the channel.
+pub fn named<T>(name: &'static str) -> (ReadHandle<'static, T>, WriteHandle<'static, T>) {
+    let mut inner = Inner::new();
+    inner.name = Some(name);
+    from_inner(inner)
+}
+
+/// Construct a new, empty read and write handle pair calling `hooks` on every read and write of
+/// the channel.
+#[cfg(feature = "hooks")]
+pub fn with_hooks<T>(hooks: impl Hooks) -> (ReadHandle<'static, T>, WriteHandle<'static, T>) {
+    let mut inner = Inner::new();
+    inner.hooks = Callbacks::new(hooks);
+    from_inner(inner)
+}
+
+fn from_inner<T>(inner: Inner<T>) -> (ReadHandle<'static, T>, WriteHandle<'static, T>) {
+    let inner = Arc::new(inner);
+    let r = ReadHandle {
+        inner: Shared::Arc(Arc::clone(&inner)),
+    };
+    let w = WriteHandle {
+        inner: Shared::Arc(inner),
+    };
+    (r, w)
+}
+
+/// Storage for a channel that is not reference counted, so that no allocation is needed.
+///
+/// It can be placed in a `static` or borrowed by the threads of a [`std::thread::scope`]: the
+/// handles returned by [`Storage::split`] borrow it, and are handed out only once.
+///
+/// ```
+/// use rustedrazors::futex_spsc::Storage;
+/// use rustedrazors::{Reader, Writer};
+///
+/// let storage = Storage::new();
+/// let (mut r, mut w) = storage.split().unwrap();
+/// std::thread::scope(|s| {
+///     s.spawn(move || w.write(42));
+/// });
+/// assert_eq!(r.read().as_deref(), Ok(&42));
+/// ```
+pub struct Storage<T> {
+    inner: Inner<T>,
+    split: AtomicBool,
+}
+
+impl<T> Storage<T> {
+    /// Constructs a new, empty storage.
+    pub const fn new() -> Self {
+        Storage {
+            inner: Inner::new(),
+            split: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the read and write handles borrowing the storage, or `None` if they were already
+    /// taken.
+    pub fn split(&self) -> Option<(ReadHandle<'_, T>, WriteHandle<'_, T>)> {
+        if self.split.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+        let r = ReadHandle {
+            inner: Shared::Borrowed(&self.inner),
+        };
+        let w = WriteHandle {
+            inner: Shared::Borrowed(&self.inner),
+        };
+        Some((r, w))
+    }
+}
+
+impl<T> Default for Storage<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
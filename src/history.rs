@@ -0,0 +1,217 @@
+use crate::atomic::{AtomicBool, AtomicU64, Ordering};
+use crate::debug::DebugState;
+use crate::shared::Shared;
+use crate::{TryReadError, Writer};
+
+use std::collections::vec_deque::{Drain, VecDeque};
+use std::sync::{Arc, Mutex};
+
+/// Last `capacity` values: the writer appends to `pending`, evicting the oldest value once it is
+/// full, and the reader swaps the whole queue out with its own empty one.
+///
+/// This sits between the latest-value modules, which keep a single value, and `ring_spsc`, which
+/// keeps every value but makes the writer wait for room.
+struct Inner<T> {
+    pending: Mutex<VecDeque<T>>,
+    capacity: usize,
+    // set while `pending` is not empty, only modified while holding it
+    dirty: AtomicBool,
+    // number of values evicted before being read, only modified by the writer
+    overwritten: AtomicU64,
+    writer_alive: AtomicBool,
+    // set right before `writer_alive` is cleared if the writer left through `close`
+    closed: AtomicBool,
+    reader_alive: AtomicBool,
+}
+
+/// Consumer side of the channel.
+///
+/// This is not a [`crate::Reader`]: every read yields all the values written since the last one
+/// instead of the latest.
+pub struct ReadHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+    // empty between drains, swapped with `pending` on the next one
+    drained: VecDeque<T>,
+}
+
+pub struct WriteHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+}
+
+impl<T> Inner<T> {
+    fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must not be zero");
+        Inner {
+            pending: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+            dirty: AtomicBool::new(false),
+            overwritten: AtomicU64::new(0),
+            writer_alive: AtomicBool::new(true),
+            closed: AtomicBool::new(false),
+            reader_alive: AtomicBool::new(true),
+        }
+    }
+
+    /// Appends the value returned by `f`, which is handed the oldest value if it had to be
+    /// evicted to make room.
+    fn push(&self, f: impl FnOnce(Option<T>) -> T) {
+        let mut pending = self.pending.lock().unwrap();
+        let evicted = if pending.len() == self.capacity {
+            self.overwritten.fetch_add(1, Ordering::Relaxed);
+            pending.pop_front()
+        } else {
+            None
+        };
+        pending.push_back(f(evicted));
+        self.dirty.store(true, Ordering::Release);
+    }
+
+    /// Error reported once the writer is gone and nothing is left to read.
+    fn disconnected(&self) -> TryReadError {
+        if self.closed.load(Ordering::Relaxed) {
+            TryReadError::Closed
+        } else {
+            TryReadError::Disconnected
+        }
+    }
+}
+
+impl<T> ReadHandle<'_, T> {
+    /// Takes every value written since the last drain, oldest first, at most `capacity` of them.
+    ///
+    /// The queue is only locked when something was written, for as long as it takes to swap it.
+    pub fn drain(&mut self) -> Drain<'_, T> {
+        if self.inner.dirty.load(Ordering::Acquire) {
+            let mut pending = self.inner.pending.lock().unwrap();
+            std::mem::swap(&mut *pending, &mut self.drained);
+            self.inner.dirty.store(false, Ordering::Relaxed);
+        }
+        self.drained.drain(..)
+    }
+
+    /// Takes every value written since the last drain, telling apart an empty channel from a dead
+    /// one.
+    ///
+    /// Returns [`TryReadError::Closed`] (or [`TryReadError::Disconnected`] if the writer was dropped
+    /// without closing) once the writer is gone and every value was drained.
+    pub fn try_drain(&mut self) -> Result<Drain<'_, T>, TryReadError> {
+        if !self.inner.dirty.load(Ordering::Acquire) {
+            if self.inner.writer_alive.load(Ordering::Acquire) {
+                return Err(TryReadError::Empty);
+            }
+            // the writer may have written right before being dropped
+            if !self.inner.dirty.load(Ordering::Acquire) {
+                return Err(self.inner.disconnected());
+            }
+        }
+        Ok(self.drain())
+    }
+
+    /// Returns whether some value was written since the last drain.
+    pub fn has_pending(&self) -> bool {
+        self.inner.dirty.load(Ordering::Relaxed)
+    }
+
+    /// Returns how many written values were evicted before they could be drained.
+    pub fn overwritten(&self) -> u64 {
+        self.inner.overwritten.load(Ordering::Relaxed)
+    }
+
+    /// Returns how many values the channel retains.
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity
+    }
+}
+
+impl<T> WriteHandle<'_, T> {
+    /// Returns how many written values were evicted before the reader could drain them.
+    pub fn overwritten(&self) -> u64 {
+        self.inner.overwritten.load(Ordering::Relaxed)
+    }
+
+    /// Returns how many values the channel retains.
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity
+    }
+}
+
+impl<T> Writer for WriteHandle<'_, T> {
+    type Item = T;
+
+    /// Appends `value`, evicting the oldest value not drained yet if there are `capacity` of them.
+    fn write(&mut self, value: T) {
+        self.inner.push(|_| value)
+    }
+
+    /// Appends a value built by `f`, which is handed the evicted value if there are `capacity`
+    /// values not drained yet, or `T::default()` otherwise.
+    fn write_with(&mut self, f: impl FnOnce(&mut T))
+    where
+        T: Default,
+    {
+        self.inner.push(|evicted| {
+            let mut value = evicted.unwrap_or_default();
+            f(&mut value);
+            value
+        })
+    }
+
+    fn close(self) {
+        self.inner.closed.store(true, Ordering::Relaxed);
+        // dropping `self` disconnects the reader
+    }
+
+    fn is_reader_alive(&self) -> bool {
+        self.inner.reader_alive.load(Ordering::Acquire)
+    }
+}
+
+impl<T> Drop for ReadHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.reader_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<T> Drop for WriteHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.writer_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<T> DebugState for Inner<T> {
+    fn debug_state(&self, d: &mut std::fmt::DebugStruct<'_, '_>) {
+        d.field("pending", &self.dirty.load(Ordering::Relaxed))
+            .field("capacity", &self.capacity)
+            .field("overwritten", &self.overwritten.load(Ordering::Relaxed))
+            .field("writer_alive", &self.writer_alive.load(Ordering::Relaxed))
+            .field("closed", &self.closed.load(Ordering::Relaxed))
+            .field("reader_alive", &self.reader_alive.load(Ordering::Relaxed));
+    }
+}
+
+impl<T> std::fmt::Debug for ReadHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "history::ReadHandle")
+    }
+}
+
+impl<T> std::fmt::Debug for WriteHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "history::WriteHandle")
+    }
+}
+
+/// Construct a new, empty read and write handle pair retaining up to `capacity` values.
+///
+/// Panics if `capacity` is zero.
+pub fn new<T>(capacity: usize) -> (ReadHandle<'static, T>, WriteHandle<'static, T>) {
+    let inner = Arc::new(Inner::new(capacity));
+    let r = ReadHandle {
+        inner: Shared::Arc(Arc::clone(&inner)),
+        drained: VecDeque::with_capacity(capacity),
+    };
+    let w = WriteHandle {
+        inner: Shared::Arc(inner),
+    };
+    (r, w)
+}
@@ -0,0 +1,84 @@
+//! User callbacks run inline on every read and write of a channel, for metrics, assertions or
+//! fault injection of your own.
+//!
+//! With the `hooks` feature, `atomic_spsc`, `blocking_spsc`, `futex_spsc`, `mutex_spsc` and
+//! `ticket_spsc` have a `with_hooks` constructor taking a [`Hooks`] implementation, called at the
+//! same points as the counters of the `stats` feature. A channel built without hooks only pays for
+//! checking that none were installed.
+//!
+//! ```
+//! use std::sync::atomic::{AtomicU64, Ordering};
+//!
+//! use rustedrazors::hooks::Hooks;
+//! use rustedrazors::{atomic_spsc, Reader, Writer};
+//!
+//! #[derive(Default)]
+//! struct Overwrites(AtomicU64);
+//!
+//! impl Hooks for Overwrites {
+//!     fn on_overwrite(&self) {
+//!         self.0.fetch_add(1, Ordering::Relaxed);
+//!     }
+//! }
+//!
+//! let (mut r, mut w) = atomic_spsc::with_hooks::<i32>(Overwrites::default());
+//! w.write(1);
+//! w.write(2);
+//! assert_eq!(r.read().as_deref(), Ok(&2));
+//! ```
+
+/// Callbacks of a channel, each one defaulting to doing nothing.
+///
+/// They run inline on the thread of the reader or of the writer, right after the operation took
+/// effect, so they should be as cheap as the operation itself: a slow hook slows down its side of
+/// the channel, which is also how to inject delays. A hook that panics unwinds through the caller,
+/// after the value was published or taken.
+pub trait Hooks: Send + Sync + 'static {
+    /// Called once a value is published.
+    fn on_write(&self) {}
+
+    /// Called once a read returned a value.
+    fn on_read_hit(&self) {}
+
+    /// Called when a read found no new value, including each check of the blocking reads before
+    /// they wait.
+    fn on_read_miss(&self) {}
+
+    /// Called right after [`Hooks::on_write`] when the published value replaced one that was never
+    /// read.
+    fn on_overwrite(&self) {}
+}
+
+/// Hooks installed by `with_hooks`, embedded in the shared state of a channel.
+pub(crate) struct Callbacks(Option<Box<dyn Hooks>>);
+
+impl Callbacks {
+    pub(crate) const fn none() -> Self {
+        Callbacks(None)
+    }
+
+    pub(crate) fn new(hooks: impl Hooks) -> Self {
+        Callbacks(Some(Box::new(hooks)))
+    }
+
+    /// Reports a read, which returned a value if `hit`.
+    pub(crate) fn read(&self, hit: bool) {
+        if let Some(hooks) = &self.0 {
+            if hit {
+                hooks.on_read_hit();
+            } else {
+                hooks.on_read_miss();
+            }
+        }
+    }
+
+    /// Reports a published value, which replaced an unread one if `overwrite`.
+    pub(crate) fn write(&self, overwrite: bool) {
+        if let Some(hooks) = &self.0 {
+            hooks.on_write();
+            if overwrite {
+                hooks.on_overwrite();
+            }
+        }
+    }
+}
@@ -0,0 +1,381 @@
+//! Latest-value channel from an interrupt handler to the main loop of a firmware.
+//!
+//! The writer is meant to live in an interrupt handler, so [`Writer::write`] must be safe to call
+//! there and the types make sure it is:
+//! - it never blocks or spins on the reader: it takes a free slot of a pool of three and publishes
+//!   it with the algorithm of `atomic_spsc`, in a bounded number of instructions (the publishing
+//!   CAS cannot fail on a single core, where the main loop does not run while the handler does,
+//!   and fails at most twice otherwise),
+//! - it never allocates nor frees: `T` must be [`Copy`], so writing is a plain copy and no
+//!   destructor ever runs, and the storage is a `static` built by the `const` [`Storage::new`],
+//! - it never wakes anybody: there is no parking reader to notify, which would take a lock the
+//!   main loop may be holding, so the main loop polls [`Reader::read`] (for instance after `wfi`).
+//!
+//! Reads copy the value out of the pool instead of leasing its slot, so no amount of guards kept
+//! alive by the main loop can leave the handler without a free slot.
+//!
+//! Along with `rtic`, this is what a `no_std` build of the crate (`default-features = false`)
+//! keeps. Targets without compare-and-swap, such as `thumbv6m-none-eabi`, also need the
+//! `portable-atomic` feature, with the `critical-section` feature of `portable-atomic` itself.
+//!
+//! ```
+//! use rustedrazors::isr_spsc::{ReadHandle, Storage, WriteHandle};
+//! use rustedrazors::{Reader, Writer};
+//!
+//! static SAMPLES: Storage<[u16; 4]> = Storage::new();
+//!
+//! // in the interrupt handler
+//! fn on_adc_done(w: &mut WriteHandle<[u16; 4]>, sample: [u16; 4]) {
+//!     w.write(sample);
+//! }
+//!
+//! let (mut r, mut w) = SAMPLES.split().unwrap();
+//! on_adc_done(&mut w, [1, 2, 3, 4]);
+//! assert_eq!(r.read().as_deref(), Ok(&[1, 2, 3, 4]));
+//! ```
+
+use crate::atomic::{AtomicBool, AtomicU32, Ordering};
+use crate::debug::DebugState;
+use crate::{Reader, TryReadError, Writer};
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+
+const POOL_SIZE: usize = 3;
+// layout of `state`, same as in `atomic_spsc`: bit `i` is set while slot `i` is free, the bits
+// from `BUFFER_SHIFT` hold the slot of the value waiting for the reader, or `NONE`
+const FREE_MASK: u32 = (1 << POOL_SIZE) - 1;
+const BUFFER_SHIFT: u32 = POOL_SIZE as u32;
+const NONE: u32 = 0b11;
+
+/// Returns the slot of the value waiting for the reader in `state`, if any.
+fn buffered(state: u32) -> Option<usize> {
+    match state >> BUFFER_SHIFT {
+        NONE => None,
+        idx => Some(idx as usize),
+    }
+}
+
+struct Inner<T> {
+    // no cache padding unlike `atomic_spsc`: microcontrollers have little RAM and no cache to
+    // contend on
+    pool: [UnsafeCell<MaybeUninit<T>>; POOL_SIZE],
+    // free slots and waiting value, see `FREE_MASK` and `BUFFER_SHIFT`
+    state: AtomicU32,
+    // number of values replaced before being read, only modified by the writer: 32 bits wide, so
+    // that it is native on every target and cannot end up behind the lock of an emulated 64-bit
+    // atomic, which the handler would spin on forever if it interrupted the main loop holding it
+    overwritten: AtomicU32,
+    writer_alive: AtomicBool,
+    // set right before `writer_alive` is cleared if the writer left through `close`
+    closed: AtomicBool,
+    reader_alive: AtomicBool,
+}
+
+/// Safety: each slot is only ever accessed by the side that took it out of `state`.
+unsafe impl<T> Sync for Inner<T> where T: Send {}
+
+pub struct ReadHandle<T: 'static> {
+    inner: &'static Inner<T>,
+}
+
+/// Write handle, safe to use from an interrupt handler, see the [module documentation](self).
+pub struct WriteHandle<T: 'static> {
+    inner: &'static Inner<T>,
+}
+
+impl<T> Inner<T> {
+    const fn new() -> Self {
+        Inner {
+            pool: [const { UnsafeCell::new(MaybeUninit::uninit()) }; POOL_SIZE],
+            state: AtomicU32::new(FREE_MASK | NONE << BUFFER_SHIFT),
+            overwritten: AtomicU32::new(0),
+            writer_alive: AtomicBool::new(true),
+            closed: AtomicBool::new(false),
+            reader_alive: AtomicBool::new(true),
+        }
+    }
+
+    fn slot(&self, idx: usize) -> *mut T {
+        unsafe { self.pool.get_unchecked(idx).get().cast() }
+    }
+
+    /// Returns the index of a free slot of the pool, while marking it as in use.
+    ///
+    /// The reader holds at most one slot, only while copying out of it, and at most one more
+    /// holds the waiting value, so one is always free.
+    fn acquire(&self) -> usize {
+        // only the writer clears free bits, so the slot cannot be taken in between
+        let free = self.state.load(Ordering::Acquire) & FREE_MASK;
+        debug_assert!(free != 0, "no free slot in the pool");
+        let idx = free.trailing_zeros();
+        self.state.fetch_and(!(1 << idx), Ordering::Relaxed);
+        idx as usize
+    }
+
+    /// Makes the slot at the given index the waiting value, freeing the one it replaces.
+    ///
+    /// The CAS can only fail if the reader took the waiting value or released a slot in the
+    /// meantime, which it does at most once each per publish.
+    fn publish(&self, idx: usize) {
+        let mut state = self.state.load(Ordering::Relaxed);
+        loop {
+            let mut new = state & FREE_MASK | (idx as u32) << BUFFER_SHIFT;
+            if let Some(buffer) = buffered(state) {
+                new |= 1 << buffer;
+            }
+            match self
+                .state
+                .compare_exchange_weak(state, new, Ordering::AcqRel, Ordering::Relaxed)
+            {
+                Ok(_) => break,
+                Err(current) => state = current,
+            }
+        }
+        if buffered(state).is_some() {
+            // the writer is the only one modifying it, no read-modify-write is needed
+            let overwritten = self.overwritten.load(Ordering::Relaxed);
+            self.overwritten
+                .store(overwritten.wrapping_add(1), Ordering::Relaxed);
+        }
+    }
+}
+
+impl<T> Inner<T>
+where
+    T: Copy,
+{
+    fn write(&self, value: T) {
+        let idx = self.acquire();
+        unsafe { self.slot(idx).write(value) }
+        self.publish(idx);
+    }
+
+    /// Copies out the waiting value, if any, then hands its slot back to the writer.
+    fn read(&self) -> Option<IsrGuard<T>> {
+        let state = self.state.fetch_or(NONE << BUFFER_SHIFT, Ordering::AcqRel);
+        let idx = buffered(state)?;
+        let value = unsafe { self.slot(idx).read() };
+        self.state.fetch_or(1 << idx, Ordering::Release);
+        Some(IsrGuard(value))
+    }
+
+    fn try_read(&self) -> Result<IsrGuard<T>, TryReadError> {
+        if let Some(guard) = self.read() {
+            return Ok(guard);
+        }
+        if self.writer_alive.load(Ordering::Acquire) {
+            return Err(TryReadError::Empty);
+        }
+        // the writer may have published right before being dropped
+        self.read().ok_or_else(|| self.disconnected())
+    }
+
+    /// Error reported once the writer is gone and nothing is left to read.
+    fn disconnected(&self) -> TryReadError {
+        if self.closed.load(Ordering::Relaxed) {
+            TryReadError::Closed
+        } else {
+            TryReadError::Disconnected
+        }
+    }
+}
+
+/// Read value, copied out of the pool.
+#[derive(Debug, Clone, Copy)]
+pub struct IsrGuard<T>(T);
+
+impl<T> IsrGuard<T> {
+    /// Returns the read value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> core::ops::Deref for IsrGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> ReadHandle<T> {
+    /// Returns how many written values were replaced before they could be read, wrapping around
+    /// after `u32::MAX`.
+    pub fn overwritten(&self) -> u64 {
+        self.inner.overwritten.load(Ordering::Relaxed).into()
+    }
+
+    /// Returns whether a value was written since the last read, without taking it.
+    pub fn has_changed(&self) -> bool {
+        buffered(self.inner.state.load(Ordering::Acquire)).is_some()
+    }
+}
+
+impl<T> WriteHandle<T> {
+    /// Returns how many written values were replaced before the reader could read them, wrapping
+    /// around after `u32::MAX`.
+    pub fn overwritten(&self) -> u64 {
+        self.inner.overwritten.load(Ordering::Relaxed).into()
+    }
+}
+
+impl<T> Reader for ReadHandle<T>
+where
+    T: Copy,
+{
+    type Item = T;
+    type Guard<'a>
+        = IsrGuard<T>
+    where
+        Self: 'a;
+
+    fn read(&mut self) -> Result<Self::Guard<'_>, TryReadError> {
+        self.inner.try_read()
+    }
+}
+
+impl<T> Writer for WriteHandle<T>
+where
+    T: Copy,
+{
+    type Item = T;
+
+    /// Publishes `value`, replacing any value that was not read yet.
+    ///
+    /// Wait-free, allocation-free and notification-free, so it can be called from an interrupt
+    /// handler.
+    fn write(&mut self, value: T) {
+        self.inner.write(value)
+    }
+
+    /// Publishes a value built in place by `f`, starting from `T::default()`.
+    ///
+    /// `f` runs in the caller's context, so from an interrupt handler it must keep to the same
+    /// contract as the handler itself.
+    fn write_with(&mut self, f: impl FnOnce(&mut T))
+    where
+        T: Default,
+    {
+        let idx = self.inner.acquire();
+        unsafe {
+            let slot = self.inner.slot(idx);
+            slot.write(T::default());
+            f(&mut *slot);
+        }
+        self.inner.publish(idx);
+    }
+
+    fn close(self) {
+        self.inner.closed.store(true, Ordering::Relaxed);
+        // dropping `self` disconnects the reader
+    }
+
+    fn is_reader_alive(&self) -> bool {
+        self.inner.reader_alive.load(Ordering::Acquire)
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<T> defmt::Format for ReadHandle<T> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(
+            f,
+            "ReadHandle {{ overwritten: {=u64}, writer_alive: {=bool} }}",
+            self.overwritten(),
+            self.inner.writer_alive.load(Ordering::Relaxed),
+        )
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<T> defmt::Format for WriteHandle<T> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(
+            f,
+            "WriteHandle {{ overwritten: {=u64}, reader_alive: {=bool} }}",
+            self.overwritten(),
+            self.inner.reader_alive.load(Ordering::Relaxed),
+        )
+    }
+}
+
+impl<T> Drop for ReadHandle<T> {
+    fn drop(&mut self) {
+        self.inner.reader_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<T> Drop for WriteHandle<T> {
+    fn drop(&mut self) {
+        self.inner.writer_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<T> DebugState for Inner<T> {
+    fn debug_state(&self, d: &mut core::fmt::DebugStruct<'_, '_>) {
+        let state = self.state.load(Ordering::Relaxed);
+        d.field("pending", &buffered(state).is_some())
+            .field("free_slots", &(state & FREE_MASK).count_ones())
+            .field("overwritten", &self.overwritten.load(Ordering::Relaxed))
+            .field("writer_alive", &self.writer_alive.load(Ordering::Relaxed))
+            .field("closed", &self.closed.load(Ordering::Relaxed))
+            .field("reader_alive", &self.reader_alive.load(Ordering::Relaxed));
+    }
+}
+
+impl<T> core::fmt::Debug for ReadHandle<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.inner.fmt_handle(f, "isr_spsc::ReadHandle")
+    }
+}
+
+impl<T> core::fmt::Debug for WriteHandle<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.inner.fmt_handle(f, "isr_spsc::WriteHandle")
+    }
+}
+
+/// Storage for a channel, to be placed in a `static`.
+///
+/// There is no reference counted constructor: [`Storage::split`] only accepts a `'static` storage,
+/// so the handles can be moved into whatever `static` the interrupt handler reads them from, and
+/// are handed out only once.
+pub struct Storage<T> {
+    inner: Inner<T>,
+    split: AtomicBool,
+}
+
+impl<T> Storage<T>
+where
+    T: Copy,
+{
+    /// Constructs a new, empty storage.
+    pub const fn new() -> Self {
+        Storage {
+            inner: Inner::new(),
+            split: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the read and write handles borrowing the storage, or `None` if they were already
+    /// taken.
+    pub fn split(&'static self) -> Option<(ReadHandle<T>, WriteHandle<T>)> {
+        if self.split.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+        let r = ReadHandle { inner: &self.inner };
+        let w = WriteHandle { inner: &self.inner };
+        Some((r, w))
+    }
+}
+
+impl<T> Default for Storage<T>
+where
+    T: Copy,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
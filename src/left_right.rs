@@ -0,0 +1,316 @@
+use crate::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use crate::backoff;
+use crate::debug::DebugState;
+use crate::shared::Shared;
+use crate::{Reader, TryReadError, Writer};
+
+use std::cell::UnsafeCell;
+use std::sync::Arc;
+
+// value of `reading` while the reader holds no guard
+const IDLE: usize = 2;
+
+/// Two full copies of the value: the reader reads the active one while the writer modifies the
+/// other, then swaps them and replays the same operation on the old copy before its next write.
+///
+/// The reader only ever loads and stores, with no read-modify-write: it announces the side it is
+/// about to read in `reading` and checks that `active` did not move meanwhile, and the writer waits
+/// for it to leave a side before touching it.
+struct Inner<T> {
+    sides: [UnsafeCell<T>; 2],
+    // number of values published times two, plus the side readers should read
+    active: AtomicU64,
+    // side the reader holds guards on, or `IDLE`
+    reading: AtomicUsize,
+    writer_alive: AtomicBool,
+    // set right before `writer_alive` is cleared if the writer left through `close`
+    closed: AtomicBool,
+    reader_alive: AtomicBool,
+}
+
+/// Safety: the writer only modifies the inactive side once the reader left it, and the reader
+/// only reads the active one.
+unsafe impl<T> Sync for Inner<T> where T: Send + Sync {}
+
+/// Operation still to be replayed on the copy that was active during the last write.
+enum Op<T> {
+    Apply(Box<dyn FnMut(&mut T) + Send>),
+    // clones the other copy, for writes that cannot be replayed
+    CopyFrom(fn(&mut T, &T)),
+}
+
+pub struct ReadHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+    // `active` when the reader last read
+    seen: u64,
+}
+
+pub struct WriteHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+    pending: Option<Op<T>>,
+}
+
+impl<T> Inner<T>
+where
+    T: Default,
+{
+    fn new() -> Self {
+        Inner {
+            sides: [UnsafeCell::new(T::default()), UnsafeCell::new(T::default())],
+            active: AtomicU64::new(0),
+            reading: AtomicUsize::new(IDLE),
+            writer_alive: AtomicBool::new(true),
+            closed: AtomicBool::new(false),
+            reader_alive: AtomicBool::new(true),
+        }
+    }
+}
+
+impl<T> Inner<T> {
+    /// Error reported once the writer is gone and nothing is left to read.
+    fn disconnected(&self) -> TryReadError {
+        if self.closed.load(Ordering::Relaxed) {
+            TryReadError::Closed
+        } else {
+            TryReadError::Disconnected
+        }
+    }
+}
+
+/// Lease on the active copy at the time of the read.
+///
+/// The writer does not touch that copy again until the lease is dropped, so writes may wait for
+/// it: guards are meant to be short lived.
+pub struct LeftRightGuard<'a, T> {
+    inner: &'a Inner<T>,
+    side: usize,
+}
+
+impl<T> std::ops::Deref for LeftRightGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.inner.sides[self.side].get() }
+    }
+}
+
+impl<T> Drop for LeftRightGuard<'_, T> {
+    fn drop(&mut self) {
+        self.inner.reading.store(IDLE, Ordering::Release);
+    }
+}
+
+impl<T> std::fmt::Debug for LeftRightGuard<'_, T>
+where
+    T: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T> WriteHandle<'_, T> {
+    /// Publishes the result of applying `op` to the current value.
+    ///
+    /// `op` is applied to the inactive copy right away, and replayed on the other one at the
+    /// beginning of the next write, so it must have the same effect both times. This is cheaper
+    /// than [`Writer::write`] when `op` only touches a small part of a large value.
+    pub fn apply(&mut self, mut op: impl FnMut(&mut T) + Send + 'static) {
+        let side = self.begin_write();
+        op(unsafe { &mut *self.inner.sides[side].get() });
+        self.publish(side, Op::Apply(Box::new(op)));
+    }
+
+    /// Waits for the reader to leave the inactive copy, brings it up to date and returns it.
+    fn begin_write(&mut self) -> usize {
+        let active = self.inner.active.load(Ordering::Relaxed);
+        let side = (active & 1) as usize ^ 1;
+        let mut spins = 0;
+        while self.inner.reading.load(Ordering::SeqCst) == side {
+            backoff::snooze(spins);
+            spins += 1;
+        }
+        // the reader may be reading the active copy at the same time, but never modifies it
+        let (dst, src) = unsafe {
+            (
+                &mut *self.inner.sides[side].get(),
+                &*self.inner.sides[side ^ 1].get(),
+            )
+        };
+        match self.pending.take() {
+            Some(Op::Apply(mut op)) => op(dst),
+            Some(Op::CopyFrom(copy)) => copy(dst, src),
+            None => {}
+        }
+        side
+    }
+
+    /// Makes `side` the active copy, keeping `op` to replay on the other one.
+    fn publish(&mut self, side: usize, op: Op<T>) {
+        let active = self.inner.active.load(Ordering::Relaxed);
+        let version = (active >> 1) + 1;
+        self.inner
+            .active
+            .store(version << 1 | side as u64, Ordering::SeqCst);
+        self.pending = Some(op);
+    }
+}
+
+impl<T> ReadHandle<'_, T> {
+    /// Announces the active copy in `reading` so that the writer leaves it alone, and returns its
+    /// side to be leased by [`ReadHandle::lease`].
+    ///
+    /// Returns `None` if `active` is `seen`, and does not write anything but `reading`.
+    fn enter(&mut self) -> Option<usize> {
+        loop {
+            let active = self.inner.active.load(Ordering::Acquire);
+            if active == self.seen {
+                return None;
+            }
+            let side = (active & 1) as usize;
+            self.inner.reading.store(side, Ordering::SeqCst);
+            // if the writer swapped in the meantime it may not have seen us on the old side
+            if self.inner.active.load(Ordering::SeqCst) == active {
+                self.seen = active;
+                return Some(side);
+            }
+            self.inner.reading.store(IDLE, Ordering::Release);
+        }
+    }
+
+    fn lease(&self, side: usize) -> LeftRightGuard<'_, T> {
+        LeftRightGuard {
+            inner: &self.inner,
+            side,
+        }
+    }
+}
+
+impl<T> Reader for ReadHandle<'_, T> {
+    type Item = T;
+    type Guard<'a>
+        = LeftRightGuard<'a, T>
+    where
+        Self: 'a;
+
+    /// Try reading the last published value.
+    fn read(&mut self) -> Result<Self::Guard<'_>, TryReadError> {
+        let side = match self.enter() {
+            Some(side) => side,
+            None if self.inner.writer_alive.load(Ordering::Acquire) => {
+                return Err(TryReadError::Empty)
+            }
+            // the writer may have published right before being dropped
+            None => self.enter().ok_or_else(|| self.inner.disconnected())?,
+        };
+        Ok(self.lease(side))
+    }
+}
+
+impl<T> Writer for WriteHandle<'_, T>
+where
+    T: Clone,
+{
+    type Item = T;
+
+    /// Publishes `value`, which is cloned into the other copy before the next write.
+    fn write(&mut self, value: T) {
+        let side = self.begin_write();
+        unsafe { *self.inner.sides[side].get() = value }
+        self.publish(side, Op::CopyFrom(T::clone_from));
+    }
+
+    /// Publishes the last written value as modified by `f`, which is cloned into the other copy
+    /// before the next write. See [`WriteHandle::apply`] to replay `f` instead.
+    fn write_with(&mut self, f: impl FnOnce(&mut T))
+    where
+        T: Default,
+    {
+        let side = self.begin_write();
+        f(unsafe { &mut *self.inner.sides[side].get() });
+        self.publish(side, Op::CopyFrom(T::clone_from));
+    }
+
+    fn close(self) {
+        self.inner.closed.store(true, Ordering::Relaxed);
+        // dropping `self` disconnects the reader
+    }
+
+    fn is_reader_alive(&self) -> bool {
+        self.inner.reader_alive.load(Ordering::Acquire)
+    }
+}
+
+impl<T> Drop for ReadHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.reader_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<T> Drop for WriteHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.writer_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<T> DebugState for Inner<T> {
+    fn debug_state(&self, d: &mut std::fmt::DebugStruct<'_, '_>) {
+        let active = self.active.load(Ordering::Relaxed);
+        let reading = self.reading.load(Ordering::Relaxed);
+        d.field("published", &(active >> 1))
+            .field("active_side", &(active & 1))
+            .field("reading", &(reading != IDLE).then_some(reading))
+            .field("writer_alive", &self.writer_alive.load(Ordering::Relaxed))
+            .field("closed", &self.closed.load(Ordering::Relaxed))
+            .field("reader_alive", &self.reader_alive.load(Ordering::Relaxed));
+    }
+}
+
+impl<T> std::fmt::Debug for ReadHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut d = f.debug_struct("left_right::ReadHandle");
+        self.inner.debug_state(&mut d);
+        d.field(
+            "pending",
+            &(self.inner.active.load(Ordering::Relaxed) != self.seen),
+        )
+        .finish()
+    }
+}
+
+impl<T> std::fmt::Debug for WriteHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "left_right::WriteHandle")
+    }
+}
+
+/// Construct a new read and write handle pair, with both copies set to `T::default()`.
+///
+/// Reads fail with [`TryReadError::Empty`] until the first write.
+pub fn new<T>() -> (ReadHandle<'static, T>, WriteHandle<'static, T>)
+where
+    T: Default,
+{
+    let inner = Arc::new(Inner::new());
+    let r = ReadHandle {
+        inner: Shared::Arc(Arc::clone(&inner)),
+        seen: 0,
+    };
+    let w = WriteHandle {
+        inner: Shared::Arc(inner),
+        pending: None,
+    };
+    (r, w)
+}
+
+/// This variant as a [`crate::Channel`], for code generic over the variant.
+pub struct Channel;
+
+impl<T: Clone + Default + 'static> crate::Channel<T> for Channel {
+    type Reader = ReadHandle<'static, T>;
+    type Writer = WriteHandle<'static, T>;
+
+    fn create() -> (Self::Reader, Self::Writer) {
+        new()
+    }
+}
@@ -1,22 +1,425 @@
+// without `std` only the traits, the adapters, `cs_spsc`, `isr_spsc` and `rtic` are left, see
+// `Cargo.toml`
+#![cfg_attr(not(feature = "std"), no_std)]
+
+/// Consumer side of a channel.
+///
+/// Every module in this crate implements this trait for its `ReadHandle`, so code can be written
+/// once generically over `R: Reader<Item = T>` and run against any variant.
+///
+/// Reads take `&mut self`, so that a handle is only ever used by one thread at a time and holds
+/// at most one guard: variants leasing their guards out of a fixed pool of slots (`atomic_spsc`,
+/// `futex_spsc`, `left_right`, `triple_buffer`...) rely on it to always leave the writer a slot to
+/// write into.
 pub trait Reader {
     /// Underlying item we are reading
     type Item;
     /// MutexGuard-like handle to be returned
-    type Guard<'a>: std::ops::Deref<Target = Self::Item>
+    type Guard<'a>: core::ops::Deref<Target = Self::Item>
     where
         Self: 'a;
 
-    fn read(&self) -> Option<Self::Guard<'_>>;
+    /// Try reading the last written value.
+    ///
+    /// Fails with [`TryReadError::Empty`] if no new value was written since the last read, and
+    /// with [`TryReadError::Closed`] (or [`TryReadError::Disconnected`] if the writer was dropped
+    /// without closing) once the writer is gone and its last value was read.
+    fn read(&mut self) -> Result<Self::Guard<'_>, TryReadError>;
+
+    /// Try reading the last written value, handing it to `f` while the variant still protects it.
+    /// Fails like [`Reader::read`], without calling `f`.
+    ///
+    /// Consumers that only need to inspect the value can use this without requiring `Clone`.
+    fn read_with<R>(&mut self, f: impl FnOnce(&Self::Item) -> R) -> Result<R, TryReadError> {
+        self.read().map(|guard| f(&guard))
+    }
+
+    /// Try reading the last written value directly into `value`.
+    /// Fails like [`Reader::read`], leaving `value` untouched.
+    ///
+    /// This reuses the storage of `value` through [`Clone::clone_from`] instead of moving a fresh
+    /// clone out of the channel.
+    fn read_into(&mut self, value: &mut Self::Item) -> Result<(), TryReadError>
+    where
+        Self::Item: Clone,
+    {
+        let guard = self.read()?;
+        value.clone_from(&guard);
+        Ok(())
+    }
+
+    /// Wraps the reader so that every read returns `f` applied to the value, exposing only the view
+    /// of it a consumer needs, such as one field of a large state.
+    ///
+    /// `f` runs while the variant still protects the value, and the result is owned by the
+    /// returned guard.
+    fn map<U, F>(self, f: F) -> adapters::MappedReader<Self, F>
+    where
+        Self: Sized,
+        F: Fn(&Self::Item) -> U,
+    {
+        adapters::MappedReader::new(self, f)
+    }
+
+    /// Wraps the reader so that it returns at most one value per `interval`, the values written in
+    /// between being conflated by the channel into the newest.
+    #[cfg(all(feature = "std", not(target_family = "wasm")))]
+    fn throttle(self, interval: std::time::Duration) -> adapters::Throttled<Self>
+    where
+        Self: Sized,
+    {
+        adapters::Throttled::new(self, interval)
+    }
+
+    /// Wraps the reader so that it returns a value only once no newer one was written for `quiet`,
+    /// letting a burst of updates settle into its last value.
+    #[cfg(all(feature = "std", not(target_family = "wasm")))]
+    fn debounce(self, quiet: std::time::Duration) -> adapters::Debounced<Self>
+    where
+        Self: Sized,
+        Self::Item: Clone,
+    {
+        adapters::Debounced::new(self, quiet)
+    }
+}
+
+/// Error returned by [`Reader::read`] and the blocking reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TryReadError {
+    /// No new value was written since the last read.
+    Empty,
+    /// No new value was written since the last read and the writer was dropped, so none ever will.
+    Disconnected,
+    /// The writer closed the channel and its last value was read.
+    Closed,
+    /// A write panicked halfway through, so the channel may no longer hold a consistent value.
+    Poisoned,
 }
 
+impl core::fmt::Display for TryReadError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            TryReadError::Empty => f.write_str("no new value was written"),
+            TryReadError::Disconnected => f.write_str("the writer was dropped"),
+            TryReadError::Closed => f.write_str("the channel was closed"),
+            TryReadError::Poisoned => f.write_str("a write panicked"),
+        }
+    }
+}
+
+impl core::error::Error for TryReadError {}
+
+/// Producer side of a channel.
+///
+/// Writes take `&mut self` for the same reason reads do: see [`Reader`].
 pub trait Writer {
     /// Underlying item we are writing
     type Item;
 
-    fn write(&self, value: Self::Item);
+    /// Publishes `value`, replacing any value that was not read yet.
+    fn write(&mut self, value: Self::Item);
+
+    /// Publishes a value built in place by `f`, replacing any value that was not read yet.
+    ///
+    /// `f` gets exclusive access to the storage the value will be read from, so nothing has to be
+    /// moved or copied into the channel. Single-slot variants (`mutex_spsc`, `ticket_spsc`) hand
+    /// out the last written value, pool-based variants hand out a free slot that still holds some
+    /// older value (or `Default::default()` if it never held one): either way `f` should leave it
+    /// in the state it wants published.
+    ///
+    /// The handle stays borrowed while `f` runs, so `f` cannot write through it: the variants
+    /// holding a lock or their last free slot meanwhile (`mutex_spsc`, `ticket_spsc`,
+    /// `blocking_spsc`) would wait on themselves forever if it did.
+    fn write_with(&mut self, f: impl FnOnce(&mut Self::Item))
+    where
+        Self::Item: Default;
+
+    /// Marks the channel as finished: the reader can still read the last written value, then every
+    /// read fails with [`TryReadError::Closed`].
+    fn close(self)
+    where
+        Self: Sized;
+
+    /// Returns whether the reader still exists, so that producers can stop once nobody listens.
+    fn is_reader_alive(&self) -> bool;
+
+    /// Publishes `value` unless it cannot be published right away, handing it back in the error.
+    ///
+    /// Fails with [`WriteError::Disconnected`] once the reader is gone. Variants whose writes can
+    /// wait on the readers or on other writers (`blocking_spsc`, `mpmc_latest`, `mpsc_latest`,
+    /// `mutex_spsc`, `ring_spsc`, `ticket_spsc`) fail with [`WriteError::WouldBlock`] instead of
+    /// waiting, and those that refuse writes after a panic with [`WriteError::Poisoned`] instead
+    /// of panicking.
+    fn try_write(&mut self, value: Self::Item) -> Result<(), WriteError<Self::Item>> {
+        if !self.is_reader_alive() {
+            return Err(WriteError::Disconnected(value));
+        }
+        self.write(value);
+        Ok(())
+    }
+
+    /// Wraps the writer so that it takes values of another type, converted by `f` before being
+    /// written.
+    fn with_map<U, F>(self, f: F) -> adapters::MappedWriter<Self, F, U>
+    where
+        Self: Sized,
+        F: Fn(U) -> Self::Item,
+    {
+        adapters::MappedWriter::new(self, f)
+    }
+
+    /// Wraps the writer so that the values rejected by `predicate` are dropped instead of written,
+    /// sparing the reader updates it has no use for.
+    fn with_filter<P>(self, predicate: P) -> adapters::FilteredWriter<Self, P>
+    where
+        Self: Sized,
+        P: Fn(&Self::Item) -> bool,
+    {
+        adapters::FilteredWriter::new(self, predicate)
+    }
+}
+
+/// Error returned by [`Writer::try_write`], holding the value that was not published.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum WriteError<T> {
+    /// The reader was dropped, so nobody would read the value.
+    Disconnected(T),
+    /// Publishing the value would have waited on the reader.
+    WouldBlock(T),
+    /// A previous write panicked halfway through, so the channel no longer takes values.
+    Poisoned(T),
+}
+
+impl<T> WriteError<T> {
+    /// Returns the value that was not published.
+    pub fn into_inner(self) -> T {
+        match self {
+            WriteError::Disconnected(value)
+            | WriteError::WouldBlock(value)
+            | WriteError::Poisoned(value) => value,
+        }
+    }
+}
+
+impl<T> core::fmt::Display for WriteError<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            WriteError::Disconnected(_) => f.write_str("the reader was dropped"),
+            WriteError::WouldBlock(_) => f.write_str("the write would have blocked"),
+            WriteError::Poisoned(_) => f.write_str("a previous write panicked"),
+        }
+    }
 }
 
+impl<T: core::fmt::Debug> core::error::Error for WriteError<T> {}
+
+// forwarding impls, so that handles can be lent or boxed to generic code: every method is
+// forwarded, not just the required ones, to keep the overrides of the variant
+
+impl<R: Reader> Reader for &mut R {
+    type Item = R::Item;
+    type Guard<'a>
+        = R::Guard<'a>
+    where
+        Self: 'a;
+
+    fn read(&mut self) -> Result<Self::Guard<'_>, TryReadError> {
+        (**self).read()
+    }
+
+    fn read_with<U>(&mut self, f: impl FnOnce(&Self::Item) -> U) -> Result<U, TryReadError> {
+        (**self).read_with(f)
+    }
+
+    fn read_into(&mut self, value: &mut Self::Item) -> Result<(), TryReadError>
+    where
+        Self::Item: Clone,
+    {
+        (**self).read_into(value)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Reader> Reader for Box<R> {
+    type Item = R::Item;
+    type Guard<'a>
+        = R::Guard<'a>
+    where
+        Self: 'a;
+
+    fn read(&mut self) -> Result<Self::Guard<'_>, TryReadError> {
+        (**self).read()
+    }
+
+    fn read_with<U>(&mut self, f: impl FnOnce(&Self::Item) -> U) -> Result<U, TryReadError> {
+        (**self).read_with(f)
+    }
+
+    fn read_into(&mut self, value: &mut Self::Item) -> Result<(), TryReadError>
+    where
+        Self::Item: Clone,
+    {
+        (**self).read_into(value)
+    }
+}
+
+// no impls for `&W` and `&mut W`: `close` consumes the handle, which a borrow cannot give away
+#[cfg(feature = "std")]
+impl<W: Writer> Writer for Box<W> {
+    type Item = W::Item;
+
+    fn write(&mut self, value: Self::Item) {
+        (**self).write(value)
+    }
+
+    fn write_with(&mut self, f: impl FnOnce(&mut Self::Item))
+    where
+        Self::Item: Default,
+    {
+        (**self).write_with(f)
+    }
+
+    fn close(self) {
+        (*self).close()
+    }
+
+    fn is_reader_alive(&self) -> bool {
+        (**self).is_reader_alive()
+    }
+
+    fn try_write(&mut self, value: Self::Item) -> Result<(), WriteError<Self::Item>> {
+        (**self).try_write(value)
+    }
+}
+
+/// Variant of channel as a type, so that code can be written once generically over
+/// `C: Channel<T>` and run against any module building its own handles.
+///
+/// Every module with a plain constructor exports a `Channel` type implementing it, such as
+/// [`atomic_spsc::Channel`].
+pub trait Channel<T> {
+    /// Consumer side of the channel
+    type Reader: Reader<Item = T>;
+    /// Producer side of the channel
+    type Writer: Writer<Item = T>;
+
+    /// Construct a new, empty read and write handle pair.
+    fn create() -> (Self::Reader, Self::Writer);
+}
+
+/// Construct a new, empty read and write handle pair of the recommended variant, `atomic_spsc`.
+///
+/// It fits most uses: reads and writes never wait on each other, any `T` goes, and the reader can
+/// still block until a value is published. The other modules, or [`channel::Channel::builder`],
+/// are there once a workload calls for another tradeoff.
+#[cfg(feature = "std")]
+pub fn channel<T>() -> (
+    atomic_spsc::ReadHandle<'static, T>,
+    atomic_spsc::WriteHandle<'static, T>,
+) {
+    atomic_spsc::new()
+}
+
+pub mod adapters;
+#[cfg(feature = "std")]
+pub mod arc_spsc;
+#[cfg(feature = "std")]
+pub mod atomic_cell_spsc;
+#[cfg(feature = "std")]
 pub mod atomic_spsc;
+#[cfg(feature = "std")]
 pub mod blocking_spsc;
+#[cfg(feature = "std")]
+pub mod box_spsc;
+#[cfg(all(feature = "std", not(target_family = "wasm")))]
+pub mod bridge;
+#[cfg(feature = "std")]
+pub mod byte_spsc;
+#[cfg(feature = "std")]
+pub mod channel;
+#[cfg(feature = "std")]
+pub mod conflating_map;
+#[cfg(feature = "critical-section")]
+pub mod cs_spsc;
+#[cfg(feature = "std")]
+pub mod disruptor;
+#[cfg(feature = "std")]
+pub mod double_buffer;
+#[cfg(feature = "epoch")]
+pub mod epoch_spsc;
+#[cfg(all(feature = "std", target_os = "linux"))]
+pub mod eventfd;
+#[cfg(feature = "std")]
+pub mod fanin;
+#[cfg(feature = "std")]
+pub mod fanout;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(all(feature = "std", any(target_os = "linux", windows)))]
+pub mod futex_spsc;
+#[cfg(feature = "std")]
+pub mod history;
+#[cfg(feature = "hooks")]
+pub mod hooks;
+pub mod isr_spsc;
+#[cfg(feature = "std")]
+pub mod left_right;
+#[cfg(feature = "std")]
+pub mod mpmc_latest;
+#[cfg(feature = "std")]
+pub mod mpsc_latest;
+#[cfg(feature = "std")]
 pub mod mutex_spsc;
+#[cfg(feature = "std")]
+pub mod oneshot;
+#[cfg(all(feature = "std", not(target_family = "wasm")))]
+pub mod pipeline;
+#[cfg(feature = "pyo3")]
+pub mod python;
+#[cfg(feature = "std")]
+pub mod recycle_spsc;
+#[cfg(feature = "std")]
+pub mod ring_spsc;
+#[cfg(feature = "rtic")]
+pub mod rtic;
+#[cfg(all(feature = "std", not(target_family = "wasm")))]
+pub mod sampler;
+#[cfg(feature = "std")]
+pub mod seqlock_spsc;
+#[cfg(all(feature = "std", target_has_atomic = "64"))]
+pub mod shm_spsc;
+#[cfg(feature = "std")]
+pub mod slot_pool;
+#[cfg(all(feature = "std", not(target_family = "wasm")))]
+pub mod stamped;
+#[cfg(feature = "stats")]
+pub mod stats;
+#[cfg(feature = "std")]
+pub mod ticket_mutex;
+#[cfg(feature = "std")]
 pub mod ticket_spsc;
+#[cfg(feature = "std")]
+pub mod triple_buffer;
+#[cfg(feature = "std")]
+pub mod watch;
+#[cfg(all(feature = "std", not(target_family = "wasm")))]
+pub mod worker;
+
+#[cfg(feature = "age")]
+mod age;
+mod atomic;
+#[cfg(feature = "std")]
+mod backoff;
+mod debug;
+#[cfg(all(feature = "std", any(target_os = "linux", windows)))]
+mod futex;
+#[cfg(feature = "std")]
+mod padded;
+#[cfg(any(feature = "std", feature = "critical-section"))]
+mod shared;
+#[cfg(feature = "std")]
+mod slots;
+#[cfg(feature = "std")]
+mod waiter;
@@ -0,0 +1,577 @@
+use crate::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use crate::backoff;
+use crate::debug::DebugState;
+use crate::padded::CachePadded;
+use crate::shared::Shared;
+use crate::{Reader, TryReadError, WriteError, Writer};
+
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::sync::Arc;
+
+const POOL_SIZE: usize = 8;
+// layout of `current`: the low bits hold the slot of the last published value, or `NONE`, the
+// others its version
+const INDEX_MASK: u64 = 0xff;
+const VERSION_SHIFT: u32 = 8;
+const NONE: u64 = INDEX_MASK;
+// set in the reference count of a slot while a writer owns it
+const WRITING: usize = 1 << (usize::BITS - 1);
+
+/// Returns the slot of the last published value in `current`, if any.
+fn published(current: u64) -> Option<usize> {
+    match current & INDEX_MASK {
+        NONE => None,
+        idx => Some(idx as usize),
+    }
+}
+
+/// Any number of writers and readers share a pool of slots, each with a reference count.
+///
+/// A writer claims a slot nobody references, which is never the published one, writes into it
+/// and publishes it by swapping it into `current`. A reader references the published slot and
+/// keeps it until its guard is dropped; if a writer claimed the slot or published another value
+/// in the meantime the reader backs off and looks at `current` again.
+///
+/// Progress guarantees:
+/// - reads are lock-free: a reader only retries because a writer published in the meantime (or,
+///   rarely, claimed the published slot and is about to hand it back);
+/// - writes are lock-free as long as some slot is free, i.e. while live guards and concurrent
+///   writes hold fewer than `POOL_SIZE - 1` slots; beyond that writers back off until one is released.
+struct Inner<T> {
+    pool: [CachePadded<UnsafeCell<MaybeUninit<T>>>; POOL_SIZE],
+    // whether each slot holds a value, only accessed by the writer owning the slot
+    occupied: [AtomicBool; POOL_SIZE],
+    // version of the value held by each slot, only modified by the writer owning the slot
+    versions: [AtomicU64; POOL_SIZE],
+    // number of guards on each slot, plus `WRITING` while a writer owns it
+    refs: [CachePadded<AtomicUsize>; POOL_SIZE],
+    // published slot and version, see `INDEX_MASK` and `VERSION_SHIFT`
+    current: CachePadded<AtomicU64>,
+    // number of live `WriteHandle`s
+    writers: AtomicUsize,
+    // set if any writer left through `close`
+    closed: AtomicBool,
+    // number of live `ReadHandle`s
+    readers: AtomicUsize,
+}
+
+/// Safety: a slot is only modified by the writer that claimed it through `refs`, and only read
+/// while referenced, which keeps writers from claiming it.
+unsafe impl<T> Sync for Inner<T> where T: Send + Sync {}
+
+/// Consumer side of the channel, which can be cloned to add more consumers.
+///
+/// Every handle tracks which values it already read on its own, see `watch`.
+pub struct ReadHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+    // version of the last value read
+    seen: u64,
+}
+
+/// Producer side of the channel, which can be cloned to add more producers.
+///
+/// Every write claims its own slot, so clones can write at the same time.
+pub struct WriteHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+}
+
+impl<T> Inner<T> {
+    /// Constructs a new, empty [`Inner`].
+    const fn new() -> Self {
+        Inner {
+            pool: [const { CachePadded::new(UnsafeCell::new(MaybeUninit::uninit())) }; POOL_SIZE],
+            occupied: [const { AtomicBool::new(false) }; POOL_SIZE],
+            versions: [const { AtomicU64::new(0) }; POOL_SIZE],
+            refs: [const { CachePadded::new(AtomicUsize::new(0)) }; POOL_SIZE],
+            current: CachePadded::new(AtomicU64::new(NONE)),
+            writers: AtomicUsize::new(1),
+            closed: AtomicBool::new(false),
+            readers: AtomicUsize::new(1),
+        }
+    }
+
+    /// Writes the provided value.
+    fn write(&self, value: T) {
+        let idx = self.acquire();
+        self.write_to(idx, value);
+        self.publish(idx);
+    }
+
+    /// Writes `value` unless live guards and other writers hold every slot.
+    fn try_write(&self, value: T) -> Result<(), WriteError<T>> {
+        let Some(idx) = self.try_acquire() else {
+            return Err(WriteError::WouldBlock(value));
+        };
+        self.write_to(idx, value);
+        self.publish(idx);
+        Ok(())
+    }
+
+    /// Writes a value built in place by `f` in a free slot of the pool.
+    fn write_with(&self, f: impl FnOnce(&mut T))
+    where
+        T: Default,
+    {
+        let idx = self.acquire();
+        let abort = Abort { inner: self, idx };
+        if !self.occupied[idx].load(Ordering::Relaxed) {
+            self.write_to(idx, T::default());
+        }
+        unsafe { f(&mut *self.slot(idx)) }
+        std::mem::forget(abort);
+        self.publish(idx);
+    }
+
+    /// Claims a slot that is neither published nor referenced, waiting while there is none.
+    fn acquire(&self) -> usize {
+        let mut spins = 0;
+        loop {
+            if let Some(idx) = self.try_acquire() {
+                return idx;
+            }
+            backoff::snooze(spins);
+            spins += 1;
+        }
+    }
+
+    /// Claims a slot that is neither published nor referenced, or returns `None` if there is none.
+    fn try_acquire(&self) -> Option<usize> {
+        for idx in 0..POOL_SIZE {
+            if published(self.current.load(Ordering::Relaxed)) == Some(idx)
+                || self.refs[idx]
+                    .compare_exchange(0, WRITING, Ordering::Acquire, Ordering::Relaxed)
+                    .is_err()
+            {
+                continue;
+            }
+            // only the owner of a slot publishes it, so it cannot become current afterwards
+            if published(self.current.load(Ordering::Acquire)) == Some(idx) {
+                self.refs[idx].fetch_sub(WRITING, Ordering::Release);
+                continue;
+            }
+            return Some(idx);
+        }
+        None
+    }
+
+    /// Makes the slot at the given index the last published value, and gives up ownership of it.
+    /// The value it replaces is dropped right away unless a reader still references it.
+    ///
+    /// Concurrent publishes are ordered by the CAS on `current`: the last one to succeed is the
+    /// freshest value, and has the highest version.
+    fn publish(&self, idx: usize) {
+        let mut current = self.current.load(Ordering::Relaxed);
+        loop {
+            let version = (current >> VERSION_SHIFT) + 1;
+            self.versions[idx].store(version, Ordering::Relaxed);
+            let new = version << VERSION_SHIFT | idx as u64;
+            match self.current.compare_exchange_weak(
+                current,
+                new,
+                Ordering::AcqRel,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(actual) => current = actual,
+            }
+        }
+        self.refs[idx].fetch_sub(WRITING, Ordering::Release);
+        if let Some(replaced) = published(current) {
+            self.discard(replaced);
+        }
+    }
+
+    /// Drops the value held by the slot at the given index if nobody references it, instead of
+    /// leaving it there until the slot is written again.
+    fn discard(&self, idx: usize) {
+        if self.refs[idx]
+            .compare_exchange(0, WRITING, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            // a guard keeps the value, or a writer is about to overwrite it
+            return;
+        }
+        // another writer may have claimed and published the slot again in the meantime
+        if published(self.current.load(Ordering::Acquire)) != Some(idx)
+            && self.occupied[idx].swap(false, Ordering::Relaxed)
+        {
+            unsafe { std::ptr::drop_in_place(self.slot(idx)) }
+        }
+        self.refs[idx].fetch_sub(WRITING, Ordering::Release);
+    }
+
+    fn write_to(&self, idx: usize, value: T) {
+        unsafe {
+            let pool = &mut *self.pool.get_unchecked(idx).get();
+            if self.occupied[idx].swap(true, Ordering::Relaxed) {
+                *pool.assume_init_mut() = value
+            } else {
+                pool.write(value);
+            }
+        }
+    }
+
+    /// Returns a pointer to the value held by the slot at the given index, which can only be
+    /// dereferenced once the slot is occupied.
+    fn slot(&self, idx: usize) -> *mut T {
+        unsafe { self.pool.get_unchecked(idx).get().cast() }
+    }
+
+    fn read_from(&self, idx: usize) -> &T {
+        unsafe {
+            let pool = self.pool.get_unchecked(idx).get();
+            (*pool).assume_init_ref()
+        }
+    }
+
+    /// References the last published value, unless its version is `seen`.
+    ///
+    /// This method is lock-free.
+    fn read(&self, seen: u64) -> Option<MpmcGuard<'_, T>> {
+        loop {
+            let current = self.current.load(Ordering::Acquire);
+            let idx = published(current)?;
+            if current >> VERSION_SHIFT == seen {
+                return None;
+            }
+            if self.refs[idx].fetch_add(1, Ordering::Acquire) & WRITING != 0 {
+                // a writer claimed it after it was replaced, look for the newer one
+                self.refs[idx].fetch_sub(1, Ordering::Relaxed);
+                continue;
+            }
+            // a writer may have replaced the value and dropped it, or rewritten the slot, before
+            // the reference was taken: only a slot still published with the same version is
+            // guaranteed to hold it, and the reference now keeps writers out of it
+            if self.current.load(Ordering::Acquire) != current {
+                self.refs[idx].fetch_sub(1, Ordering::Relaxed);
+                continue;
+            }
+            return Some(MpmcGuard { inner: self, idx });
+        }
+    }
+
+    /// Error reported once every writer is gone and nothing is left to read.
+    fn disconnected(&self) -> TryReadError {
+        if self.closed.load(Ordering::Relaxed) {
+            TryReadError::Closed
+        } else {
+            TryReadError::Disconnected
+        }
+    }
+}
+
+impl<T> Drop for Inner<T> {
+    fn drop(&mut self) {
+        for (slot, occupied) in self.pool.iter_mut().zip(&mut self.occupied) {
+            if *occupied.get_mut() {
+                unsafe { slot.get_mut().assume_init_drop() }
+            }
+        }
+    }
+}
+
+/// Drops the value being written and gives up ownership of its slot if dropped, which only
+/// happens when a write panics before publishing it. The other writers and the readers are not
+/// affected.
+struct Abort<'a, T> {
+    inner: &'a Inner<T>,
+    idx: usize,
+}
+
+impl<T> Drop for Abort<'_, T> {
+    fn drop(&mut self) {
+        if self.inner.occupied[self.idx].swap(false, Ordering::Relaxed) {
+            unsafe { std::ptr::drop_in_place(self.inner.slot(self.idx)) }
+        }
+        self.inner.refs[self.idx].fetch_sub(WRITING, Ordering::Release);
+    }
+}
+
+/// Zero-copy lease on the pool slot holding a read value.
+///
+/// The slot stays referenced until the guard is dropped, so no writer can claim it in the
+/// meantime. Other readers may reference the same slot at the same time.
+pub struct MpmcGuard<'a, T> {
+    inner: &'a Inner<T>,
+    idx: usize,
+}
+
+impl<T> MpmcGuard<'_, T> {
+    /// Returns the version of the value, i.e. how many values had been published including it.
+    pub fn version(&self) -> u64 {
+        self.inner.versions[self.idx].load(Ordering::Relaxed)
+    }
+}
+
+impl<T> std::ops::Deref for MpmcGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.inner.read_from(self.idx)
+    }
+}
+
+impl<T> Drop for MpmcGuard<'_, T> {
+    fn drop(&mut self) {
+        self.inner.refs[self.idx].fetch_sub(1, Ordering::Release);
+    }
+}
+
+impl<T> std::fmt::Debug for MpmcGuard<'_, T>
+where
+    T: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T> ReadHandle<'_, T> {
+    /// Returns the last published value, even if this handle already read it, and marks it as
+    /// read. Returns `None` only before the first write.
+    pub fn latest(&mut self) -> Option<MpmcGuard<'_, T>> {
+        // no version is ever 0
+        let guard = self.inner.read(0)?;
+        self.seen = guard.version();
+        Some(guard)
+    }
+
+    /// Returns whether a value was published since this handle last read one.
+    ///
+    /// This method is wait-free.
+    pub fn has_changed(&self) -> bool {
+        let current = self.inner.current.load(Ordering::Acquire);
+        published(current).is_some() && current >> VERSION_SHIFT != self.seen
+    }
+
+    /// Leases the last published value unless this handle already read it.
+    fn take(&mut self) -> Option<MpmcGuard<'_, T>> {
+        let guard = self.inner.read(self.seen)?;
+        self.seen = guard.version();
+        Some(guard)
+    }
+}
+
+impl<T> Clone for ReadHandle<'_, T> {
+    /// Adds a consumer, which has already read whatever this handle has.
+    fn clone(&self) -> Self {
+        self.inner.readers.fetch_add(1, Ordering::Relaxed);
+        ReadHandle {
+            inner: self.inner.clone(),
+            seen: self.seen,
+        }
+    }
+}
+
+impl<T> WriteHandle<'_, T> {
+    /// Returns how many `ReadHandle`s are alive.
+    pub fn reader_count(&self) -> usize {
+        self.inner.readers.load(Ordering::Acquire)
+    }
+
+    /// Returns how many `WriteHandle`s are alive.
+    pub fn writer_count(&self) -> usize {
+        self.inner.writers.load(Ordering::Acquire)
+    }
+}
+
+impl<T> Clone for WriteHandle<'_, T> {
+    /// Adds a producer.
+    fn clone(&self) -> Self {
+        self.inner.writers.fetch_add(1, Ordering::Relaxed);
+        WriteHandle {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> Reader for ReadHandle<'_, T> {
+    type Item = T;
+    type Guard<'a>
+        = MpmcGuard<'a, T>
+    where
+        Self: 'a;
+
+    /// Try reading the last published value, failing with [`TryReadError::Empty`] if none was
+    /// published since this handle last read one.
+    ///
+    /// Only reports the channel as dead once every writer is gone, as [`TryReadError::Closed`] if
+    /// any of them left through [`Writer::close`].
+    fn read(&mut self) -> Result<Self::Guard<'_>, TryReadError> {
+        // the last writer is only seen gone once its last value is published, so a read after
+        // checking is enough to catch it
+        let err = if self.inner.writers.load(Ordering::Acquire) > 0 {
+            TryReadError::Empty
+        } else {
+            self.inner.disconnected()
+        };
+        self.take().ok_or(err)
+    }
+}
+
+impl<T> Writer for WriteHandle<'_, T> {
+    type Item = T;
+
+    fn write(&mut self, value: T) {
+        self.inner.write(value)
+    }
+
+    /// If `f` panics, the value it was building is dropped and its slot freed, without
+    /// poisoning the channel: the other writers and the readers carry on.
+    fn write_with(&mut self, f: impl FnOnce(&mut T))
+    where
+        T: Default,
+    {
+        self.inner.write_with(f)
+    }
+
+    fn close(self) {
+        self.inner.closed.store(true, Ordering::Relaxed);
+        // dropping `self` disconnects the readers if it was the last writer
+    }
+
+    /// Returns whether any `ReadHandle` is still alive.
+    fn is_reader_alive(&self) -> bool {
+        self.reader_count() > 0
+    }
+
+    /// Fails with [`WriteError::WouldBlock`] instead of waiting while live guards and other writers
+    /// hold every slot but the published one.
+    fn try_write(&mut self, value: T) -> Result<(), WriteError<T>> {
+        if !self.is_reader_alive() {
+            return Err(WriteError::Disconnected(value));
+        }
+        self.inner.try_write(value)
+    }
+}
+
+impl<T> Drop for ReadHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.readers.fetch_sub(1, Ordering::Release);
+    }
+}
+
+impl<T> Drop for WriteHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.writers.fetch_sub(1, Ordering::Release);
+    }
+}
+
+impl<T> DebugState for Inner<T> {
+    fn debug_state(&self, d: &mut std::fmt::DebugStruct<'_, '_>) {
+        let current = self.current.load(Ordering::Relaxed);
+        let busy_slots = self
+            .refs
+            .iter()
+            .filter(|refs| refs.load(Ordering::Relaxed) != 0)
+            .count();
+        d.field("slot", &published(current))
+            .field("version", &(current >> VERSION_SHIFT))
+            .field("busy_slots", &busy_slots)
+            .field("writers", &self.writers.load(Ordering::Relaxed))
+            .field("closed", &self.closed.load(Ordering::Relaxed))
+            .field("readers", &self.readers.load(Ordering::Relaxed));
+    }
+}
+
+impl<T> std::fmt::Debug for ReadHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut d = f.debug_struct("mpmc_latest::ReadHandle");
+        self.inner.debug_state(&mut d);
+        d.field("seen", &self.seen)
+            .field("pending", &self.has_changed())
+            .finish()
+    }
+}
+
+impl<T> std::fmt::Debug for WriteHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "mpmc_latest::WriteHandle")
+    }
+}
+
+/// Construct a new, empty pair of handles, both of which can be cloned to add more readers and
+/// writers.
+///
+/// Reads fail with [`TryReadError::Empty`] until the first write.
+pub fn new<T>() -> (ReadHandle<'static, T>, WriteHandle<'static, T>) {
+    let inner = Arc::new(Inner::new());
+    let r = ReadHandle {
+        inner: Shared::Arc(Arc::clone(&inner)),
+        seen: 0,
+    };
+    let w = WriteHandle {
+        inner: Shared::Arc(inner),
+    };
+    (r, w)
+}
+
+/// This variant as a [`crate::Channel`], for code generic over the variant.
+pub struct Channel;
+
+impl<T: 'static> crate::Channel<T> for Channel {
+    type Reader = ReadHandle<'static, T>;
+    type Writer = WriteHandle<'static, T>;
+
+    fn create() -> (Self::Reader, Self::Writer) {
+        new()
+    }
+}
+
+/// Storage for a channel that is not reference counted, so that no allocation is needed.
+///
+/// It can be placed in a `static` or borrowed by the threads of a [`std::thread::scope`]: the
+/// handles returned by [`Storage::split`] borrow it, and are handed out only once. Both handles
+/// can still be cloned.
+///
+/// ```
+/// use rustedrazors::mpmc_latest::Storage;
+/// use rustedrazors::{Reader, Writer};
+///
+/// static CONFIG: Storage<u32> = Storage::new();
+///
+/// let (mut r, mut w) = CONFIG.split().unwrap();
+/// let mut r2 = r.clone();
+/// std::thread::scope(|s| {
+///     s.spawn(move || w.write(42));
+/// });
+/// assert_eq!(r.read().as_deref(), Ok(&42));
+/// assert_eq!(r2.read().as_deref(), Ok(&42));
+/// ```
+pub struct Storage<T> {
+    inner: Inner<T>,
+    split: AtomicBool,
+}
+
+impl<T> Storage<T> {
+    /// Constructs a new, empty storage.
+    pub const fn new() -> Self {
+        Storage {
+            inner: Inner::new(),
+            split: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the read and write handles borrowing the storage, or `None` if they were already
+    /// taken.
+    pub fn split(&self) -> Option<(ReadHandle<'_, T>, WriteHandle<'_, T>)> {
+        if self.split.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+        let r = ReadHandle {
+            inner: Shared::Borrowed(&self.inner),
+            seen: 0,
+        };
+        let w = WriteHandle {
+            inner: Shared::Borrowed(&self.inner),
+        };
+        Some((r, w))
+    }
+}
+
+impl<T> Default for Storage<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,498 @@
+use crate::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use crate::backoff;
+use crate::debug::DebugState;
+use crate::padded::CachePadded;
+use crate::shared::Shared;
+use crate::{Reader, TryReadError, WriteError, Writer};
+
+use std::cell::UnsafeCell;
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::sync::Arc;
+
+const POOL_SIZE: usize = 8;
+// layout of `state`: bit `i` is set while slot `i` is free, the bits from `BUFFER_SHIFT` hold the
+// slot of the value waiting for the reader, or `NONE`
+const FREE_MASK: u32 = (1 << POOL_SIZE) - 1;
+const BUFFER_SHIFT: u32 = POOL_SIZE as u32;
+const NONE: u32 = 0b1111;
+
+/// Returns the slot of the value waiting for the reader in `state`, if any.
+fn buffered(state: u32) -> Option<usize> {
+    match state >> BUFFER_SHIFT {
+        NONE => None,
+        idx => Some(idx as usize),
+    }
+}
+
+/// Same pool as `atomic_spsc`, except that free slots are claimed with a CAS since several writers
+/// compete for them.
+///
+/// Each writer holds at most one slot while writing, the reader leases at most one (its guard
+/// borrows the read handle) and one more holds the value waiting for it, so up to `POOL_SIZE - 2`
+/// writers can write at the same time without waiting.
+struct Inner<T> {
+    pool: [CachePadded<UnsafeCell<MaybeUninit<T>>>; POOL_SIZE],
+    // whether each slot holds a value, only accessed by whoever holds the slot
+    occupied: [AtomicBool; POOL_SIZE],
+    // free slots and waiting value, see `FREE_MASK` and `BUFFER_SHIFT`
+    state: CachePadded<AtomicU32>,
+    // number of values replaced before being read
+    overwritten: AtomicU64,
+    // number of live `WriteHandle`s
+    writers: AtomicUsize,
+    // set if any writer left through `close`
+    closed: AtomicBool,
+    reader_alive: AtomicBool,
+}
+
+/// Safety: a slot is only accessed by the side that claimed it through `state`, see `atomic_spsc`.
+unsafe impl<T> Sync for Inner<T> where T: Send {}
+
+pub struct ReadHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+}
+
+/// Producer side of the channel, which can be cloned to add more producers.
+///
+/// Every write claims its own slot, so clones can write at the same time: each thread writes
+/// through a clone of its own, since writes borrow the handle mutably.
+pub struct WriteHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+}
+
+impl<T> Inner<T> {
+    /// Constructs a new, empty [`Inner`].
+    const fn new() -> Self {
+        Inner {
+            pool: [const { CachePadded::new(UnsafeCell::new(MaybeUninit::uninit())) }; POOL_SIZE],
+            occupied: [const { AtomicBool::new(false) }; POOL_SIZE],
+            state: CachePadded::new(AtomicU32::new(FREE_MASK | NONE << BUFFER_SHIFT)),
+            overwritten: AtomicU64::new(0),
+            writers: AtomicUsize::new(1),
+            closed: AtomicBool::new(false),
+            reader_alive: AtomicBool::new(true),
+        }
+    }
+
+    /// Writes the provided value.
+    fn write(&self, value: T) {
+        let idx = self.acquire();
+        self.write_to(idx, value);
+        self.publish(idx);
+    }
+
+    /// Writes `value` unless other writers hold every free slot.
+    fn try_write(&self, value: T) -> Result<(), WriteError<T>> {
+        let Some(idx) = self.try_acquire() else {
+            return Err(WriteError::WouldBlock(value));
+        };
+        self.write_to(idx, value);
+        self.publish(idx);
+        Ok(())
+    }
+
+    /// Writes a value built in place by `f` in a free slot of the pool.
+    fn write_with(&self, f: impl FnOnce(&mut T))
+    where
+        T: Default,
+    {
+        let idx = self.acquire();
+        let abort = Abort { inner: self, idx };
+        if !self.occupied[idx].load(Ordering::Relaxed) {
+            self.write_to(idx, T::default());
+        }
+        unsafe { f(&mut *self.slot(idx)) }
+        std::mem::forget(abort);
+        self.publish(idx);
+    }
+
+    /// Makes the slot at the given index the value waiting for the reader, dropping the value it
+    /// replaces before freeing its slot.
+    ///
+    /// Concurrent publishes are ordered by the CAS on `state`: the last one to succeed is the
+    /// value the reader gets.
+    fn publish(&self, idx: usize) {
+        let mut state = self.state.load(Ordering::Relaxed);
+        loop {
+            let new = state & FREE_MASK | (idx as u32) << BUFFER_SHIFT;
+            match self
+                .state
+                .compare_exchange_weak(state, new, Ordering::AcqRel, Ordering::Relaxed)
+            {
+                Ok(_) => break,
+                Err(current) => state = current,
+            }
+        }
+        if let Some(buffer) = buffered(state) {
+            // the replaced slot is neither free nor waiting, so no other writer can claim it
+            // before it is released
+            self.discard(buffer);
+            self.release(buffer);
+            self.overwritten.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn write_to(&self, idx: usize, value: T) {
+        unsafe {
+            let pool = &mut *self.pool.get_unchecked(idx).get();
+            if self.occupied[idx].swap(true, Ordering::Relaxed) {
+                *pool.assume_init_mut() = value
+            } else {
+                pool.write(value);
+            }
+        }
+    }
+
+    /// Drops the value held by the slot at the given index right away, instead of leaving it
+    /// there until the slot is written again.
+    fn discard(&self, idx: usize) {
+        if self.occupied[idx].swap(false, Ordering::Relaxed) {
+            unsafe { std::ptr::drop_in_place(self.slot(idx)) }
+        }
+    }
+
+    /// Returns a pointer to the value held by the slot at the given index, which can only be
+    /// dereferenced once the slot is occupied.
+    fn slot(&self, idx: usize) -> *mut T {
+        unsafe { self.pool.get_unchecked(idx).get().cast() }
+    }
+
+    fn read_from(&self, idx: usize) -> &T {
+        unsafe {
+            let pool = self.pool.get_unchecked(idx).get();
+            (*pool).assume_init_ref()
+        }
+    }
+
+    /// Try reading the last written value.
+    /// The operation may fail if no new value was written since the last read.
+    ///
+    /// This method is wait-free.
+    fn read(&self) -> Option<MpscGuard<'_, T>> {
+        let state = self.state.fetch_or(NONE << BUFFER_SHIFT, Ordering::AcqRel);
+        buffered(state).map(|idx| MpscGuard {
+            inner: self,
+            idx,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Try reading the last written value, reporting whether every writer is gone.
+    fn try_read(&self) -> Result<MpscGuard<'_, T>, TryReadError> {
+        if let Some(guard) = self.read() {
+            return Ok(guard);
+        }
+        if self.writers.load(Ordering::Acquire) > 0 {
+            return Err(TryReadError::Empty);
+        }
+        // the last writer may have published right before being dropped
+        self.read().ok_or_else(|| self.disconnected())
+    }
+
+    /// Error reported once every writer is gone and nothing is left to read.
+    fn disconnected(&self) -> TryReadError {
+        if self.closed.load(Ordering::Relaxed) {
+            TryReadError::Closed
+        } else {
+            TryReadError::Disconnected
+        }
+    }
+
+    /// Claims a free slot of the pool, waiting while other writers hold every one of them.
+    ///
+    /// This method is lock-free as long as at most `POOL_SIZE - 2` writers write at the same time,
+    /// see [`Inner`].
+    fn acquire(&self) -> usize {
+        let mut spins = 0;
+        loop {
+            if let Some(idx) = self.try_acquire() {
+                return idx;
+            }
+            backoff::snooze(spins);
+            spins += 1;
+        }
+    }
+
+    /// Claims a free slot of the pool, or returns `None` if other writers hold every one of them.
+    fn try_acquire(&self) -> Option<usize> {
+        let mut state = self.state.load(Ordering::Relaxed);
+        loop {
+            let free = state & FREE_MASK;
+            if free == 0 {
+                return None;
+            }
+            let idx = free.trailing_zeros();
+            match self.state.compare_exchange_weak(
+                state,
+                state & !(1 << idx),
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return Some(idx as usize),
+                Err(current) => state = current,
+            }
+        }
+    }
+
+    /// Marks the object at the given index in the pool as free.
+    fn release(&self, idx: usize) {
+        self.state.fetch_or(1 << idx, Ordering::Release);
+    }
+}
+
+impl<T> Drop for Inner<T> {
+    fn drop(&mut self) {
+        for (slot, occupied) in self.pool.iter_mut().zip(&mut self.occupied) {
+            if *occupied.get_mut() {
+                unsafe { slot.get_mut().assume_init_drop() }
+            }
+        }
+    }
+}
+
+/// Drops the value being written and frees its slot if dropped, which only happens when a write
+/// panics before publishing it. The other writers and the reader are not affected.
+struct Abort<'a, T> {
+    inner: &'a Inner<T>,
+    idx: usize,
+}
+
+impl<T> Drop for Abort<'_, T> {
+    fn drop(&mut self) {
+        self.inner.discard(self.idx);
+        self.inner.release(self.idx);
+    }
+}
+
+/// Zero-copy lease on the pool slot holding a read value.
+///
+/// The slot stays reserved until the guard is dropped, so later writes cannot overwrite the value
+/// while it is borrowed.
+pub struct MpscGuard<'a, T> {
+    inner: &'a Inner<T>,
+    idx: usize,
+    // `Inner` is `Sync` for any `T: Send`, see `atomic_spsc::AtomicGuard`
+    _marker: PhantomData<&'a mut T>,
+}
+
+impl<T> std::ops::Deref for MpscGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.inner.read_from(self.idx)
+    }
+}
+
+impl<T> Drop for MpscGuard<'_, T> {
+    fn drop(&mut self) {
+        self.inner.release(self.idx);
+    }
+}
+
+impl<T> std::fmt::Debug for MpscGuard<'_, T>
+where
+    T: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T> ReadHandle<'_, T> {
+    /// Returns how many written values were replaced before they could be read.
+    pub fn overwritten(&self) -> u64 {
+        self.inner.overwritten.load(Ordering::Relaxed)
+    }
+}
+
+impl<T> WriteHandle<'_, T> {
+    /// Returns how many written values were replaced before the reader could read them.
+    pub fn overwritten(&self) -> u64 {
+        self.inner.overwritten.load(Ordering::Relaxed)
+    }
+
+    /// Returns how many `WriteHandle`s are alive.
+    pub fn writer_count(&self) -> usize {
+        self.inner.writers.load(Ordering::Acquire)
+    }
+}
+
+impl<T> Clone for WriteHandle<'_, T> {
+    /// Adds a producer.
+    fn clone(&self) -> Self {
+        self.inner.writers.fetch_add(1, Ordering::Relaxed);
+        WriteHandle {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> Reader for ReadHandle<'_, T> {
+    type Item = T;
+    type Guard<'a>
+        = MpscGuard<'a, T>
+    where
+        Self: 'a;
+
+    /// Try reading the last written value, telling apart an empty channel from a dead one.
+    ///
+    /// Only reports the channel as dead once every writer is gone, as [`TryReadError::Closed`] if
+    /// any of them left through [`Writer::close`].
+    fn read(&mut self) -> Result<Self::Guard<'_>, TryReadError> {
+        self.inner.try_read()
+    }
+}
+
+impl<T> Writer for WriteHandle<'_, T> {
+    type Item = T;
+
+    fn write(&mut self, value: T) {
+        self.inner.write(value)
+    }
+
+    /// If `f` panics, the value it was building is dropped and its slot freed, without
+    /// poisoning the channel: the other writers and the reader carry on.
+    fn write_with(&mut self, f: impl FnOnce(&mut T))
+    where
+        T: Default,
+    {
+        self.inner.write_with(f)
+    }
+
+    fn close(self) {
+        self.inner.closed.store(true, Ordering::Relaxed);
+        // dropping `self` disconnects the reader if it was the last writer
+    }
+
+    fn is_reader_alive(&self) -> bool {
+        self.inner.reader_alive.load(Ordering::Acquire)
+    }
+
+    /// Fails with [`WriteError::WouldBlock`] instead of waiting while other writers hold every
+    /// free slot, which takes more than 6 of them writing at the same time.
+    fn try_write(&mut self, value: T) -> Result<(), WriteError<T>> {
+        if !self.is_reader_alive() {
+            return Err(WriteError::Disconnected(value));
+        }
+        self.inner.try_write(value)
+    }
+}
+
+impl<T> Drop for ReadHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.reader_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<T> Drop for WriteHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.writers.fetch_sub(1, Ordering::Release);
+    }
+}
+
+impl<T> DebugState for Inner<T> {
+    fn debug_state(&self, d: &mut std::fmt::DebugStruct<'_, '_>) {
+        let state = self.state.load(Ordering::Relaxed);
+        d.field("pending", &buffered(state).is_some())
+            .field("free_slots", &(state & FREE_MASK).count_ones())
+            .field("overwritten", &self.overwritten.load(Ordering::Relaxed))
+            .field("writers", &self.writers.load(Ordering::Relaxed))
+            .field("closed", &self.closed.load(Ordering::Relaxed))
+            .field("reader_alive", &self.reader_alive.load(Ordering::Relaxed));
+    }
+}
+
+impl<T> std::fmt::Debug for ReadHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "mpsc_latest::ReadHandle")
+    }
+}
+
+impl<T> std::fmt::Debug for WriteHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "mpsc_latest::WriteHandle")
+    }
+}
+
+/// Construct a new, empty read handle and a first write handle, which can be cloned to add more
+/// writers.
+///
+/// Reads fail with [`TryReadError::Empty`] until the first write.
+pub fn new<T>() -> (ReadHandle<'static, T>, WriteHandle<'static, T>) {
+    let inner = Arc::new(Inner::new());
+    let r = ReadHandle {
+        inner: Shared::Arc(Arc::clone(&inner)),
+    };
+    let w = WriteHandle {
+        inner: Shared::Arc(inner),
+    };
+    (r, w)
+}
+
+/// This variant as a [`crate::Channel`], for code generic over the variant.
+pub struct Channel;
+
+impl<T: 'static> crate::Channel<T> for Channel {
+    type Reader = ReadHandle<'static, T>;
+    type Writer = WriteHandle<'static, T>;
+
+    fn create() -> (Self::Reader, Self::Writer) {
+        new()
+    }
+}
+
+/// Storage for a channel that is not reference counted, so that no allocation is needed.
+///
+/// It can be placed in a `static` or borrowed by the threads of a [`std::thread::scope`]: the
+/// handles returned by [`Storage::split`] borrow it, and are handed out only once. The write
+/// handle can still be cloned.
+///
+/// ```
+/// use rustedrazors::mpsc_latest::Storage;
+/// use rustedrazors::{Reader, Writer};
+///
+/// let storage = Storage::new();
+/// let (mut r, mut w) = storage.split().unwrap();
+/// std::thread::scope(|s| {
+///     let mut w2 = w.clone();
+///     s.spawn(move || w.write(42));
+///     s.spawn(move || w2.write(42));
+/// });
+/// assert_eq!(r.read().as_deref(), Ok(&42));
+/// ```
+pub struct Storage<T> {
+    inner: Inner<T>,
+    split: AtomicBool,
+}
+
+impl<T> Storage<T> {
+    /// Constructs a new, empty storage.
+    pub const fn new() -> Self {
+        Storage {
+            inner: Inner::new(),
+            split: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the read and write handles borrowing the storage, or `None` if they were already
+    /// taken.
+    pub fn split(&self) -> Option<(ReadHandle<'_, T>, WriteHandle<'_, T>)> {
+        if self.split.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+        let r = ReadHandle {
+            inner: Shared::Borrowed(&self.inner),
+        };
+        let w = WriteHandle {
+            inner: Shared::Borrowed(&self.inner),
+        };
+        Some((r, w))
+    }
+}
+
+impl<T> Default for Storage<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,74 +1,637 @@
-use crate::{Reader, Writer};
+#[cfg(feature = "age")]
+use crate::age::LastUpdate;
+use crate::atomic::{AtomicBool, AtomicU64, Ordering};
+use crate::debug::DebugState;
+#[cfg(feature = "hooks")]
+use crate::hooks::{Callbacks, Hooks};
+use crate::shared::Shared;
+#[cfg(feature = "stats")]
+use crate::stats::{Counters, Stats};
+use crate::{Reader, TryReadError, WriteError, Writer};
 
-/// Implement a trivial atomic_spsc-like data structures using a Mutex
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::TryLockError;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
 
 struct Inner<T> {
     data: Mutex<T>,
     to_read: AtomicBool,
+    published: Condvar,
+    // only modified while holding `data`, so that waiting readers cannot miss it
+    // number of values replaced before being read, only modified by the writer
+    overwritten: AtomicU64,
+    #[cfg(feature = "stats")]
+    stats: Counters,
+    // callbacks given to `with_hooks`, run next to the counters
+    #[cfg(feature = "hooks")]
+    hooks: Callbacks,
+    // instant of the last publish, returned by `last_update` and `age`
+    #[cfg(feature = "age")]
+    updated: LastUpdate,
+    // label given to `named`, shown by `Debug` and `stats` and tagging the `tracing` events
+    name: Option<&'static str>,
+    writer_alive: AtomicBool,
+    // set right before `writer_alive` is cleared if the writer left through `close`
+    closed: AtomicBool,
+    reader_alive: AtomicBool,
 }
 
-pub struct ReadHandle<T> {
-    inner: Arc<Inner<T>>,
+pub struct ReadHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
 }
 
-pub struct WriteHandle<T> {
-    inner: Arc<Inner<T>>,
+pub struct WriteHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+}
+
+/// Third handle of a channel, for a thread that only observes it, such as a metrics or debug thread.
+///
+/// Peeks look at the current value without consuming it, so the reader still gets every update it
+/// would have got. They are best effort: a monitor misses the values replaced between two peeks,
+/// and sees the same one again if nothing was written in between. Monitors can be cloned and
+/// shared between threads.
+pub struct MonitorHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
 }
 
 impl<T> Inner<T> {
-    fn new(init: T) -> Self {
+    const fn new(init: T) -> Self {
         Inner {
             data: Mutex::new(init),
             to_read: AtomicBool::new(false),
+            published: Condvar::new(),
+            overwritten: AtomicU64::new(0),
+            #[cfg(feature = "stats")]
+            stats: Counters::new(),
+            #[cfg(feature = "hooks")]
+            hooks: Callbacks::none(),
+            #[cfg(feature = "age")]
+            updated: LastUpdate::new(),
+            name: None,
+            writer_alive: AtomicBool::new(true),
+            closed: AtomicBool::new(false),
+            reader_alive: AtomicBool::new(true),
         }
     }
 
     fn write(&self, value: T) {
-        let mut data = self.data.lock().unwrap();
+        let mut data = self.lock();
+        *data = value;
+        self.mark_unread();
+        self.published.notify_one();
+    }
+
+    /// Writes `value` unless the reader (or a monitor) holds the lock or a panic poisoned it.
+    fn try_write(&self, value: T) -> Result<(), WriteError<T>> {
+        let mut data = match self.data.try_lock() {
+            Ok(data) => data,
+            Err(TryLockError::WouldBlock) => return Err(WriteError::WouldBlock(value)),
+            Err(TryLockError::Poisoned(_)) => return Err(WriteError::Poisoned(value)),
+        };
+        *data = value;
+        self.mark_unread();
+        self.published.notify_one();
+        Ok(())
+    }
+
+    fn write_if_changed(&self, value: T) -> bool
+    where
+        T: PartialEq,
+    {
+        let mut data = self.lock();
+        if *data == value {
+            return false;
+        }
         *data = value;
-        self.to_read.store(true, Ordering::Release);
+        self.mark_unread();
+        self.published.notify_one();
+        true
+    }
+
+    fn write_with(&self, f: impl FnOnce(&mut T)) {
+        let mut data = self.lock();
+        f(&mut data);
+        self.mark_unread();
+        self.published.notify_one();
+    }
+
+    /// Locks the value for the writer, recording how long the reader kept it locked.
+    fn lock(&self) -> MutexGuard<'_, T> {
+        #[cfg(feature = "stats")]
+        return match self.data.try_lock() {
+            Err(TryLockError::WouldBlock) => self.stats.stall(|| self.data.lock().unwrap()),
+            res => res.unwrap(),
+        };
+        #[cfg(not(feature = "stats"))]
+        self.data.lock().unwrap()
+    }
+
+    /// Flags the value as unread, counting the previous one as overwritten if it never was.
+    fn mark_unread(&self) {
+        let overwrite = self.to_read.swap(true, Ordering::Release);
+        if overwrite {
+            self.overwritten.fetch_add(1, Ordering::Relaxed);
+        }
+        #[cfg(feature = "age")]
+        self.updated.touch();
+        #[cfg(feature = "stats")]
+        self.stats.write(overwrite);
+        #[cfg(feature = "hooks")]
+        self.hooks.write(overwrite);
+        #[cfg(feature = "tracing")]
+        tracing::trace!(channel = self.name, overwrite, "write");
     }
 
     fn read(&self) -> Option<MutexGuard<'_, T>> {
-        if self.to_read.load(Ordering::Acquire) {
-            let guard = self.data.lock().ok()?;
-            self.to_read.store(false, Ordering::Release);
-            Some(guard)
+        let guard = if self.to_read.load(Ordering::Acquire) {
+            self.data.lock().ok()
         } else {
             None
+        };
+        if guard.is_some() {
+            self.to_read.store(false, Ordering::Release);
+        }
+        #[cfg(feature = "stats")]
+        self.stats.read(guard.is_some());
+        #[cfg(feature = "hooks")]
+        self.hooks.read(guard.is_some());
+        #[cfg(feature = "tracing")]
+        if guard.is_some() {
+            tracing::trace!(channel = self.name, "read");
+        }
+        guard
+    }
+
+    /// Try reading the last written value, reporting whether the writer is gone.
+    fn try_read(&self) -> Result<MutexGuard<'_, T>, TryReadError> {
+        if let Some(guard) = self.read() {
+            return Ok(guard);
+        }
+        if self.writer_alive.load(Ordering::Acquire) && !self.data.is_poisoned() {
+            return Err(TryReadError::Empty);
+        }
+        // the writer may have published right before being dropped
+        self.read().ok_or_else(|| self.disconnected())
+    }
+
+    /// Error reported once the writer is gone or poisoned the lock and nothing is left to read.
+    fn disconnected(&self) -> TryReadError {
+        if self.data.is_poisoned() {
+            TryReadError::Poisoned
+        } else if self.closed.load(Ordering::Relaxed) {
+            TryReadError::Closed
+        } else {
+            TryReadError::Disconnected
         }
     }
+
+    /// Waits on the condition variable until a value is written, the writer is dropped or
+    /// `deadline` is reached.
+    fn read_deadline(&self, deadline: Option<Instant>) -> Result<MutexGuard<'_, T>, TryReadError> {
+        // a poisoned lock means the writer panicked mid-write
+        fn poisoned<E>(_: E) -> TryReadError {
+            TryReadError::Poisoned
+        }
+        let mut guard = self.data.lock().map_err(poisoned)?;
+        while !self.to_read.load(Ordering::Acquire) {
+            #[cfg(feature = "stats")]
+            self.stats.read(false);
+            #[cfg(feature = "hooks")]
+            self.hooks.read(false);
+            if !self.writer_alive.load(Ordering::Acquire) {
+                return Err(self.disconnected());
+            }
+            guard = match deadline {
+                None => self.published.wait(guard).map_err(poisoned)?,
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Err(TryReadError::Empty);
+                    }
+                    let timeout = deadline - now;
+                    self.published
+                        .wait_timeout(guard, timeout)
+                        .map_err(poisoned)?
+                        .0
+                }
+            };
+        }
+        self.to_read.store(false, Ordering::Release);
+        #[cfg(feature = "stats")]
+        self.stats.read(true);
+        #[cfg(feature = "hooks")]
+        self.hooks.read(true);
+        #[cfg(feature = "tracing")]
+        tracing::trace!(channel = self.name, "read");
+        Ok(guard)
+    }
+
+    /// Marks the writer as gone and wakes up a waiting reader.
+    fn disconnect(&self) {
+        let _guard = self.data.lock();
+        self.writer_alive.store(false, Ordering::Release);
+        self.published.notify_one();
+    }
 }
 
-impl<T> Reader for ReadHandle<T> {
+impl<T> ReadHandle<'_, T> {
+    /// Try reading the last written value by swapping it with `scratch` instead of cloning it.
+    /// Fails like [`Reader::read`], leaving `scratch` untouched.
+    ///
+    /// The previous content of `scratch` becomes the value the writer modifies through
+    /// [`Writer::write_with`] and [`WriteHandle::update`] and compares against in
+    /// [`WriteHandle::write_if_changed`].
+    pub fn read_swap(&mut self, scratch: &mut T) -> Result<(), TryReadError> {
+        let mut guard = self.inner.try_read()?;
+        std::mem::swap(&mut *guard, scratch);
+        Ok(())
+    }
+
+    /// Returns how many written values were replaced before they could be read.
+    pub fn overwritten(&self) -> u64 {
+        self.inner.overwritten.load(Ordering::Relaxed)
+    }
+
+    /// Returns the label given to [`named`], if any.
+    pub fn name(&self) -> Option<&'static str> {
+        self.inner.name
+    }
+
+    /// Returns a snapshot of the usage counters of the channel, shared with the writer.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.inner.stats.snapshot(self.inner.name)
+    }
+
+    /// Returns when the writer last published a value, whether it was read or not, or `None` if it
+    /// never did.
+    #[cfg(feature = "age")]
+    pub fn last_update(&self) -> Option<Instant> {
+        self.inner.updated.get()
+    }
+
+    /// Returns how long ago the writer last published a value, or `None` if it never did.
+    ///
+    /// Comparing it against a deadline detects a stalled producer, which a read finding no new
+    /// value cannot tell apart from a slow one.
+    #[cfg(feature = "age")]
+    pub fn age(&self) -> Option<Duration> {
+        self.last_update().map(|instant| instant.elapsed())
+    }
+
+    /// Reads the next written value, blocking until the writer publishes one.
+    /// Fails with [`TryReadError::Closed`] or [`TryReadError::Disconnected`] once the writer is gone
+    /// and its last value was read.
+    pub fn read_blocking(&mut self) -> Result<MutexGuard<'_, T>, TryReadError> {
+        self.inner.read_deadline(None)
+    }
+
+    /// Reads the next written value, blocking for at most `timeout`.
+    /// Fails with [`TryReadError::Empty`] if nothing was published in the meantime.
+    pub fn read_timeout(&mut self, timeout: Duration) -> Result<MutexGuard<'_, T>, TryReadError> {
+        self.inner.read_deadline(Some(Instant::now() + timeout))
+    }
+}
+
+impl<T> WriteHandle<'_, T> {
+    /// Publishes the last written value (or the initial value before the first write) as modified
+    /// by `f`, so the producer does not have to keep its own copy of the current state.
+    ///
+    /// The value is modified in place, same as [`Writer::write_with`].
+    pub fn update(&mut self, f: impl FnOnce(&mut T)) {
+        self.inner.write_with(f)
+    }
+
+    /// Writes the provided value unless it is equal to the last written one (or to the initial
+    /// value before the first write), sparing the reader a redundant update.
+    /// Returns whether the value was published.
+    pub fn write_if_changed(&mut self, value: T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.inner.write_if_changed(value)
+    }
+
+    /// Returns how many written values were replaced before the reader could read them.
+    pub fn overwritten(&self) -> u64 {
+        self.inner.overwritten.load(Ordering::Relaxed)
+    }
+
+    /// Returns the label given to [`named`], if any.
+    pub fn name(&self) -> Option<&'static str> {
+        self.inner.name
+    }
+
+    /// Returns a snapshot of the usage counters of the channel, shared with the reader.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.inner.stats.snapshot(self.inner.name)
+    }
+}
+
+impl<T> Reader for ReadHandle<'_, T> {
     type Item = T;
     type Guard<'a>
         = MutexGuard<'a, T>
     where
-        T: 'a;
+        Self: 'a;
 
-    fn read(&self) -> Option<Self::Guard<'_>> {
-        self.inner.read()
+    fn read(&mut self) -> Result<Self::Guard<'_>, TryReadError> {
+        self.inner.try_read()
     }
 }
 
-impl<T> Writer for WriteHandle<T> {
+impl<T> Writer for WriteHandle<'_, T> {
     type Item = T;
 
-    fn write(&self, value: T) {
+    fn write(&mut self, value: T) {
         self.inner.write(value)
     }
+
+    fn write_with(&mut self, f: impl FnOnce(&mut T))
+    where
+        T: Default,
+    {
+        self.inner.write_with(f)
+    }
+
+    fn close(self) {
+        self.inner.closed.store(true, Ordering::Relaxed);
+        // dropping `self` disconnects the reader
+    }
+
+    fn is_reader_alive(&self) -> bool {
+        self.inner.reader_alive.load(Ordering::Acquire)
+    }
+
+    fn try_write(&mut self, value: T) -> Result<(), WriteError<T>> {
+        if !self.is_reader_alive() {
+            return Err(WriteError::Disconnected(value));
+        }
+        self.inner.try_write(value)
+    }
+}
+
+impl<'a, T> ReadHandle<'a, T> {
+    /// Returns a [`MonitorHandle`] observing the channel without taking part in it.
+    pub fn monitor(&self) -> MonitorHandle<'a, T> {
+        MonitorHandle {
+            inner: self.inner.clone(),
+        }
+    }
+
+    /// Turns the handle into an opaque pointer to the state of the channel, for places that cannot
+    /// hold a generic handle, such as the context of a C callback.
+    ///
+    /// The handle is not dropped: the channel still sees it alive until the pointer is turned back
+    /// into a handle by [`ReadHandle::from_raw`], which must happen exactly once to release it.
+    pub fn into_raw(self) -> *const () {
+        let this = std::mem::ManuallyDrop::new(self);
+        unsafe { std::ptr::read(&this.inner) }.into_raw()
+    }
+
+    /// Turns a pointer returned by [`ReadHandle::into_raw`] back into the handle.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must come from [`ReadHandle::into_raw`] on a handle of this module and of the same `T`,
+    /// and must be turned back only once: two handles built from it would share one side of the
+    /// channel. A handle of a [`Storage`] must not be turned back after the storage is gone.
+    pub unsafe fn from_raw(ptr: *const ()) -> Self {
+        ReadHandle {
+            inner: unsafe { Shared::from_raw(ptr) },
+        }
+    }
+}
+
+impl<T> Drop for ReadHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.reader_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<'a, T> WriteHandle<'a, T> {
+    /// Returns a [`MonitorHandle`] observing the channel without taking part in it.
+    pub fn monitor(&self) -> MonitorHandle<'a, T> {
+        MonitorHandle {
+            inner: self.inner.clone(),
+        }
+    }
+
+    /// Turns the handle into an opaque pointer to the state of the channel, for places that cannot
+    /// hold a generic handle, such as the context of a C callback.
+    ///
+    /// The handle is not dropped: the channel still sees it alive until the pointer is turned back
+    /// into a handle by [`WriteHandle::from_raw`], which must happen exactly once to release it.
+    pub fn into_raw(self) -> *const () {
+        let this = std::mem::ManuallyDrop::new(self);
+        unsafe { std::ptr::read(&this.inner) }.into_raw()
+    }
+
+    /// Turns a pointer returned by [`WriteHandle::into_raw`] back into the handle.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must come from [`WriteHandle::into_raw`] on a handle of this module and of the same `T`,
+    /// and must be turned back only once: two handles built from it would share one side of the
+    /// channel. A handle of a [`Storage`] must not be turned back after the storage is gone.
+    pub unsafe fn from_raw(ptr: *const ()) -> Self {
+        WriteHandle {
+            inner: unsafe { Shared::from_raw(ptr) },
+        }
+    }
+}
+
+impl<T> Drop for WriteHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.disconnect();
+    }
+}
+
+impl<T> MonitorHandle<'_, T> {
+    /// Hands the last written value (or the initial value before the first write) to `f`, without
+    /// consuming it.
+    ///
+    /// The lock of the value is held while `f` runs, so the reader and the writer wait on it
+    /// meanwhile: `f` should be quick.
+    ///
+    /// A value left halfway through by a panicking write is handed to `f` as is.
+    pub fn peek_with<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        let data = self
+            .inner
+            .data
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        f(&data)
+    }
+
+    /// Returns a copy of the last written value (or of the initial value before the first write),
+    /// without consuming it.
+    pub fn peek(&self) -> T
+    where
+        T: Clone,
+    {
+        self.peek_with(T::clone)
+    }
+
+    /// Returns the label given to [`named`], if any.
+    pub fn name(&self) -> Option<&'static str> {
+        self.inner.name
+    }
+}
+
+impl<T> Clone for MonitorHandle<'_, T> {
+    fn clone(&self) -> Self {
+        MonitorHandle {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> DebugState for Inner<T> {
+    fn debug_state(&self, d: &mut std::fmt::DebugStruct<'_, '_>) {
+        if let Some(name) = self.name {
+            d.field("name", &name);
+        }
+        d.field("pending", &self.to_read.load(Ordering::Relaxed))
+            .field("overwritten", &self.overwritten.load(Ordering::Relaxed))
+            .field("poisoned", &self.data.is_poisoned())
+            .field("writer_alive", &self.writer_alive.load(Ordering::Relaxed))
+            .field("closed", &self.closed.load(Ordering::Relaxed))
+            .field("reader_alive", &self.reader_alive.load(Ordering::Relaxed));
+    }
+}
+
+impl<T> std::fmt::Debug for ReadHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "mutex_spsc::ReadHandle")
+    }
+}
+
+impl<T> std::fmt::Debug for WriteHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "mutex_spsc::WriteHandle")
+    }
+}
+
+impl<T> std::fmt::Debug for MonitorHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "mutex_spsc::MonitorHandle")
+    }
+}
+
+pub fn new<T>(init: T) -> (ReadHandle<'static, T>, WriteHandle<'static, T>) {
+    from_inner(Inner::new(init))
+}
+
+/// This variant as a [`crate::Channel`], starting from `T::default()`, for code generic over the
+/// variant.
+pub struct Channel;
+
+impl<T: Default + 'static> crate::Channel<T> for Channel {
+    type Reader = ReadHandle<'static, T>;
+    type Writer = WriteHandle<'static, T>;
+
+    fn create() -> (Self::Reader, Self::Writer) {
+        new(T::default())
+    }
+}
+
+/// Like [`new`], with the channel labelled `name` in `Debug`, `stats()` and the `tracing` events.
+pub fn named<T>(name: &'static str, init: T) -> (ReadHandle<'static, T>, WriteHandle<'static, T>) {
+    let mut inner = Inner::new(init);
+    inner.name = Some(name);
+    from_inner(inner)
+}
+
+/// Like [`new`], with `hooks` called on every read and write of the channel.
+#[cfg(feature = "hooks")]
+pub fn with_hooks<T>(
+    hooks: impl Hooks,
+    init: T,
+) -> (ReadHandle<'static, T>, WriteHandle<'static, T>) {
+    let mut inner = Inner::new(init);
+    inner.hooks = Callbacks::new(hooks);
+    from_inner(inner)
+}
+
+/// Takes the value back out of the channel, consuming both of its handles, instead of leaving it to
+/// be dropped along with the channel.
+///
+/// This is the last written value, or `init` if nothing was written. Returns `None` if the channel
+/// lives in a [`Storage`], which keeps its value.
+///
+/// Panics if the handles do not belong to the same channel.
+pub fn into_inner<T>(r: ReadHandle<'_, T>, w: WriteHandle<'_, T>) -> Option<T> {
+    assert!(
+        std::ptr::eq(&*r.inner, &*w.inner),
+        "handles of different channels"
+    );
+    let inner = w.inner.clone();
+    drop(r);
+    drop(w);
+    let inner = inner.into_owned()?;
+    Some(
+        inner
+            .data
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner),
+    )
 }
 
-pub fn new<T>(init: T) -> (ReadHandle<T>, WriteHandle<T>) {
-    let inner = Arc::new(Inner::new(init));
+fn from_inner<T>(inner: Inner<T>) -> (ReadHandle<'static, T>, WriteHandle<'static, T>) {
+    let inner = Arc::new(inner);
     let r = ReadHandle {
-        inner: Arc::clone(&inner),
+        inner: Shared::Arc(Arc::clone(&inner)),
     };
     let w = WriteHandle {
-        inner: Arc::clone(&inner),
+        inner: Shared::Arc(inner),
     };
     (r, w)
 }
+
+/// Storage for a channel that is not reference counted, so that no allocation is needed.
+///
+/// It can be placed in a `static` or borrowed by the threads of a [`std::thread::scope`]: the
+/// handles returned by [`Storage::split`] borrow it, and are handed out only once.
+///
+/// ```
+/// use rustedrazors::mutex_spsc::Storage;
+/// use rustedrazors::{Reader, Writer};
+///
+/// let storage = Storage::new(0);
+/// let (mut r, mut w) = storage.split().unwrap();
+/// std::thread::scope(|s| {
+///     s.spawn(move || w.write(42));
+/// });
+/// assert_eq!(r.read().as_deref(), Ok(&42));
+/// ```
+pub struct Storage<T> {
+    inner: Inner<T>,
+    split: AtomicBool,
+}
+
+impl<T> Storage<T> {
+    /// Constructs a new storage holding `init`.
+    pub const fn new(init: T) -> Self {
+        Storage {
+            inner: Inner::new(init),
+            split: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the read and write handles borrowing the storage, or `None` if they were already
+    /// taken.
+    pub fn split(&self) -> Option<(ReadHandle<'_, T>, WriteHandle<'_, T>)> {
+        if self.split.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+        let r = ReadHandle {
+            inner: Shared::Borrowed(&self.inner),
+        };
+        let w = WriteHandle {
+            inner: Shared::Borrowed(&self.inner),
+        };
+        Some((r, w))
+    }
+}
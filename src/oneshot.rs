@@ -0,0 +1,201 @@
+use crate::atomic::{AtomicU8, Ordering};
+use crate::debug::DebugState;
+use crate::shared::Shared;
+use crate::waiter::Waiter;
+use crate::TryReadError;
+
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::sync::Arc;
+#[cfg(not(target_family = "wasm"))]
+use std::time::{Duration, Instant};
+
+// nothing was sent yet and both handles are alive
+const EMPTY: u8 = 0;
+// `value` holds the sent value
+const FULL: u8 = 1;
+// the value was received
+const TAKEN: u8 = 2;
+// the writer was dropped without sending
+const WRITER_GONE: u8 = 3;
+// the reader was dropped, whatever it did not receive was dropped with it
+const READER_GONE: u8 = 4;
+
+/// Single value moved from the writer to the reader.
+///
+/// Like in `atomic_cell_spsc` there is no pool: the value lives in the only slot, and `state` alone
+/// tells who owns it, so sending and receiving are a single atomic operation each.
+struct Inner<T> {
+    value: UnsafeCell<MaybeUninit<T>>,
+    state: AtomicU8,
+    waiter: Waiter,
+}
+
+/// Safety: `value` is written once by the writer before `state` becomes `FULL`, and only read by
+/// whoever moves `state` away from `FULL`.
+unsafe impl<T> Sync for Inner<T> where T: Send {}
+
+pub struct ReadHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+}
+
+pub struct WriteHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+}
+
+impl<T> Inner<T> {
+    fn new() -> Self {
+        Inner {
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+            state: AtomicU8::new(EMPTY),
+            waiter: Waiter::new(),
+        }
+    }
+
+    /// Moves the value out, if it was sent and not received yet.
+    fn take(&self) -> Result<T, TryReadError> {
+        match self
+            .state
+            .compare_exchange(FULL, TAKEN, Ordering::Acquire, Ordering::Acquire)
+        {
+            Ok(_) => Ok(unsafe { (*self.value.get()).assume_init_read() }),
+            Err(EMPTY) => Err(TryReadError::Empty),
+            Err(TAKEN) => Err(TryReadError::Closed),
+            Err(_) => Err(TryReadError::Disconnected),
+        }
+    }
+
+    /// Receives the value, parking the current thread until it is sent, the writer is dropped or
+    /// `deadline` is reached.
+    #[cfg(not(target_family = "wasm"))]
+    fn recv_deadline(&self, deadline: Option<Instant>) -> Result<T, TryReadError> {
+        loop {
+            match self.take() {
+                Err(TryReadError::Empty) => {}
+                res => return res,
+            }
+            let ready = || self.state.load(Ordering::Acquire) != EMPTY;
+            if !self.waiter.wait(ready, deadline) {
+                return Err(TryReadError::Empty);
+            }
+        }
+    }
+}
+
+impl<T> ReadHandle<'_, T> {
+    /// Receives the value if it was sent, without waiting.
+    ///
+    /// Fails with [`TryReadError::Disconnected`] if the writer was dropped without sending, and
+    /// with [`TryReadError::Closed`] if the value was already received.
+    pub fn try_recv(&mut self) -> Result<T, TryReadError> {
+        self.inner.take()
+    }
+
+    /// Receives the value, parking the current thread until it is sent.
+    ///
+    /// Fails with [`TryReadError::Disconnected`] if the writer was dropped without sending.
+    #[cfg(not(target_family = "wasm"))]
+    pub fn recv(self) -> Result<T, TryReadError> {
+        self.inner.recv_deadline(None)
+    }
+
+    /// Receives the value, parking the current thread for at most `timeout` until it is sent.
+    ///
+    /// Returns [`TryReadError::Empty`] on timeout, the handle can then be used to try again.
+    #[cfg(not(target_family = "wasm"))]
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<T, TryReadError> {
+        self.inner
+            .recv_deadline(Instant::now().checked_add(timeout))
+    }
+
+    /// Returns whether the value was sent and can be received without waiting.
+    pub fn is_ready(&self) -> bool {
+        self.inner.state.load(Ordering::Relaxed) == FULL
+    }
+}
+
+impl<T> WriteHandle<'_, T> {
+    /// Sends `value`, waking up the reader if it is waiting for it.
+    ///
+    /// The value is handed back if the reader was already dropped.
+    pub fn send(self, value: T) -> Result<(), T> {
+        unsafe { (*self.inner.value.get()).write(value) };
+        match self
+            .inner
+            .state
+            .compare_exchange(EMPTY, FULL, Ordering::Release, Ordering::Relaxed)
+        {
+            Ok(_) => {
+                self.inner.waiter.notify();
+                Ok(())
+            }
+            // the reader is gone and will never look at the slot again
+            Err(_) => Err(unsafe { (*self.inner.value.get()).assume_init_read() }),
+        }
+    }
+
+    /// Returns whether the reader still exists, so that the writer can skip building a value
+    /// nobody will receive.
+    pub fn is_reader_alive(&self) -> bool {
+        self.inner.state.load(Ordering::Relaxed) != READER_GONE
+    }
+}
+
+impl<T> Drop for ReadHandle<'_, T> {
+    fn drop(&mut self) {
+        if self.inner.state.swap(READER_GONE, Ordering::Acquire) == FULL {
+            unsafe { (*self.inner.value.get()).assume_init_drop() }
+        }
+    }
+}
+
+impl<T> Drop for WriteHandle<'_, T> {
+    fn drop(&mut self) {
+        // fails if `send` was called, or if the reader is gone and does not care
+        if self
+            .inner
+            .state
+            .compare_exchange(EMPTY, WRITER_GONE, Ordering::Release, Ordering::Relaxed)
+            .is_ok()
+        {
+            self.inner.waiter.notify();
+        }
+    }
+}
+
+impl<T> DebugState for Inner<T> {
+    fn debug_state(&self, d: &mut std::fmt::DebugStruct<'_, '_>) {
+        let state = match self.state.load(Ordering::Relaxed) {
+            EMPTY => "empty",
+            FULL => "full",
+            TAKEN => "taken",
+            WRITER_GONE => "writer_gone",
+            _ => "reader_gone",
+        };
+        d.field("state", &state);
+    }
+}
+
+impl<T> std::fmt::Debug for ReadHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "oneshot::ReadHandle")
+    }
+}
+
+impl<T> std::fmt::Debug for WriteHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "oneshot::WriteHandle")
+    }
+}
+
+/// Construct a new read and write handle pair, through which exactly one value can be sent.
+pub fn new<T>() -> (ReadHandle<'static, T>, WriteHandle<'static, T>) {
+    let inner = Arc::new(Inner::new());
+    let r = ReadHandle {
+        inner: Shared::Arc(Arc::clone(&inner)),
+    };
+    let w = WriteHandle {
+        inner: Shared::Arc(inner),
+    };
+    (r, w)
+}
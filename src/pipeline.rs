@@ -0,0 +1,183 @@
+//! Chains of channels with a thread per stage, built from a source reader.
+//!
+//! ```
+//! use rustedrazors::pipeline::Pipeline;
+//! use rustedrazors::{atomic_spsc, Writer};
+//!
+//! let (mut r, mut w) = atomic_spsc::new::<i32>();
+//! let (mut out, sink) = atomic_spsc::new::<String>();
+//! let running = Pipeline::source(r)
+//!     .filter(|value| value % 2 == 0)
+//!     .map(|value| value.to_string())
+//!     .sink(sink);
+//!
+//! w.write(42);
+//! assert_eq!(out.read_blocking().as_deref().map(String::as_str), Ok("42"));
+//!
+//! // closing the source closes every stage after it
+//! w.close();
+//! running.join().unwrap();
+//! ```
+//!
+//! Every stage reads the newest value of the stage before it, so a slow stage conflates the values
+//! it could not keep up with, same as any reader of the crate. Stages shut down in both directions:
+//! once the writer of the source is gone every stage after it leaves, closing its own output if the
+//! source was closed, and once the reader of the sink is gone every stage before it leaves, so that
+//! the writer of the source sees its reader dropped.
+
+use crate::backoff;
+use crate::{atomic_spsc, Reader, TryReadError, Writer};
+
+use std::thread::{self, JoinHandle};
+
+/// Chain of stages ending with the reader `R`, each running on its own thread.
+///
+/// Dropping an unfinished pipeline drops `R`, shutting down the stages before it.
+pub struct Pipeline<R> {
+    output: R,
+    stages: Vec<JoinHandle<()>>,
+}
+
+/// Threads of a pipeline connected to its sink, returned by [`Pipeline::sink`].
+///
+/// Dropping it detaches the threads, which keep running until the pipeline shuts down.
+pub struct Running {
+    stages: Vec<JoinHandle<()>>,
+}
+
+impl<R> Pipeline<R>
+where
+    R: Reader + Send + 'static,
+{
+    /// Starts a pipeline reading the values of `r`.
+    pub fn source(r: R) -> Self {
+        Pipeline {
+            output: r,
+            stages: Vec::new(),
+        }
+    }
+
+    /// Adds a stage publishing `f` applied to every value it reads.
+    pub fn map<U, F>(self, mut f: F) -> Pipeline<atomic_spsc::ReadHandle<'static, U>>
+    where
+        U: Send + 'static,
+        F: FnMut(&R::Item) -> U + Send + 'static,
+    {
+        self.stage(move |value| Some(f(value)))
+    }
+
+    /// Adds a stage publishing the values it reads that `predicate` accepts.
+    pub fn filter<P>(self, mut predicate: P) -> Pipeline<atomic_spsc::ReadHandle<'static, R::Item>>
+    where
+        R::Item: Clone + Send + 'static,
+        P: FnMut(&R::Item) -> bool + Send + 'static,
+    {
+        self.stage(move |value| predicate(value).then(|| value.clone()))
+    }
+
+    /// Adds a last stage copying every value it reads to `w`, and returns the threads of the
+    /// pipeline.
+    pub fn sink<W>(mut self, w: W) -> Running
+    where
+        R::Item: Clone,
+        W: Writer<Item = R::Item> + Send + 'static,
+    {
+        let r = self.output;
+        self.stages.push(thread::spawn(move || {
+            pump(r, w, |value| Some(value.clone()))
+        }));
+        Running {
+            stages: self.stages,
+        }
+    }
+
+    /// Returns the reader of the last stage along with the threads of the pipeline, for a consumer
+    /// reading the pipeline itself instead of a sink.
+    pub fn into_reader(self) -> (R, Running) {
+        let running = Running {
+            stages: self.stages,
+        };
+        (self.output, running)
+    }
+
+    fn stage<U, F>(mut self, f: F) -> Pipeline<atomic_spsc::ReadHandle<'static, U>>
+    where
+        U: Send + 'static,
+        F: FnMut(&R::Item) -> Option<U> + Send + 'static,
+    {
+        let (output, w) = atomic_spsc::new();
+        let r = self.output;
+        self.stages.push(thread::spawn(move || pump(r, w, f)));
+        Pipeline {
+            output,
+            stages: self.stages,
+        }
+    }
+}
+
+impl Running {
+    /// Waits for every stage to shut down.
+    /// Fails with the payload of the first stage that panicked, if any.
+    pub fn join(self) -> thread::Result<()> {
+        let mut res = Ok(());
+        for stage in self.stages {
+            let joined = stage.join();
+            if res.is_ok() {
+                res = joined;
+            }
+        }
+        res
+    }
+
+    /// Returns whether every stage has shut down.
+    pub fn is_finished(&self) -> bool {
+        self.stages.iter().all(JoinHandle::is_finished)
+    }
+}
+
+impl<R: std::fmt::Debug> std::fmt::Debug for Pipeline<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Pipeline")
+            .field("output", &self.output)
+            .field("stages", &self.stages.len())
+            .finish()
+    }
+}
+
+impl std::fmt::Debug for Running {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Running")
+            .field("stages", &self.stages.len())
+            .field("finished", &self.is_finished())
+            .finish()
+    }
+}
+
+/// Body of a stage: publishes to `w` what `f` makes of the values read from `r`, until either side
+/// is gone.
+fn pump<R, W>(mut r: R, mut w: W, mut f: impl FnMut(&R::Item) -> Option<W::Item>)
+where
+    R: Reader,
+    W: Writer,
+{
+    let mut idle = 0;
+    while w.is_reader_alive() {
+        match r.read() {
+            Ok(guard) => {
+                idle = 0;
+                let value = f(&guard);
+                drop(guard);
+                if let Some(value) = value {
+                    w.write(value);
+                }
+            }
+            Err(TryReadError::Empty) => {
+                backoff::idle(idle);
+                idle += 1;
+            }
+            Err(TryReadError::Closed) => return w.close(),
+            // dropping `w` tells the next stage the source is gone
+            Err(TryReadError::Disconnected | TryReadError::Poisoned) => return,
+        }
+    }
+}
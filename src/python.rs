@@ -0,0 +1,221 @@
+//! Python bindings to a latest-value channel of `bytes`, for instance to hand the last frame of a
+//! sensor read by Rust code to a Python thread.
+//!
+//! The channel is an `atomic_spsc` of byte buffers. Rust code usually keeps the write handle and
+//! passes the read handle to Python wrapped in a [`PyReader`]:
+//!
+//! ```no_run
+//! use pyo3::prelude::*;
+//! use rustedrazors::atomic_spsc;
+//! use rustedrazors::python::PyReader;
+//!
+//! # fn main() -> PyResult<()> {
+//! let (mut r, mut w) = atomic_spsc::new::<Vec<u8>>();
+//! let reader = Python::attach(|py| Py::new(py, PyReader::from(r)))?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`register`] adds the classes, the exceptions and a `channel()` function to a Python module.
+//! Blocking reads release the GIL while they wait, so other Python threads keep running.
+
+use crate::atomic_spsc::{self, ReadHandle, WriteHandle};
+use crate::{Reader, TryReadError, Writer};
+
+use pyo3::create_exception;
+use pyo3::exceptions::{PyEOFError, PyRuntimeError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+create_exception!(
+    rustedrazors,
+    DisconnectedError,
+    PyEOFError,
+    "The writer was dropped, no new value will ever be written."
+);
+create_exception!(
+    rustedrazors,
+    ClosedError,
+    PyEOFError,
+    "The writer closed the channel and its last value was read."
+);
+create_exception!(
+    rustedrazors,
+    PoisonedError,
+    PyRuntimeError,
+    "A write panicked halfway through, the channel can no longer be used."
+);
+
+/// Turns the failures of a read into the matching exception, or `None` if nothing new was written.
+fn empty_or_raise(err: TryReadError) -> PyResult<Option<Vec<u8>>> {
+    match err {
+        TryReadError::Empty => Ok(None),
+        TryReadError::Disconnected => Err(DisconnectedError::new_err(err.to_string())),
+        TryReadError::Closed => Err(ClosedError::new_err(err.to_string())),
+        TryReadError::Poisoned => Err(PoisonedError::new_err(err.to_string())),
+    }
+}
+
+/// Read handle exposed to Python as `Reader`.
+///
+/// Reads need the handle mutably while Python objects are shared, so the handle sits behind a
+/// mutex, only ever contended if several Python threads read at once. A blocking read holds it while waiting
+/// with the GIL released, so it is only ever locked with the GIL released too: otherwise a thread
+/// waiting for it would keep the writer from getting the GIL back to publish.
+#[pyclass(name = "Reader", module = "rustedrazors")]
+pub struct PyReader {
+    handle: Mutex<ReadHandle<'static, Vec<u8>>>,
+}
+
+/// Write handle exposed to Python as `Writer`, emptied by `close`.
+#[pyclass(name = "Writer", module = "rustedrazors")]
+pub struct PyWriter {
+    handle: Mutex<Option<WriteHandle<'static, Vec<u8>>>>,
+}
+
+impl From<ReadHandle<'static, Vec<u8>>> for PyReader {
+    fn from(handle: ReadHandle<'static, Vec<u8>>) -> Self {
+        PyReader {
+            handle: Mutex::new(handle),
+        }
+    }
+}
+
+impl From<WriteHandle<'static, Vec<u8>>> for PyWriter {
+    fn from(handle: WriteHandle<'static, Vec<u8>>) -> Self {
+        PyWriter {
+            handle: Mutex::new(Some(handle)),
+        }
+    }
+}
+
+impl PyReader {
+    /// Waits for a new value with the GIL released, copying it out so it can be turned into
+    /// `bytes` once the GIL is held again.
+    fn wait(&self, py: Python<'_>, timeout: Option<f64>) -> PyResult<Option<Vec<u8>>> {
+        let timeout = timeout
+            .map(Duration::try_from_secs_f64)
+            .transpose()
+            .map_err(|err| pyo3::exceptions::PyValueError::new_err(err.to_string()))?;
+        let res = py.detach(|| {
+            let mut handle = self.handle.lock().unwrap();
+            let res = match timeout {
+                None => handle.read_blocking(),
+                Some(timeout) => handle.read_timeout(timeout),
+            };
+            res.map(|value| value.clone())
+        });
+        res.map_or_else(empty_or_raise, |value| Ok(Some(value)))
+    }
+}
+
+#[pymethods]
+impl PyReader {
+    /// Returns the last written value if it was not read yet, or `None`, without waiting.
+    ///
+    /// Raises `ClosedError` (or `DisconnectedError` if the writer was dropped without closing)
+    /// once the writer is gone and its last value was read.
+    fn read<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyBytes>>> {
+        let res = py.detach(|| {
+            let mut handle = self.handle.lock().unwrap();
+            handle.read().map(|value| value.clone())
+        });
+        let value = res.map_or_else(empty_or_raise, |value| Ok(Some(value)))?;
+        Ok(value.map(|value| PyBytes::new(py, &value)))
+    }
+
+    /// Waits for a value that was not read yet, for at most `timeout` seconds if given, and
+    /// returns it, or `None` on timeout.
+    ///
+    /// The GIL is released while waiting. Raises like `read` once the writer is gone.
+    #[pyo3(signature = (timeout=None))]
+    fn read_blocking<'py>(
+        &self,
+        py: Python<'py>,
+        timeout: Option<f64>,
+    ) -> PyResult<Option<Bound<'py, PyBytes>>> {
+        let value = self.wait(py, timeout)?;
+        Ok(value.map(|value| PyBytes::new(py, &value)))
+    }
+
+    /// Returns how many written values were replaced before they could be read.
+    fn overwritten(&self, py: Python<'_>) -> u64 {
+        py.detach(|| self.handle.lock().unwrap().overwritten())
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// Waits for the next value like `read_blocking`, stopping once the writer is gone.
+    fn __next__<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyBytes>>> {
+        match self.wait(py, None) {
+            Ok(value) => Ok(value.map(|value| PyBytes::new(py, &value))),
+            Err(err)
+                if err.is_instance_of::<DisconnectedError>(py)
+                    || err.is_instance_of::<ClosedError>(py) =>
+            {
+                Ok(None)
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
+
+#[pymethods]
+impl PyWriter {
+    /// Publishes `value`, replacing any value that was not read yet.
+    ///
+    /// Raises `ClosedError` if the writer was closed.
+    fn write(&self, value: &[u8]) -> PyResult<()> {
+        let mut handle = self.handle.lock().unwrap();
+        let handle = handle
+            .as_mut()
+            .ok_or_else(|| ClosedError::new_err("the writer was closed"))?;
+        // reuses the storage of the buffer handed back by the reader
+        handle.write_with(|buffer| {
+            buffer.clear();
+            buffer.extend_from_slice(value);
+        });
+        Ok(())
+    }
+
+    /// Marks the channel as finished: the reader can still read the last written value, then
+    /// every read raises `ClosedError`. Does nothing if the writer was already closed.
+    fn close(&self) {
+        if let Some(handle) = self.handle.lock().unwrap().take() {
+            handle.close();
+        }
+    }
+
+    /// Returns whether the reader still exists, so that producers can stop once nobody listens.
+    fn is_reader_alive(&self) -> bool {
+        let handle = self.handle.lock().unwrap();
+        handle
+            .as_ref()
+            .is_some_and(|handle| handle.is_reader_alive())
+    }
+}
+
+/// Creates a new channel and returns its `(Reader, Writer)` pair.
+#[pyfunction]
+fn channel() -> (PyReader, PyWriter) {
+    let (r, w) = atomic_spsc::new();
+    (r.into(), w.into())
+}
+
+/// Adds `Reader`, `Writer`, `channel`, `DisconnectedError`, `ClosedError` and `PoisonedError` to
+/// `module`.
+pub fn register(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = module.py();
+    module.add_class::<PyReader>()?;
+    module.add_class::<PyWriter>()?;
+    module.add_function(wrap_pyfunction!(channel, module)?)?;
+    module.add("DisconnectedError", py.get_type::<DisconnectedError>())?;
+    module.add("ClosedError", py.get_type::<ClosedError>())?;
+    module.add("PoisonedError", py.get_type::<PoisonedError>())?;
+    Ok(())
+}
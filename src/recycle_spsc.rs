@@ -0,0 +1,339 @@
+use crate::atomic::{AtomicBool, AtomicPtr, AtomicU64, Ordering};
+use crate::debug::DebugState;
+use crate::shared::Shared;
+use crate::{Reader, TryReadError, Writer};
+
+use std::marker::PhantomData;
+use std::ptr;
+use std::sync::Arc;
+
+/// Like `box_spsc`, values move through the channel as `Box`es leaked into `buffer`, with a second
+/// slot for the way back: once the reader is done with a value its box is leaked into `returned`,
+/// where the writer takes it to build a later value in, instead of allocating a new one.
+struct Inner<T> {
+    buffer: AtomicPtr<T>,
+    // box the reader is done with, null until the writer takes it back
+    returned: AtomicPtr<T>,
+    // number of values replaced before being read, only modified by the writer
+    overwritten: AtomicU64,
+    writer_alive: AtomicBool,
+    // set right before `writer_alive` is cleared if the writer left through `close`
+    closed: AtomicBool,
+    reader_alive: AtomicBool,
+    // both slots own a `Box<T>`, so they are only `Send` and `Sync` if that is
+    _owns: PhantomData<Box<T>>,
+}
+
+pub struct ReadHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+}
+
+pub struct WriteHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+    // boxes of the values replaced before being read, reused once the returned one is taken
+    spare: Vec<Box<T>>,
+    // number of boxes allocated by the writer
+    allocations: u64,
+}
+
+impl<T> Inner<T> {
+    fn new() -> Self {
+        Inner {
+            buffer: AtomicPtr::new(ptr::null_mut()),
+            returned: AtomicPtr::new(ptr::null_mut()),
+            overwritten: AtomicU64::new(0),
+            writer_alive: AtomicBool::new(true),
+            closed: AtomicBool::new(false),
+            reader_alive: AtomicBool::new(true),
+            _owns: PhantomData,
+        }
+    }
+
+    /// Writes the provided value, returning the one it replaces if it was not read.
+    ///
+    /// This method is wait-free.
+    fn write(&self, value: Box<T>) -> Option<Box<T>> {
+        let old = self.buffer.swap(Box::into_raw(value), Ordering::AcqRel);
+        if old.is_null() {
+            return None;
+        }
+        self.overwritten.fetch_add(1, Ordering::Relaxed);
+        Some(unsafe { Box::from_raw(old) })
+    }
+
+    /// Try reading the last written value.
+    /// The operation may fail if no new value was written since the last read.
+    ///
+    /// This method is wait-free.
+    fn read(&self) -> Option<Box<T>> {
+        take(&self.buffer)
+    }
+
+    /// Try reading the last written value, reporting whether the writer is gone.
+    fn try_read(&self) -> Result<Box<T>, TryReadError> {
+        if let Some(value) = self.read() {
+            return Ok(value);
+        }
+        if self.writer_alive.load(Ordering::Acquire) {
+            return Err(TryReadError::Empty);
+        }
+        // the writer may have published right before being dropped
+        self.read().ok_or_else(|| self.disconnected())
+    }
+
+    /// Error reported once the writer is gone and nothing is left to read.
+    fn disconnected(&self) -> TryReadError {
+        if self.closed.load(Ordering::Relaxed) {
+            TryReadError::Closed
+        } else {
+            TryReadError::Disconnected
+        }
+    }
+
+    /// Hands a box the reader is done with back to the writer, dropping the one it returned before
+    /// if the writer did not take it yet.
+    ///
+    /// This method is wait-free.
+    fn give_back(&self, value: Box<T>) {
+        if !self.writer_alive.load(Ordering::Relaxed) {
+            return;
+        }
+        let old = self.returned.swap(Box::into_raw(value), Ordering::AcqRel);
+        if !old.is_null() {
+            drop(unsafe { Box::from_raw(old) });
+        }
+    }
+}
+
+/// Takes the box leaked into `slot`, if any.
+fn take<T>(slot: &AtomicPtr<T>) -> Option<Box<T>> {
+    if slot.load(Ordering::Relaxed).is_null() {
+        return None;
+    }
+    let value = slot.swap(ptr::null_mut(), Ordering::AcqRel);
+    if value.is_null() {
+        return None;
+    }
+    Some(unsafe { Box::from_raw(value) })
+}
+
+impl<T> Drop for Inner<T> {
+    fn drop(&mut self) {
+        for slot in [self.buffer.get_mut(), self.returned.get_mut()] {
+            if !slot.is_null() {
+                drop(unsafe { Box::from_raw(*slot) });
+            }
+        }
+    }
+}
+
+/// Read value.
+///
+/// The guard owns the box taken out of the channel, and hands it back to the writer once dropped.
+/// It can instead be kept through [`RecycleGuard::into_box`].
+pub struct RecycleGuard<'a, T> {
+    value: Option<Box<T>>,
+    inner: &'a Inner<T>,
+}
+
+impl<T> RecycleGuard<'_, T> {
+    /// Returns the box written by the writer, which will not be handed back to it.
+    pub fn into_box(mut self) -> Box<T> {
+        self.value.take().unwrap()
+    }
+}
+
+impl<T> std::ops::Deref for RecycleGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value.as_ref().unwrap()
+    }
+}
+
+impl<T> std::ops::DerefMut for RecycleGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.value.as_mut().unwrap()
+    }
+}
+
+impl<T> Drop for RecycleGuard<'_, T> {
+    fn drop(&mut self) {
+        if let Some(value) = self.value.take() {
+            self.inner.give_back(value);
+        }
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for RecycleGuard<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("RecycleGuard").field(&**self).finish()
+    }
+}
+
+impl<T> ReadHandle<'_, T> {
+    /// Returns how many written values were replaced before they could be read.
+    pub fn overwritten(&self) -> u64 {
+        self.inner.overwritten.load(Ordering::Relaxed)
+    }
+}
+
+impl<T> WriteHandle<'_, T> {
+    /// Returns how many boxes the writer allocated, which only grows when no box was handed back
+    /// or replaced since the last write.
+    pub fn allocations(&self) -> u64 {
+        self.allocations
+    }
+
+    /// Returns how many written values were replaced before the reader could read them.
+    pub fn overwritten(&self) -> u64 {
+        self.inner.overwritten.load(Ordering::Relaxed)
+    }
+
+    /// Returns a box to write the next value in: the one handed back by the reader, or else one
+    /// replaced before being read.
+    ///
+    /// The returned box is taken first, so that the slot is free again when the reader is done with
+    /// the next value.
+    fn recycled(&mut self) -> Option<Box<T>> {
+        take(&self.inner.returned).or_else(|| self.spare.pop())
+    }
+
+    fn allocate(&mut self, value: T) -> Box<T> {
+        self.allocations += 1;
+        Box::new(value)
+    }
+
+    fn publish(&mut self, value: Box<T>) {
+        if let Some(old) = self.inner.write(value) {
+            self.spare.push(old);
+        }
+    }
+}
+
+impl<T> Reader for ReadHandle<'_, T> {
+    type Item = T;
+    type Guard<'a>
+        = RecycleGuard<'a, T>
+    where
+        Self: 'a;
+
+    fn read(&mut self) -> Result<Self::Guard<'_>, TryReadError> {
+        let value = self.inner.try_read()?;
+        Ok(RecycleGuard {
+            value: Some(value),
+            inner: &self.inner,
+        })
+    }
+}
+
+impl<T> Writer for WriteHandle<'_, T> {
+    type Item = T;
+
+    /// Writes the provided value, moving it into a recycled box if there is one.
+    ///
+    /// The previous content of the box is dropped: see [`Writer::write_with`] to reuse it too.
+    fn write(&mut self, value: T) {
+        let boxed = match self.recycled() {
+            Some(mut boxed) => {
+                *boxed = value;
+                boxed
+            }
+            None => self.allocate(value),
+        };
+        self.publish(boxed);
+    }
+
+    /// Writes a value built in place by `f`, in a recycled box if there is one, which still holds
+    /// the value it was used for last, or else in a new box holding `T::default()`.
+    ///
+    /// Reusing the content too, such as clearing and refilling a `Vec<u8>` frame, makes a
+    /// steady flow of values allocation-free.
+    fn write_with(&mut self, f: impl FnOnce(&mut T))
+    where
+        T: Default,
+    {
+        let mut value = self
+            .recycled()
+            .unwrap_or_else(|| self.allocate(T::default()));
+        f(&mut value);
+        self.publish(value);
+    }
+
+    fn close(self) {
+        self.inner.closed.store(true, Ordering::Relaxed);
+        // dropping `self` disconnects the reader
+    }
+
+    fn is_reader_alive(&self) -> bool {
+        self.inner.reader_alive.load(Ordering::Acquire)
+    }
+}
+
+impl<T> Drop for ReadHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.reader_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<T> Drop for WriteHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.writer_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<T> DebugState for Inner<T> {
+    fn debug_state(&self, d: &mut std::fmt::DebugStruct<'_, '_>) {
+        d.field("pending", &!self.buffer.load(Ordering::Relaxed).is_null())
+            .field(
+                "returned",
+                &!self.returned.load(Ordering::Relaxed).is_null(),
+            )
+            .field("overwritten", &self.overwritten.load(Ordering::Relaxed))
+            .field("writer_alive", &self.writer_alive.load(Ordering::Relaxed))
+            .field("closed", &self.closed.load(Ordering::Relaxed))
+            .field("reader_alive", &self.reader_alive.load(Ordering::Relaxed));
+    }
+}
+
+impl<T> std::fmt::Debug for ReadHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "recycle_spsc::ReadHandle")
+    }
+}
+
+impl<T> std::fmt::Debug for WriteHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut d = f.debug_struct("recycle_spsc::WriteHandle");
+        self.inner.debug_state(&mut d);
+        d.field("allocations", &self.allocations).finish()
+    }
+}
+
+/// Construct a new, empty read and write handle pair.
+///
+/// Reads fail with [`TryReadError::Empty`] until the first write.
+pub fn new<T>() -> (ReadHandle<'static, T>, WriteHandle<'static, T>) {
+    let inner = Arc::new(Inner::new());
+    let r = ReadHandle {
+        inner: Shared::Arc(Arc::clone(&inner)),
+    };
+    let w = WriteHandle {
+        inner: Shared::Arc(inner),
+        spare: Vec::new(),
+        allocations: 0,
+    };
+    (r, w)
+}
+
+/// This variant as a [`crate::Channel`], for code generic over the variant.
+pub struct Channel;
+
+impl<T: 'static> crate::Channel<T> for Channel {
+    type Reader = ReadHandle<'static, T>;
+    type Writer = WriteHandle<'static, T>;
+
+    fn create() -> (Self::Reader, Self::Writer) {
+        new()
+    }
+}
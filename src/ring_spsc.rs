@@ -0,0 +1,371 @@
+use crate::atomic::{AtomicBool, AtomicUsize, Ordering};
+use crate::backoff;
+use crate::debug::DebugState;
+use crate::padded::CachePadded;
+use crate::shared::Shared;
+use crate::{Reader, TryReadError, WriteError, Writer};
+
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::sync::Arc;
+
+/// Bounded FIFO queue: unlike the other modules every written value is read, in order, and the
+/// writer waits for room instead of replacing values.
+///
+/// The counters wrap around at `usize::MAX`, so the number of slots is rounded up to a power of two
+/// for every count to keep mapping to the same slot across the wrap; at most `capacity` of them are
+/// used at a time.
+struct Inner<T> {
+    slots: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    // number of values the queue holds when full, at most `slots.len()`
+    capacity: usize,
+    // number of values popped so far, only modified by the reader
+    head: CachePadded<AtomicUsize>,
+    // number of values pushed so far, only modified by the writer
+    tail: CachePadded<AtomicUsize>,
+    writer_alive: AtomicBool,
+    // set right before `writer_alive` is cleared if the writer left through `close`
+    closed: AtomicBool,
+    reader_alive: AtomicBool,
+}
+
+/// Safety: slots between `head` and `tail` belong to the reader, the others to the writer, and
+/// each side only moves its own counter.
+unsafe impl<T> Sync for Inner<T> where T: Send {}
+
+pub struct ReadHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+}
+
+pub struct WriteHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+}
+
+impl<T> Inner<T> {
+    fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must not be zero");
+        Inner {
+            slots: (0..capacity.next_power_of_two())
+                .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+                .collect(),
+            capacity,
+            head: CachePadded::new(AtomicUsize::new(0)),
+            tail: CachePadded::new(AtomicUsize::new(0)),
+            writer_alive: AtomicBool::new(true),
+            closed: AtomicBool::new(false),
+            reader_alive: AtomicBool::new(true),
+        }
+    }
+
+    fn slot(&self, count: usize) -> *mut MaybeUninit<T> {
+        self.slots[count & (self.slots.len() - 1)].get()
+    }
+
+    fn len(&self) -> usize {
+        let tail = self.tail.load(Ordering::Acquire);
+        tail.wrapping_sub(self.head.load(Ordering::Acquire))
+    }
+
+    /// Pushes a value built in place by `f`, or hands `f` back if the queue is full.
+    fn try_push_with<F>(&self, f: F) -> Result<(), F>
+    where
+        F: FnOnce(&mut MaybeUninit<T>),
+    {
+        let tail = self.tail.load(Ordering::Relaxed);
+        if tail.wrapping_sub(self.head.load(Ordering::Acquire)) == self.capacity {
+            return Err(f);
+        }
+        unsafe { f(&mut *self.slot(tail)) }
+        self.tail.store(tail.wrapping_add(1), Ordering::Release);
+        Ok(())
+    }
+
+    /// Pushes a value built in place by `f`, spinning until there is room.
+    /// The value is dropped without calling `f` if the reader is gone, since nobody would pop it.
+    fn push_with(&self, mut f: impl FnOnce(&mut MaybeUninit<T>)) {
+        for i in 0.. {
+            f = match self.try_push_with(f) {
+                Ok(()) => return,
+                Err(f) => f,
+            };
+            if !self.reader_alive.load(Ordering::Acquire) {
+                return;
+            }
+            backoff::snooze(i);
+        }
+    }
+
+    /// Pops the oldest value.
+    ///
+    /// This method is wait-free.
+    fn pop(&self) -> Option<T> {
+        let head = self.head.load(Ordering::Relaxed);
+        if head == self.tail.load(Ordering::Acquire) {
+            return None;
+        }
+        let value = unsafe { (*self.slot(head)).assume_init_read() };
+        self.head.store(head.wrapping_add(1), Ordering::Release);
+        Some(value)
+    }
+
+    /// Pops the oldest value, reporting whether the writer is gone.
+    fn try_pop(&self) -> Result<T, TryReadError> {
+        if let Some(value) = self.pop() {
+            return Ok(value);
+        }
+        if self.writer_alive.load(Ordering::Acquire) {
+            return Err(TryReadError::Empty);
+        }
+        // the writer may have pushed right before being dropped
+        self.pop().ok_or_else(|| self.disconnected())
+    }
+
+    /// Error reported once the writer is gone and nothing is left to read.
+    fn disconnected(&self) -> TryReadError {
+        if self.closed.load(Ordering::Relaxed) {
+            TryReadError::Closed
+        } else {
+            TryReadError::Disconnected
+        }
+    }
+}
+
+impl<T> Drop for Inner<T> {
+    fn drop(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+
+/// Value popped from the queue.
+///
+/// Popping moves the value out of its slot right away, so the guard owns it and the slot is
+/// already available to the writer.
+#[derive(Debug)]
+pub struct RingGuard<T>(T);
+
+impl<T> RingGuard<T> {
+    /// Returns the popped value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> std::ops::Deref for RingGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> ReadHandle<'_, T> {
+    /// Pops the oldest value not read yet.
+    /// Returns `None` if the queue is empty.
+    pub fn pop(&mut self) -> Option<T> {
+        self.inner.pop()
+    }
+
+    /// Returns how many values are waiting to be read.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns whether no value is waiting to be read.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns how many values the queue can hold.
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity
+    }
+}
+
+impl<T> WriteHandle<'_, T> {
+    /// Pushes `value` unless the queue is full, in which case it is handed back.
+    ///
+    /// This method is wait-free.
+    pub fn try_push(&mut self, value: T) -> Result<(), T> {
+        let mut value = Some(value);
+        let res = self.inner.try_push_with(|slot| {
+            slot.write(value.take().unwrap());
+        });
+        match res {
+            Ok(()) => Ok(()),
+            Err(_) => Err(value.take().unwrap()),
+        }
+    }
+
+    /// Returns how many values are waiting to be read.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns whether no value is waiting to be read.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns how many values the queue can hold.
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity
+    }
+}
+
+impl<T> Reader for ReadHandle<'_, T> {
+    type Item = T;
+    type Guard<'a>
+        = RingGuard<T>
+    where
+        Self: 'a;
+
+    /// Pops the oldest value not read yet.
+    fn read(&mut self) -> Result<Self::Guard<'_>, TryReadError> {
+        self.inner.try_pop().map(RingGuard)
+    }
+}
+
+impl<T> Writer for WriteHandle<'_, T> {
+    type Item = T;
+
+    /// Pushes `value`, spinning until there is room in the queue.
+    ///
+    /// The value is dropped if the reader is gone, since nobody would pop it.
+    fn write(&mut self, value: T) {
+        self.inner.push_with(|slot| {
+            slot.write(value);
+        })
+    }
+
+    /// Pushes a value built in place by `f`, starting from `T::default()`, spinning until there
+    /// is room in the queue.
+    fn write_with(&mut self, f: impl FnOnce(&mut T))
+    where
+        T: Default,
+    {
+        self.inner.push_with(|slot| f(slot.write(T::default())))
+    }
+
+    fn close(self) {
+        self.inner.closed.store(true, Ordering::Relaxed);
+        // dropping `self` disconnects the reader
+    }
+
+    fn is_reader_alive(&self) -> bool {
+        self.inner.reader_alive.load(Ordering::Acquire)
+    }
+
+    fn try_write(&mut self, value: T) -> Result<(), WriteError<T>> {
+        if !self.is_reader_alive() {
+            return Err(WriteError::Disconnected(value));
+        }
+        self.try_push(value).map_err(WriteError::WouldBlock)
+    }
+}
+
+impl<T> Drop for ReadHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.reader_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<T> Drop for WriteHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.writer_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<T> DebugState for Inner<T> {
+    fn debug_state(&self, d: &mut std::fmt::DebugStruct<'_, '_>) {
+        d.field(
+            "len",
+            &self
+                .tail
+                .load(Ordering::Relaxed)
+                .wrapping_sub(self.head.load(Ordering::Relaxed)),
+        )
+        .field("capacity", &self.capacity)
+        .field("writer_alive", &self.writer_alive.load(Ordering::Relaxed))
+        .field("closed", &self.closed.load(Ordering::Relaxed))
+        .field("reader_alive", &self.reader_alive.load(Ordering::Relaxed));
+    }
+}
+
+impl<T> std::fmt::Debug for ReadHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "ring_spsc::ReadHandle")
+    }
+}
+
+impl<T> std::fmt::Debug for WriteHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "ring_spsc::WriteHandle")
+    }
+}
+
+/// Construct a new read and write handle pair for a queue holding up to `capacity` values.
+/// Room is allocated for `capacity` rounded up to a power of two.
+///
+/// Panics if `capacity` is zero.
+pub fn new<T>(capacity: usize) -> (ReadHandle<'static, T>, WriteHandle<'static, T>) {
+    let inner = Arc::new(Inner::new(capacity));
+    let r = ReadHandle {
+        inner: Shared::Arc(Arc::clone(&inner)),
+    };
+    let w = WriteHandle {
+        inner: Shared::Arc(inner),
+    };
+    (r, w)
+}
+
+/// This variant as a [`crate::Channel`], holding up to `CAPACITY` values, for code generic over
+/// the variant.
+pub struct Channel<const CAPACITY: usize>;
+
+impl<T: 'static, const CAPACITY: usize> crate::Channel<T> for Channel<CAPACITY> {
+    type Reader = ReadHandle<'static, T>;
+    type Writer = WriteHandle<'static, T>;
+
+    fn create() -> (Self::Reader, Self::Writer) {
+        new(CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counter_wrap() {
+        // Test a full queue whose counters wrap around `usize::MAX`, with a capacity that does not
+        // divide the number of counts: every value should be read once, in order
+
+        let alive = Arc::new(());
+        let inner = Inner::new(3);
+        inner.head.store(usize::MAX - 4, Ordering::Relaxed);
+        inner.tail.store(usize::MAX - 4, Ordering::Relaxed);
+        let push = |i| {
+            let value = (i, Arc::clone(&alive));
+            inner.try_push_with(|slot| {
+                slot.write(value);
+            })
+        };
+
+        for i in 0..3 {
+            assert!(push(i).is_ok());
+        }
+        for i in 3..13 {
+            assert!(push(i).is_err(), "Queue should have been full");
+            assert_eq!(inner.pop().map(|(i, _)| i), Some(i - 3));
+            assert!(push(i).is_ok(), "Popping should have made room");
+        }
+        assert_eq!(inner.len(), 3);
+        assert_eq!(inner.pop().map(|(i, _)| i), Some(10));
+        drop(inner);
+        assert_eq!(
+            Arc::strong_count(&alive),
+            1,
+            "Every value should have been dropped exactly once"
+        );
+    }
+}
@@ -0,0 +1,91 @@
+//! Latest-value mailbox for [RTIC](https://rtic.rs) applications, built on `isr_spsc`.
+//!
+//! RTIC hands `init` a `&'static mut` to each of its `local` resources, and moves whatever `init`
+//! returns into the `#[local]` resources of the tasks. A [`Mailbox`] fits that model: declared as
+//! a local of `init` it needs no `static` of its own, [`Mailbox::split`] cannot fail, and the
+//! [`Producer`] and [`Consumer`] it returns are `Send` late resources for the hardware task and
+//! for `idle` (or a software task). Neither side ever needs a `lock`: writes are wait-free and
+//! reads copy the value out without blocking the writer.
+//!
+//! ```ignore
+//! #[rtic::app(device = pac)]
+//! mod app {
+//!     use rustedrazors::rtic::{Consumer, Mailbox, Producer};
+//!     use rustedrazors::{Reader, Writer};
+//!
+//!     #[shared]
+//!     struct Shared {}
+//!
+//!     #[local]
+//!     struct Local {
+//!         producer: Producer<[u16; 4]>,
+//!         consumer: Consumer<[u16; 4]>,
+//!     }
+//!
+//!     #[init(local = [mailbox: Mailbox<[u16; 4]> = Mailbox::new()])]
+//!     fn init(cx: init::Context) -> (Shared, Local) {
+//!         let (consumer, producer) = cx.local.mailbox.split();
+//!         (Shared {}, Local { producer, consumer })
+//!     }
+//!
+//!     #[task(binds = ADC, local = [producer])]
+//!     fn adc(cx: adc::Context) {
+//!         cx.local.producer.write(read_adc());
+//!     }
+//!
+//!     #[idle(local = [consumer])]
+//!     fn idle(cx: idle::Context) -> ! {
+//!         loop {
+//!             if let Ok(sample) = cx.local.consumer.read() {
+//!                 process(*sample);
+//!             }
+//!             rtic::export::wfi();
+//!         }
+//!     }
+//! }
+//! ```
+
+use crate::isr_spsc::{ReadHandle, Storage, WriteHandle};
+
+/// Write side of a [`Mailbox`], to be bound to a hardware task.
+pub type Producer<T> = WriteHandle<T>;
+
+/// Read side of a [`Mailbox`], to be polled from `idle` or a software task.
+pub type Consumer<T> = ReadHandle<T>;
+
+/// Storage of a latest-value channel, meant to be a `local` resource of `init`.
+pub struct Mailbox<T> {
+    storage: Storage<T>,
+}
+
+impl<T> Mailbox<T>
+where
+    T: Copy,
+{
+    /// Constructs a new, empty mailbox, usable as the initializer of an RTIC local resource.
+    pub const fn new() -> Self {
+        Mailbox {
+            storage: Storage::new(),
+        }
+    }
+
+    /// Returns both sides of the mailbox.
+    ///
+    /// Taking `&'static mut self` by value means this can only ever be called once on a mailbox,
+    /// so, unlike [`Storage::split`], it always succeeds.
+    pub fn split(&'static mut self) -> (Consumer<T>, Producer<T>) {
+        let storage: &'static Storage<T> = &self.storage;
+        storage
+            .split()
+            .expect("the mailbox is exclusively borrowed for 'static")
+    }
+}
+
+impl<T> Default for Mailbox<T>
+where
+    T: Copy,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
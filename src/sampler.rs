@@ -0,0 +1,104 @@
+//! Producers publishing a fresh sample at a fixed interval, each running on its own thread.
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use rustedrazors::sampler::Sampler;
+//! use rustedrazors::{atomic_spsc, Reader, Writer};
+//!
+//! let (mut r, mut w) = atomic_spsc::new::<u64>();
+//! let mut ticks = 0;
+//! let sampler = Sampler::spawn(w, Duration::from_millis(10), move || {
+//!     ticks += 1;
+//!     ticks
+//! });
+//!
+//! assert!(r.read_blocking().is_ok());
+//!
+//! // stopping hands the writer back, to close the channel for the reader
+//! sampler.stop().unwrap().close();
+//! ```
+//!
+//! The first sample is published right away, then one per interval counted from the first, so
+//! that the sampling does not drift by the time `f` takes. A sample running late delays the next
+//! one instead of being followed by a burst catching up.
+
+use crate::Writer;
+
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Thread publishing a sample to the writer `W` every interval, until the sampler is dropped or the
+/// reader is.
+///
+/// Dropping the sampler wakes the thread right away and waits for it to finish the sample it may be
+/// taking, then drops `W`, which the reader sees as a disconnection.
+pub struct Sampler<W> {
+    // dropped to wake up the thread and stop it
+    stop: Option<mpsc::Sender<()>>,
+    thread: Option<JoinHandle<W>>,
+}
+
+impl<W> Sampler<W>
+where
+    W: Writer + Send + 'static,
+{
+    /// Spawns a thread publishing `f()` to `w` every `interval`.
+    pub fn spawn<F>(mut w: W, interval: Duration, mut f: F) -> Self
+    where
+        F: FnMut() -> W::Item + Send + 'static,
+    {
+        let (stop, stopped) = mpsc::channel();
+        let thread = thread::spawn(move || {
+            let mut next = Instant::now();
+            while w.is_reader_alive() {
+                w.write(f());
+                let now = Instant::now();
+                next = (next + interval).max(now);
+                match stopped.recv_timeout(next - now) {
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Ok(()) | Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+            w
+        });
+        Sampler {
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+}
+
+impl<W> Sampler<W> {
+    /// Stops the thread and returns the writer, for instance to close the channel.
+    /// Fails with the payload of `f` if it panicked.
+    pub fn stop(mut self) -> thread::Result<W> {
+        self.stop = None;
+        self.thread.take().unwrap().join()
+    }
+
+    /// Returns whether the thread has stopped on its own, because the reader is gone or `f`
+    /// panicked.
+    pub fn is_finished(&self) -> bool {
+        self.thread.as_ref().is_none_or(JoinHandle::is_finished)
+    }
+}
+
+impl<W> Drop for Sampler<W> {
+    fn drop(&mut self) {
+        self.stop = None;
+        if let Some(thread) = self.thread.take() {
+            // a panic of `f` was already reported by the thread
+            let _ = thread.join();
+        }
+    }
+}
+
+impl<W> std::fmt::Debug for Sampler<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Sampler")
+            .field("finished", &self.is_finished())
+            .finish()
+    }
+}
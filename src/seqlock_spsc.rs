@@ -0,0 +1,430 @@
+use crate::atomic::{fence, AtomicBool, AtomicU64, Ordering};
+use crate::debug::DebugState;
+use crate::shared::Shared;
+use crate::{Reader, TryReadError, Writer};
+
+use std::cell::UnsafeCell;
+use std::mem::{size_of, MaybeUninit};
+// the value is copied through its own memory, so these are always the native types
+use std::sync::atomic::{AtomicU8, AtomicUsize};
+use std::sync::Arc;
+
+const WORD: usize = size_of::<usize>();
+
+/// Values the sequence lock can copy a word at a time while the other side may be copying too.
+///
+/// # Safety
+///
+/// Every byte of the value must be initialized: types with padding or `MaybeUninit` fields must
+/// not implement it. Any bit pattern does not have to be a valid value though, the reader only
+/// keeps the copies that were not torn.
+pub unsafe trait Plain: Copy {}
+
+macro_rules! plain {
+    ($($ty:ty),* $(,)?) => {$(
+        unsafe impl Plain for $ty {}
+    )*};
+}
+
+plain!(u8, u16, u32, u64, u128, usize);
+plain!(i8, i16, i32, i64, i128, isize);
+plain!(f32, f64, bool, char, ());
+
+unsafe impl<T, const N: usize> Plain for [T; N] where T: Plain {}
+
+/// Memory of the value, only ever accessed with relaxed atomic loads and stores so that the
+/// reader racing with the writer is not a data race, a torn copy is simply discarded.
+///
+/// It is word aligned whatever `T` is, so that the copies can go a word at a time.
+#[repr(C, align(8))]
+struct Slot<T>(UnsafeCell<MaybeUninit<T>>);
+
+impl<T> Slot<T> {
+    const fn uninit() -> Self {
+        Slot(UnsafeCell::new(MaybeUninit::uninit()))
+    }
+
+    fn as_ptr(&self) -> *mut u8 {
+        self.0.get().cast()
+    }
+}
+
+impl<T> Slot<T>
+where
+    T: Plain,
+{
+    /// Copies `value` in, the last word byte by byte.
+    fn store(&self, value: T) {
+        let src = Slot(UnsafeCell::new(MaybeUninit::new(value)));
+        let (src, dst) = (src.as_ptr(), self.as_ptr());
+        let words = size_of::<T>() / WORD;
+        // Safety: both slots are word aligned, and `T: Plain` has no uninitialized byte
+        unsafe {
+            for i in 0..words {
+                let word = src.cast::<usize>().add(i).read();
+                AtomicUsize::from_ptr(dst.cast::<usize>().add(i)).store(word, Ordering::Relaxed);
+            }
+            for i in words * WORD..size_of::<T>() {
+                AtomicU8::from_ptr(dst.add(i)).store(src.add(i).read(), Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Copies the value out, which may be torn if a store is in progress.
+    ///
+    /// Safety: a value must have been stored before.
+    unsafe fn load(&self) -> MaybeUninit<T> {
+        let dst = Slot::<T>::uninit();
+        let (src, dst_ptr) = (self.as_ptr(), dst.as_ptr());
+        let words = size_of::<T>() / WORD;
+        for i in 0..words {
+            let word = AtomicUsize::from_ptr(src.cast::<usize>().add(i)).load(Ordering::Relaxed);
+            dst_ptr.cast::<usize>().add(i).write(word);
+        }
+        for i in words * WORD..size_of::<T>() {
+            dst_ptr
+                .add(i)
+                .write(AtomicU8::from_ptr(src.add(i)).load(Ordering::Relaxed));
+        }
+        dst.0.into_inner()
+    }
+}
+
+/// A single copy of the value guarded by a sequence counter, which is odd while the writer is
+/// copying a new value in. The reader copies the value out and retries if the counter moved.
+struct Inner<T> {
+    data: Slot<T>,
+    // number of started writes times two, plus one while a write is in progress
+    seq: AtomicU64,
+    // `seq` of the last value read, only modified by the reader
+    read_seq: AtomicU64,
+    // number of values replaced before the reader's last read, only modified by the reader: the
+    // writer cannot tell whether a value it replaces was just copied out by the reader
+    skipped: AtomicU64,
+    writer_alive: AtomicBool,
+    // set right before `writer_alive` is cleared if the writer left through `close`
+    closed: AtomicBool,
+    reader_alive: AtomicBool,
+}
+
+/// Safety: `data` is only written by the writer, and the reader discards any copy that may have
+/// raced with a write before looking at it.
+unsafe impl<T> Sync for Inner<T> where T: Send {}
+
+pub struct ReadHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+}
+
+pub struct WriteHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+}
+
+impl<T> Inner<T> {
+    const fn new() -> Self {
+        Inner {
+            data: Slot::uninit(),
+            seq: AtomicU64::new(0),
+            read_seq: AtomicU64::new(0),
+            skipped: AtomicU64::new(0),
+            writer_alive: AtomicBool::new(true),
+            closed: AtomicBool::new(false),
+            reader_alive: AtomicBool::new(true),
+        }
+    }
+}
+
+impl<T> Inner<T> {
+    /// Returns how many written values were replaced before being read: those skipped by the
+    /// last reads, and all but the last of those written since.
+    ///
+    /// The counters are not read at once, so this may be off while a read is in progress.
+    fn overwritten(&self) -> u64 {
+        let skipped = self.skipped.load(Ordering::Relaxed);
+        let read_seq = self.read_seq.load(Ordering::Acquire);
+        // a write in progress has not replaced anything yet
+        let seq = self.seq.load(Ordering::Relaxed) & !1;
+        skipped + (seq.saturating_sub(read_seq) / 2).saturating_sub(1)
+    }
+}
+
+impl<T> Inner<T>
+where
+    T: Plain,
+{
+    /// Writes the provided value.
+    ///
+    /// This method is wait-free, the writer never waits for the reader.
+    fn write(&self, value: T) {
+        let seq = self.seq.load(Ordering::Relaxed);
+        self.seq.store(seq + 1, Ordering::Relaxed);
+        // keeps the copy below from being seen before the odd sequence
+        fence(Ordering::Release);
+        self.data.store(value);
+        self.seq.store(seq + 2, Ordering::Release);
+    }
+
+    fn write_with(&self, f: impl FnOnce(&mut T))
+    where
+        T: Default,
+    {
+        // only the writer modifies `data`, so it can read it without checking `seq`
+        let mut value = if self.seq.load(Ordering::Relaxed) == 0 {
+            T::default()
+        } else {
+            unsafe { self.data.load().assume_init() }
+        };
+        f(&mut value);
+        self.write(value);
+    }
+
+    /// Try reading the last written value.
+    /// The operation may fail if no new value was written since the last read.
+    ///
+    /// This method spins while the writer is copying in a value the reader has not read yet.
+    fn read(&self) -> Option<SeqGuard<T>> {
+        let last = self.read_seq.load(Ordering::Relaxed);
+        loop {
+            let seq = self.seq.load(Ordering::Acquire);
+            if seq & 1 == 1 {
+                if seq - 1 == last {
+                    // the value being written is the first one not read yet
+                    return None;
+                }
+                std::hint::spin_loop();
+                continue;
+            }
+            if seq == last {
+                return None;
+            }
+            // may be torn, it is only looked at if `seq` did not move in the meantime
+            let value = unsafe { self.data.load() };
+            fence(Ordering::Acquire);
+            if self.seq.load(Ordering::Relaxed) == seq {
+                let skipped = (seq - last) / 2 - 1;
+                if skipped > 0 {
+                    self.skipped.fetch_add(skipped, Ordering::Relaxed);
+                }
+                self.read_seq.store(seq, Ordering::Release);
+                return Some(SeqGuard(unsafe { value.assume_init() }));
+            }
+        }
+    }
+
+    /// Try reading the last written value, reporting whether the writer is gone.
+    fn try_read(&self) -> Result<SeqGuard<T>, TryReadError> {
+        if let Some(guard) = self.read() {
+            return Ok(guard);
+        }
+        if self.writer_alive.load(Ordering::Acquire) {
+            return Err(TryReadError::Empty);
+        }
+        // the writer may have published right before being dropped
+        self.read().ok_or_else(|| self.disconnected())
+    }
+
+    /// Error reported once the writer is gone and nothing is left to read.
+    fn disconnected(&self) -> TryReadError {
+        if self.closed.load(Ordering::Relaxed) {
+            TryReadError::Closed
+        } else {
+            TryReadError::Disconnected
+        }
+    }
+}
+
+/// Copy of a read value.
+///
+/// The value is copied out of the sequence lock, so the guard owns it and the writer is never
+/// held back.
+#[derive(Debug, Clone, Copy)]
+pub struct SeqGuard<T>(T);
+
+impl<T> std::ops::Deref for SeqGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> ReadHandle<'_, T> {
+    /// Returns how many written values were replaced before they could be read.
+    pub fn overwritten(&self) -> u64 {
+        self.inner.overwritten()
+    }
+}
+
+impl<T> WriteHandle<'_, T> {
+    /// Returns how many written values were replaced before the reader could read them.
+    pub fn overwritten(&self) -> u64 {
+        self.inner.overwritten()
+    }
+}
+
+impl<T> Reader for ReadHandle<'_, T>
+where
+    T: Plain,
+{
+    type Item = T;
+    type Guard<'a>
+        = SeqGuard<T>
+    where
+        Self: 'a;
+
+    fn read(&mut self) -> Result<Self::Guard<'_>, TryReadError> {
+        self.inner.try_read()
+    }
+}
+
+impl<T> Writer for WriteHandle<'_, T>
+where
+    T: Plain,
+{
+    type Item = T;
+
+    fn write(&mut self, value: T) {
+        self.inner.write(value)
+    }
+
+    fn write_with(&mut self, f: impl FnOnce(&mut T))
+    where
+        T: Default,
+    {
+        self.inner.write_with(f)
+    }
+
+    fn close(self) {
+        self.inner.closed.store(true, Ordering::Relaxed);
+        // dropping `self` disconnects the reader
+    }
+
+    fn is_reader_alive(&self) -> bool {
+        self.inner.reader_alive.load(Ordering::Acquire)
+    }
+}
+
+impl<T> Drop for ReadHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.reader_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<T> Drop for WriteHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.writer_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<T> DebugState for Inner<T> {
+    fn debug_state(&self, d: &mut std::fmt::DebugStruct<'_, '_>) {
+        let seq = self.seq.load(Ordering::Relaxed);
+        let read_seq = self.read_seq.load(Ordering::Relaxed);
+        d.field("pending", &(seq & !1 != read_seq))
+            .field("writing", &(seq & 1 == 1))
+            .field("seq", &seq)
+            .field("read_seq", &read_seq)
+            .field("skipped", &self.skipped.load(Ordering::Relaxed))
+            .field("writer_alive", &self.writer_alive.load(Ordering::Relaxed))
+            .field("closed", &self.closed.load(Ordering::Relaxed))
+            .field("reader_alive", &self.reader_alive.load(Ordering::Relaxed));
+    }
+}
+
+impl<T> std::fmt::Debug for ReadHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "seqlock_spsc::ReadHandle")
+    }
+}
+
+impl<T> std::fmt::Debug for WriteHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "seqlock_spsc::WriteHandle")
+    }
+}
+
+/// Construct a new, empty read and write handle pair.
+///
+/// Reads fail with [`TryReadError::Empty`] until the first write.
+pub fn new<T>() -> (ReadHandle<'static, T>, WriteHandle<'static, T>)
+where
+    T: Plain,
+{
+    let inner = Arc::new(Inner::new());
+    let r = ReadHandle {
+        inner: Shared::Arc(Arc::clone(&inner)),
+    };
+    let w = WriteHandle {
+        inner: Shared::Arc(inner),
+    };
+    (r, w)
+}
+
+/// This variant as a [`crate::Channel`], for code generic over the variant.
+pub struct Channel;
+
+impl<T: Plain + 'static> crate::Channel<T> for Channel {
+    type Reader = ReadHandle<'static, T>;
+    type Writer = WriteHandle<'static, T>;
+
+    fn create() -> (Self::Reader, Self::Writer) {
+        new()
+    }
+}
+
+/// Storage for a channel that is not reference counted, so that no allocation is needed.
+///
+/// It can be placed in a `static` or borrowed by the threads of a [`std::thread::scope`]: the
+/// handles returned by [`Storage::split`] borrow it, and are handed out only once.
+///
+/// ```
+/// use rustedrazors::seqlock_spsc::Storage;
+/// use rustedrazors::{Reader, Writer};
+///
+/// let storage = Storage::new();
+/// let (mut r, mut w) = storage.split().unwrap();
+/// std::thread::scope(|s| {
+///     s.spawn(move || w.write([42u64; 4]));
+/// });
+/// assert_eq!(r.read().as_deref(), Ok(&[42; 4]));
+/// ```
+pub struct Storage<T> {
+    inner: Inner<T>,
+    split: AtomicBool,
+}
+
+impl<T> Storage<T>
+where
+    T: Plain,
+{
+    /// Constructs a new, empty storage.
+    pub const fn new() -> Self {
+        Storage {
+            inner: Inner::new(),
+            split: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the read and write handles borrowing the storage, or `None` if they were already
+    /// taken.
+    pub fn split(&self) -> Option<(ReadHandle<'_, T>, WriteHandle<'_, T>)> {
+        if self.split.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+        let r = ReadHandle {
+            inner: Shared::Borrowed(&self.inner),
+        };
+        let w = WriteHandle {
+            inner: Shared::Borrowed(&self.inner),
+        };
+        Some((r, w))
+    }
+}
+
+impl<T> Default for Storage<T>
+where
+    T: Plain,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
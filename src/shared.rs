@@ -0,0 +1,76 @@
+#[cfg(feature = "std")]
+use std::sync::Arc;
+
+// low bit of the raw pointer of a borrowed state, never set on the pointer of an `Arc`
+#[cfg(feature = "std")]
+const BORROWED: usize = 1;
+
+/// Handle-side access to the state of a channel, either reference counted or borrowed from a
+/// `Storage` that outlives the handles.
+///
+/// Without `std`, only `cs_spsc` uses it and its state can only be borrowed.
+pub(crate) enum Shared<'a, I> {
+    #[cfg(feature = "std")]
+    Arc(Arc<I>),
+    Borrowed(&'a I),
+}
+
+#[cfg(feature = "std")]
+impl<I> Shared<'_, I> {
+    /// Returns the state of the channel if this was the last handle to it, or `None` if it is
+    /// still shared or borrowed from a `Storage`.
+    pub(crate) fn into_owned(self) -> Option<I> {
+        match self {
+            Shared::Arc(inner) => Arc::try_unwrap(inner).ok(),
+            Shared::Borrowed(_) => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a, I> Shared<'a, I> {
+    /// Turns this into a raw pointer, keeping the reference count (or the borrow) alive until
+    /// [`Shared::from_raw`]. The low bit of the pointer tells a borrowed state apart.
+    pub(crate) fn into_raw(self) -> *const () {
+        const { assert!(std::mem::align_of::<I>() > BORROWED) };
+        match self {
+            Shared::Arc(inner) => Arc::into_raw(inner).cast(),
+            Shared::Borrowed(inner) => std::ptr::from_ref(inner)
+                .map_addr(|addr| addr | BORROWED)
+                .cast(),
+        }
+    }
+
+    /// Turns a pointer returned by [`Shared::into_raw`] back.
+    ///
+    /// Only sound once per call to [`Shared::into_raw`], on a pointer to the same `I`.
+    pub(crate) unsafe fn from_raw(ptr: *const ()) -> Self {
+        if ptr.addr() & BORROWED == 0 {
+            Shared::Arc(unsafe { Arc::from_raw(ptr.cast()) })
+        } else {
+            Shared::Borrowed(unsafe { &*ptr.map_addr(|addr| addr & !BORROWED).cast() })
+        }
+    }
+}
+
+impl<I> core::ops::Deref for Shared<'_, I> {
+    type Target = I;
+
+    fn deref(&self) -> &I {
+        match self {
+            #[cfg(feature = "std")]
+            Shared::Arc(inner) => inner,
+            Shared::Borrowed(inner) => inner,
+        }
+    }
+}
+
+impl<I> Clone for Shared<'_, I> {
+    fn clone(&self) -> Self {
+        match self {
+            #[cfg(feature = "std")]
+            Shared::Arc(inner) => Shared::Arc(Arc::clone(inner)),
+            Shared::Borrowed(inner) => Shared::Borrowed(inner),
+        }
+    }
+}
@@ -0,0 +1,378 @@
+use crate::debug::DebugState;
+use crate::shared::Shared;
+use crate::{Reader, TryReadError, Writer};
+
+use std::cell::UnsafeCell;
+use std::mem::{align_of, offset_of, size_of};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+
+// set in `middle` when it holds a buffer published since the reader last took one
+const DIRTY: u32 = 0b100;
+const INDEX: u32 = 0b011;
+
+// stored last by `create`, `open` fails until it sees it
+const MAGIC: u64 = u64::from_le_bytes(*b"rzr-shm\0");
+
+/// Version of the layout of the region, checked by [`open`].
+///
+/// It is bumped whenever the layout changes, so that a consumer never attaches to a region created
+/// by an incompatible producer, whichever of the two binaries is newer.
+pub const LAYOUT_VERSION: u32 = 1;
+
+/// Triple buffer laid out in a region shared by two processes, see `triple_buffer` for the
+/// algorithm.
+///
+/// Everything lives in the region, including the indices owned by each side, so that a reader can
+/// be opened again after the previous one was dropped.
+///
+/// `repr(C)` pins every field before `buffers` to the offsets asserted below whatever `T` is, so
+/// binaries built at different times, or by different compilers, agree on the layout as long as
+/// they agree on [`LAYOUT_VERSION`] and on `T`.
+#[repr(C)]
+struct Inner<T> {
+    magic: AtomicU64,
+    version: u32,
+    middle: AtomicU32,
+    // only modified by the writer
+    back: AtomicU32,
+    // only modified by the reader
+    front: AtomicU32,
+    writer_alive: AtomicBool,
+    // set right before `writer_alive` is cleared if the writer left through `close`
+    closed: AtomicBool,
+    reader_alive: AtomicBool,
+    // layout of `T` in the process that created the region, checked by `open`
+    value_size: u64,
+    value_align: u64,
+    // number of values replaced before being read, only modified by the writer
+    overwritten: AtomicU64,
+    buffers: [UnsafeCell<T>; 3],
+}
+
+const _: () = {
+    assert!(offset_of!(Inner<u8>, magic) == 0);
+    assert!(offset_of!(Inner<u8>, version) == 8);
+    assert!(offset_of!(Inner<u8>, middle) == 12);
+    assert!(offset_of!(Inner<u8>, back) == 16);
+    assert!(offset_of!(Inner<u8>, front) == 20);
+    assert!(offset_of!(Inner<u8>, writer_alive) == 24);
+    assert!(offset_of!(Inner<u8>, closed) == 25);
+    assert!(offset_of!(Inner<u8>, reader_alive) == 26);
+    assert!(offset_of!(Inner<u8>, value_size) == 32);
+    assert!(offset_of!(Inner<u8>, value_align) == 40);
+    assert!(offset_of!(Inner<u8>, overwritten) == 48);
+    assert!(offset_of!(Inner<u8>, buffers) == 56);
+};
+
+/// Safety: each buffer is only ever accessed by the side that currently owns it, and ownership
+/// changes hands through `middle`.
+unsafe impl<T> Sync for Inner<T> where T: Send {}
+
+impl<T> Inner<T> {
+    /// Error reported once the writer is gone and nothing is left to read.
+    fn disconnected(&self) -> TryReadError {
+        if self.closed.load(Ordering::Relaxed) {
+            TryReadError::Closed
+        } else {
+            TryReadError::Disconnected
+        }
+    }
+}
+
+/// Error returned by [`create`] and [`open`] when the region cannot hold a channel of `T`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RegionError {
+    /// The region is smaller than [`region_size`].
+    TooSmall,
+    /// The region is not aligned to [`region_align`].
+    Misaligned,
+    /// No channel was created in the region yet, or its creation is still in progress.
+    Uninitialized,
+    /// The channel in the region was created with another [`LAYOUT_VERSION`].
+    VersionMismatch,
+    /// The channel in the region was created for a type with another size or alignment.
+    LayoutMismatch,
+    /// Another reader is attached to the channel.
+    AlreadyOpen,
+}
+
+impl std::fmt::Display for RegionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RegionError::TooSmall => f.write_str("the region is too small"),
+            RegionError::Misaligned => f.write_str("the region is misaligned"),
+            RegionError::Uninitialized => f.write_str("no channel was created in the region"),
+            RegionError::VersionMismatch => f.write_str("the channel has another layout version"),
+            RegionError::LayoutMismatch => f.write_str("the channel holds another type"),
+            RegionError::AlreadyOpen => f.write_str("a reader is already attached"),
+        }
+    }
+}
+
+impl std::error::Error for RegionError {}
+
+pub struct ReadHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+}
+
+pub struct WriteHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+}
+
+/// Lease on the front buffer of the reader.
+///
+/// The guard borrows the read handle, which cannot swap in newer values while it is alive, so the
+/// buffer cannot be handed back to the writer under it.
+pub struct ShmGuard<'a, T> {
+    value: &'a T,
+}
+
+impl<T> std::ops::Deref for ShmGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+impl<T> std::fmt::Debug for ShmGuard<'_, T>
+where
+    T: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T> ReadHandle<'_, T> {
+    /// Leases the front buffer, which is the last value read (or `T::default()` before the first
+    /// one).
+    pub fn front_buffer(&mut self) -> ShmGuard<'_, T> {
+        let front = self.inner.front.load(Ordering::Relaxed);
+        let value = unsafe { &*self.inner.buffers[front as usize].get() };
+        ShmGuard { value }
+    }
+
+    /// Returns how many written values were replaced before they could be read.
+    pub fn overwritten(&self) -> u64 {
+        self.inner.overwritten.load(Ordering::Relaxed)
+    }
+
+    fn take(&mut self) -> bool {
+        if self.inner.middle.load(Ordering::Relaxed) & DIRTY == 0 {
+            return false;
+        }
+        let front = self.inner.front.load(Ordering::Relaxed);
+        let middle = self.inner.middle.swap(front, Ordering::AcqRel);
+        self.inner.front.store(middle & INDEX, Ordering::Relaxed);
+        true
+    }
+}
+
+impl<T> WriteHandle<'_, T> {
+    /// Returns how many written values were replaced before the reader could read them.
+    pub fn overwritten(&self) -> u64 {
+        self.inner.overwritten.load(Ordering::Relaxed)
+    }
+
+    fn back_buffer(&mut self) -> *mut T {
+        self.inner.buffers[self.inner.back.load(Ordering::Relaxed) as usize].get()
+    }
+
+    fn swap_back(&mut self) {
+        let back = self.inner.back.load(Ordering::Relaxed);
+        let middle = self.inner.middle.swap(back | DIRTY, Ordering::AcqRel);
+        self.inner.back.store(middle & INDEX, Ordering::Relaxed);
+        if middle & DIRTY != 0 {
+            self.inner.overwritten.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+impl<T> Reader for ReadHandle<'_, T> {
+    type Item = T;
+    type Guard<'a>
+        = ShmGuard<'a, T>
+    where
+        Self: 'a;
+
+    /// Try reading the last published value.
+    fn read(&mut self) -> Result<Self::Guard<'_>, TryReadError> {
+        if !self.take() {
+            if self.inner.writer_alive.load(Ordering::Acquire) {
+                return Err(TryReadError::Empty);
+            }
+            // the writer may have published right before being dropped
+            if !self.take() {
+                return Err(self.inner.disconnected());
+            }
+        }
+        Ok(self.front_buffer())
+    }
+}
+
+impl<T> Writer for WriteHandle<'_, T> {
+    type Item = T;
+
+    fn write(&mut self, value: T) {
+        unsafe { *self.back_buffer() = value }
+        self.swap_back();
+    }
+
+    fn write_with(&mut self, f: impl FnOnce(&mut T))
+    where
+        T: Default,
+    {
+        f(unsafe { &mut *self.back_buffer() });
+        self.swap_back();
+    }
+
+    fn close(self) {
+        self.inner.closed.store(true, Ordering::Relaxed);
+        // dropping `self` disconnects the reader
+    }
+
+    fn is_reader_alive(&self) -> bool {
+        self.inner.reader_alive.load(Ordering::Acquire)
+    }
+}
+
+impl<T> Drop for ReadHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.reader_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<T> Drop for WriteHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.writer_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<T> DebugState for Inner<T> {
+    fn debug_state(&self, d: &mut std::fmt::DebugStruct<'_, '_>) {
+        let middle = self.middle.load(Ordering::Relaxed);
+        d.field("pending", &(middle & DIRTY != 0))
+            .field("middle", &(middle & INDEX))
+            .field("front", &self.front.load(Ordering::Relaxed))
+            .field("back", &self.back.load(Ordering::Relaxed))
+            .field("overwritten", &self.overwritten.load(Ordering::Relaxed))
+            .field("writer_alive", &self.writer_alive.load(Ordering::Relaxed))
+            .field("closed", &self.closed.load(Ordering::Relaxed))
+            .field("reader_alive", &self.reader_alive.load(Ordering::Relaxed));
+    }
+}
+
+impl<T> std::fmt::Debug for ReadHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "shm_spsc::ReadHandle")
+    }
+}
+
+impl<T> std::fmt::Debug for WriteHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "shm_spsc::WriteHandle")
+    }
+}
+
+/// Returns how many bytes a region must span to hold a channel of `T`.
+pub const fn region_size<T>() -> usize {
+    size_of::<Inner<T>>()
+}
+
+/// Returns the alignment a region must have to hold a channel of `T`. Regions returned by `mmap`
+/// are page aligned, which is always enough.
+pub const fn region_align<T>() -> usize {
+    align_of::<Inner<T>>()
+}
+
+/// Checks that `region` can hold a channel of `T`.
+fn check_region<T>(region: *mut u8, len: usize) -> Result<(), RegionError> {
+    if !(region as usize).is_multiple_of(region_align::<T>()) {
+        return Err(RegionError::Misaligned);
+    }
+    if len < region_size::<T>() {
+        return Err(RegionError::TooSmall);
+    }
+    Ok(())
+}
+
+/// Creates a channel in `region`, with every buffer set to `T::default()`, and returns its write
+/// handle. The other process can then [`open`] it.
+///
+/// Reads fail with [`TryReadError::Empty`] until the first write.
+///
+/// # Safety
+///
+/// `region` must point to `len` bytes that stay mapped and writable for `'a`, and must not be in
+/// use by any other handle: a region is created once and any previous channel in it is wiped.
+///
+/// `T` is copied between address spaces as raw bytes, so it must not hold pointers or references,
+/// and both processes must agree on its definition (only its size and alignment are checked).
+pub unsafe fn create<'a, T>(region: *mut u8, len: usize) -> Result<WriteHandle<'a, T>, RegionError>
+where
+    T: Copy + Default + Send,
+{
+    check_region::<T>(region, len)?;
+    let inner = region.cast::<Inner<T>>();
+    // the region may hold garbage, so it is initialized without reading or dropping anything
+    inner.write(Inner {
+        magic: AtomicU64::new(0),
+        version: LAYOUT_VERSION,
+        value_size: size_of::<T>() as u64,
+        value_align: align_of::<T>() as u64,
+        middle: AtomicU32::new(1),
+        back: AtomicU32::new(0),
+        front: AtomicU32::new(2),
+        writer_alive: AtomicBool::new(true),
+        closed: AtomicBool::new(false),
+        reader_alive: AtomicBool::new(false),
+        overwritten: AtomicU64::new(0),
+        buffers: [(); 3].map(|_| UnsafeCell::new(T::default())),
+    });
+    let inner = &*inner;
+    // pairs with the load in `open`: a reader seeing the magic sees the whole initialized state
+    inner.magic.store(MAGIC, Ordering::Release);
+    Ok(WriteHandle {
+        inner: Shared::Borrowed(inner),
+    })
+}
+
+/// Attaches to the channel created in `region` by [`create`], possibly in another process, and
+/// returns its read handle.
+///
+/// Fails with [`RegionError::Uninitialized`] until the channel is created, so the consumer can
+/// start first and retry. Only one reader can be attached at a time, another one can be opened
+/// once it is dropped.
+///
+/// # Safety
+///
+/// `region` must point to `len` bytes that stay mapped and writable for `'a`, and that are only
+/// ever accessed through this module. See [`create`] for the requirements on `T`.
+pub unsafe fn open<'a, T>(region: *mut u8, len: usize) -> Result<ReadHandle<'a, T>, RegionError>
+where
+    T: Copy + Default + Send,
+{
+    check_region::<T>(region, len)?;
+    let inner = &*region.cast::<Inner<T>>();
+    if inner.magic.load(Ordering::Acquire) != MAGIC {
+        return Err(RegionError::Uninitialized);
+    }
+    if inner.version != LAYOUT_VERSION {
+        return Err(RegionError::VersionMismatch);
+    }
+    if inner.value_size != size_of::<T>() as u64 || inner.value_align != align_of::<T>() as u64 {
+        return Err(RegionError::LayoutMismatch);
+    }
+    if inner
+        .reader_alive
+        .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+        .is_err()
+    {
+        return Err(RegionError::AlreadyOpen);
+    }
+    Ok(ReadHandle {
+        inner: Shared::Borrowed(inner),
+    })
+}
@@ -0,0 +1,208 @@
+//! Fixed set of reusable slots, handed out and given back without locking.
+//!
+//! This is the free-slot bookkeeping of `atomic_spsc` on its own: a bitmask with a bit set per
+//! free slot, cleared by whoever takes the slot and set again by whoever is done with it, possibly
+//! on another thread. A value stays in its slot between leases, so a slot that held a `Vec` can be
+//! refilled without allocating.
+//!
+//! ```
+//! use rustedrazors::slot_pool::Pool;
+//!
+//! let pool = Pool::<Vec<u8>, 2>::new();
+//! let mut frame = pool.acquire().unwrap();
+//! frame.extend_from_slice(b"abc");
+//! assert_eq!(pool.available(), 1);
+//!
+//! // the index can travel through an atomic, for a protocol of your own
+//! let idx = frame.into_index();
+//! let frame = unsafe { rustedrazors::slot_pool::SlotGuard::from_index(&pool, idx) };
+//! assert_eq!(frame.as_slice(), b"abc");
+//! drop(frame);
+//! assert_eq!(pool.available(), 2);
+//! ```
+
+use crate::atomic::{AtomicU32, Ordering};
+use crate::padded::CachePadded;
+
+use std::cell::UnsafeCell;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+/// Pool of `N` values of type `T`, each leased to one [`SlotGuard`] at a time.
+///
+/// Free slots are tracked by the bits of a single 32-bit word, so that the pool also works on
+/// targets without 64-bit atomics: `N` is at most 32. Taking a slot is lock-free and giving one
+/// back is wait-free. Each slot gets its own cache line, so that the threads working on different
+/// slots do not keep invalidating each other's.
+pub struct Pool<T, const N: usize> {
+    slots: [CachePadded<UnsafeCell<T>>; N],
+    // bit `i` is set while slot `i` is free
+    free: CachePadded<AtomicU32>,
+}
+
+unsafe impl<T, const N: usize> Sync for Pool<T, N> where T: Send {}
+
+impl<T, const N: usize> Pool<T, N> {
+    /// Creates a pool with every slot free, slot `i` holding `f(i)`.
+    pub fn from_fn(mut f: impl FnMut(usize) -> T) -> Self {
+        const { assert!(N > 0 && N <= 32, "a pool holds between 1 and 32 slots") };
+        Pool {
+            slots: std::array::from_fn(|idx| CachePadded::new(UnsafeCell::new(f(idx)))),
+            free: CachePadded::new(AtomicU32::new(u32::MAX >> (32 - N))),
+        }
+    }
+
+    /// Creates a pool with every slot free, holding `T::default()`.
+    pub fn new() -> Self
+    where
+        T: Default,
+    {
+        Pool::from_fn(|_| T::default())
+    }
+
+    /// Leases the first free slot, or returns `None` if every slot is leased.
+    ///
+    /// The slot still holds the value it was left with by the previous lease.
+    pub fn acquire(&self) -> Option<SlotGuard<'_, T, N>> {
+        let mut free = self.free.load(Ordering::Relaxed);
+        loop {
+            if free == 0 {
+                return None;
+            }
+            let idx = free.trailing_zeros();
+            // pairs with `release`: the new lease sees the value the previous one left
+            match self.free.compare_exchange_weak(
+                free,
+                free & !(1 << idx),
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => {
+                    return Some(SlotGuard {
+                        pool: self,
+                        idx: idx as usize,
+                        _marker: PhantomData,
+                    })
+                }
+                Err(current) => free = current,
+            }
+        }
+    }
+
+    /// Returns how many slots are free right now.
+    pub fn available(&self) -> usize {
+        self.free.load(Ordering::Relaxed).count_ones() as usize
+    }
+
+    /// Returns the number of slots, `N`.
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns a mutable reference to every value, no lease is needed since the borrow is
+    /// exclusive.
+    pub fn get_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.slots.iter_mut().map(|slot| slot.get_mut())
+    }
+
+    /// Consumes the pool and returns its values, in slot order.
+    pub fn into_inner(self) -> [T; N] {
+        self.slots.map(|slot| slot.into_inner().into_inner())
+    }
+
+    fn release(&self, idx: usize) {
+        self.free.fetch_or(1 << idx, Ordering::Release);
+    }
+}
+
+impl<T, const N: usize> Default for Pool<T, N>
+where
+    T: Default,
+{
+    fn default() -> Self {
+        Pool::new()
+    }
+}
+
+impl<T, const N: usize> std::fmt::Debug for Pool<T, N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("slot_pool::Pool")
+            .field("capacity", &N)
+            .field("available", &self.available())
+            .finish()
+    }
+}
+
+/// Exclusive lease on a slot of a [`Pool`], freeing the slot on drop.
+///
+/// The guard can be sent to another thread and dropped there, which is how a consumer hands a
+/// slot back to the producer that filled it.
+pub struct SlotGuard<'a, T, const N: usize> {
+    pool: &'a Pool<T, N>,
+    idx: usize,
+    // the guard hands out `&mut T`, so it is only `Send` and `Sync` if that is
+    _marker: PhantomData<&'a mut T>,
+}
+
+impl<'a, T, const N: usize> SlotGuard<'a, T, N> {
+    /// Returns the index of the leased slot.
+    pub fn index(&self) -> usize {
+        self.idx
+    }
+
+    /// Gives up the guard without freeing the slot, returning its index so that the lease can be
+    /// resumed with [`SlotGuard::from_index`], possibly on another thread.
+    pub fn into_index(self) -> usize {
+        std::mem::ManuallyDrop::new(self).idx
+    }
+
+    /// Resumes the lease on slot `idx` of `pool` given up by [`SlotGuard::into_index`].
+    ///
+    /// # Safety
+    ///
+    /// `idx` must come from `into_index` on a guard of the same pool, and the lease must be
+    /// resumed at most once.
+    pub unsafe fn from_index(pool: &'a Pool<T, N>, idx: usize) -> Self {
+        debug_assert!(
+            pool.free.load(Ordering::Relaxed) & (1 << idx) == 0,
+            "slot is not leased"
+        );
+        SlotGuard {
+            pool,
+            idx,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T, const N: usize> Deref for SlotGuard<'_, T, N> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.pool.slots.get_unchecked(self.idx).get() }
+    }
+}
+
+impl<T, const N: usize> DerefMut for SlotGuard<'_, T, N> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.pool.slots.get_unchecked(self.idx).get() }
+    }
+}
+
+impl<T, const N: usize> Drop for SlotGuard<'_, T, N> {
+    fn drop(&mut self) {
+        self.pool.release(self.idx)
+    }
+}
+
+impl<T, const N: usize> std::fmt::Debug for SlotGuard<'_, T, N>
+where
+    T: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SlotGuard")
+            .field("index", &self.idx)
+            .field("value", &**self)
+            .finish()
+    }
+}
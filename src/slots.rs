@@ -0,0 +1,452 @@
+//! Pool of slots shared by the writer and the reader of `atomic_spsc`, `blocking_spsc` and
+//! `futex_spsc`.
+//!
+//! The writer takes a free slot, writes its value there and publishes it as the value waiting for
+//! the reader, freeing the one it replaces. The reader takes the waiting value and frees its slot
+//! once done with it. The channels only differ in how the reader waits for a value, and how the
+//! writer waits for a free slot if there is none.
+
+use crate::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use crate::debug::DebugState;
+use crate::padded::CachePadded;
+
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+
+// set in `last` while either side is using the last published slot as the writer's reference value
+const BUSY: usize = 1 << (usize::BITS - 1);
+
+pub(crate) struct Slots<T, const N: usize> {
+    // each slot and `state` get their own cache line so that the reader and the writer do not
+    // keep invalidating each other's
+    pool: [CachePadded<UnsafeCell<MaybeUninit<T>>>; N],
+    // whether each slot holds a value, only modified by the writer
+    occupied: [AtomicBool; N],
+    // version of the value held by each slot
+    versions: [AtomicU64; N],
+    // bit `i` is set while slot `i` is free, the bits from `N` hold the slot of the value waiting
+    // for the reader, or `NONE`. The free bits work like those of `slot_pool::Pool`, but share a
+    // word with the waiting slot so that `publish` updates both at once
+    state: CachePadded<AtomicU32>,
+    // slot of the last published value, with the BUSY bit set while either side is using
+    // it as the writer's reference value
+    last: AtomicUsize,
+    // number of values published so far, only modified by the writer
+    published: AtomicU64,
+    // set if a write panicked before publishing its slot, see `Abort`
+    poisoned: AtomicBool,
+}
+
+impl<T, const N: usize> Slots<T, N> {
+    const FREE_MASK: u32 = (1 << N) - 1;
+    const NONE: u32 = u32::MAX >> N;
+
+    /// Constructs a pool with every slot free and empty.
+    pub(crate) const fn new() -> Self {
+        const { assert!(N >= 2 && N <= 16, "a pool holds between 2 and 16 slots") };
+        Slots {
+            pool: [const { CachePadded::new(UnsafeCell::new(MaybeUninit::uninit())) }; N],
+            occupied: [const { AtomicBool::new(false) }; N],
+            versions: [const { AtomicU64::new(0) }; N],
+            state: CachePadded::new(AtomicU32::new(Self::FREE_MASK | Self::NONE << N)),
+            last: AtomicUsize::new(0),
+            published: AtomicU64::new(0),
+            poisoned: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the slot of the value waiting for the reader in `state`, if any.
+    fn buffered(state: u32) -> Option<usize> {
+        match state >> N {
+            none if none == Self::NONE => None,
+            idx => Some(idx as usize),
+        }
+    }
+
+    /// Returns the index of a free slot while marking it as in use, or `None` if the reader holds
+    /// every slot but the one of the waiting value.
+    ///
+    /// Panics if the channel was poisoned.
+    pub(crate) fn acquire(&self) -> Option<usize> {
+        assert!(
+            !self.poisoned.load(Ordering::Relaxed),
+            "channel poisoned by a panic during a previous write"
+        );
+        // only the writer clears free bits, so the slot cannot be taken in between
+        let free = self.state.load(Ordering::Acquire) & Self::FREE_MASK;
+        if free == 0 {
+            return None;
+        }
+        let idx = free.trailing_zeros();
+        self.state.fetch_and(!(1 << idx), Ordering::Relaxed);
+        Some(idx as usize)
+    }
+
+    /// Returns whether any slot is free, so that [`Slots::acquire`] would succeed.
+    #[cfg(not(target_family = "wasm"))]
+    pub(crate) fn has_free(&self) -> bool {
+        self.state.load(Ordering::Relaxed) & Self::FREE_MASK != 0
+    }
+
+    /// Marks the slot at the given index as free.
+    pub(crate) fn release(&self, idx: usize) {
+        self.state.fetch_or(1 << idx, Ordering::Release);
+    }
+
+    /// Makes the slot at the given index the value waiting for the reader, freeing the one it
+    /// replaces and dropping its value.
+    ///
+    /// Returns the version of the value and whether it replaced one the reader did not take. The
+    /// CAS can only fail if the reader took the waiting value or released a slot in the meantime,
+    /// which it does at most once each per publish, so this stays wait-free.
+    pub(crate) fn publish(&self, idx: usize) -> (u64, bool) {
+        let version = self.published.load(Ordering::Relaxed) + 1;
+        self.versions[idx].store(version, Ordering::Relaxed);
+        self.published.store(version, Ordering::Relaxed);
+        // pairs with the failed CAS of `swap_out`: a reader seeing the new slot also sees the
+        // writer done with the previous one
+        self.last.store(idx, Ordering::Release);
+        let mut state = self.state.load(Ordering::Relaxed);
+        loop {
+            let mut new = state & Self::FREE_MASK | (idx as u32) << N;
+            if let Some(buffer) = Self::buffered(state) {
+                new |= 1 << buffer;
+            }
+            // SeqCst so that a reader about to sleep either sees the value or is seen sleeping,
+            // see `futex_spsc`
+            match self
+                .state
+                .compare_exchange_weak(state, new, Ordering::SeqCst, Ordering::Relaxed)
+            {
+                Ok(_) => break,
+                Err(current) => state = current,
+            }
+        }
+        let replaced = Self::buffered(state);
+        if let Some(buffer) = replaced {
+            // only the writer takes free slots, so the value can be dropped after freeing it
+            self.discard(buffer);
+        }
+        (version, replaced.is_some())
+    }
+
+    /// Takes the value waiting for the reader, if any. Its slot stays in use until the reader
+    /// releases it.
+    ///
+    /// This method is wait-free.
+    pub(crate) fn take(&self) -> Option<usize> {
+        let state = self.state.fetch_or(Self::NONE << N, Ordering::AcqRel);
+        Self::buffered(state)
+    }
+
+    /// Returns whether a value is waiting for the reader.
+    pub(crate) fn is_pending(&self) -> bool {
+        // pairs with the CAS of `publish`
+        Self::buffered(self.state.load(Ordering::SeqCst)).is_some()
+    }
+
+    /// Marks the channel as poisoned after a write panicked, freeing the slot it was writing to.
+    ///
+    /// The value left in the slot is never published, later writes panic and reads fail with
+    /// [`TryReadError::Poisoned`](crate::TryReadError::Poisoned) once the values published before
+    /// are read.
+    pub(crate) fn poison(&self, idx: usize) {
+        // SeqCst like `publish`, for a reader about to sleep
+        self.poisoned.store(true, Ordering::SeqCst);
+        self.release(idx);
+    }
+
+    /// Returns whether a write panicked halfway through.
+    pub(crate) fn is_poisoned(&self) -> bool {
+        self.poisoned.load(Ordering::SeqCst)
+    }
+
+    /// Returns how many values were published so far.
+    pub(crate) fn published(&self) -> u64 {
+        self.published.load(Ordering::Relaxed)
+    }
+
+    /// Returns the version of the value held by the slot at the given index.
+    pub(crate) fn version(&self, idx: usize) -> u64 {
+        self.versions[idx].load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn write_to(&self, idx: usize, value: T) {
+        unsafe {
+            let pool = &mut *self.pool.get_unchecked(idx).get();
+            if self.occupied[idx].swap(true, Ordering::Relaxed) {
+                *pool.assume_init_mut() = value
+            } else {
+                pool.write(value);
+            }
+        }
+    }
+
+    /// Fills the slot at the given index with `T::default()` if it never held a value, so that it
+    /// can be handed out for writing in place.
+    pub(crate) fn fill(&self, idx: usize)
+    where
+        T: Default,
+    {
+        if !self.occupied[idx].load(Ordering::Relaxed) {
+            self.write_to(idx, T::default());
+        }
+    }
+
+    /// Drops the value held by the slot at the given index right away, instead of leaving it
+    /// there until the slot is written again.
+    fn discard(&self, idx: usize) {
+        if self.occupied[idx].swap(false, Ordering::Relaxed) {
+            unsafe { std::ptr::drop_in_place(self.slot(idx)) }
+        }
+    }
+
+    /// Drops the values of the free slots right away, once the writer is gone for good.
+    pub(crate) fn discard_free(&self) {
+        let free = self.state.load(Ordering::Acquire) & Self::FREE_MASK;
+        for idx in 0..N {
+            if free & 1 << idx != 0 {
+                self.discard(idx);
+            }
+        }
+    }
+
+    /// Returns a pointer to the value held by the slot at the given index, which can only be
+    /// dereferenced once the slot is occupied.
+    pub(crate) fn slot(&self, idx: usize) -> *mut T {
+        unsafe { self.pool.get_unchecked(idx).get().cast() }
+    }
+
+    pub(crate) fn read_from(&self, idx: usize) -> &T {
+        unsafe {
+            let pool = self.pool.get_unchecked(idx).get();
+            (*pool).assume_init_ref()
+        }
+    }
+
+    /// Returns whether the reader is swapping the last written value out, see [`Slots::swap_out`].
+    #[cfg(feature = "stats")]
+    pub(crate) fn is_last_locked(&self) -> bool {
+        self.last.load(Ordering::Relaxed) & BUSY != 0
+    }
+
+    /// Returns the slot of the last written value, after making sure the reader is not swapping
+    /// its content out and preventing it from doing so until [`Slots::unlock_last`].
+    pub(crate) fn lock_last(&self) -> usize {
+        loop {
+            let last = self.last.load(Ordering::Relaxed);
+            if last & BUSY == 0
+                && self
+                    .last
+                    .compare_exchange_weak(last, last | BUSY, Ordering::Acquire, Ordering::Relaxed)
+                    .is_ok()
+            {
+                return last;
+            }
+            std::hint::spin_loop();
+        }
+    }
+
+    pub(crate) fn unlock_last(&self, last: usize) {
+        self.last.store(last, Ordering::Release);
+    }
+
+    /// Clones the last written value, held by the locked slot `last`, into the slot at the given
+    /// index, unless that is the same slot.
+    pub(crate) fn clone_last(&self, idx: usize, last: usize)
+    where
+        T: Clone,
+    {
+        if idx == last {
+            return;
+        }
+        if self.occupied[idx].load(Ordering::Relaxed) {
+            unsafe { (*self.slot(idx)).clone_from(self.read_from(last)) }
+        } else {
+            self.write_to(idx, self.read_from(last).clone());
+        }
+    }
+
+    /// Swaps the value of the slot at the given index, leased by the reader, with `scratch`.
+    pub(crate) fn swap_out(&self, idx: usize, scratch: &mut T) {
+        // the writer may be reading this slot if it is still its last written one
+        let locked = loop {
+            match self
+                .last
+                .compare_exchange(idx, idx | BUSY, Ordering::Acquire, Ordering::Acquire)
+            {
+                Ok(_) => break true,
+                Err(last) if last == idx | BUSY => std::hint::spin_loop(),
+                Err(_) => break false,
+            }
+        };
+        unsafe {
+            std::ptr::swap(self.slot(idx), scratch);
+        }
+        if locked {
+            // the writer may have published something else in the meantime
+            let _ =
+                self.last
+                    .compare_exchange(idx | BUSY, idx, Ordering::Release, Ordering::Relaxed);
+        }
+    }
+
+    /// Moves the last written value out of its slot, or returns `None` if nothing was written.
+    ///
+    /// Only sound once neither handle can touch the slot anymore.
+    pub(crate) unsafe fn take_last(&self) -> Option<T> {
+        if self.published() == 0 {
+            return None;
+        }
+        let idx = self.last.load(Ordering::Relaxed) & !BUSY;
+        self.occupied[idx]
+            .swap(false, Ordering::Relaxed)
+            .then(|| unsafe { std::ptr::read(self.slot(idx)) })
+    }
+}
+
+impl<T, const N: usize> DebugState for Slots<T, N> {
+    fn debug_state(&self, d: &mut std::fmt::DebugStruct<'_, '_>) {
+        let state = self.state.load(Ordering::Relaxed);
+        d.field("pending", &Self::buffered(state).is_some())
+            .field("last", &(self.last.load(Ordering::Relaxed) & !BUSY))
+            .field("free_slots", &(state & Self::FREE_MASK).count_ones())
+            .field("published", &self.published())
+            .field("poisoned", &self.poisoned.load(Ordering::Relaxed));
+    }
+}
+
+impl<T, const N: usize> Drop for Slots<T, N> {
+    fn drop(&mut self) {
+        for (slot, occupied) in self.pool.iter_mut().zip(&mut self.occupied) {
+            if *occupied.get_mut() {
+                unsafe { slot.get_mut().assume_init_drop() }
+            }
+        }
+    }
+}
+
+/// Poisons the channel if dropped, which only happens when a write panics before publishing the
+/// slot it acquired, then runs `wake` so that a waiting reader sees it.
+pub(crate) struct Abort<'a, T, const N: usize, W: Fn()> {
+    slots: &'a Slots<T, N>,
+    idx: usize,
+    wake: W,
+    // whether the writer holds the lock on `last` taken by `Slots::lock_last`
+    pub(crate) locked: bool,
+}
+
+impl<'a, T, const N: usize, W: Fn()> Abort<'a, T, N, W> {
+    pub(crate) fn new(slots: &'a Slots<T, N>, idx: usize, wake: W) -> Self {
+        Abort {
+            slots,
+            idx,
+            wake,
+            locked: false,
+        }
+    }
+
+    /// Called once the slot is ready to be published.
+    pub(crate) fn disarm(self) {
+        std::mem::forget(self)
+    }
+}
+
+impl<T, const N: usize, W: Fn()> Drop for Abort<'_, T, N, W> {
+    fn drop(&mut self) {
+        if self.locked {
+            self.slots.last.fetch_and(!BUSY, Ordering::Release);
+        }
+        self.slots.poison(self.idx);
+        (self.wake)();
+    }
+}
+
+/// Proofs of the slot invariants the `get_unchecked` calls rely on, run with `cargo kani`.
+///
+/// Kani does not model threads, so the harnesses interleave the steps of both sides by hand: a
+/// write is split between taking a slot and publishing it, and the reader may take or release its
+/// slot in between.
+#[cfg(kani)]
+mod proofs {
+    use super::*;
+
+    const STEPS: usize = 8;
+
+    /// Runs `STEPS` operations picked by Kani on a pool of `N` slots, calling `check` after each
+    /// with the slots held by the reader and by the write in progress, if any.
+    fn history<const N: usize>(check: impl Fn(&Slots<u8, N>, Option<usize>, Option<usize>)) {
+        let slots = Slots::<u8, N>::new();
+        let mut reading = None;
+        let mut writing = None;
+        for _ in 0..STEPS {
+            match kani::any::<u8>() % 4 {
+                0 if writing.is_none() => {
+                    // only a pool of at least 3 slots always has one free
+                    let acquired = slots.acquire();
+                    assert!(N < 3 || acquired.is_some(), "no free slot");
+                    if let Some(idx) = acquired {
+                        assert!(idx < N, "acquired slot out of range");
+                        slots.write_to(idx, kani::any());
+                        writing = Some(idx);
+                    }
+                }
+                1 => {
+                    if let Some(idx) = writing.take() {
+                        slots.publish(idx);
+                    }
+                }
+                2 if reading.is_none() => reading = slots.take(),
+                3 => {
+                    if let Some(idx) = reading.take() {
+                        slots.release(idx);
+                    }
+                }
+                _ => {}
+            }
+            check(&slots, reading, writing);
+        }
+    }
+
+    /// Checks that the waiting value is in range and not free, and that the held slots are
+    /// neither free, nor the waiting value, nor the same.
+    fn invariants<const N: usize>(
+        slots: &Slots<u8, N>,
+        reading: Option<usize>,
+        writing: Option<usize>,
+    ) {
+        let state = slots.state.load(Ordering::Relaxed);
+        let free = |idx: usize| state & 1 << idx != 0;
+        let buffer = Slots::<u8, N>::buffered(state);
+        if let Some(buffer) = buffer {
+            assert!(buffer < N, "buffer out of range");
+            assert!(!free(buffer), "waiting value in a free slot");
+        }
+        for idx in [reading, writing].into_iter().flatten() {
+            assert!(idx < N, "held slot out of range");
+            assert!(!free(idx), "held slot is free");
+            assert_ne!(buffer, Some(idx), "held slot is the waiting value");
+        }
+        if reading.is_some() {
+            assert_ne!(reading, writing, "reader and writer hold the same slot");
+        }
+    }
+
+    #[kani::proof]
+    #[kani::unwind(9)]
+    fn acquire_in_range() {
+        history::<3>(|_, _, _| {});
+    }
+
+    #[kani::proof]
+    #[kani::unwind(9)]
+    fn no_aliasing() {
+        history::<3>(invariants);
+    }
+
+    #[kani::proof]
+    #[kani::unwind(9)]
+    fn no_aliasing_blocking() {
+        history::<2>(invariants);
+    }
+}
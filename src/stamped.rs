@@ -0,0 +1,144 @@
+use crate::{atomic_spsc, Reader, TryReadError, WriteError, Writer};
+
+use std::marker::PhantomData;
+use std::time::{Duration, Instant};
+
+/// Item of a stamped channel: the instant the value was written, then the value.
+pub type Stamped<T> = (Instant, T);
+
+/// Consumer side of a stamped channel, reading `(Instant, T)` pairs out of the wrapped read handle
+/// of any variant.
+pub struct ReadHandle<T, R = atomic_spsc::ReadHandle<'static, Stamped<T>>> {
+    inner: R,
+    _item: PhantomData<fn() -> T>,
+}
+
+/// Producer side of a stamped channel, taking plain values and writing them to the wrapped write
+/// handle along with the instant of the write.
+pub struct WriteHandle<T, W = atomic_spsc::WriteHandle<'static, Stamped<T>>> {
+    inner: W,
+    _item: PhantomData<fn() -> T>,
+}
+
+impl<T, R> ReadHandle<T, R>
+where
+    R: Reader<Item = Stamped<T>>,
+{
+    /// Try reading the last written value along with the time elapsed since it was written.
+    /// Fails like [`Reader::read`].
+    pub fn read_latency(&mut self) -> Result<(Duration, R::Guard<'_>), TryReadError> {
+        self.inner.read().map(|guard| (guard.0.elapsed(), guard))
+    }
+
+    /// Returns the wrapped read handle.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<T, W> WriteHandle<T, W>
+where
+    W: Writer<Item = Stamped<T>>,
+{
+    /// Returns the wrapped write handle.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<T, R> Reader for ReadHandle<T, R>
+where
+    R: Reader<Item = Stamped<T>>,
+{
+    type Item = Stamped<T>;
+    type Guard<'a>
+        = R::Guard<'a>
+    where
+        Self: 'a;
+
+    fn read(&mut self) -> Result<Self::Guard<'_>, TryReadError> {
+        self.inner.read()
+    }
+}
+
+impl<T, W> Writer for WriteHandle<T, W>
+where
+    W: Writer<Item = Stamped<T>>,
+{
+    type Item = T;
+
+    fn write(&mut self, value: T) {
+        self.inner.write((Instant::now(), value));
+    }
+
+    /// Publishes a value built by `f`, stamped with the instant `f` returned.
+    ///
+    /// The wrapped channel holds `(Instant, T)` pairs, which have no default to build in place, so
+    /// `f` gets a fresh `T::default()` instead of a slot of the channel.
+    fn write_with(&mut self, f: impl FnOnce(&mut T))
+    where
+        T: Default,
+    {
+        let mut value = T::default();
+        f(&mut value);
+        self.write(value);
+    }
+
+    fn close(self) {
+        self.inner.close();
+    }
+
+    fn is_reader_alive(&self) -> bool {
+        self.inner.is_reader_alive()
+    }
+
+    fn try_write(&mut self, value: T) -> Result<(), WriteError<T>> {
+        self.inner
+            .try_write((Instant::now(), value))
+            .map_err(|err| match err {
+                WriteError::Disconnected((_, value)) => WriteError::Disconnected(value),
+                WriteError::WouldBlock((_, value)) => WriteError::WouldBlock(value),
+                WriteError::Poisoned((_, value)) => WriteError::Poisoned(value),
+            })
+    }
+}
+
+impl<T, R: std::fmt::Debug> std::fmt::Debug for ReadHandle<T, R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("stamped::ReadHandle")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<T, W: std::fmt::Debug> std::fmt::Debug for WriteHandle<T, W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("stamped::WriteHandle")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+/// Wraps the handles of a channel of `(Instant, T)` pairs, so that every value written is stamped
+/// with the instant of its write.
+pub fn wrap<T, R, W>(r: R, w: W) -> (ReadHandle<T, R>, WriteHandle<T, W>)
+where
+    R: Reader<Item = Stamped<T>>,
+    W: Writer<Item = Stamped<T>>,
+{
+    let r = ReadHandle {
+        inner: r,
+        _item: PhantomData,
+    };
+    let w = WriteHandle {
+        inner: w,
+        _item: PhantomData,
+    };
+    (r, w)
+}
+
+/// Construct a new, empty read and write handle pair on top of `atomic_spsc`.
+pub fn new<T>() -> (ReadHandle<T>, WriteHandle<T>) {
+    let (r, w) = atomic_spsc::new();
+    wrap(r, w)
+}
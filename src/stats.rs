@@ -0,0 +1,93 @@
+//! Usage counters of a channel, to size pools and compare variants on a live workload.
+//!
+//! With the `stats` feature, the handles of `atomic_spsc`, `blocking_spsc`, `futex_spsc`,
+//! `mutex_spsc` and `ticket_spsc` count what goes through them and return a [`Stats`] snapshot
+//! from their `stats()` method. Every counter is a relaxed atomic bumped next to the operation it
+//! counts, and the writer only reads the clock once it has to wait.
+
+use crate::atomic::{AtomicU64, Ordering};
+
+use std::time::Duration;
+#[cfg(not(target_family = "wasm"))]
+use std::time::Instant;
+
+/// Snapshot of the counters of a channel, returned by `stats()` on either handle.
+///
+/// Counters are read one by one while the channel is in use, so they may be off by the
+/// operations in flight.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Label of the channel, if it was built by `named`.
+    pub name: Option<&'static str>,
+    /// Reads that returned a value.
+    pub reads: u64,
+    /// Reads that found no new value, including each check of the blocking reads before sleeping.
+    pub read_misses: u64,
+    /// Values published.
+    pub writes: u64,
+    /// Values replaced before they could be read.
+    pub overwrites: u64,
+    /// Longest time a single write waited on the reader: for a free slot in `blocking_spsc`, for
+    /// the lock in the other variants. Always zero on `wasm32`, which has no clock.
+    pub max_writer_stall: Duration,
+}
+
+/// Counters behind [`Stats`], embedded in the shared state of a channel.
+pub(crate) struct Counters {
+    reads: AtomicU64,
+    read_misses: AtomicU64,
+    writes: AtomicU64,
+    overwrites: AtomicU64,
+    // in nanoseconds
+    max_writer_stall: AtomicU64,
+}
+
+impl Counters {
+    pub(crate) const fn new() -> Self {
+        Counters {
+            reads: AtomicU64::new(0),
+            read_misses: AtomicU64::new(0),
+            writes: AtomicU64::new(0),
+            overwrites: AtomicU64::new(0),
+            max_writer_stall: AtomicU64::new(0),
+        }
+    }
+
+    /// Counts a read, which returned a value if `hit`.
+    pub(crate) fn read(&self, hit: bool) {
+        let counter = if hit { &self.reads } else { &self.read_misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts a published value, which replaced an unread one if `overwrite`.
+    pub(crate) fn write(&self, overwrite: bool) {
+        self.writes.fetch_add(1, Ordering::Relaxed);
+        if overwrite {
+            self.overwrites.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Runs `wait`, during which the writer is stalled by the reader, and records how long it took.
+    pub(crate) fn stall<R>(&self, wait: impl FnOnce() -> R) -> R {
+        #[cfg(not(target_family = "wasm"))]
+        let start = Instant::now();
+        let res = wait();
+        #[cfg(not(target_family = "wasm"))]
+        {
+            let nanos = u64::try_from(start.elapsed().as_nanos()).unwrap_or(u64::MAX);
+            self.max_writer_stall.fetch_max(nanos, Ordering::Relaxed);
+        }
+        res
+    }
+
+    pub(crate) fn snapshot(&self, name: Option<&'static str>) -> Stats {
+        Stats {
+            name,
+            reads: self.reads.load(Ordering::Relaxed),
+            read_misses: self.read_misses.load(Ordering::Relaxed),
+            writes: self.writes.load(Ordering::Relaxed),
+            overwrites: self.overwrites.load(Ordering::Relaxed),
+            max_writer_stall: Duration::from_nanos(self.max_writer_stall.load(Ordering::Relaxed)),
+        }
+    }
+}
@@ -0,0 +1,150 @@
+//! Fair spinlock: threads are served in the order they asked for the lock.
+
+use crate::atomic::{AtomicU32, Ordering};
+use crate::backoff;
+
+use std::cell::UnsafeCell;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+/// Mutual exclusion lock handing out tickets, like the queue at a deli counter: `lock` takes the
+/// next ticket and spins until it is served, `unlock` serves the next one.
+///
+/// Unlike `std::sync::Mutex` waiting threads never sleep and cannot overtake each other, so the
+/// lock suits short critical sections between a handful of threads that must not starve. It is
+/// the lock behind `ticket_spsc`.
+///
+/// Tickets are 32 bits wide so that the lock also works on targets without 64-bit atomics. Both
+/// counters wrap around and are only ever compared for equality, which stays correct as long as
+/// fewer than 2^32 threads wait at once.
+pub struct TicketMutex<T> {
+    data: UnsafeCell<T>,
+    now_serving: AtomicU32,
+    next_ticket: AtomicU32,
+}
+
+unsafe impl<T> Sync for TicketMutex<T> where T: Send {}
+
+impl<T> TicketMutex<T> {
+    /// Creates a new, unlocked mutex holding `init`.
+    pub const fn new(init: T) -> Self {
+        TicketMutex {
+            data: UnsafeCell::new(init),
+            now_serving: AtomicU32::new(0),
+            next_ticket: AtomicU32::new(0),
+        }
+    }
+
+    /// Takes a ticket and spins until it is served, then returns a guard releasing the lock on
+    /// drop.
+    pub fn lock(&self) -> TicketGuard<'_, T> {
+        let ticket = self.next_ticket.fetch_add(1, Ordering::Relaxed);
+        let mut i = 0;
+        loop {
+            let serving = self.now_serving.load(Ordering::Acquire);
+            if serving == ticket {
+                return TicketGuard {
+                    mutex: self,
+                    _marker: PhantomData,
+                };
+            }
+            backoff::snooze_on(i, &self.now_serving, serving);
+            i += 1;
+        }
+    }
+
+    /// Takes the lock only if no ticket is waiting to be served, instead of queuing behind the
+    /// current holder.
+    pub fn try_lock(&self) -> Option<TicketGuard<'_, T>> {
+        let ticket = self.now_serving.load(Ordering::Acquire);
+        // the lock is free exactly when the next ticket would be served right away
+        self.next_ticket
+            .compare_exchange(
+                ticket,
+                ticket.wrapping_add(1),
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            )
+            .ok()?;
+        Some(TicketGuard {
+            mutex: self,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Returns a mutable reference to the value, no locking is needed since the borrow is
+    /// exclusive.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.data.get_mut()
+    }
+
+    /// Consumes the mutex and returns the value.
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+
+    fn unlock(&self) {
+        let now_serving = self.now_serving.load(Ordering::Relaxed).wrapping_add(1);
+        self.now_serving.store(now_serving, Ordering::Release);
+    }
+}
+
+impl<T> Default for TicketMutex<T>
+where
+    T: Default,
+{
+    fn default() -> Self {
+        TicketMutex::new(T::default())
+    }
+}
+
+impl<T> std::fmt::Debug for TicketMutex<T>
+where
+    T: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut d = f.debug_struct("TicketMutex");
+        match self.try_lock() {
+            Some(guard) => d.field("data", &&*guard),
+            None => d.field("data", &format_args!("<locked>")),
+        };
+        d.finish_non_exhaustive()
+    }
+}
+
+/// Exclusive access to the value of a [`TicketMutex`], serving the next ticket on drop.
+pub struct TicketGuard<'a, T> {
+    mutex: &'a TicketMutex<T>,
+    // the mutex is `Sync` for any `T: Send`, but the guard hands out `&T` and `&mut T`, so it is
+    // only `Send` and `Sync` if `&mut T` is
+    _marker: PhantomData<&'a mut T>,
+}
+
+impl<T> Deref for TicketGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.mutex.data.get() }
+    }
+}
+
+impl<T> DerefMut for TicketGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.mutex.data.get() }
+    }
+}
+
+impl<T> Drop for TicketGuard<'_, T> {
+    fn drop(&mut self) {
+        self.mutex.unlock()
+    }
+}
+
+impl<T> std::fmt::Debug for TicketGuard<'_, T>
+where
+    T: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&**self, f)
+    }
+}
@@ -1,149 +1,760 @@
-use crate::{Reader, Writer};
+#[cfg(feature = "age")]
+use crate::age::LastUpdate;
+use crate::atomic::{AtomicBool, AtomicU64, Ordering};
+use crate::debug::DebugState;
+#[cfg(target_os = "linux")]
+use crate::eventfd::EventFd;
+#[cfg(feature = "hooks")]
+use crate::hooks::{Callbacks, Hooks};
+use crate::shared::Shared;
+#[cfg(feature = "stats")]
+use crate::stats::{Counters, Stats};
+use crate::ticket_mutex::TicketMutex;
+use crate::waiter::Waiter;
+use crate::{Reader, TryReadError, WriteError, Writer};
 
-use std::cell::UnsafeCell;
-use std::ops::{Deref, DerefMut};
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
+#[cfg(not(target_family = "wasm"))]
+use std::time::{Duration, Instant};
 
-struct TicketMutex<T> {
-    data: UnsafeCell<T>,
-    now_serving: AtomicU64,
-    next_ticket: AtomicU64,
+/// Lease on the value returned by the reads, holding the lock of the channel.
+pub use crate::ticket_mutex::TicketGuard;
+
+struct Inner<T> {
+    data: TicketMutex<T>,
+    to_read: AtomicBool,
+    waiter: Waiter,
+    // number of values replaced before being read, only modified by the writer
+    overwritten: AtomicU64,
+    #[cfg(feature = "stats")]
+    stats: Counters,
+    // callbacks given to `with_hooks`, run next to the counters
+    #[cfg(feature = "hooks")]
+    hooks: Callbacks,
+    // instant of the last publish, returned by `last_update` and `age`
+    #[cfg(feature = "age")]
+    updated: LastUpdate,
+    // label given to `named`, shown by `Debug` and `stats` and tagging the `tracing` events
+    name: Option<&'static str>,
+    writer_alive: AtomicBool,
+    // set right before `writer_alive` is cleared if the writer left through `close`
+    closed: AtomicBool,
+    reader_alive: AtomicBool,
+    // set if a write panicked while holding the lock, see `Abort`
+    poisoned: AtomicBool,
+}
+
+pub struct ReadHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
 }
 
-unsafe impl<T> Sync for TicketMutex<T> where T: Send {}
+pub struct WriteHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+}
+
+/// Third handle of a channel, for a thread that only observes it, such as a metrics or debug thread.
+///
+/// Peeks look at the current value without consuming it, so the reader still gets every update it
+/// would have got. They are best effort: a monitor misses the values replaced between two peeks,
+/// and sees the same one again if nothing was written in between. Monitors can be cloned and
+/// shared between threads.
+pub struct MonitorHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+}
+
+impl<T> Inner<T> {
+    const fn new(init: T) -> Self {
+        Inner {
+            data: TicketMutex::new(init),
+            to_read: AtomicBool::new(false),
+            waiter: Waiter::new(),
+            overwritten: AtomicU64::new(0),
+            #[cfg(feature = "stats")]
+            stats: Counters::new(),
+            #[cfg(feature = "hooks")]
+            hooks: Callbacks::none(),
+            #[cfg(feature = "age")]
+            updated: LastUpdate::new(),
+            name: None,
+            writer_alive: AtomicBool::new(true),
+            closed: AtomicBool::new(false),
+            reader_alive: AtomicBool::new(true),
+            poisoned: AtomicBool::new(false),
+        }
+    }
+
+    fn write(&self, value: T) {
+        {
+            let mut data = self.lock();
+            *data = value;
+            self.mark_unread();
+        }
+        self.waiter.notify();
+    }
+
+    /// Writes `value` unless the reader (or a monitor) holds the lock or a panic poisoned the
+    /// channel.
+    fn try_write(&self, value: T) -> Result<(), WriteError<T>> {
+        if self.is_poisoned() {
+            return Err(WriteError::Poisoned(value));
+        }
+        {
+            let Some(mut data) = self.data.try_lock() else {
+                return Err(WriteError::WouldBlock(value));
+            };
+            *data = value;
+            self.mark_unread();
+        }
+        self.waiter.notify();
+        Ok(())
+    }
+
+    fn write_if_changed(&self, value: T) -> bool
+    where
+        T: PartialEq,
+    {
+        {
+            let mut data = self.lock();
+            if *data == value {
+                return false;
+            }
+            *data = value;
+            self.mark_unread();
+        }
+        self.waiter.notify();
+        true
+    }
+
+    fn write_with(&self, f: impl FnOnce(&mut T)) {
+        {
+            let mut data = self.lock();
+            // dropped before `data`, so the channel is poisoned before the lock is released
+            let abort = Abort(self);
+            f(&mut data);
+            std::mem::forget(abort);
+            self.mark_unread();
+        }
+        self.waiter.notify();
+    }
+
+    /// Locks the value for the writer, recording how long the reader kept it locked.
+    ///
+    /// Panics if the channel was poisoned.
+    fn lock(&self) -> TicketGuard<'_, T> {
+        assert!(
+            !self.is_poisoned(),
+            "channel poisoned by a panic during a previous write"
+        );
+        #[cfg(any(feature = "stats", feature = "tracing"))]
+        if let Some(guard) = self.data.try_lock() {
+            return guard;
+        }
+        // the reader (or a monitor) holds the lock
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("lock_wait", channel = self.name).entered();
+        #[cfg(feature = "tracing")]
+        tracing::debug!("waiting for the reader to unlock the value");
+        #[cfg(feature = "stats")]
+        return self.stats.stall(|| self.data.lock());
+        #[cfg(not(feature = "stats"))]
+        self.data.lock()
+    }
+
+    /// Flags the value as unread, counting the previous one as overwritten if it never was.
+    fn mark_unread(&self) {
+        let overwrite = self.to_read.swap(true, Ordering::Release);
+        if overwrite {
+            self.overwritten.fetch_add(1, Ordering::Relaxed);
+        }
+        #[cfg(feature = "age")]
+        self.updated.touch();
+        #[cfg(feature = "stats")]
+        self.stats.write(overwrite);
+        #[cfg(feature = "hooks")]
+        self.hooks.write(overwrite);
+        #[cfg(feature = "tracing")]
+        tracing::trace!(channel = self.name, overwrite, "write");
+    }
+
+    /// Takes the value if it was not read yet, the writer is not busy writing the next one and
+    /// did not leave it halfway through by panicking.
+    fn read(&self) -> Option<TicketGuard<'_, T>> {
+        let guard = if self.to_read.load(Ordering::Acquire) {
+            // the channel is poisoned before the lock is released, see `write_with`
+            self.data.try_lock().filter(|_| !self.is_poisoned())
+        } else {
+            None
+        };
+        if guard.is_some() {
+            self.to_read.store(false, Ordering::Release);
+        }
+        #[cfg(feature = "stats")]
+        self.stats.read(guard.is_some());
+        #[cfg(feature = "hooks")]
+        self.hooks.read(guard.is_some());
+        #[cfg(feature = "tracing")]
+        if guard.is_some() {
+            tracing::trace!(channel = self.name, "read");
+        }
+        guard
+    }
+
+    /// Try reading the last written value, reporting whether the writer is gone.
+    fn try_read(&self) -> Result<TicketGuard<'_, T>, TryReadError> {
+        if let Some(guard) = self.read() {
+            return Ok(guard);
+        }
+        if self.writer_alive.load(Ordering::Acquire) && !self.is_poisoned() {
+            return Err(TryReadError::Empty);
+        }
+        // the writer may have published right before being dropped
+        self.read().ok_or_else(|| self.disconnected())
+    }
 
-impl<T> TicketMutex<T> {
-    fn new(init: T) -> Self {
-        TicketMutex {
-            data: UnsafeCell::new(init),
-            now_serving: AtomicU64::new(0),
-            next_ticket: AtomicU64::new(0),
+    /// Error reported once the writer is gone or poisoned the channel and nothing is left to read.
+    fn disconnected(&self) -> TryReadError {
+        if self.is_poisoned() {
+            TryReadError::Poisoned
+        } else if self.closed.load(Ordering::Relaxed) {
+            TryReadError::Closed
+        } else {
+            TryReadError::Disconnected
         }
     }
 
-    fn lock(&self) -> Result<TicketGuard<'_, T>, ()> {
-        let ticket = self.next_ticket.fetch_add(1, Ordering::Relaxed);
-        let mut i = 0;
-        while self.now_serving.load(Ordering::Acquire) != ticket {
-            i += 1;
-            if i >= 20 {
-                std::thread::yield_now();
+    /// Returns whether a value was written since the last read.
+    #[cfg(any(not(target_family = "wasm"), feature = "async"))]
+    fn has_changed(&self) -> bool {
+        self.to_read.load(Ordering::Acquire)
+    }
+
+    /// Returns whether a read would not fail with [`TryReadError::Empty`].
+    #[cfg(any(not(target_family = "wasm"), feature = "async"))]
+    fn ready(&self) -> bool {
+        self.has_changed() || !self.writer_alive.load(Ordering::Acquire) || self.is_poisoned()
+    }
+
+    /// Returns whether a write panicked halfway through.
+    fn is_poisoned(&self) -> bool {
+        self.poisoned.load(Ordering::Acquire)
+    }
+
+    /// Reads the next written value, parking the current thread until one is available, the
+    /// writer is dropped or `deadline` is reached.
+    #[cfg(not(target_family = "wasm"))]
+    fn read_deadline(&self, deadline: Option<Instant>) -> Result<TicketGuard<'_, T>, TryReadError> {
+        loop {
+            match self.try_read() {
+                Err(TryReadError::Empty) => {}
+                res => return res,
+            }
+            if !self.waiter.wait(|| self.ready(), deadline) {
+                return Err(TryReadError::Empty);
             }
         }
-        Ok(TicketGuard::new(&self))
     }
 
-    fn unlock(&self) {
-        let now_serving = self.now_serving.load(Ordering::Relaxed) + 1;
-        self.now_serving.store(now_serving, Ordering::Release);
+    /// Reads the next written value, registering `waker` to be woken up by the next write if there
+    /// is none yet.
+    #[cfg(feature = "async")]
+    fn poll_read(
+        &self,
+        waker: &std::task::Waker,
+    ) -> std::task::Poll<Result<TicketGuard<'_, T>, TryReadError>> {
+        loop {
+            match self.try_read() {
+                Err(TryReadError::Empty) => {}
+                res => return std::task::Poll::Ready(res),
+            }
+            std::task::ready!(self.waiter.poll_wait(waker, || self.ready()));
+        }
     }
 }
 
-pub struct TicketGuard<'a, T> {
-    mutex: &'a TicketMutex<T>,
+/// Poisons the channel if dropped, which only happens when a write panics while holding the lock,
+/// then wakes up a waiting reader so that it sees it.
+struct Abort<'a, T>(&'a Inner<T>);
+
+impl<T> Drop for Abort<'_, T> {
+    fn drop(&mut self) {
+        self.0.poisoned.store(true, Ordering::Release);
+        self.0.waiter.notify();
+    }
 }
 
-impl<'mutex, T> TicketGuard<'mutex, T> {
-    fn new(mutex: &'mutex TicketMutex<T>) -> Self {
-        TicketGuard { mutex }
+impl<T> ReadHandle<'_, T> {
+    /// Try reading the last written value by swapping it with `scratch` instead of cloning it.
+    /// Fails like [`Reader::read`], leaving `scratch` untouched.
+    ///
+    /// The previous content of `scratch` becomes the value the writer modifies through
+    /// [`Writer::write_with`] and [`WriteHandle::update`] and compares against in
+    /// [`WriteHandle::write_if_changed`].
+    pub fn read_swap(&mut self, scratch: &mut T) -> Result<(), TryReadError> {
+        let mut guard = self.inner.try_read()?;
+        std::mem::swap(&mut *guard, scratch);
+        Ok(())
+    }
+
+    /// Returns how many written values were replaced before they could be read.
+    pub fn overwritten(&self) -> u64 {
+        self.inner.overwritten.load(Ordering::Relaxed)
+    }
+
+    /// Returns the label given to [`named`], if any.
+    pub fn name(&self) -> Option<&'static str> {
+        self.inner.name
+    }
+
+    /// Returns a snapshot of the usage counters of the channel, shared with the writer.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.inner.stats.snapshot(self.inner.name)
+    }
+
+    /// Returns when the writer last published a value, whether it was read or not, or `None` if it
+    /// never did.
+    #[cfg(feature = "age")]
+    pub fn last_update(&self) -> Option<Instant> {
+        self.inner.updated.get()
+    }
+
+    /// Returns how long ago the writer last published a value, or `None` if it never did.
+    ///
+    /// Comparing it against a deadline detects a stalled producer, which a read finding no new
+    /// value cannot tell apart from a slow one.
+    #[cfg(feature = "age")]
+    pub fn age(&self) -> Option<Duration> {
+        self.last_update().map(|instant| instant.elapsed())
+    }
+
+    /// Reads the next written value, blocking until the writer publishes one.
+    /// Fails with [`TryReadError::Closed`] or [`TryReadError::Disconnected`] once the writer is gone
+    /// and its last value was read.
+    #[cfg(not(target_family = "wasm"))]
+    pub fn read_blocking(&mut self) -> Result<TicketGuard<'_, T>, TryReadError> {
+        self.inner.read_deadline(None)
+    }
+
+    /// Reads the next written value, blocking for at most `timeout`.
+    /// Fails with [`TryReadError::Empty`] if nothing was published in the meantime.
+    #[cfg(not(target_family = "wasm"))]
+    pub fn read_timeout(&mut self, timeout: Duration) -> Result<TicketGuard<'_, T>, TryReadError> {
+        self.inner.read_deadline(Some(Instant::now() + timeout))
     }
 }
 
-impl<T> Deref for TicketGuard<'_, T> {
-    type Target = T;
+impl<T> WriteHandle<'_, T> {
+    /// Publishes the last written value (or the initial value before the first write) as modified
+    /// by `f`, so the producer does not have to keep its own copy of the current state.
+    ///
+    /// The value is modified in place, same as [`Writer::write_with`].
+    pub fn update(&mut self, f: impl FnOnce(&mut T)) {
+        self.inner.write_with(f)
+    }
+
+    /// Writes the provided value unless it is equal to the last written one (or to the initial
+    /// value before the first write), sparing the reader a redundant update.
+    /// Returns whether the value was published.
+    pub fn write_if_changed(&mut self, value: T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.inner.write_if_changed(value)
+    }
+
+    /// Returns how many written values were replaced before the reader could read them.
+    pub fn overwritten(&self) -> u64 {
+        self.inner.overwritten.load(Ordering::Relaxed)
+    }
+
+    /// Returns the label given to [`named`], if any.
+    pub fn name(&self) -> Option<&'static str> {
+        self.inner.name
+    }
 
-    fn deref(&self) -> &T {
-        unsafe { &*self.mutex.data.get() }
+    /// Returns a snapshot of the usage counters of the channel, shared with the reader.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.inner.stats.snapshot(self.inner.name)
     }
 }
 
-impl<T> DerefMut for TicketGuard<'_, T> {
-    fn deref_mut(&mut self) -> &mut T {
-        unsafe { &mut *self.mutex.data.get() }
+impl<T> Reader for ReadHandle<'_, T> {
+    type Item = T;
+    type Guard<'a>
+        = TicketGuard<'a, T>
+    where
+        Self: 'a;
+
+    /// Try reading the last written value.
+    ///
+    /// Returns `None` instead of waiting while the writer holds the lock, the value it writes is
+    /// then taken by the next read.
+    fn read(&mut self) -> Result<Self::Guard<'_>, TryReadError> {
+        self.inner.try_read()
     }
 }
 
-impl<T> Drop for TicketGuard<'_, T> {
-    fn drop(&mut self) {
-        self.mutex.unlock()
+#[cfg(target_os = "linux")]
+impl<T> ReadHandle<'_, T> {
+    /// Attaches an eventfd to the channel and returns it, so that the reader can wait in an `epoll`
+    /// (or `mio`) event loop next to sockets. Attaching again returns the same eventfd.
+    ///
+    /// The writer only signals it while it is armed by [`ReadHandle::arm_eventfd`], so publishing
+    /// costs a syscall at most once per wakeup.
+    pub fn attach_eventfd(&self) -> std::io::Result<&EventFd> {
+        self.inner.waiter.attach_eventfd()
+    }
+
+    /// Returns the eventfd attached by [`ReadHandle::attach_eventfd`], if any.
+    pub fn eventfd(&self) -> Option<&EventFd> {
+        self.inner.waiter.eventfd()
+    }
+
+    /// Arms the attached eventfd: it becomes readable once a read would not fail with
+    /// [`TryReadError::Empty`], and stays readable until armed again.
+    ///
+    /// Returns `true`, with the eventfd already readable, if a read would not fail right away.
+    pub fn arm_eventfd(&mut self) -> bool {
+        let inner = &*self.inner;
+        inner.waiter.arm_eventfd(|| inner.ready())
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T> ReadHandle<'_, T> {
+    /// Try reading the last written value, registering the task of `cx` to be woken up by the next
+    /// write (or by the writer being dropped) if there is none yet.
+    ///
+    /// This is what the `futures` and `tokio` features build on, and works the same on any
+    /// runtime.
+    pub fn poll_read(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<TicketGuard<'_, T>, TryReadError>> {
+        self.inner.poll_read(cx.waker())
     }
 }
 
-impl<T> std::fmt::Debug for TicketGuard<'_, T>
+#[cfg(feature = "futures")]
+impl<T> futures_core::Stream for ReadHandle<'_, T>
 where
-    T: std::fmt::Debug,
+    T: Clone,
 {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        std::fmt::Debug::fmt(&**self, f)
+    type Item = T;
+
+    /// Yields a clone of the last written value whenever a new one is published, and ends once the
+    /// writer is gone and its last value was read.
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<T>> {
+        self.poll_read(cx)
+            .map(|res| res.ok().map(|guard| T::clone(&guard)))
     }
 }
 
-struct Inner<T> {
-    data: TicketMutex<T>,
-    to_read: AtomicBool,
+#[cfg(feature = "tokio")]
+impl<T> ReadHandle<'_, T> {
+    /// Waits until a value is written that was not read yet, like
+    /// `tokio::sync::watch::Receiver::changed`. The value is left for the next read.
+    ///
+    /// Fails with [`TryReadError::Closed`] (or [`TryReadError::Disconnected`] if the writer was
+    /// dropped without closing) once the writer is gone and its last value was read.
+    pub async fn changed(&mut self) -> Result<(), TryReadError> {
+        let inner = &*self.inner;
+        std::future::poll_fn(|cx| inner.waiter.poll_wait(cx.waker(), || inner.ready())).await;
+        if inner.has_changed() {
+            Ok(())
+        } else {
+            Err(inner.disconnected())
+        }
+    }
+}
+
+impl<T> Writer for WriteHandle<'_, T> {
+    type Item = T;
+
+    fn write(&mut self, value: T) {
+        self.inner.write(value)
+    }
+
+    fn write_with(&mut self, f: impl FnOnce(&mut T))
+    where
+        T: Default,
+    {
+        self.inner.write_with(f)
+    }
+
+    fn close(self) {
+        self.inner.closed.store(true, Ordering::Relaxed);
+        // dropping `self` disconnects the reader
+    }
+
+    fn is_reader_alive(&self) -> bool {
+        self.inner.reader_alive.load(Ordering::Acquire)
+    }
+
+    fn try_write(&mut self, value: T) -> Result<(), WriteError<T>> {
+        if !self.is_reader_alive() {
+            return Err(WriteError::Disconnected(value));
+        }
+        self.inner.try_write(value)
+    }
 }
 
-pub struct ReadHandle<T> {
-    inner: Arc<Inner<T>>,
+impl<'a, T> ReadHandle<'a, T> {
+    /// Returns a [`MonitorHandle`] observing the channel without taking part in it.
+    pub fn monitor(&self) -> MonitorHandle<'a, T> {
+        MonitorHandle {
+            inner: self.inner.clone(),
+        }
+    }
+
+    /// Turns the handle into an opaque pointer to the state of the channel, for places that cannot
+    /// hold a generic handle, such as the context of a C callback.
+    ///
+    /// The handle is not dropped: the channel still sees it alive until the pointer is turned back
+    /// into a handle by [`ReadHandle::from_raw`], which must happen exactly once to release it.
+    pub fn into_raw(self) -> *const () {
+        let this = std::mem::ManuallyDrop::new(self);
+        unsafe { std::ptr::read(&this.inner) }.into_raw()
+    }
+
+    /// Turns a pointer returned by [`ReadHandle::into_raw`] back into the handle.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must come from [`ReadHandle::into_raw`] on a handle of this module and of the same `T`,
+    /// and must be turned back only once: two handles built from it would share one side of the
+    /// channel. A handle of a [`Storage`] must not be turned back after the storage is gone.
+    pub unsafe fn from_raw(ptr: *const ()) -> Self {
+        ReadHandle {
+            inner: unsafe { Shared::from_raw(ptr) },
+        }
+    }
 }
 
-pub struct WriteHandle<T> {
-    inner: Arc<Inner<T>>,
+impl<T> Drop for ReadHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.reader_alive.store(false, Ordering::Release);
+    }
 }
 
-impl<T> Inner<T> {
-    fn new(init: T) -> Self {
-        Inner {
-            data: TicketMutex::new(init),
-            to_read: AtomicBool::new(false),
+impl<'a, T> WriteHandle<'a, T> {
+    /// Returns a [`MonitorHandle`] observing the channel without taking part in it.
+    pub fn monitor(&self) -> MonitorHandle<'a, T> {
+        MonitorHandle {
+            inner: self.inner.clone(),
         }
     }
 
-    fn write(&self, value: T) {
-        let mut data = self.data.lock().unwrap();
-        *data = value;
-        self.to_read.store(true, Ordering::Release);
+    /// Turns the handle into an opaque pointer to the state of the channel, for places that cannot
+    /// hold a generic handle, such as the context of a C callback.
+    ///
+    /// The handle is not dropped: the channel still sees it alive until the pointer is turned back
+    /// into a handle by [`WriteHandle::from_raw`], which must happen exactly once to release it.
+    pub fn into_raw(self) -> *const () {
+        let this = std::mem::ManuallyDrop::new(self);
+        unsafe { std::ptr::read(&this.inner) }.into_raw()
     }
 
-    fn read(&self) -> Option<TicketGuard<'_, T>> {
-        if self.to_read.load(Ordering::Acquire) {
-            let guard = self.data.lock().ok()?;
-            self.to_read.store(false, Ordering::Release);
-            Some(guard)
-        } else {
-            None
+    /// Turns a pointer returned by [`WriteHandle::into_raw`] back into the handle.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must come from [`WriteHandle::into_raw`] on a handle of this module and of the same `T`,
+    /// and must be turned back only once: two handles built from it would share one side of the
+    /// channel. A handle of a [`Storage`] must not be turned back after the storage is gone.
+    pub unsafe fn from_raw(ptr: *const ()) -> Self {
+        WriteHandle {
+            inner: unsafe { Shared::from_raw(ptr) },
         }
     }
 }
 
-impl<T> Reader for ReadHandle<T> {
-    type Item = T;
-    type Guard<'a>
-        = TicketGuard<'a, T>
+impl<T> Drop for WriteHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.writer_alive.store(false, Ordering::Release);
+        self.inner.waiter.notify();
+    }
+}
+
+impl<T> MonitorHandle<'_, T> {
+    /// Hands the last written value (or the initial value before the first write) to `f`, without
+    /// consuming it.
+    ///
+    /// The lock of the value is held while `f` runs, so the reader and the writer wait on it
+    /// meanwhile: `f` should be quick.
+    ///
+    /// A value left halfway through by a panicking write is handed to `f` as is.
+    pub fn peek_with<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        let data = self.inner.data.lock();
+        f(&data)
+    }
+
+    /// Returns a copy of the last written value (or of the initial value before the first write),
+    /// without consuming it.
+    pub fn peek(&self) -> T
     where
-        T: 'a;
+        T: Clone,
+    {
+        self.peek_with(T::clone)
+    }
 
-    fn read(&self) -> Option<Self::Guard<'_>> {
-        self.inner.read()
+    /// Returns the label given to [`named`], if any.
+    pub fn name(&self) -> Option<&'static str> {
+        self.inner.name
     }
 }
 
-impl<T> Writer for WriteHandle<T> {
-    type Item = T;
+impl<T> Clone for MonitorHandle<'_, T> {
+    fn clone(&self) -> Self {
+        MonitorHandle {
+            inner: self.inner.clone(),
+        }
+    }
+}
 
-    fn write(&self, value: T) {
-        self.inner.write(value)
+impl<T> DebugState for Inner<T> {
+    fn debug_state(&self, d: &mut std::fmt::DebugStruct<'_, '_>) {
+        if let Some(name) = self.name {
+            d.field("name", &name);
+        }
+        d.field("pending", &self.to_read.load(Ordering::Relaxed))
+            .field("overwritten", &self.overwritten.load(Ordering::Relaxed))
+            .field("poisoned", &self.poisoned.load(Ordering::Relaxed))
+            .field("writer_alive", &self.writer_alive.load(Ordering::Relaxed))
+            .field("closed", &self.closed.load(Ordering::Relaxed))
+            .field("reader_alive", &self.reader_alive.load(Ordering::Relaxed));
+    }
+}
+
+impl<T> std::fmt::Debug for ReadHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "ticket_spsc::ReadHandle")
+    }
+}
+
+impl<T> std::fmt::Debug for WriteHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "ticket_spsc::WriteHandle")
+    }
+}
+
+impl<T> std::fmt::Debug for MonitorHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "ticket_spsc::MonitorHandle")
+    }
+}
+
+pub fn new<T>(init: T) -> (ReadHandle<'static, T>, WriteHandle<'static, T>) {
+    from_inner(Inner::new(init))
+}
+
+/// This variant as a [`crate::Channel`], starting from `T::default()`, for code generic over the
+/// variant.
+pub struct Channel;
+
+impl<T: Default + 'static> crate::Channel<T> for Channel {
+    type Reader = ReadHandle<'static, T>;
+    type Writer = WriteHandle<'static, T>;
+
+    fn create() -> (Self::Reader, Self::Writer) {
+        new(T::default())
     }
 }
 
-pub fn new<T>(init: T) -> (ReadHandle<T>, WriteHandle<T>) {
-    let inner = Arc::new(Inner::new(init));
+/// Like [`new`], with the channel labelled `name` in `Debug`, `stats()` and the `tracing` events.
+pub fn named<T>(name: &'static str, init: T) -> (ReadHandle<'static, T>, WriteHandle<'static, T>) {
+    let mut inner = Inner::new(init);
+    inner.name = Some(name);
+    from_inner(inner)
+}
+
+/// Like [`new`], with `hooks` called on every read and write of the channel.
+#[cfg(feature = "hooks")]
+pub fn with_hooks<T>(
+    hooks: impl Hooks,
+    init: T,
+) -> (ReadHandle<'static, T>, WriteHandle<'static, T>) {
+    let mut inner = Inner::new(init);
+    inner.hooks = Callbacks::new(hooks);
+    from_inner(inner)
+}
+
+/// Takes the value back out of the channel, consuming both of its handles, instead of leaving it to
+/// be dropped along with the channel.
+///
+/// This is the last written value, or `init` if nothing was written. Returns `None` if the channel
+/// lives in a [`Storage`], which keeps its value.
+///
+/// Panics if the handles do not belong to the same channel.
+pub fn into_inner<T>(r: ReadHandle<'_, T>, w: WriteHandle<'_, T>) -> Option<T> {
+    assert!(
+        std::ptr::eq(&*r.inner, &*w.inner),
+        "handles of different channels"
+    );
+    let inner = w.inner.clone();
+    drop(r);
+    drop(w);
+    let inner = inner.into_owned()?;
+    Some(inner.data.into_inner())
+}
+
+fn from_inner<T>(inner: Inner<T>) -> (ReadHandle<'static, T>, WriteHandle<'static, T>) {
+    let inner = Arc::new(inner);
     let r = ReadHandle {
-        inner: Arc::clone(&inner),
+        inner: Shared::Arc(Arc::clone(&inner)),
     };
     let w = WriteHandle {
-        inner: Arc::clone(&inner),
+        inner: Shared::Arc(inner),
     };
     (r, w)
 }
+
+/// Storage for a channel that is not reference counted, so that no allocation is needed.
+///
+/// It can be placed in a `static` or borrowed by the threads of a [`std::thread::scope`]: the
+/// handles returned by [`Storage::split`] borrow it, and are handed out only once.
+///
+/// ```
+/// use rustedrazors::ticket_spsc::Storage;
+/// use rustedrazors::{Reader, Writer};
+///
+/// let storage = Storage::new(0);
+/// let (mut r, mut w) = storage.split().unwrap();
+/// std::thread::scope(|s| {
+///     s.spawn(move || w.write(42));
+/// });
+/// assert_eq!(r.read().as_deref(), Ok(&42));
+/// ```
+pub struct Storage<T> {
+    inner: Inner<T>,
+    split: AtomicBool,
+}
+
+impl<T> Storage<T> {
+    /// Constructs a new storage holding `init`.
+    pub const fn new(init: T) -> Self {
+        Storage {
+            inner: Inner::new(init),
+            split: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the read and write handles borrowing the storage, or `None` if they were already
+    /// taken.
+    pub fn split(&self) -> Option<(ReadHandle<'_, T>, WriteHandle<'_, T>)> {
+        if self.split.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+        let r = ReadHandle {
+            inner: Shared::Borrowed(&self.inner),
+        };
+        let w = WriteHandle {
+            inner: Shared::Borrowed(&self.inner),
+        };
+        Some((r, w))
+    }
+}
@@ -0,0 +1,269 @@
+use crate::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use crate::debug::DebugState;
+use crate::shared::Shared;
+use crate::{Reader, TryReadError, Writer};
+
+use std::cell::UnsafeCell;
+use std::sync::Arc;
+
+// set in `middle` when it holds a buffer published since the reader last took one
+const DIRTY: usize = 0b100;
+const INDEX: usize = 0b011;
+
+/// Classic triple buffer: the writer owns a back buffer, the reader owns a front buffer, and the
+/// third one sits in between. Publishing and taking a value are a single swap of `middle`.
+struct Inner<T> {
+    buffers: [UnsafeCell<T>; 3],
+    middle: AtomicUsize,
+    // number of values replaced before being read, only modified by the writer
+    overwritten: AtomicU64,
+    writer_alive: AtomicBool,
+    // set right before `writer_alive` is cleared if the writer left through `close`
+    closed: AtomicBool,
+    reader_alive: AtomicBool,
+}
+
+/// Safety: each buffer is only ever accessed by the side that currently owns it, and ownership
+/// changes hands through `middle`.
+unsafe impl<T> Sync for Inner<T> where T: Send {}
+
+pub struct ReadHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+    front: usize,
+}
+
+pub struct WriteHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+    back: usize,
+}
+
+impl<T> Inner<T>
+where
+    T: Default,
+{
+    fn new() -> Self {
+        Inner {
+            buffers: [(); 3].map(|_| UnsafeCell::new(T::default())),
+            middle: AtomicUsize::new(1),
+            overwritten: AtomicU64::new(0),
+            writer_alive: AtomicBool::new(true),
+            closed: AtomicBool::new(false),
+            reader_alive: AtomicBool::new(true),
+        }
+    }
+}
+
+impl<T> Inner<T> {
+    /// Error reported once the writer is gone and nothing is left to read.
+    fn disconnected(&self) -> TryReadError {
+        if self.closed.load(Ordering::Relaxed) {
+            TryReadError::Closed
+        } else {
+            TryReadError::Disconnected
+        }
+    }
+}
+
+/// Lease on the front buffer of the reader.
+///
+/// The guard borrows the read handle, which cannot swap in newer values while it is alive, so the
+/// buffer cannot be handed back to the writer under it.
+pub struct TripleGuard<'a, T> {
+    value: &'a T,
+}
+
+impl<T> std::ops::Deref for TripleGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+impl<T> std::fmt::Debug for TripleGuard<'_, T>
+where
+    T: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T> ReadHandle<'_, T> {
+    /// Swaps in the last published buffer as the front buffer.
+    /// Returns `false`, keeping the current front buffer, if nothing was published since the last
+    /// update.
+    pub fn update(&mut self) -> bool {
+        self.take()
+    }
+
+    /// Leases the front buffer, which is the last value taken by [`ReadHandle::update`] or
+    /// [`Reader::read`] (or `T::default()` before the first one).
+    ///
+    /// The handle stays borrowed by the guard, so that no newer value can be taken under it.
+    pub fn front_buffer(&mut self) -> TripleGuard<'_, T> {
+        let value = unsafe { &*self.inner.buffers[self.front].get() };
+        TripleGuard { value }
+    }
+
+    /// Returns how many written values were replaced before they could be read.
+    pub fn overwritten(&self) -> u64 {
+        self.inner.overwritten.load(Ordering::Relaxed)
+    }
+
+    fn take(&mut self) -> bool {
+        if self.inner.middle.load(Ordering::Relaxed) & DIRTY == 0 {
+            return false;
+        }
+        let middle = self.inner.middle.swap(self.front, Ordering::AcqRel);
+        self.front = middle & INDEX;
+        true
+    }
+}
+
+impl<T> WriteHandle<'_, T> {
+    /// Returns the back buffer, to be modified in place before calling [`WriteHandle::publish`].
+    ///
+    /// It holds an older value handed back by the reader (or `T::default()`), not necessarily the
+    /// last published one.
+    pub fn back_buffer(&mut self) -> &mut T {
+        unsafe { &mut *self.inner.buffers[self.back].get() }
+    }
+
+    /// Makes the back buffer available to the reader and takes another one as back buffer.
+    pub fn publish(&mut self) {
+        self.swap_back();
+    }
+
+    /// Returns how many written values were replaced before the reader could read them.
+    pub fn overwritten(&self) -> u64 {
+        self.inner.overwritten.load(Ordering::Relaxed)
+    }
+
+    fn swap_back(&mut self) {
+        let middle = self.inner.middle.swap(self.back | DIRTY, Ordering::AcqRel);
+        self.back = middle & INDEX;
+        if middle & DIRTY != 0 {
+            self.inner.overwritten.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+impl<T> Reader for ReadHandle<'_, T> {
+    type Item = T;
+    type Guard<'a>
+        = TripleGuard<'a, T>
+    where
+        Self: 'a;
+
+    /// Try reading the last published value.
+    fn read(&mut self) -> Result<Self::Guard<'_>, TryReadError> {
+        if !self.take() {
+            if self.inner.writer_alive.load(Ordering::Acquire) {
+                return Err(TryReadError::Empty);
+            }
+            // the writer may have published right before being dropped
+            if !self.take() {
+                return Err(self.inner.disconnected());
+            }
+        }
+        Ok(self.front_buffer())
+    }
+}
+
+impl<T> Writer for WriteHandle<'_, T> {
+    type Item = T;
+
+    fn write(&mut self, value: T) {
+        unsafe { *self.inner.buffers[self.back].get() = value }
+        self.swap_back();
+    }
+
+    fn write_with(&mut self, f: impl FnOnce(&mut T))
+    where
+        T: Default,
+    {
+        f(unsafe { &mut *self.inner.buffers[self.back].get() });
+        self.swap_back();
+    }
+
+    fn close(self) {
+        self.inner.closed.store(true, Ordering::Relaxed);
+        // dropping `self` disconnects the reader
+    }
+
+    fn is_reader_alive(&self) -> bool {
+        self.inner.reader_alive.load(Ordering::Acquire)
+    }
+}
+
+impl<T> Drop for ReadHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.reader_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<T> Drop for WriteHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.writer_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<T> DebugState for Inner<T> {
+    fn debug_state(&self, d: &mut std::fmt::DebugStruct<'_, '_>) {
+        let middle = self.middle.load(Ordering::Relaxed);
+        d.field("pending", &(middle & DIRTY != 0))
+            .field("middle", &(middle & INDEX))
+            .field("overwritten", &self.overwritten.load(Ordering::Relaxed))
+            .field("writer_alive", &self.writer_alive.load(Ordering::Relaxed))
+            .field("closed", &self.closed.load(Ordering::Relaxed))
+            .field("reader_alive", &self.reader_alive.load(Ordering::Relaxed));
+    }
+}
+
+impl<T> std::fmt::Debug for ReadHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut d = f.debug_struct("triple_buffer::ReadHandle");
+        self.inner.debug_state(&mut d);
+        d.field("front", &self.front).finish()
+    }
+}
+
+impl<T> std::fmt::Debug for WriteHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut d = f.debug_struct("triple_buffer::WriteHandle");
+        self.inner.debug_state(&mut d);
+        d.field("back", &self.back).finish()
+    }
+}
+
+/// Construct a new read and write handle pair, with every buffer set to `T::default()`.
+///
+/// Reads fail with [`TryReadError::Empty`] until the first publish.
+pub fn new<T>() -> (ReadHandle<'static, T>, WriteHandle<'static, T>)
+where
+    T: Default,
+{
+    let inner = Arc::new(Inner::new());
+    let r = ReadHandle {
+        inner: Shared::Arc(Arc::clone(&inner)),
+        front: 2,
+    };
+    let w = WriteHandle {
+        inner: Shared::Arc(inner),
+        back: 0,
+    };
+    (r, w)
+}
+
+/// This variant as a [`crate::Channel`], for code generic over the variant.
+pub struct Channel;
+
+impl<T: Default + 'static> crate::Channel<T> for Channel {
+    type Reader = ReadHandle<'static, T>;
+    type Writer = WriteHandle<'static, T>;
+
+    fn create() -> (Self::Reader, Self::Writer) {
+        new()
+    }
+}
@@ -0,0 +1,156 @@
+use crate::atomic::{fence, AtomicBool, Ordering};
+#[cfg(target_os = "linux")]
+use crate::eventfd::EventFd;
+#[cfg(feature = "async")]
+use atomic_waker::AtomicWaker;
+
+#[cfg(not(target_family = "wasm"))]
+use std::sync::Mutex;
+#[cfg(target_os = "linux")]
+use std::sync::OnceLock;
+#[cfg(feature = "async")]
+use std::task::{Poll, Waker};
+#[cfg(not(target_family = "wasm"))]
+use std::thread::{self, Thread};
+#[cfg(not(target_family = "wasm"))]
+use std::time::Instant;
+
+/// Lets the reader of a channel sleep until the writer publishes something.
+///
+/// The writer only pays for a fence and a relaxed load on [`Waiter::notify`], the registered thread
+/// is touched (and unparked) only while the reader is actually sleeping.
+///
+/// Parking goes through `std::thread::park`, which already sleeps on the thread's own word with
+/// `futex` on Linux and `WaitOnAddress` on Windows, so wakeup latency is comparable on both.
+///
+/// With the `async` feature, a task can wait the same way through [`Waiter::poll_wait`]. On Linux,
+/// an event loop can wait on an attached eventfd after [`Waiter::arm_eventfd`].
+///
+/// On `wasm32` threads cannot park (the main thread of a browser is not even allowed to block), so
+/// only tasks can wait.
+pub(crate) struct Waiter {
+    #[cfg(not(target_family = "wasm"))]
+    thread: Mutex<Option<Thread>>,
+    #[cfg(feature = "async")]
+    waker: AtomicWaker,
+    #[cfg(target_os = "linux")]
+    eventfd: OnceLock<EventFd>,
+    sleeping: AtomicBool,
+}
+
+impl Waiter {
+    pub(crate) const fn new() -> Self {
+        Waiter {
+            #[cfg(not(target_family = "wasm"))]
+            thread: Mutex::new(None),
+            #[cfg(feature = "async")]
+            waker: AtomicWaker::new(),
+            #[cfg(target_os = "linux")]
+            eventfd: OnceLock::new(),
+            sleeping: AtomicBool::new(false),
+        }
+    }
+
+    /// Parks the current thread until `ready` returns `true` or `deadline` is reached.
+    /// Returns whether `ready` was satisfied.
+    ///
+    /// `ready` must observe whatever the writer publishes before calling [`Waiter::notify`].
+    #[cfg(not(target_family = "wasm"))]
+    pub(crate) fn wait(&self, ready: impl Fn() -> bool, deadline: Option<Instant>) -> bool {
+        *self.thread.lock().unwrap() = Some(thread::current());
+        let res = loop {
+            self.sleeping.store(true, Ordering::Relaxed);
+            // pairs with the fence in `notify`: either we see the new value or the writer sees us
+            fence(Ordering::SeqCst);
+            if ready() {
+                break true;
+            }
+            match deadline {
+                None => thread::park(),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        break false;
+                    }
+                    thread::park_timeout(deadline - now);
+                }
+            }
+        };
+        self.sleeping.store(false, Ordering::Relaxed);
+        res
+    }
+
+    /// Registers `waker` to be woken by the next [`Waiter::notify`], unless `ready` already
+    /// returns `true`.
+    ///
+    /// `ready` must observe whatever the writer publishes before calling [`Waiter::notify`].
+    #[cfg(feature = "async")]
+    pub(crate) fn poll_wait(&self, waker: &Waker, ready: impl Fn() -> bool) -> Poll<()> {
+        self.waker.register(waker);
+        self.sleeping.store(true, Ordering::Relaxed);
+        // pairs with the fence in `notify`, as in `wait`
+        fence(Ordering::SeqCst);
+        if ready() {
+            self.sleeping.store(false, Ordering::Relaxed);
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+
+    /// Attaches an eventfd, signaled by [`Waiter::notify`] once armed, and returns it.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn attach_eventfd(&self) -> std::io::Result<&EventFd> {
+        if let Some(eventfd) = self.eventfd.get() {
+            return Ok(eventfd);
+        }
+        let eventfd = EventFd::new()?;
+        Ok(self.eventfd.get_or_init(|| eventfd))
+    }
+
+    /// Returns the eventfd attached by [`Waiter::attach_eventfd`], if any.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn eventfd(&self) -> Option<&EventFd> {
+        self.eventfd.get()
+    }
+
+    /// Resets the attached eventfd so that the next [`Waiter::notify`] signals it, unless `ready`
+    /// already returns `true`, in which case it is signaled right away. Returns `ready()`.
+    ///
+    /// `ready` must observe whatever the writer publishes before calling [`Waiter::notify`].
+    #[cfg(target_os = "linux")]
+    pub(crate) fn arm_eventfd(&self, ready: impl Fn() -> bool) -> bool {
+        let Some(eventfd) = self.eventfd.get() else {
+            return ready();
+        };
+        eventfd.drain();
+        self.sleeping.store(true, Ordering::Relaxed);
+        // pairs with the fence in `notify`, as in `wait`
+        fence(Ordering::SeqCst);
+        if ready() {
+            self.sleeping.store(false, Ordering::Relaxed);
+            eventfd.signal();
+            return true;
+        }
+        false
+    }
+
+    /// Wakes up the waiting thread, task or event loop, if any.
+    pub(crate) fn notify(&self) {
+        fence(Ordering::SeqCst);
+        if self.sleeping.load(Ordering::Relaxed) {
+            // tasks and event loops may read without going through `wait`, and never clear it
+            self.sleeping.store(false, Ordering::Relaxed);
+            #[cfg(not(target_family = "wasm"))]
+            if let Some(thread) = self.thread.lock().unwrap().as_ref() {
+                thread.unpark();
+            }
+            #[cfg(feature = "async")]
+            self.waker.wake();
+            #[cfg(target_os = "linux")]
+            if let Some(eventfd) = self.eventfd.get() {
+                eventfd.signal();
+            }
+        }
+    }
+}
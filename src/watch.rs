@@ -0,0 +1,256 @@
+use crate::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use crate::debug::DebugState;
+use crate::shared::Shared;
+use crate::{Reader, TryReadError, Writer};
+
+use std::sync::{Arc, Mutex};
+
+/// Single writer, many readers: the last value is kept behind an `Arc` that readers clone out, and
+/// every reader compares `version` with the last one it saw to tell whether something is new.
+struct Inner<T> {
+    current: Mutex<Option<Arc<T>>>,
+    // number of values written, only modified while holding `current`
+    version: AtomicU64,
+    writer_alive: AtomicBool,
+    // set right before `writer_alive` is cleared if the writer left through `close`
+    closed: AtomicBool,
+    // number of live `ReadHandle`s
+    readers: AtomicUsize,
+}
+
+/// Observer of the channel, which can be cloned to add more observers.
+///
+/// Every handle tracks which values it already read on its own, so each of them reads every value
+/// it did not miss, regardless of the others.
+pub struct ReadHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+    // `version` of the last value read
+    seen: u64,
+}
+
+pub struct WriteHandle<'a, T> {
+    inner: Shared<'a, Inner<T>>,
+}
+
+impl<T> Inner<T> {
+    const fn new() -> Self {
+        Inner {
+            current: Mutex::new(None),
+            version: AtomicU64::new(0),
+            writer_alive: AtomicBool::new(true),
+            closed: AtomicBool::new(false),
+            readers: AtomicUsize::new(1),
+        }
+    }
+
+    fn write(&self, value: T) {
+        let value = Arc::new(value);
+        let old = {
+            let mut current = self.current.lock().unwrap();
+            let version = self.version.load(Ordering::Relaxed);
+            self.version.store(version + 1, Ordering::Release);
+            current.replace(value)
+        };
+        // readers may still hold it, and if not it is dropped outside the lock
+        drop(old);
+    }
+
+    /// Returns the last written value along with its version.
+    fn latest(&self) -> Option<(Arc<T>, u64)> {
+        let current = self.current.lock().unwrap();
+        let version = self.version.load(Ordering::Relaxed);
+        current.as_ref().map(|value| (Arc::clone(value), version))
+    }
+
+    /// Error reported once the writer is gone and nothing is left to read.
+    fn disconnected(&self) -> TryReadError {
+        if self.closed.load(Ordering::Relaxed) {
+            TryReadError::Closed
+        } else {
+            TryReadError::Disconnected
+        }
+    }
+}
+
+/// Shared reference to a read value.
+///
+/// Every reader holding the same value shares it, and the writer never waits for them: newer
+/// values are written next to it, and it is dropped with its last guard.
+#[derive(Debug)]
+pub struct WatchGuard<T>(Arc<T>);
+
+impl<T> WatchGuard<T> {
+    /// Returns the shared value.
+    pub fn into_arc(self) -> Arc<T> {
+        self.0
+    }
+}
+
+impl<T> std::ops::Deref for WatchGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> ReadHandle<'_, T> {
+    /// Returns the last written value, even if this handle already read it, and marks it as read.
+    /// Returns `None` only before the first write.
+    pub fn latest(&mut self) -> Option<WatchGuard<T>> {
+        let (value, version) = self.inner.latest()?;
+        self.seen = version;
+        Some(WatchGuard(value))
+    }
+
+    /// Returns whether a value was written since this handle last read one.
+    ///
+    /// This method is wait-free.
+    pub fn has_changed(&self) -> bool {
+        self.inner.version.load(Ordering::Acquire) != self.seen
+    }
+
+    /// Reads the last written value unless this handle already read it.
+    fn take(&mut self) -> Option<WatchGuard<T>> {
+        if !self.has_changed() {
+            return None;
+        }
+        self.latest()
+    }
+}
+
+impl<T> Clone for ReadHandle<'_, T> {
+    /// Adds an observer, which has already read whatever this handle has.
+    fn clone(&self) -> Self {
+        self.inner.readers.fetch_add(1, Ordering::Relaxed);
+        ReadHandle {
+            inner: self.inner.clone(),
+            seen: self.seen,
+        }
+    }
+}
+
+impl<T> WriteHandle<'_, T> {
+    /// Returns how many `ReadHandle`s are alive.
+    pub fn reader_count(&self) -> usize {
+        self.inner.readers.load(Ordering::Acquire)
+    }
+}
+
+impl<T> Reader for ReadHandle<'_, T> {
+    type Item = T;
+    type Guard<'a>
+        = WatchGuard<T>
+    where
+        Self: 'a;
+
+    /// Try reading the last written value, failing with [`TryReadError::Empty`] if none was
+    /// written since this handle last read one.
+    ///
+    /// The check is a single atomic load, the lock is only taken when there is something to read.
+    fn read(&mut self) -> Result<Self::Guard<'_>, TryReadError> {
+        if let Some(guard) = self.take() {
+            return Ok(guard);
+        }
+        if self.inner.writer_alive.load(Ordering::Acquire) {
+            return Err(TryReadError::Empty);
+        }
+        // the writer may have written right before being dropped
+        self.take().ok_or_else(|| self.inner.disconnected())
+    }
+}
+
+impl<T> Writer for WriteHandle<'_, T> {
+    type Item = T;
+
+    fn write(&mut self, value: T) {
+        self.inner.write(value)
+    }
+
+    /// Publishes a value built in place by `f`, starting from `T::default()`.
+    ///
+    /// Readers may still hold older values, so they cannot be reused.
+    fn write_with(&mut self, f: impl FnOnce(&mut T))
+    where
+        T: Default,
+    {
+        let mut value = T::default();
+        f(&mut value);
+        self.inner.write(value)
+    }
+
+    fn close(self) {
+        self.inner.closed.store(true, Ordering::Relaxed);
+        // dropping `self` disconnects the readers
+    }
+
+    /// Returns whether any `ReadHandle` is still alive.
+    fn is_reader_alive(&self) -> bool {
+        self.reader_count() > 0
+    }
+}
+
+impl<T> Drop for ReadHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.readers.fetch_sub(1, Ordering::Release);
+    }
+}
+
+impl<T> Drop for WriteHandle<'_, T> {
+    fn drop(&mut self) {
+        self.inner.writer_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<T> DebugState for Inner<T> {
+    fn debug_state(&self, d: &mut std::fmt::DebugStruct<'_, '_>) {
+        d.field("version", &self.version.load(Ordering::Relaxed))
+            .field("writer_alive", &self.writer_alive.load(Ordering::Relaxed))
+            .field("closed", &self.closed.load(Ordering::Relaxed))
+            .field("readers", &self.readers.load(Ordering::Relaxed));
+    }
+}
+
+impl<T> std::fmt::Debug for ReadHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut d = f.debug_struct("watch::ReadHandle");
+        self.inner.debug_state(&mut d);
+        d.field("seen", &self.seen)
+            .field("pending", &self.has_changed())
+            .finish()
+    }
+}
+
+impl<T> std::fmt::Debug for WriteHandle<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt_handle(f, "watch::WriteHandle")
+    }
+}
+
+/// Construct a new, empty write handle and a first read handle, which can be cloned to add more
+/// readers.
+///
+/// Reads fail with [`TryReadError::Empty`] until the first write.
+pub fn new<T>() -> (ReadHandle<'static, T>, WriteHandle<'static, T>) {
+    let inner = Arc::new(Inner::new());
+    let r = ReadHandle {
+        inner: Shared::Arc(Arc::clone(&inner)),
+        seen: 0,
+    };
+    let w = WriteHandle {
+        inner: Shared::Arc(inner),
+    };
+    (r, w)
+}
+
+/// This variant as a [`crate::Channel`], for code generic over the variant.
+pub struct Channel;
+
+impl<T: 'static> crate::Channel<T> for Channel {
+    type Reader = ReadHandle<'static, T>;
+    type Writer = WriteHandle<'static, T>;
+
+    fn create() -> (Self::Reader, Self::Writer) {
+        new()
+    }
+}
@@ -0,0 +1,155 @@
+//! Background threads stepping a state and publishing it after every step, controlled from a
+//! handle.
+//!
+//! ```
+//! use rustedrazors::worker::Worker;
+//! use rustedrazors::Reader;
+//!
+//! let (mut r, worker) = Worker::spawn(0u64, |count| *count += 1);
+//! // the initial state comes first, unless a step already replaced it
+//! while r.read_blocking().is_ok_and(|count| *count == 0) {}
+//!
+//! worker.pause();
+//! worker.resume();
+//!
+//! // stopping closes the channel and returns the last state
+//! let count = worker.stop().unwrap();
+//! assert!(count > 0);
+//! ```
+
+use crate::{atomic_spsc, Writer};
+
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+const RUNNING: u8 = 0;
+const PAUSED: u8 = 1;
+const STOPPED: u8 = 2;
+
+/// Control handle of a thread owning a state `S`, publishing a clone of it after every step.
+///
+/// Dropping the handle stops the thread, as [`Worker::stop`] does.
+pub struct Worker<S> {
+    // one of `RUNNING`, `PAUSED` or `STOPPED`, the thread parks while it is `PAUSED`
+    run: Arc<AtomicU8>,
+    thread: Option<JoinHandle<S>>,
+}
+
+impl<S> Worker<S>
+where
+    S: Clone + Send + 'static,
+{
+    /// Spawns a thread publishing `init`, then calling `step` on the state and publishing the
+    /// result over and over, and returns the reader of the published states.
+    ///
+    /// `step` paces the thread: it is called again as soon as it returns, so it is expected to
+    /// block on whatever it samples or waits for. The thread stops on its own once the reader is
+    /// gone.
+    pub fn spawn<F>(init: S, mut step: F) -> (atomic_spsc::ReadHandle<'static, S>, Self)
+    where
+        F: FnMut(&mut S) + Send + 'static,
+    {
+        let (r, mut w) = atomic_spsc::new();
+        let run = Arc::new(AtomicU8::new(RUNNING));
+        let thread = {
+            let run = Arc::clone(&run);
+            thread::spawn(move || {
+                let mut state = init;
+                w.write(state.clone());
+                loop {
+                    match run.load(Ordering::Acquire) {
+                        RUNNING => {}
+                        PAUSED => {
+                            // `resume` and `stop` unpark the thread after changing `run`
+                            thread::park();
+                            continue;
+                        }
+                        _ => break,
+                    }
+                    if !w.is_reader_alive() {
+                        return state;
+                    }
+                    step(&mut state);
+                    w.write(state.clone());
+                }
+                w.close();
+                state
+            })
+        };
+        let worker = Worker {
+            run,
+            thread: Some(thread),
+        };
+        (r, worker)
+    }
+}
+
+impl<S> Worker<S> {
+    /// Pauses the thread once the current step is done, until [`Worker::resume`].
+    pub fn pause(&self) {
+        let _ = self
+            .run
+            .compare_exchange(RUNNING, PAUSED, Ordering::Release, Ordering::Relaxed);
+    }
+
+    /// Resumes a paused thread.
+    pub fn resume(&self) {
+        if self
+            .run
+            .compare_exchange(PAUSED, RUNNING, Ordering::Release, Ordering::Relaxed)
+            .is_ok()
+        {
+            self.unpark();
+        }
+    }
+
+    /// Returns whether the thread is paused, or about to be once the current step is done.
+    pub fn is_paused(&self) -> bool {
+        self.run.load(Ordering::Relaxed) == PAUSED
+    }
+
+    /// Stops the thread once the current step is done, paused or not, closes the channel and
+    /// returns the last state.
+    /// Fails with the payload of `step` if it panicked.
+    pub fn stop(mut self) -> thread::Result<S> {
+        self.shut_down();
+        self.thread.take().unwrap().join()
+    }
+
+    /// Returns whether the thread has stopped on its own, because the reader is gone or `step`
+    /// panicked.
+    pub fn is_finished(&self) -> bool {
+        self.thread.as_ref().is_none_or(JoinHandle::is_finished)
+    }
+
+    fn shut_down(&self) {
+        self.run.store(STOPPED, Ordering::Release);
+        self.unpark();
+    }
+
+    fn unpark(&self) {
+        if let Some(thread) = &self.thread {
+            thread.thread().unpark();
+        }
+    }
+}
+
+impl<S> Drop for Worker<S> {
+    fn drop(&mut self) {
+        if self.thread.is_some() {
+            self.shut_down();
+            // a panic of `step` was already reported by the thread
+            let _ = self.thread.take().unwrap().join();
+        }
+    }
+}
+
+impl<S> std::fmt::Debug for Worker<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Worker")
+            .field("paused", &self.is_paused())
+            .field("finished", &self.is_finished())
+            .finish()
+    }
+}
@@ -0,0 +1,303 @@
+#[cfg(test)]
+mod tests {
+
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use rustedrazors::adapters::{merge, zip};
+    use rustedrazors::stamped::{self, Stamped};
+    use rustedrazors::{atomic_spsc, mutex_spsc, ticket_spsc, watch};
+    use rustedrazors::{Reader, TryReadError, WriteError, Writer};
+
+    #[derive(Clone, Default)]
+    struct State {
+        position: (i32, i32),
+        label: String,
+    }
+
+    #[test]
+    fn test_map() {
+        // Test a reader exposing a single field of the state, read through the mapping
+
+        let (r, mut w) = atomic_spsc::new::<State>();
+        let mut r = r.map(|state| state.position);
+        assert!(r.read().is_err(), "Read should have failed");
+
+        w.write(State {
+            position: (1, 2),
+            label: String::from("a"),
+        });
+        assert_eq!(r.read().as_deref(), Ok(&(1, 2)));
+        assert!(r.read().is_err(), "Read should have failed");
+
+        w.write_with(|state| state.position = (3, 4));
+        assert_eq!(r.read_with(|&(x, y)| x + y), Ok(7));
+
+        w.write(State::default());
+        let mut position = (0, 1);
+        assert!(r.read_into(&mut position).is_ok());
+        assert_eq!(position, (0, 0));
+
+        w.close();
+        assert_eq!(r.read().err(), Some(TryReadError::Closed));
+    }
+
+    #[test]
+    fn test_map_generic() {
+        // Test that a mapped reader fits wherever a reader of the mapped type does
+
+        fn latest<R: Reader<Item = usize>>(mut r: R) -> Result<usize, TryReadError> {
+            r.read().map(|guard| *guard)
+        }
+
+        let (r, mut w) = mutex_spsc::new(State::default());
+        w.write(State {
+            position: (0, 0),
+            label: String::from("abc"),
+        });
+        let mut r = r.map(|state| state.label.len());
+        assert!(format!("{r:?}").starts_with("MappedReader { inner: mutex_spsc::ReadHandle"));
+        assert_eq!(latest(&mut r), Ok(3));
+
+        let mut r = r.into_inner();
+        assert!(r.read().is_err(), "Read should have failed");
+    }
+
+    #[test]
+    fn test_with_map() {
+        // Test a writer taking values of another type, converted before being written
+
+        let (mut r, w) = atomic_spsc::new::<State>();
+        let mut w = w.with_map(|position| State {
+            position,
+            label: String::from("mapped"),
+        });
+
+        w.write((1, 2));
+        assert_eq!(
+            r.read_with(|state| (state.position, state.label.clone())),
+            Ok(((1, 2), String::from("mapped")))
+        );
+        w.write_with(|position| position.0 = 3);
+        assert_eq!(r.read_with(|state| state.position), Ok((3, 0)));
+        assert_eq!(w.try_write((5, 6)), Ok(()));
+        assert_eq!(r.read_with(|state| state.position), Ok((5, 6)));
+
+        assert!(format!("{w:?}").starts_with("MappedWriter { inner: atomic_spsc::WriteHandle"));
+        drop(r);
+        assert_eq!(w.try_write((7, 8)), Err(WriteError::Disconnected((7, 8))));
+    }
+
+    #[test]
+    fn test_with_filter() {
+        // Test a writer dropping the values its predicate rejects
+
+        let (mut r, w) = ticket_spsc::new(0);
+        let mut w = w.with_filter(|value: &i32| value % 2 == 0);
+
+        w.write(1);
+        assert!(r.read().is_err(), "Odd value should have been dropped");
+        w.write(2);
+        assert_eq!(r.read().as_deref(), Ok(&2));
+
+        assert_eq!(
+            w.try_write(3),
+            Ok(()),
+            "Rejected value should count as written"
+        );
+        assert!(r.read().is_err(), "Odd value should have been dropped");
+        w.write(4);
+        {
+            let _guard = r.read();
+            assert_eq!(
+                w.try_write(6),
+                Err(WriteError::WouldBlock(6)),
+                "Accepted value should have waited on the reader"
+            );
+            assert_eq!(w.try_write(7), Ok(()), "Rejected value should not wait");
+        }
+        w.write_with(|value| *value = 5);
+        w.write_with(|value| *value = 6);
+        assert_eq!(r.read().as_deref(), Ok(&6));
+
+        let mut w = w.with_map(|value: u8| i32::from(value));
+        w.write(8);
+        assert_eq!(r.read().as_deref(), Ok(&8));
+
+        w.close();
+        assert_eq!(r.read().err(), Some(TryReadError::Closed));
+    }
+
+    #[test]
+    fn test_merge() {
+        // Test a reader returning whichever of two channels was written last
+
+        let t0 = Instant::now();
+        let at = |ms| t0 + Duration::from_millis(ms);
+        let (r1, mut w1) = atomic_spsc::new::<Stamped<i32>>();
+        let (r2, mut w2) = watch::new::<Stamped<i32>>();
+        let mut r = merge(r1, r2);
+        assert_eq!(r.read().err(), Some(TryReadError::Empty));
+
+        w1.write((at(1), 1));
+        assert_eq!(r.read().map(|value| value.1), Ok(1));
+        w2.write((at(2), 2));
+        assert_eq!(r.read().map(|value| value.1), Ok(2));
+        assert!(r.read().is_err(), "Read should have failed");
+
+        w1.write((at(4), 4));
+        w2.write((at(3), 3));
+        assert_eq!(
+            r.read().map(|value| value.1),
+            Ok(4),
+            "Newer value should have been returned"
+        );
+        assert!(r.read().is_err(), "Older value should have been dropped");
+        w1.write((at(5), 5));
+        w2.write((at(6), 6));
+        assert_eq!(r.read().map(|value| value.1), Ok(6));
+        assert!(r.read().is_err(), "Older value should have been dropped");
+
+        w1.write((at(5), 7));
+        assert_eq!(
+            r.read().err(),
+            Some(TryReadError::Empty),
+            "Value written before the last one returned should have been skipped"
+        );
+
+        w1.write((at(8), 8));
+        w1.close();
+        assert_eq!(r.read().map(|value| value.1), Ok(8));
+        assert_eq!(
+            r.read().err(),
+            Some(TryReadError::Empty),
+            "The second channel may still publish"
+        );
+        w2.write((at(9), 9));
+        w2.close();
+        assert_eq!(r.read_with(|value| value.1), Ok(9));
+        assert_eq!(r.read().err(), Some(TryReadError::Closed));
+
+        let (mut r1, _r2) = r.into_inner();
+        assert_eq!(r1.read().err(), Some(TryReadError::Closed));
+
+        // stamped channels fill in the stamps
+        let (r1, mut w1) = stamped::new::<i32>();
+        let (r2, mut w2) = stamped::new::<i32>();
+        let mut r = merge(r1, r2);
+        w2.write(1);
+        w1.write(2);
+        assert_eq!(r.read().map(|value| value.1), Ok(2));
+    }
+
+    #[test]
+    fn test_zip() {
+        // Test a reader pairing the new values of two channels once both have one
+
+        let (r1, mut w1) = atomic_spsc::new::<i32>();
+        let (r2, mut w2) = mutex_spsc::new(String::new());
+        let mut r = zip(r1, r2);
+        assert_eq!(r.read().err(), Some(TryReadError::Empty));
+
+        w1.write(1);
+        assert!(r.read().is_err(), "The second channel has no value yet");
+        w1.write(2);
+        assert!(r.read().is_err(), "The second channel has no value yet");
+        w2.write(String::from("a"));
+        assert_eq!(
+            r.read().as_deref(),
+            Ok(&(2, String::from("a"))),
+            "The newest value of each side should have been paired"
+        );
+        assert!(r.read().is_err(), "Both values were paired already");
+
+        w2.write(String::from("b"));
+        assert!(r.read().is_err(), "The first channel has no new value");
+        w1.write(3);
+        assert_eq!(
+            r.read_with(|(number, text)| format!("{number}{text}")),
+            Ok(String::from("3b"))
+        );
+
+        w2.write(String::from("c"));
+        w2.close();
+        assert_eq!(
+            r.read().err(),
+            Some(TryReadError::Empty),
+            "The last value of the second channel is kept for the next pair"
+        );
+        w1.write(4);
+        assert_eq!(r.read().as_deref(), Ok(&(4, String::from("c"))));
+        assert_eq!(r.read().err(), Some(TryReadError::Closed));
+        assert!(format!("{r:?}").starts_with("Zip { first: atomic_spsc::ReadHandle"));
+    }
+
+    #[test]
+    fn test_throttle() {
+        // Test a reader returning at most one value per interval, the newest one
+
+        let (r, mut w) = atomic_spsc::new::<i32>();
+        let mut r = r.throttle(Duration::from_millis(50));
+        w.write(1);
+        assert_eq!(r.read().as_deref(), Ok(&1));
+
+        w.write(2);
+        w.write(3);
+        assert_eq!(
+            r.read().err(),
+            Some(TryReadError::Empty),
+            "Read should have been throttled"
+        );
+        thread::sleep(Duration::from_millis(60));
+        assert_eq!(
+            r.read().as_deref(),
+            Ok(&3),
+            "The values written meanwhile should have been conflated"
+        );
+
+        w.close();
+        thread::sleep(Duration::from_millis(60));
+        assert_eq!(r.read().err(), Some(TryReadError::Closed));
+        assert!(format!("{r:?}").starts_with("Throttled { inner: atomic_spsc::ReadHandle"));
+    }
+
+    #[test]
+    fn test_debounce() {
+        // Test a reader returning a value only once it was not replaced for a while
+
+        let (r, mut w) = atomic_spsc::new::<i32>();
+        let mut r = r.debounce(Duration::from_millis(100));
+        assert_eq!(r.read().err(), Some(TryReadError::Empty));
+
+        w.write(1);
+        assert!(r.read().is_err(), "The value should not have settled yet");
+        thread::sleep(Duration::from_millis(40));
+        w.write(2);
+        assert!(
+            r.read().is_err(),
+            "The newer value should not have settled yet"
+        );
+        thread::sleep(Duration::from_millis(40));
+        assert!(
+            r.read().is_err(),
+            "The newer value should not have settled yet"
+        );
+        thread::sleep(Duration::from_millis(80));
+        assert_eq!(
+            r.read().as_deref(),
+            Ok(&2),
+            "Only the settled value should be returned"
+        );
+        assert!(r.read().is_err(), "Read should have failed");
+
+        w.write(3);
+        assert!(r.read().is_err(), "The value should not have settled yet");
+        w.close();
+        assert_eq!(
+            r.read().as_deref(),
+            Ok(&3),
+            "The last value should be returned once the writer is gone"
+        );
+        assert_eq!(r.read().err(), Some(TryReadError::Closed));
+    }
+}
@@ -0,0 +1,133 @@
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+    use std::thread;
+
+    use rustedrazors::arc_spsc;
+    use rustedrazors::{Reader, TryReadError, Writer};
+
+    #[test]
+    fn test_basics() {
+        // Test basic API
+
+        let (mut r, mut w) = arc_spsc::new::<[u8; 1024]>();
+
+        for _ in 0..5 {
+            let res = r.read();
+            assert!(res.is_err(), "Read should have failed");
+        }
+
+        w.write([42; 1024]);
+
+        let res = r.read();
+        assert_eq!(
+            res.as_deref(),
+            Ok(&[42; 1024]),
+            "Read should have returned the value previously written"
+        );
+
+        for _ in 0..5 {
+            let res = r.read();
+            assert!(res.is_err(), "Read should have failed");
+        }
+    }
+
+    #[test]
+    fn test_write_arc() {
+        // Test that shared values are handed over without cloning them
+
+        struct NotClone(i32);
+
+        let (mut r, mut w) = arc_spsc::new::<NotClone>();
+        let snapshot = Arc::new(NotClone(42));
+
+        w.write_arc(Arc::clone(&snapshot));
+        let value = r.read().unwrap().into_arc();
+        assert!(
+            Arc::ptr_eq(&value, &snapshot),
+            "Reader should have received the same allocation"
+        );
+        assert_eq!(value.0, 42);
+    }
+
+    #[test]
+    fn test_overwritten() {
+        // Test that replaced values are counted and dropped
+
+        let alive = Arc::new(());
+        let (r, mut w) = arc_spsc::new::<Arc<()>>();
+
+        w.write(Arc::clone(&alive));
+        w.write(Arc::clone(&alive));
+        w.write(Arc::clone(&alive));
+        assert_eq!(w.overwritten(), 2);
+        assert_eq!(
+            Arc::strong_count(&alive),
+            2,
+            "Replaced values should have been dropped"
+        );
+
+        drop(r);
+        drop(w);
+        assert_eq!(
+            Arc::strong_count(&alive),
+            1,
+            "Pending value should have been dropped"
+        );
+    }
+
+    #[test]
+    fn test_disconnected() {
+        // Test that readers can tell a dead writer apart from a silent one
+
+        let (mut r, mut w) = arc_spsc::new::<i32>();
+
+        assert_eq!(r.read().err(), Some(TryReadError::Empty));
+        w.write(22);
+        drop(w);
+
+        assert_eq!(
+            r.read().as_deref().ok(),
+            Some(&22),
+            "Pending value should still be readable after the writer is gone"
+        );
+        assert_eq!(r.read().err(), Some(TryReadError::Disconnected));
+    }
+
+    #[test]
+    fn test_threading() {
+        // Test arc_spsc with i32 across threads with multiple iterations.
+        // Values must be read in the order they were written.
+
+        let (mut r, mut w) = arc_spsc::new::<i32>();
+
+        let read_res = thread::spawn(move || {
+            let mut last = -1;
+            loop {
+                match r.read() {
+                    Ok(value) => {
+                        assert!(*value > last, "Values should have been read in order");
+                        last = *value;
+                    }
+                    Err(TryReadError::Empty) => {}
+                    Err(_) => break,
+                }
+            }
+        });
+        let write_res = thread::spawn(move || {
+            for i in 0..10000 {
+                w.write(i);
+            }
+        })
+        .join();
+        assert!(
+            write_res.is_ok(),
+            "Writer thread should have ended peacefully"
+        );
+        assert!(
+            read_res.join().is_ok(),
+            "Reader thread should have ended peacefully"
+        );
+    }
+}
@@ -0,0 +1,144 @@
+#[cfg(test)]
+mod tests {
+
+    use std::thread;
+
+    use rustedrazors::atomic_cell_spsc;
+    use rustedrazors::{Reader, TryReadError, Writer};
+
+    #[test]
+    fn test_basics() {
+        // Test basic API
+
+        let (mut r, mut w) = atomic_cell_spsc::new::<i32>();
+
+        for _ in 0..5 {
+            let res = r.read();
+            assert!(res.is_err(), "Read should have failed");
+        }
+
+        w.write(-22);
+
+        let res = r.read();
+        assert_eq!(
+            res.as_deref(),
+            Ok(&-22),
+            "Read should have returned the value previously written"
+        );
+
+        for _ in 0..5 {
+            let res = r.read();
+            assert!(res.is_err(), "Read should have failed");
+        }
+    }
+
+    #[test]
+    fn test_values() {
+        // Test that every provided payload survives the trip through the atomic word
+
+        let (mut r, mut w) = atomic_cell_spsc::new::<f32>();
+        w.write(-1.5);
+        assert_eq!(r.read().as_deref(), Ok(&-1.5));
+
+        let (mut r, mut w) = atomic_cell_spsc::new::<char>();
+        w.write('ř');
+        assert_eq!(r.read().as_deref(), Ok(&'ř'));
+
+        let (mut r, mut w) = atomic_cell_spsc::new::<u32>();
+        w.write(u32::MAX);
+        assert_eq!(r.read().as_deref(), Ok(&u32::MAX));
+
+        let (mut r, mut w) = atomic_cell_spsc::new::<bool>();
+        w.write(true);
+        assert_eq!(r.read().as_deref(), Ok(&true));
+    }
+
+    #[test]
+    fn test_write_with() {
+        // Test that in place writes start from the last written value
+
+        let (mut r, mut w) = atomic_cell_spsc::new::<i32>();
+
+        w.write_with(|value| *value += 1);
+        assert_eq!(
+            r.read().as_deref(),
+            Ok(&1),
+            "First write should have started from the default value"
+        );
+
+        w.write(41);
+        w.write_with(|value| *value += 1);
+        assert_eq!(r.read().as_deref(), Ok(&42));
+    }
+
+    #[test]
+    fn test_overwritten() {
+        // Test that only values replaced before being read are counted
+
+        let (mut r, mut w) = atomic_cell_spsc::new::<i32>();
+
+        w.write(22);
+        let _ = r.read();
+        assert_eq!(w.overwritten(), 0, "Read values should not be counted");
+
+        w.write(42);
+        w.write(62);
+        w.write(82);
+        assert_eq!(w.overwritten(), 2);
+        assert_eq!(r.overwritten(), 2);
+    }
+
+    #[test]
+    fn test_disconnected() {
+        // Test that readers can tell a dead writer apart from a silent one
+
+        let (mut r, mut w) = atomic_cell_spsc::new::<i32>();
+
+        assert_eq!(r.read().err(), Some(TryReadError::Empty));
+        w.write(22);
+        drop(w);
+
+        assert_eq!(
+            r.read().as_deref().ok(),
+            Some(&22),
+            "Pending value should still be readable after the writer is gone"
+        );
+        assert_eq!(r.read().err(), Some(TryReadError::Disconnected));
+    }
+
+    #[test]
+    fn test_threading() {
+        // Test atomic_cell_spsc with i32 across threads with multiple iterations.
+        // Values must be read in the order they were written.
+
+        let (mut r, mut w) = atomic_cell_spsc::new::<i32>();
+
+        let read_res = thread::spawn(move || {
+            let mut last = -1;
+            loop {
+                match r.read() {
+                    Ok(value) => {
+                        assert!(*value > last, "Values should have been read in order");
+                        last = *value;
+                    }
+                    Err(TryReadError::Empty) => {}
+                    Err(_) => break,
+                }
+            }
+        });
+        let write_res = thread::spawn(move || {
+            for i in 0..10000 {
+                w.write(i);
+            }
+        })
+        .join();
+        assert!(
+            write_res.is_ok(),
+            "Writer thread should have ended peacefully"
+        );
+        assert!(
+            read_res.join().is_ok(),
+            "Reader thread should have ended peacefully"
+        );
+    }
+}
@@ -1,10 +1,12 @@
 #[cfg(test)]
 mod tests {
 
+    use std::sync::Arc;
     use std::thread;
+    use std::time::Duration;
 
     use rustedrazors::blocking_spsc;
-    use rustedrazors::{Reader, Writer};
+    use rustedrazors::{Reader, TryReadError, WriteError, Writer};
 
     #[derive(Clone)]
     struct ClonePayload {
@@ -34,7 +36,7 @@ mod tests {
         // Allow creation and usage across threads of blocking_spsc with either clonable and copayable
         // types
 
-        let (clone_r, clone_w) = blocking_spsc::new::<ClonePayload>(ClonePayload::default());
+        let (mut clone_r, mut clone_w) = blocking_spsc::new::<ClonePayload>();
 
         let _ = thread::spawn(move || {
             let _ = clone_r.read();
@@ -46,7 +48,7 @@ mod tests {
         })
         .join();
 
-        let (copy_r, copy_w) = blocking_spsc::new::<CopyPayload>(CopyPayload::default());
+        let (mut copy_r, mut copy_w) = blocking_spsc::new::<CopyPayload>();
         let _ = thread::spawn(move || {
             let _ = copy_r.read();
         })
@@ -58,15 +60,50 @@ mod tests {
         .join();
     }
 
+    #[test]
+    fn test_uninit() {
+        // Test that types neither Clone nor Default can be sent, and that the pool drops them
+
+        struct Token {
+            _alive: Arc<()>,
+        }
+
+        let alive = Arc::new(());
+        let (mut r, mut w) = blocking_spsc::new::<Token>();
+        assert!(r.read().is_err(), "Read should have failed");
+
+        for _ in 0..5 {
+            w.write(Token {
+                _alive: Arc::clone(&alive),
+            });
+        }
+        assert!(
+            r.read().is_ok(),
+            "Read should have returned the value previously written"
+        );
+        assert!(
+            Arc::strong_count(&alive) <= 4,
+            "Overwritten values should have been dropped"
+        );
+
+        drop(r);
+        drop(w);
+        assert_eq!(
+            Arc::strong_count(&alive),
+            1,
+            "Pooled values should have been dropped along with the channel"
+        );
+    }
+
     #[test]
     fn test_basics() {
         // Test basic API
 
-        let (r, w) = blocking_spsc::new::<i32>(0);
+        let (mut r, mut w) = blocking_spsc::new::<i32>();
 
         for _ in 0..5 {
             let res = r.read();
-            assert!(res.is_none(), "Read should have failed");
+            assert!(res.is_err(), "Read should have failed");
         }
 
         w.write(22);
@@ -75,7 +112,7 @@ mod tests {
             let res = r.read();
             assert_eq!(
                 res.as_deref(),
-                Some(&22),
+                Ok(&22),
                 "Read should have returned the value previously written"
             );
             // drop the guard
@@ -83,7 +120,7 @@ mod tests {
 
         {
             let res = r.read();
-            assert!(res.is_none(), "Read should have failed");
+            assert!(res.is_err(), "Read should have failed");
             // drop the guard
         }
 
@@ -94,19 +131,288 @@ mod tests {
             let res = r.read();
             assert_eq!(
                 res.as_deref(),
-                Some(&62),
+                Ok(&62),
                 "Read should have returned the value previously written"
             );
             // drop the guard
         }
     }
 
+    #[test]
+    fn test_begin_write() {
+        // Test building a value in place and publishing it on commit or drop
+
+        let (mut r, mut w) = blocking_spsc::new::<Vec<i32>>();
+
+        let mut slot = w.begin_write();
+        slot.clear();
+        slot.extend([1, 2, 3]);
+        assert!(r.read().is_err(), "Uncommitted slot should not be readable");
+        slot.commit();
+
+        {
+            let res = r.read();
+            assert_eq!(
+                res.as_deref().map(Vec::as_slice),
+                Ok(&[1, 2, 3][..]),
+                "Read should have returned the committed value"
+            );
+            // drop the guard
+        }
+
+        {
+            let mut slot = w.begin_write();
+            slot.clear();
+            slot.push(4);
+            // drop the guard
+        }
+
+        let res = r.read();
+        assert_eq!(
+            res.as_deref().map(Vec::as_slice),
+            Ok(&[4][..]),
+            "Read should have returned the value published on drop"
+        );
+    }
+
+    #[test]
+    fn test_read_versioned() {
+        // Test that versions count publishes, including the skipped ones
+
+        let (mut r, mut w) = blocking_spsc::new::<i32>();
+        assert_eq!(w.published(), 0);
+
+        w.write(22);
+        {
+            let (version, res) = r.read_versioned().expect("Read should have succeeded");
+            assert_eq!((version, *res), (1, 22));
+            // drop the guard
+        }
+
+        w.write(42);
+        w.write(62);
+        {
+            let (version, res) = r.read_versioned().expect("Read should have succeeded");
+            assert_eq!(
+                (version, *res),
+                (3, 62),
+                "One value should have been skipped"
+            );
+            // drop the guard
+        }
+
+        assert!(r.read_versioned().is_err(), "Read should have failed");
+        assert_eq!(r.published(), 3);
+        assert_eq!(w.published(), 3);
+    }
+
+    #[test]
+    fn test_read_blocking() {
+        // Test that a blocked reader is woken up by the writer
+
+        let (mut r, mut w) = blocking_spsc::new::<i32>();
+
+        let write_res = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            w.write(42);
+        });
+        assert_eq!(
+            *r.read_blocking().unwrap(),
+            42,
+            "Read should have returned the value written by the other thread"
+        );
+        assert!(
+            write_res.join().is_ok(),
+            "Writer thread should have ended peacefully"
+        );
+    }
+
+    #[test]
+    fn test_slow_reader() {
+        // Test that a writer waiting on a reader holding its slot resumes once it is released
+
+        let (mut r, mut w) = blocking_spsc::new::<i32>();
+        w.write(1);
+        let guard = r.read().expect("Read should have succeeded");
+
+        let writer = thread::spawn(move || {
+            for i in 2..=4 {
+                w.write(i);
+            }
+            w
+        });
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(*guard, 1, "Leased value should not have changed");
+        drop(guard);
+
+        let w = writer
+            .join()
+            .expect("Writer thread should have ended peacefully");
+        assert_eq!(r.read().as_deref(), Ok(&4));
+        drop(w);
+    }
+
+    #[test]
+    fn test_read_timeout() {
+        // Test that a timed read gives up without a writer and is woken up by one
+
+        let (mut r, mut w) = blocking_spsc::new::<i32>();
+
+        let res = r.read_timeout(Duration::from_millis(10));
+        assert_eq!(
+            res.err(),
+            Some(TryReadError::Empty),
+            "Read should have timed out"
+        );
+
+        let write_res = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            w.write(42);
+        });
+        {
+            let res = r.read_timeout(Duration::from_secs(10));
+            assert_eq!(
+                res.as_deref().ok(),
+                Some(&42),
+                "Read should have returned the value written by the other thread"
+            );
+            // drop the guard
+        }
+        assert!(
+            write_res.join().is_ok(),
+            "Writer thread should have ended peacefully"
+        );
+    }
+
+    #[test]
+    fn test_read_swap() {
+        // Test exchanging buffers with the channel instead of cloning them
+
+        let (mut r, mut w) = blocking_spsc::new::<Vec<i32>>();
+        let mut scratch = Vec::with_capacity(8);
+
+        assert!(
+            r.read_swap(&mut scratch).is_err(),
+            "Read should have failed"
+        );
+
+        w.write(vec![1, 2]);
+        assert!(
+            r.read_swap(&mut scratch).is_ok(),
+            "Read should have succeeded"
+        );
+        assert_eq!(scratch, [1, 2]);
+        assert!(
+            r.read_swap(&mut scratch).is_err(),
+            "Read should have failed"
+        );
+        assert_eq!(scratch, [1, 2], "Failed read should not touch the buffer");
+    }
+
+    #[test]
+    fn test_update() {
+        // Test that updates always start from the last written value
+
+        let (mut r, mut w) = blocking_spsc::new::<Vec<i32>>();
+
+        w.update(|value| value.push(1));
+        w.update(|value| value.push(2));
+        assert_eq!(
+            r.read().as_deref(),
+            Ok(&vec![1, 2]),
+            "First update should have started from the default value"
+        );
+
+        w.write(vec![3]);
+        w.update(|value| value.push(4));
+        w.update(|value| value.push(5));
+        assert_eq!(r.read().as_deref(), Ok(&vec![3, 4, 5]));
+    }
+
+    #[test]
+    fn test_write_if_changed() {
+        // Test that values equal to the last written one are not published
+
+        let (mut r, mut w) = blocking_spsc::new::<i32>();
+
+        assert!(
+            w.write_if_changed(0),
+            "First value should always be written"
+        );
+        assert_eq!(r.read().as_deref(), Ok(&0));
+
+        assert!(w.write_if_changed(22));
+        assert!(!w.write_if_changed(22));
+        assert_eq!(r.read().as_deref(), Ok(&22));
+
+        assert!(
+            !w.write_if_changed(22),
+            "Read values should still be compared"
+        );
+        assert!(r.read().is_err(), "Read should have failed");
+    }
+
+    #[test]
+    fn test_overwritten() {
+        // Test that only values replaced before being read are counted
+
+        let (mut r, mut w) = blocking_spsc::new::<i32>();
+
+        w.write(22);
+        let _ = r.read();
+        assert_eq!(w.overwritten(), 0, "Read values should not be counted");
+
+        w.write(42);
+        w.write(62);
+        w.write(82);
+        assert_eq!(w.overwritten(), 2);
+        assert_eq!(r.overwritten(), 2);
+    }
+
+    #[test]
+    fn test_disconnected() {
+        // Test that readers can tell a dead writer apart from a silent one
+
+        let (mut r, mut w) = blocking_spsc::new::<i32>();
+
+        assert_eq!(r.read().err(), Some(TryReadError::Empty));
+        w.write(22);
+        drop(w);
+
+        {
+            let res = r.read();
+            assert_eq!(
+                res.as_deref().ok(),
+                Some(&22),
+                "Pending value should still be readable after the writer is gone"
+            );
+            // drop the guard
+        }
+        assert_eq!(r.read().err(), Some(TryReadError::Disconnected));
+        assert_eq!(r.read_blocking().err(), Some(TryReadError::Disconnected));
+
+        let (mut r, w) = blocking_spsc::new::<i32>();
+        let write_res = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            drop(w);
+        });
+        assert_eq!(
+            r.read_blocking().err(),
+            Some(TryReadError::Disconnected),
+            "Blocked reader should have been woken up by the writer going away"
+        );
+        assert!(
+            write_res.join().is_ok(),
+            "Writer thread should have ended peacefully"
+        );
+    }
+
     #[test]
     fn test_threading() {
         // Test blocking_spsc with i32 across threads with multiple iterations.
         // Maybe find a way to enable thread sanitizers?
 
-        let (r, w) = blocking_spsc::new::<i32>(0);
+        let (mut r, mut w) = blocking_spsc::new::<i32>();
 
         let read_res = thread::spawn(move || {
             for _ in 0..1000 {
@@ -129,4 +435,321 @@ mod tests {
             "Writer thread should have ended peacefully"
         );
     }
+
+    #[test]
+    fn test_drop() {
+        // Test that a value replaced before being read is dropped right away, and that every
+        // written value is dropped exactly once
+
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static DROPS: [AtomicUsize; 5] = [const { AtomicUsize::new(0) }; 5];
+
+        struct Token(usize);
+
+        impl Drop for Token {
+            fn drop(&mut self) {
+                DROPS[self.0].fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let dropped = || {
+            DROPS
+                .iter()
+                .map(|d| d.load(Ordering::Relaxed))
+                .collect::<Vec<_>>()
+        };
+        let (mut r, mut w) = blocking_spsc::new();
+
+        w.write(Token(0));
+        w.write(Token(1));
+        assert_eq!(
+            dropped(),
+            [1, 0, 0, 0, 0],
+            "Replaced value should have been dropped"
+        );
+
+        let guard = r.read().unwrap();
+        assert_eq!(guard.0, 1);
+        w.write(Token(2));
+        assert_eq!(
+            dropped(),
+            [1, 0, 0, 0, 0],
+            "Leased value should not have been dropped"
+        );
+        // with a pool of two, the next write waits for the guard to be released
+        drop(guard);
+        w.write(Token(3));
+        assert_eq!(
+            dropped(),
+            [1, 1, 1, 0, 0],
+            "Replaced value and value of the reused slot should have been dropped"
+        );
+        w.write(Token(4));
+        assert_eq!(
+            dropped(),
+            [1, 1, 1, 1, 0],
+            "Replaced value should have been dropped"
+        );
+
+        drop(w);
+        assert_eq!(
+            dropped(),
+            [1, 1, 1, 1, 0],
+            "Unread value should have outlived the writer"
+        );
+        drop(r);
+        assert_eq!(
+            dropped(),
+            [1; 5],
+            "Unread value should have gone with the channel"
+        );
+    }
+
+    #[test]
+    fn test_poisoned() {
+        // Test that a write panicking halfway through poisons the channel instead of leaking its
+        // slot, whether it panics in the closure, in `Clone` or with a write guard alive
+
+        use std::panic::{self, AssertUnwindSafe};
+
+        let (mut r, mut w) = blocking_spsc::new::<Vec<i32>>();
+        w.write(vec![1]);
+        let res = panic::catch_unwind(AssertUnwindSafe(|| {
+            w.write_with(|value| {
+                value.push(2);
+                panic!("write failed");
+            })
+        }));
+        assert!(res.is_err());
+        assert!(
+            w.is_poisoned(),
+            "Writer should have seen the channel poisoned"
+        );
+        assert!(
+            r.is_poisoned(),
+            "Reader should have seen the channel poisoned"
+        );
+        assert_eq!(
+            r.read().as_deref(),
+            Ok(&vec![1]),
+            "Value published before the panic should have been read"
+        );
+        assert_eq!(r.read().err(), Some(TryReadError::Poisoned));
+        let res = panic::catch_unwind(AssertUnwindSafe(|| w.write(vec![3])));
+        assert!(res.is_err(), "Write should have panicked");
+        assert_eq!(
+            w.try_write(vec![3]),
+            Err(WriteError::Poisoned(vec![3])),
+            "Write should have been refused"
+        );
+
+        #[derive(Default)]
+        struct Bomb;
+
+        impl Clone for Bomb {
+            fn clone(&self) -> Self {
+                panic!("clone failed")
+            }
+        }
+
+        let (mut r, mut w) = blocking_spsc::new::<Bomb>();
+        w.write(Bomb);
+        let res = panic::catch_unwind(AssertUnwindSafe(|| w.update(|_| {})));
+        assert!(res.is_err());
+        // would spin forever if the panic left the last value locked
+        assert!(
+            r.read_swap(&mut Bomb).is_ok(),
+            "Value published before the panic should have been read"
+        );
+        assert_eq!(r.read().err(), Some(TryReadError::Poisoned));
+
+        let (mut r, mut w) = blocking_spsc::new::<i32>();
+        let res = panic::catch_unwind(AssertUnwindSafe(|| {
+            let mut guard = w.begin_write();
+            *guard = 1;
+            panic!("write failed");
+        }));
+        assert!(res.is_err());
+        assert_eq!(
+            r.read().err(),
+            Some(TryReadError::Poisoned),
+            "Guard dropped by the panic should not have been published"
+        );
+    }
+
+    #[cfg(feature = "stats")]
+    #[test]
+    fn test_stats() {
+        // Test the counters shared by both handles and the writer waiting for a free slot
+
+        let (mut r, mut w) = blocking_spsc::new();
+        w.write(1);
+        w.write(2);
+        assert_eq!(r.read().as_deref(), Ok(&2));
+        assert!(r.read().is_err());
+        let stats = r.stats();
+        assert_eq!(
+            (
+                stats.reads,
+                stats.read_misses,
+                stats.writes,
+                stats.overwrites
+            ),
+            (1, 1, 2, 1)
+        );
+        assert_eq!(stats, w.stats(), "Both handles should share the counters");
+        assert_eq!(
+            stats.max_writer_stall,
+            Duration::ZERO,
+            "The writer should not have waited"
+        );
+
+        // the writer waits for the reader to release a slot
+        w.write(3);
+        let guard = r.read();
+        w.write(4);
+        let writer = thread::spawn(move || {
+            w.write(5);
+            w
+        });
+        thread::sleep(Duration::from_millis(20));
+        drop(guard);
+        let w = writer.join().unwrap();
+        assert!(
+            w.stats().max_writer_stall >= Duration::from_millis(20),
+            "The writer should have waited for the reader"
+        );
+    }
+
+    #[cfg(feature = "hooks")]
+    #[test]
+    fn test_hooks() {
+        // Test the callbacks installed at construction, called where the counters are bumped
+
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        use rustedrazors::hooks::Hooks;
+
+        // writes, read hits, read misses, overwrites
+        struct Recorder(Arc<[AtomicUsize; 4]>);
+
+        impl Hooks for Recorder {
+            fn on_write(&self) {
+                self.0[0].fetch_add(1, Ordering::Relaxed);
+            }
+            fn on_read_hit(&self) {
+                self.0[1].fetch_add(1, Ordering::Relaxed);
+            }
+            fn on_read_miss(&self) {
+                self.0[2].fetch_add(1, Ordering::Relaxed);
+            }
+            fn on_overwrite(&self) {
+                self.0[3].fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let counts = Arc::new([const { AtomicUsize::new(0) }; 4]);
+        let (mut r, mut w) = blocking_spsc::with_hooks::<i32>(Recorder(Arc::clone(&counts)));
+        w.write(1);
+        w.write(2);
+        assert_eq!(r.read().as_deref(), Ok(&2));
+        assert!(r.read().is_err());
+        assert_eq!(
+            counts.each_ref().map(|count| count.load(Ordering::Relaxed)),
+            [2, 1, 1, 1]
+        );
+
+        drop((r, w));
+        assert_eq!(
+            Arc::strong_count(&counts),
+            1,
+            "Hooks should be dropped with the channel"
+        );
+    }
+
+    #[test]
+    fn test_named() {
+        // Test the label given to a channel, shown by both handles
+
+        let (r, w) = blocking_spsc::named::<i32>("quotes");
+        assert_eq!(r.name(), Some("quotes"));
+        assert_eq!(w.name(), Some("quotes"));
+        assert!(
+            format!("{r:?}").contains("name: \"quotes\""),
+            "The label should show up in Debug"
+        );
+        #[cfg(feature = "stats")]
+        assert_eq!(w.stats().name, Some("quotes"));
+
+        let (r, _w) = blocking_spsc::new::<i32>();
+        assert_eq!(r.name(), None, "Channels are unlabelled by default");
+    }
+
+    #[cfg(feature = "age")]
+    #[test]
+    fn test_age() {
+        // Test the instant of the last publish, kept whether the value was read or not
+
+        let (mut r, mut w) = blocking_spsc::new::<i32>();
+        assert_eq!(r.age(), None, "Nothing was published yet");
+        w.write(1);
+        let first = r.last_update().unwrap();
+        assert!(r.read().is_ok());
+        thread::sleep(Duration::from_millis(10));
+        assert!(
+            r.age().unwrap() >= Duration::from_millis(10),
+            "Reading should not reset the age"
+        );
+        w.write(2);
+        assert!(r.last_update().unwrap() > first);
+    }
+
+    #[test]
+    fn test_try_write() {
+        // Test the writes that fail instead of waiting for the reader to release a slot
+
+        let (mut r, mut w) = blocking_spsc::new();
+        assert_eq!(w.try_write(1), Ok(()));
+        {
+            let guard = r.read().unwrap();
+            assert_eq!(*guard, 1);
+            assert_eq!(w.try_write(2), Ok(()));
+            // both slots are taken, by the lease and by the unread value
+            assert_eq!(
+                w.try_write(3),
+                Err(WriteError::WouldBlock(3)),
+                "No slot should have been free"
+            );
+        }
+        assert_eq!(w.try_write(3), Ok(()));
+        assert_eq!(r.read().as_deref(), Ok(&3));
+
+        drop(r);
+        assert_eq!(w.try_write(4), Err(WriteError::Disconnected(4)));
+    }
+
+    #[test]
+    fn test_raw() {
+        // Test handles turned into raw pointers and back, which must stay alive meanwhile
+
+        let (r, mut w) = blocking_spsc::new::<i32>();
+        let r = r.into_raw();
+        assert!(w.is_reader_alive(), "Reader should still be alive");
+        w.write(1);
+        let mut r = unsafe { blocking_spsc::ReadHandle::<i32>::from_raw(r) };
+        assert_eq!(r.read().as_deref(), Ok(&1));
+
+        let w = w.into_raw();
+        drop(r);
+        let w = unsafe { blocking_spsc::WriteHandle::<i32>::from_raw(w) };
+        assert!(!w.is_reader_alive(), "Reader should have been dropped");
+
+        let storage = blocking_spsc::Storage::new();
+        let (mut r, w) = storage.split().unwrap();
+        let mut w = unsafe { blocking_spsc::WriteHandle::<i32>::from_raw(w.into_raw()) };
+        w.write(2);
+        assert_eq!(r.read().as_deref(), Ok(&2));
+    }
 }
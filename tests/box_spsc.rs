@@ -0,0 +1,153 @@
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+    use std::thread;
+
+    use rustedrazors::box_spsc;
+    use rustedrazors::{Reader, TryReadError, Writer};
+
+    #[test]
+    fn test_basics() {
+        // Test basic API
+
+        let (mut r, mut w) = box_spsc::new::<i32>();
+
+        for _ in 0..5 {
+            let res = r.read();
+            assert!(res.is_err(), "Read should have failed");
+        }
+
+        w.write(22);
+
+        let res = r.read();
+        assert_eq!(
+            res.as_deref(),
+            Ok(&22),
+            "Read should have returned the value previously written"
+        );
+
+        for _ in 0..5 {
+            let res = r.read();
+            assert!(res.is_err(), "Read should have failed");
+        }
+    }
+
+    #[test]
+    fn test_ownership() {
+        // Test that the very same box moves through the channel
+
+        struct NotClone(i32);
+
+        let (mut r, mut w) = box_spsc::new::<NotClone>();
+        let value = Box::new(NotClone(42));
+        let addr: *const NotClone = &*value;
+
+        assert!(w.write_box(value).is_none());
+        let value = r.read().unwrap().into_box();
+        assert!(
+            std::ptr::eq(&*value, addr),
+            "Reader should have received the same box"
+        );
+        assert_eq!(value.0, 42);
+    }
+
+    #[test]
+    fn test_displaced() {
+        // Test that unread boxes are handed back to the writer
+
+        let (mut r, mut w) = box_spsc::new::<i32>();
+
+        assert!(w.write_box(Box::new(22)).is_none());
+        assert_eq!(
+            w.write_box(Box::new(42)),
+            Some(Box::new(22)),
+            "Unread box should have been handed back"
+        );
+        assert_eq!(w.overwritten(), 1);
+
+        assert_eq!(r.read_box(), Ok(Box::new(42)));
+        assert!(
+            w.write_box(Box::new(62)).is_none(),
+            "Read box should not be handed back"
+        );
+    }
+
+    #[test]
+    fn test_drop() {
+        // Test that replaced and pending values are dropped
+
+        let alive = Arc::new(());
+        let (r, mut w) = box_spsc::new::<Arc<()>>();
+
+        w.write(Arc::clone(&alive));
+        w.write(Arc::clone(&alive));
+        assert_eq!(
+            Arc::strong_count(&alive),
+            2,
+            "Replaced value should have been dropped"
+        );
+
+        drop(r);
+        drop(w);
+        assert_eq!(
+            Arc::strong_count(&alive),
+            1,
+            "Pending value should have been dropped"
+        );
+    }
+
+    #[test]
+    fn test_disconnected() {
+        // Test that readers can tell a dead writer apart from a silent one
+
+        let (mut r, mut w) = box_spsc::new::<i32>();
+
+        assert_eq!(r.read().err(), Some(TryReadError::Empty));
+        w.write(22);
+        drop(w);
+
+        assert_eq!(
+            r.read().as_deref().ok(),
+            Some(&22),
+            "Pending value should still be readable after the writer is gone"
+        );
+        assert_eq!(r.read().err(), Some(TryReadError::Disconnected));
+    }
+
+    #[test]
+    fn test_threading() {
+        // Test box_spsc with i32 across threads with multiple iterations.
+        // Values must be read in the order they were written.
+
+        let (mut r, mut w) = box_spsc::new::<i32>();
+
+        let read_res = thread::spawn(move || {
+            let mut last = -1;
+            loop {
+                match r.read() {
+                    Ok(value) => {
+                        assert!(*value > last, "Values should have been read in order");
+                        last = *value;
+                    }
+                    Err(TryReadError::Empty) => {}
+                    Err(_) => break,
+                }
+            }
+        });
+        let write_res = thread::spawn(move || {
+            for i in 0..10000 {
+                w.write(i);
+            }
+        })
+        .join();
+        assert!(
+            write_res.is_ok(),
+            "Writer thread should have ended peacefully"
+        );
+        assert!(
+            read_res.join().is_ok(),
+            "Reader thread should have ended peacefully"
+        );
+    }
+}
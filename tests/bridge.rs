@@ -0,0 +1,170 @@
+#[cfg(test)]
+mod tests {
+
+    use std::sync::mpsc;
+    use std::time::{Duration, Instant};
+
+    use rustedrazors::bridge::{self, Conflation};
+    use rustedrazors::{atomic_spsc, mutex_spsc, Reader, TryReadError, Writer};
+
+    #[test]
+    fn test_from_receiver() {
+        // Test messages of a queue reaching a reader, then the senders dropped closing it
+
+        let (tx, rx) = mpsc::channel();
+        let (mut r, w) = atomic_spsc::new::<u32>();
+        let bridge = bridge::from_receiver(rx, w, Conflation::default());
+
+        tx.send(1).unwrap();
+        assert_eq!(r.read_timeout(Duration::from_secs(5)).as_deref(), Ok(&1));
+        tx.send(2).unwrap();
+        tx.send(3).unwrap();
+        assert!(format!("{bridge:?}").starts_with("Bridge"));
+
+        drop(tx);
+        bridge.join().unwrap();
+        assert_eq!(
+            r.read().as_deref(),
+            Ok(&3),
+            "The newest message should have been written last"
+        );
+        assert_eq!(r.read().err(), Some(TryReadError::Closed));
+    }
+
+    #[test]
+    fn test_conflation() {
+        // Test queued messages written one by one or only the newest of them
+
+        for (conflation, writes) in [(Conflation::Every, 4), (Conflation::Latest, 1)] {
+            let (tx, rx) = mpsc::channel();
+            for value in 1..=4 {
+                tx.send(value).unwrap();
+            }
+            drop(tx);
+
+            let (mut r, w) = atomic_spsc::new::<u32>();
+            bridge::from_receiver(rx, w, conflation).join().unwrap();
+            assert_eq!(r.read().as_deref(), Ok(&4));
+            assert_eq!(
+                r.overwritten(),
+                writes - 1,
+                "{conflation:?} should have written {writes} values"
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_receiver_reader_dropped() {
+        // Test that a bridge stops at the next message once the reader is gone
+
+        let (tx, rx) = mpsc::channel();
+        let (r, w) = atomic_spsc::new::<u32>();
+        let bridge = bridge::from_receiver(rx, w, Conflation::Every);
+        drop(r);
+
+        tx.send(1).unwrap();
+        bridge.join().unwrap();
+        assert!(tx.send(2).is_err(), "The receiver should have been dropped");
+    }
+
+    #[test]
+    fn test_to_sender() {
+        // Test values read from a channel sent on a queue, then the writer closing it
+
+        let (r, mut w) = mutex_spsc::new(String::new());
+        let (tx, rx) = mpsc::channel();
+        let bridge = bridge::to_sender(r, tx);
+
+        w.write(String::from("a"));
+        assert_eq!(
+            rx.recv_timeout(Duration::from_secs(5)),
+            Ok(String::from("a"))
+        );
+        w.write(String::from("b"));
+        assert_eq!(
+            rx.recv_timeout(Duration::from_secs(5)),
+            Ok(String::from("b"))
+        );
+
+        w.close();
+        assert!(rx.recv().is_err(), "The sender should have been dropped");
+        bridge.join().unwrap();
+    }
+
+    #[test]
+    fn test_to_sync_sender() {
+        // Test a bounded queue blocking the bridge while the reader conflates the values
+
+        let (r, mut w) = atomic_spsc::new::<u32>();
+        let (tx, rx) = mpsc::sync_channel(0);
+        let bridge = bridge::to_sync_sender(r, tx);
+
+        w.write(1);
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)), Ok(1));
+        for value in 2..=100 {
+            w.write(value);
+        }
+        let mut last = 1;
+        let start = Instant::now();
+        while last != 100 {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            let value = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+            assert!(value > last, "Values should be sent in order");
+            last = value;
+        }
+
+        drop(rx);
+        w.write(101);
+        bridge.join().unwrap();
+        assert!(!w.is_reader_alive(), "The bridge should have stopped");
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_to_watch() {
+        // Test values of a real-time thread observed by async code, then the writer dropping the
+        // sender
+
+        let (r, mut w) = atomic_spsc::new::<u32>();
+        let (tx, mut rx) = tokio::sync::watch::channel(0);
+        let bridge = bridge::to_watch(r, tx);
+
+        let writer = std::thread::spawn(move || {
+            for value in 1..=100 {
+                w.write(value);
+                std::thread::sleep(Duration::from_micros(100));
+            }
+        });
+        let mut last = 0;
+        while rx.changed().await.is_ok() {
+            let value = *rx.borrow_and_update();
+            assert!(value > last, "Values should be published in order");
+            last = value;
+        }
+        assert_eq!(last, 100, "The last value should have been published");
+        writer.join().unwrap();
+        bridge.join().unwrap();
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_from_watch() {
+        // Test the current and later values of a watch mirrored into a channel, then the sender
+        // dropped closing it
+
+        let (tx, rx) = tokio::sync::watch::channel(String::from("initial"));
+        let (mut r, w) = mutex_spsc::new(String::new());
+        let mirror = tokio::spawn(bridge::from_watch(rx, w));
+
+        // the test runs on a single thread, yielding lets the mirror catch up
+        tokio::task::yield_now().await;
+        assert_eq!(r.read_with(String::clone), Ok(String::from("initial")));
+        tx.send(String::from("next")).unwrap();
+        tokio::task::yield_now().await;
+        assert_eq!(r.read_with(String::clone), Ok(String::from("next")));
+
+        drop(tx);
+        mirror.await.unwrap();
+        assert_eq!(r.read().err(), Some(TryReadError::Closed));
+    }
+}
@@ -0,0 +1,175 @@
+#[cfg(test)]
+mod tests {
+
+    use std::io::{ErrorKind, Read, Write};
+    use std::thread;
+
+    use rustedrazors::byte_spsc;
+
+    #[test]
+    fn test_basics() {
+        // Test basic API
+
+        let (mut r, mut w) = byte_spsc::new(8);
+        let mut buf = [0; 8];
+
+        assert_eq!(r.try_read(&mut buf), 0, "Read should have failed");
+
+        w.write_all(b"hello").unwrap();
+        assert_eq!(r.len(), 5);
+        assert_eq!(r.read(&mut buf).unwrap(), 5);
+        assert_eq!(&buf[..5], b"hello");
+        assert!(r.is_empty());
+    }
+
+    #[test]
+    fn test_wrap_around() {
+        // Test that bytes crossing the end of the buffer come out in order
+
+        let (mut r, mut w) = byte_spsc::new(4);
+        let mut buf = [0; 4];
+
+        assert_eq!(w.try_write(b"abc"), 3);
+        assert_eq!(r.try_read(&mut buf[..2]), 2);
+        assert_eq!(&buf[..2], b"ab");
+
+        assert_eq!(
+            w.try_write(b"defgh"),
+            3,
+            "Only the free bytes should be written"
+        );
+        assert_eq!(r.try_read(&mut buf), 4);
+        assert_eq!(&buf, b"cdef");
+    }
+
+    #[test]
+    fn test_eof() {
+        // Test that pending bytes are read before reporting the end of the stream
+
+        let (mut r, mut w) = byte_spsc::new(16);
+        w.write_all(b"bye").unwrap();
+        drop(w);
+
+        let mut s = String::new();
+        assert_eq!(r.read_to_string(&mut s).unwrap(), 3);
+        assert_eq!(s, "bye");
+        assert_eq!(r.read(&mut [0; 4]).unwrap(), 0, "Stream should have ended");
+    }
+
+    #[test]
+    fn test_broken_pipe() {
+        // Test that a writer blocked on a full pipe fails once the reader is gone
+
+        let (r, mut w) = byte_spsc::new(2);
+        w.write_all(b"ab").unwrap();
+
+        let write_res = thread::spawn(move || w.write_all(b"cd"));
+        drop(r);
+        assert_eq!(
+            write_res.join().unwrap().unwrap_err().kind(),
+            ErrorKind::BrokenPipe
+        );
+    }
+
+    #[test]
+    fn test_threading() {
+        // Test byte_spsc across threads with a stream larger than the buffer.
+        // Every byte must be read, in the order it was written.
+
+        let (mut r, mut w) = byte_spsc::new(64);
+        let data: Vec<u8> = (0..100_000).map(|i| (i % 251) as u8).collect();
+        let expected = data.clone();
+
+        let read_res = thread::spawn(move || {
+            let mut out = Vec::new();
+            r.read_to_end(&mut out).map(|_| out)
+        });
+        let write_res = thread::spawn(move || {
+            for chunk in data.chunks(37) {
+                w.write_all(chunk).unwrap();
+            }
+        })
+        .join();
+        assert!(
+            write_res.is_ok(),
+            "Writer thread should have ended peacefully"
+        );
+        assert!(
+            read_res.join().unwrap().unwrap() == expected,
+            "Every byte should have been read in order"
+        );
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_async() {
+        // Test the async adapters on a single thread, with a stream larger than the buffer.
+        // Both tasks must be woken up by the other one instead of spinning.
+
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let (r, w) = byte_spsc::new(16);
+        let (mut r, mut w) = (r.into_async(), w.into_async());
+        let data: Vec<u8> = (0..10_000).map(|i| (i % 251) as u8).collect();
+        let expected = data.clone();
+
+        let writer = tokio::spawn(async move {
+            for chunk in data.chunks(37) {
+                w.write_all(chunk).await.unwrap();
+            }
+            w.shutdown().await.unwrap();
+            assert_eq!(
+                w.write(b"late").await.unwrap_err().kind(),
+                ErrorKind::BrokenPipe,
+                "Writes should fail after a shutdown"
+            );
+        });
+        let mut out = Vec::new();
+        r.read_to_end(&mut out).await.unwrap();
+        writer.await.unwrap();
+        assert!(out == expected, "Every byte should have been read in order");
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_async_broken_pipe() {
+        // Test that a task blocked on a full pipe is woken up once the reader is gone
+
+        use tokio::io::AsyncWriteExt;
+
+        let (r, w) = byte_spsc::new(2);
+        let mut w = w.into_async();
+        w.write_all(b"ab").await.unwrap();
+
+        let writer = tokio::spawn(async move { w.write_all(b"cd").await });
+        tokio::task::yield_now().await;
+        drop(r);
+        assert_eq!(
+            writer.await.unwrap().unwrap_err().kind(),
+            ErrorKind::BrokenPipe
+        );
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn test_async_sync_mix() {
+        // Test that an async reader is woken up by a writer thread
+
+        use tokio::io::AsyncReadExt;
+
+        let (r, mut w) = byte_spsc::new(8);
+        let mut r = r.into_async();
+        let writer = thread::spawn(move || {
+            for _ in 0..1000 {
+                w.write_all(b"0123456789").unwrap();
+            }
+        });
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let mut out = Vec::new();
+        runtime.block_on(r.read_to_end(&mut out)).unwrap();
+        writer.join().unwrap();
+        assert_eq!(out.len(), 10_000, "Every byte should have been read");
+    }
+}
@@ -0,0 +1,50 @@
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+
+    use std::thread;
+    use std::time::Duration;
+
+    use tokio::time::timeout;
+
+    use rustedrazors::{atomic_spsc, blocking_spsc, ticket_spsc};
+    use rustedrazors::{Reader, TryReadError, Writer};
+
+    macro_rules! check_changed {
+        ($module:ident $(, $init:expr)?) => {{
+            let (mut r, mut w) = $module::new($($init)?);
+
+            assert!(
+                timeout(Duration::from_millis(10), r.changed()).await.is_err(),
+                "Nothing should have been written"
+            );
+
+            w.write(1);
+            assert_eq!(r.changed().await, Ok(()));
+            assert_eq!(
+                r.changed().await,
+                Ok(()),
+                "Value should have been left for the next read"
+            );
+            assert_eq!(r.read().as_deref(), Ok(&1));
+
+            let writer = thread::spawn(move || {
+                thread::sleep(Duration::from_millis(10));
+                w.write(2);
+                w.close();
+            });
+            assert_eq!(r.changed().await, Ok(()), "Task should have been woken up");
+            writer.join().unwrap();
+            assert_eq!(r.read().as_deref(), Ok(&2));
+            assert_eq!(r.changed().await, Err(TryReadError::Closed));
+        }};
+    }
+
+    #[tokio::test]
+    async fn test_changed() {
+        // Test that changed() waits for a new value and reports the writer leaving
+
+        check_changed!(atomic_spsc);
+        check_changed!(blocking_spsc);
+        check_changed!(ticket_spsc, 0);
+    }
+}
@@ -0,0 +1,114 @@
+#[cfg(test)]
+mod tests {
+
+    use std::thread;
+    use std::time::Duration;
+
+    use rustedrazors::channel::{BuildError, Channel, Kind};
+    use rustedrazors::{Reader, TryReadError, Writer};
+
+    const KINDS: &[Kind] = &[
+        Kind::Arc,
+        Kind::Atomic,
+        Kind::Blocking,
+        Kind::Box,
+        Kind::Disruptor,
+        #[cfg(any(target_os = "linux", windows))]
+        Kind::Futex,
+        Kind::Mutex,
+        Kind::Ring,
+        Kind::Ticket,
+        Kind::Watch,
+    ];
+
+    #[test]
+    fn test_basics() {
+        // Test a roundtrip through every kind, dispatched to the handles of its module
+
+        for &kind in KINDS {
+            let mut builder = Channel::builder().kind(kind);
+            if kind.has_pool_size() {
+                builder = builder.pool_size(4);
+            }
+            let (mut r, mut w) = builder.build::<i32>().unwrap();
+            assert_eq!(r.kind(), kind);
+            assert_eq!(w.kind(), kind);
+            assert!(r.read().is_err(), "{kind:?}: Read should have failed");
+
+            w.write(22);
+            assert_eq!(r.read().as_deref(), Ok(&22), "{kind:?}");
+            w.write_with(|value| *value = 42);
+            assert_eq!(r.read_with(|value| *value), Ok(42), "{kind:?}");
+
+            assert!(format!("{w:?}").contains("WriteHandle"), "{kind:?}");
+            w.close();
+            assert_eq!(
+                r.read().err(),
+                Some(TryReadError::Closed),
+                "{kind:?}: Read should have seen the channel closed"
+            );
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        // Test the options refused by the kinds that do not support them
+
+        assert_eq!(
+            Channel::builder().kind(Kind::Ring).build::<i32>().err(),
+            Some(BuildError::PoolSize(Kind::Ring)),
+            "Ring needs a pool size"
+        );
+        assert_eq!(
+            Channel::builder()
+                .kind(Kind::Ring)
+                .pool_size(0)
+                .build::<i32>()
+                .err(),
+            Some(BuildError::PoolSize(Kind::Ring)),
+            "The pool should not be empty"
+        );
+        assert_eq!(
+            Channel::builder().pool_size(4).build::<i32>().err(),
+            Some(BuildError::PoolSize(Kind::Atomic)),
+            "Atomic has a fixed pool"
+        );
+        assert_eq!(
+            Channel::builder()
+                .kind(Kind::Watch)
+                .blocking_reads(true)
+                .build::<i32>()
+                .err(),
+            Some(BuildError::BlockingReads(Kind::Watch)),
+            "Watch has no blocking reads"
+        );
+    }
+
+    #[test]
+    fn test_blocking_reads() {
+        // Test the blocking reads of the kinds that have them
+
+        for kind in KINDS
+            .iter()
+            .copied()
+            .filter(|kind| kind.has_blocking_reads())
+        {
+            let (mut r, mut w) = Channel::builder()
+                .kind(kind)
+                .blocking_reads(true)
+                .build::<i32>()
+                .unwrap();
+            assert_eq!(
+                r.read_timeout(Duration::from_millis(1)).err(),
+                Some(TryReadError::Empty),
+                "{kind:?}: Read should have timed out"
+            );
+            let writer = thread::spawn(move || {
+                thread::sleep(Duration::from_millis(10));
+                w.write(1);
+            });
+            assert_eq!(r.read_blocking().as_deref().ok(), Some(&1), "{kind:?}");
+            writer.join().unwrap();
+        }
+    }
+}
@@ -0,0 +1,136 @@
+#[cfg(test)]
+mod tests {
+
+    use std::collections::HashMap;
+    use std::thread;
+
+    use rustedrazors::conflating_map;
+    use rustedrazors::TryReadError;
+
+    #[test]
+    fn test_basics() {
+        // Test basic API
+
+        let (mut r, w) = conflating_map::new::<&str, i32>();
+
+        assert_eq!(r.drain().count(), 0, "Nothing should have been published");
+        assert!(!r.has_pending());
+
+        w.publish("a", 1);
+        w.publish("b", 2);
+        assert!(r.has_pending());
+        let mut drained: Vec<_> = r.drain().collect();
+        drained.sort();
+        assert_eq!(drained, [("a", 1), ("b", 2)]);
+
+        assert!(!r.has_pending());
+        assert_eq!(
+            r.drain().count(),
+            0,
+            "Every update should have been drained"
+        );
+    }
+
+    #[test]
+    fn test_conflation() {
+        // Test that only the latest value of every key is drained
+
+        let (mut r, w) = conflating_map::new::<&str, i32>();
+
+        w.publish("a", 1);
+        w.publish("b", 2);
+        w.publish("a", 3);
+        w.publish_all([("b", 4), ("c", 5), ("b", 6)]);
+        assert_eq!(w.overwritten(), 3);
+
+        let drained: HashMap<_, _> = r.drain().collect();
+        assert_eq!(drained, HashMap::from([("a", 3), ("b", 6), ("c", 5)]));
+
+        w.publish("a", 7);
+        assert_eq!(
+            r.overwritten(),
+            3,
+            "Drained values should not count as overwritten"
+        );
+        assert_eq!(r.drain().collect::<Vec<_>>(), [("a", 7)]);
+    }
+
+    #[test]
+    fn test_partial_drain() {
+        // Test that values left in a dropped drain are discarded, not drained again
+
+        let (mut r, w) = conflating_map::new::<i32, i32>();
+        w.publish_all((0..10).map(|i| (i, i)));
+
+        assert!(r.drain().next().is_some());
+        assert_eq!(r.drain().count(), 0);
+    }
+
+    #[test]
+    fn test_disconnected() {
+        // Test that pending updates are drained before reporting the disconnection
+
+        let (mut r, w) = conflating_map::new::<i32, i32>();
+        assert!(matches!(r.try_drain(), Err(TryReadError::Empty)));
+
+        w.publish(1, 1);
+        drop(w);
+        assert_eq!(r.try_drain().unwrap().collect::<Vec<_>>(), [(1, 1)]);
+        assert!(matches!(r.try_drain(), Err(TryReadError::Disconnected)));
+
+        let (mut r, w) = conflating_map::new::<i32, i32>();
+        w.close();
+        assert!(matches!(r.try_drain(), Err(TryReadError::Closed)));
+
+        let (r, w) = conflating_map::new::<i32, i32>();
+        assert!(w.is_reader_alive());
+        drop(r);
+        assert!(!w.is_reader_alive(), "Reader should have been dropped");
+    }
+
+    #[test]
+    fn test_threading() {
+        // Test conflating_map across threads.
+        // Values of every key must be drained in increasing order, ending with the last one.
+
+        const KEYS: u64 = 8;
+        const UPDATES: u64 = 100_000;
+
+        let (mut r, w) = conflating_map::new::<u64, u64>();
+
+        let read_res = thread::spawn(move || {
+            let mut latest = HashMap::new();
+            loop {
+                match r.try_drain() {
+                    Ok(drain) => {
+                        for (key, value) in drain {
+                            if let Some(&prev) = latest.get(&key) {
+                                assert!(value > prev, "Values should only increase");
+                            }
+                            latest.insert(key, value);
+                        }
+                    }
+                    Err(TryReadError::Empty) => thread::yield_now(),
+                    Err(_) => return latest,
+                }
+            }
+        });
+        let write_res = thread::spawn(move || {
+            for i in 0..UPDATES {
+                w.publish(i % KEYS, i);
+            }
+            w.close();
+        })
+        .join();
+        assert!(
+            write_res.is_ok(),
+            "Writer thread should have ended peacefully"
+        );
+        let latest = read_res.join().unwrap();
+        assert_eq!(
+            latest,
+            (0..KEYS).map(|k| (k, UPDATES - KEYS + k)).collect(),
+            "Last value of every key should have been drained"
+        );
+    }
+}
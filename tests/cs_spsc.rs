@@ -0,0 +1,91 @@
+#[cfg(all(test, feature = "critical-section"))]
+mod tests {
+
+    use std::thread;
+
+    use rustedrazors::cs_spsc::{self, Storage};
+    use rustedrazors::{Reader, TryReadError, Writer};
+
+    #[test]
+    fn test_basics() {
+        // Test basic API
+
+        let (mut r, mut w) = cs_spsc::new::<i32>();
+
+        assert!(r.read().is_err(), "Read should have failed");
+
+        w.write(22);
+        assert_eq!(r.read().as_deref(), Ok(&22));
+        assert!(r.read().is_err(), "Read should have failed");
+
+        w.write(1);
+        w.write(2);
+        assert_eq!(r.read().map(|v| v.into_inner()), Ok(2));
+        assert_eq!(r.overwritten(), 1);
+        assert_eq!(w.overwritten(), 1);
+    }
+
+    #[test]
+    fn test_write_with() {
+        // Test that write_with starts from the unread value, or the default one
+
+        let (mut r, mut w) = cs_spsc::new::<Vec<i32>>();
+
+        w.write_with(|v| v.push(1));
+        w.write_with(|v| v.push(2));
+        assert_eq!(r.read().as_deref(), Ok(&vec![1, 2]));
+        assert_eq!(r.overwritten(), 1);
+
+        w.write_with(|v| v.push(3));
+        assert_eq!(r.read().as_deref(), Ok(&vec![3]));
+    }
+
+    #[test]
+    fn test_disconnected() {
+        // Test that readers can tell a dead writer apart from a silent one
+
+        let (mut r, mut w) = cs_spsc::new::<i32>();
+        assert_eq!(r.read().err(), Some(TryReadError::Empty));
+        w.write(42);
+        drop(w);
+        assert_eq!(r.read().as_deref(), Ok(&42));
+        assert_eq!(r.read().err(), Some(TryReadError::Disconnected));
+
+        let (mut r, w) = cs_spsc::new::<i32>();
+        w.close();
+        assert_eq!(r.read().err(), Some(TryReadError::Closed));
+
+        let (r, w) = cs_spsc::new::<i32>();
+        assert!(w.is_reader_alive());
+        drop(r);
+        assert!(!w.is_reader_alive());
+    }
+
+    #[test]
+    fn test_storage() {
+        // Test handles borrowing a static storage
+
+        static STORAGE: Storage<u32> = Storage::new();
+
+        let (mut r, mut w) = STORAGE.split().unwrap();
+        assert!(STORAGE.split().is_none(), "Handles should have been taken");
+
+        thread::spawn(move || {
+            for i in 1..=1_000 {
+                w.write(i);
+            }
+        });
+        let mut last = 0;
+        loop {
+            match r.read() {
+                Ok(value) => {
+                    assert!(*value > last, "Values should have been read in order");
+                    last = *value;
+                }
+                Err(TryReadError::Empty) => thread::yield_now(),
+                Err(_) => break,
+            }
+        }
+        assert_eq!(last, 1_000);
+    }
+}
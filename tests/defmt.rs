@@ -0,0 +1,29 @@
+#[cfg(all(test, feature = "defmt"))]
+mod tests {
+
+    use rustedrazors::{atomic_spsc, isr_spsc, TryReadError};
+
+    fn assert_format<T: defmt::Format>() {}
+
+    #[test]
+    fn test_format() {
+        // Test that the types logged by embedded users implement `defmt::Format`
+
+        assert_format::<TryReadError>();
+        assert_format::<atomic_spsc::ReadHandle<'static, u32>>();
+        assert_format::<atomic_spsc::WriteHandle<'static, u32>>();
+        assert_format::<isr_spsc::ReadHandle<u32>>();
+        assert_format::<isr_spsc::WriteHandle<u32>>();
+    }
+
+    #[cfg(feature = "critical-section")]
+    #[test]
+    fn test_format_cs() {
+        // Test that the `cs_spsc` handles implement `defmt::Format` as well
+
+        use rustedrazors::cs_spsc;
+
+        assert_format::<cs_spsc::ReadHandle<'static, u32>>();
+        assert_format::<cs_spsc::WriteHandle<'static, u32>>();
+    }
+}
@@ -0,0 +1,178 @@
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+    use std::thread;
+
+    use rustedrazors::disruptor;
+    use rustedrazors::{Reader, TryReadError, WriteError, Writer};
+
+    #[test]
+    fn test_basics() {
+        // Test basic API
+
+        let (mut r, mut w) = disruptor::new::<i32>(4);
+
+        for _ in 0..5 {
+            let res = r.read();
+            assert!(res.is_err(), "Read should have failed");
+        }
+
+        w.write(22);
+        w.write(42);
+        assert_eq!(r.pending(), 2);
+
+        {
+            let res = r.read();
+            assert_eq!(
+                res.as_deref(),
+                Ok(&22),
+                "Read should have returned the oldest value"
+            );
+        }
+        assert_eq!(r.read().map(|guard| guard.sequence()), Ok(1));
+
+        for _ in 0..5 {
+            let res = r.read();
+            assert!(res.is_err(), "Read should have failed");
+        }
+    }
+
+    #[test]
+    fn test_readers() {
+        // Test that every reader reads every value, and that the slowest one holds the writer back
+
+        let (mut r1, mut w) = disruptor::new::<i32>(2);
+        let mut r2 = r1.clone();
+        assert_eq!(w.reader_count(), 2);
+
+        assert!(w.try_write(0).is_ok());
+        assert!(w.try_write(1).is_ok());
+        assert_eq!(
+            w.try_write(2),
+            Err(WriteError::WouldBlock(2)),
+            "Ring should have been full"
+        );
+
+        assert_eq!(r1.read().as_deref(), Ok(&0));
+        assert_eq!(r1.read().as_deref(), Ok(&1));
+        assert_eq!(
+            w.try_write(2),
+            Err(WriteError::WouldBlock(2)),
+            "Slowest reader should have held the writer back"
+        );
+
+        assert_eq!(r2.read().as_deref(), Ok(&0));
+        assert!(w.try_write(2).is_ok());
+
+        let mut r3 = r2.clone();
+        for r in [&mut r2, &mut r3] {
+            assert_eq!(r.read().as_deref(), Ok(&1));
+            assert_eq!(r.read().as_deref(), Ok(&2));
+        }
+        assert_eq!(r1.read().as_deref(), Ok(&2));
+
+        drop(r1);
+        drop(r2);
+        drop(r3);
+        assert!(!w.is_reader_alive());
+        assert_eq!(
+            w.try_write(0),
+            Err(WriteError::Disconnected(0)),
+            "Nobody is left to read the value"
+        );
+        // the writer should not be held back without readers
+        for i in 0..10 {
+            w.write(i);
+        }
+    }
+
+    #[test]
+    fn test_drop() {
+        // Test that values left in the ring are dropped with it
+
+        let alive = Arc::new(());
+        let (mut r, mut w) = disruptor::new::<Arc<()>>(4);
+        for _ in 0..3 {
+            w.write(Arc::clone(&alive));
+        }
+        drop(r.read());
+        drop(r);
+        for _ in 0..3 {
+            w.write(Arc::clone(&alive));
+        }
+        assert_eq!(
+            Arc::strong_count(&alive),
+            5,
+            "Overwritten values should have been dropped"
+        );
+        drop(w);
+        assert_eq!(
+            Arc::strong_count(&alive),
+            1,
+            "Every value should have been dropped"
+        );
+    }
+
+    #[test]
+    fn test_disconnected() {
+        // Test that every pending value is read before reporting the dead writer
+
+        let (mut r, mut w) = disruptor::new::<i32>(4);
+
+        assert_eq!(r.read().err(), Some(TryReadError::Empty));
+        w.write(22);
+        w.write(42);
+        drop(w);
+
+        assert_eq!(r.read().as_deref().ok(), Some(&22));
+        assert_eq!(r.read().as_deref().ok(), Some(&42));
+        assert_eq!(r.read().err(), Some(TryReadError::Disconnected));
+    }
+
+    #[test]
+    fn test_threading() {
+        // Test disruptor with several reader threads with multiple iterations.
+        // Every reader must read every value, in the order it was written.
+
+        let (r, mut w) = disruptor::new::<i32>(16);
+
+        let readers: Vec<_> = (0..3)
+            .map(|_| {
+                let mut r = r.clone();
+                thread::spawn(move || {
+                    let mut next = 0;
+                    loop {
+                        match r.read() {
+                            Ok(value) => {
+                                assert_eq!(*value, next, "Values should have been read in order");
+                                next += 1;
+                            }
+                            Err(TryReadError::Empty) => {}
+                            Err(_) => break,
+                        }
+                    }
+                    next
+                })
+            })
+            .collect();
+        drop(r);
+        let write_res = thread::spawn(move || {
+            for i in 0..10000 {
+                w.write(i);
+            }
+        })
+        .join();
+        assert!(
+            write_res.is_ok(),
+            "Writer thread should have ended peacefully"
+        );
+        for read_res in readers {
+            assert_eq!(
+                read_res.join().ok(),
+                Some(10000),
+                "Every value should have been read"
+            );
+        }
+    }
+}
@@ -0,0 +1,183 @@
+#[cfg(test)]
+mod tests {
+
+    use std::thread;
+
+    use rustedrazors::double_buffer;
+    use rustedrazors::{Reader, TryReadError, WriteError, Writer};
+
+    #[test]
+    fn test_basics() {
+        // Test basic API
+
+        let (mut r, mut w) = double_buffer::new::<i32>();
+
+        for _ in 0..5 {
+            let res = r.read();
+            assert!(res.is_err(), "Read should have failed");
+        }
+
+        w.write(22);
+
+        {
+            let res = r.read();
+            assert_eq!(
+                res.as_deref(),
+                Ok(&22),
+                "Read should have returned the value previously written"
+            );
+            // drop the guard
+        }
+
+        for _ in 0..5 {
+            let res = r.read();
+            assert!(res.is_err(), "Read should have failed");
+        }
+    }
+
+    #[test]
+    fn test_refresh() {
+        // Test that the front buffer stays stable until the reader refreshes it
+
+        let (mut r, mut w) = double_buffer::new::<Vec<i32>>();
+
+        assert!(!r.refresh(), "Refresh should have failed");
+        assert_eq!(*r.front_buffer(), Vec::<i32>::new());
+
+        w.write(vec![1, 2]);
+        assert_eq!(
+            *r.front_buffer(),
+            Vec::<i32>::new(),
+            "Front buffer should not have changed before refreshing"
+        );
+        assert!(
+            r.refresh(),
+            "Refresh should have taken the published buffer"
+        );
+        assert_eq!(*r.front_buffer(), vec![1, 2]);
+        assert!(!r.refresh(), "Refresh should have failed");
+        assert_eq!(
+            *r.front_buffer(),
+            vec![1, 2],
+            "Front buffer should have been kept"
+        );
+
+        w.write_with(|back| {
+            back.clear();
+            back.push(3);
+        });
+        assert!(r.refresh());
+        assert_eq!(*r.front_buffer(), vec![3]);
+    }
+
+    #[test]
+    fn test_read_lease() {
+        // Test that a held guard keeps its value while writes go on
+
+        let (mut r, mut w) = double_buffer::new::<i32>();
+
+        w.write(22);
+        let guard = r.read().expect("Read should have succeeded");
+        w.write(42);
+        w.write(62);
+        assert_eq!(*guard, 22, "Leased value should not have changed");
+
+        assert_eq!(r.read().as_deref(), Ok(&62));
+        assert_eq!(r.overwritten(), 1);
+    }
+
+    #[test]
+    fn test_disconnected() {
+        // Test that readers can tell a dead writer apart from a silent one
+
+        let (mut r, mut w) = double_buffer::new::<i32>();
+
+        assert_eq!(r.read().err(), Some(TryReadError::Empty));
+        w.write(22);
+        drop(w);
+
+        {
+            let res = r.read();
+            assert_eq!(
+                res.as_deref().ok(),
+                Some(&22),
+                "Pending value should still be readable after the writer is gone"
+            );
+            // drop the guard
+        }
+        assert_eq!(r.read().err(), Some(TryReadError::Disconnected));
+    }
+
+    #[test]
+    fn test_poisoned() {
+        // Test that a write panicking halfway through poisons the channel instead of leaving the
+        // back buffer flagged as being written, which would hide every later value
+
+        use std::panic::{self, AssertUnwindSafe};
+
+        let (mut r, mut w) = double_buffer::new::<Vec<i32>>();
+        w.write(vec![1]);
+        assert_eq!(r.read().as_deref(), Ok(&vec![1]));
+        let res = panic::catch_unwind(AssertUnwindSafe(|| {
+            w.write_with(|back| {
+                back.push(2);
+                panic!("write failed");
+            })
+        }));
+        assert!(res.is_err());
+        assert!(
+            w.is_poisoned(),
+            "Writer should have seen the channel poisoned"
+        );
+        assert!(
+            r.is_poisoned(),
+            "Reader should have seen the channel poisoned"
+        );
+        assert_eq!(r.read().err(), Some(TryReadError::Poisoned));
+        assert_eq!(
+            *r.front_buffer(),
+            vec![1],
+            "Front buffer should not have been touched by the failed write"
+        );
+        let res = panic::catch_unwind(AssertUnwindSafe(|| w.write(vec![3])));
+        assert!(res.is_err(), "Write should have panicked");
+        assert_eq!(
+            w.try_write(vec![3]),
+            Err(WriteError::Poisoned(vec![3])),
+            "Write should have been refused"
+        );
+        assert_eq!(r.read().err(), Some(TryReadError::Poisoned));
+    }
+
+    #[test]
+    fn test_threading() {
+        // Test double_buffer with arrays across threads, values must never be torn
+
+        let (mut r, mut w) = double_buffer::new::<[u64; 32]>();
+
+        let read_res = thread::spawn(move || loop {
+            match r.read() {
+                Ok(value) => assert!(
+                    value.iter().all(|&v| v == value[0]),
+                    "Read should not have observed a partial write"
+                ),
+                Err(TryReadError::Empty) => {}
+                Err(_) => break,
+            }
+        });
+        let write_res = thread::spawn(move || {
+            for i in 0..10000 {
+                w.write_with(|back| back.fill(i));
+            }
+        })
+        .join();
+        assert!(
+            write_res.is_ok(),
+            "Writer thread should have ended peacefully"
+        );
+        assert!(
+            read_res.join().is_ok(),
+            "Reader thread should have ended peacefully"
+        );
+    }
+}
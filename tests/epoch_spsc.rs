@@ -0,0 +1,123 @@
+#[cfg(all(test, feature = "epoch"))]
+mod tests {
+
+    use std::sync::Arc;
+    use std::thread;
+
+    use rustedrazors::epoch_spsc;
+    use rustedrazors::{Reader, TryReadError, Writer};
+
+    #[test]
+    fn test_basics() {
+        // Test basic API
+
+        let (mut r, mut w) = epoch_spsc::new::<i32>();
+
+        for _ in 0..5 {
+            let res = r.read();
+            assert!(res.is_err(), "Read should have failed");
+        }
+
+        w.write(22);
+
+        let res = r.read();
+        assert_eq!(
+            res.as_deref(),
+            Ok(&22),
+            "Read should have returned the value previously written"
+        );
+        assert_eq!(res.unwrap().version(), 1);
+
+        for _ in 0..5 {
+            let res = r.read();
+            assert!(res.is_err(), "Read should have failed");
+        }
+    }
+
+    #[test]
+    fn test_read_lease() {
+        // Test that held values survive newer writes
+
+        let (mut r, mut w) = epoch_spsc::new::<String>();
+
+        w.write("old".to_string());
+        let guard = r.read().unwrap();
+        for i in 0..100 {
+            w.write(i.to_string());
+        }
+        assert_eq!(&*guard, "old");
+        drop(guard);
+        assert_eq!(r.read().as_deref().map(String::as_str), Ok("99"));
+        assert_eq!(w.overwritten(), 99);
+    }
+
+    #[test]
+    fn test_drop() {
+        // Test that the last value is dropped with the channel
+
+        let alive = Arc::new(());
+        let (r, mut w) = epoch_spsc::new::<Arc<()>>();
+        w.write(Arc::clone(&alive));
+        drop(r);
+        drop(w);
+        assert_eq!(
+            Arc::strong_count(&alive),
+            1,
+            "Pending value should have been dropped"
+        );
+    }
+
+    #[test]
+    fn test_disconnected() {
+        // Test that readers can tell a dead writer apart from a silent one
+
+        let (mut r, mut w) = epoch_spsc::new::<i32>();
+
+        assert_eq!(r.read().err(), Some(TryReadError::Empty));
+        w.write(22);
+        drop(w);
+
+        assert_eq!(
+            r.read().as_deref().ok(),
+            Some(&22),
+            "Pending value should still be readable after the writer is gone"
+        );
+        assert_eq!(r.read().err(), Some(TryReadError::Disconnected));
+    }
+
+    #[test]
+    fn test_threading() {
+        // Test epoch_spsc with i32 across threads with multiple iterations.
+        // Values must be read in the order they were written.
+
+        let (mut r, mut w) = epoch_spsc::new::<i32>();
+
+        let read_res = thread::spawn(move || {
+            let mut last = -1;
+            loop {
+                match r.read() {
+                    Ok(value) => {
+                        assert!(*value > last, "Values should have been read in order");
+                        last = *value;
+                    }
+                    Err(TryReadError::Empty) => {}
+                    Err(_) => break,
+                }
+            }
+        });
+        let write_res = thread::spawn(move || {
+            for i in 0..10000 {
+                w.write(i);
+            }
+        })
+        .join();
+        assert!(
+            write_res.is_ok(),
+            "Writer thread should have ended peacefully"
+        );
+        assert!(
+            read_res.join().is_ok(),
+            "Reader thread should have ended peacefully"
+        );
+    }
+}
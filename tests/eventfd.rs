@@ -0,0 +1,84 @@
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+
+    use std::os::fd::{AsRawFd, RawFd};
+    use std::thread;
+    use std::time::Duration;
+
+    use rustedrazors::{atomic_spsc, blocking_spsc, ticket_spsc};
+    use rustedrazors::{Reader, Writer};
+
+    /// Returns whether `fd` becomes readable within `timeout_ms`.
+    fn readable(fd: RawFd, timeout_ms: i32) -> bool {
+        let mut pollfd = libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        unsafe { libc::poll(&mut pollfd, 1, timeout_ms) == 1 }
+    }
+
+    macro_rules! check_eventfd {
+        ($module:ident $(, $init:expr)?) => {{
+            let (mut r, mut w) = $module::new($($init)?);
+            // an event loop keeps the raw descriptor, not the borrow of the read handle
+            let fd = r.attach_eventfd().unwrap().as_raw_fd();
+
+            assert!(!r.arm_eventfd());
+            assert!(!readable(fd, 0), "Nothing should have been written");
+
+            w.write(1);
+            assert!(readable(fd, 0), "Eventfd should have been signaled");
+            assert_eq!(r.read().as_deref(), Ok(&1));
+            assert!(readable(fd, 0), "Eventfd should stay readable until armed");
+
+            assert!(!r.arm_eventfd());
+            assert!(!readable(fd, 0));
+            let writer = thread::spawn(move || {
+                thread::sleep(Duration::from_millis(10));
+                w.write(2);
+                w
+            });
+            assert!(readable(fd, 1000), "Event loop should have been woken up");
+            assert_eq!(r.read().as_deref(), Ok(&2));
+            let mut w = writer.join().unwrap();
+
+            w.write(3);
+            assert!(r.arm_eventfd(), "Value should already be readable");
+            assert!(readable(fd, 0));
+            assert_eq!(r.read().as_deref(), Ok(&3));
+
+            assert!(!r.arm_eventfd());
+            drop(w);
+            assert!(readable(fd, 0), "Dropping the writer should signal the eventfd");
+        }};
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore = "Miri does not support poll")]
+    fn test_eventfd() {
+        // Test that the eventfd becomes readable once armed and something is written
+
+        check_eventfd!(atomic_spsc);
+        check_eventfd!(blocking_spsc);
+        check_eventfd!(ticket_spsc, 0);
+    }
+
+    #[test]
+    fn test_attach_eventfd() {
+        // Test that there is no eventfd until one is attached, and that attaching again returns it
+
+        let (r, _w) = atomic_spsc::new::<i32>();
+        assert!(
+            r.eventfd().is_none(),
+            "No eventfd should have been attached"
+        );
+        let fd = r.attach_eventfd().unwrap().as_raw_fd();
+        assert_eq!(r.eventfd().map(AsRawFd::as_raw_fd), Some(fd));
+        assert_eq!(
+            r.attach_eventfd().unwrap().as_raw_fd(),
+            fd,
+            "Attaching again should have returned the same eventfd"
+        );
+    }
+}
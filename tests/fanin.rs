@@ -0,0 +1,64 @@
+#[cfg(test)]
+mod tests {
+
+    use std::thread;
+
+    use rustedrazors::fanin;
+    use rustedrazors::{TryReadError, Writer};
+
+    #[test]
+    fn test_basics() {
+        // Test a reader polling the slot of every writer
+
+        let (mut r, mut writers) = fanin::new::<i32>(3);
+        assert_eq!(r.slots(), 3);
+        assert_eq!(r.iter().count(), 0, "No slot should hold a value");
+
+        writers[0].write(1);
+        writers[2].write(2);
+        writers[2].write(3);
+        let values: Vec<_> = r.iter().map(|(slot, value)| (slot, *value)).collect();
+        assert_eq!(
+            values,
+            vec![(0, 1), (2, 3)],
+            "Every slot holding a new value should have been read, in order"
+        );
+        assert_eq!(r.iter().count(), 0, "Every value was read already");
+
+        writers[1].write(4);
+        assert_eq!(r.read(1).as_deref(), Ok(&4));
+        assert!(r.read(1).is_err(), "Read should have failed");
+        assert!(format!("{r:?}").starts_with("fanin::ReadHandle { slots: [atomic_spsc::ReadHandle"));
+    }
+
+    #[test]
+    fn test_threads() {
+        // Test producers on their own threads, each keeping its own slot
+
+        let (mut r, writers) = fanin::new::<usize>(4);
+        let threads: Vec<_> = writers
+            .into_iter()
+            .enumerate()
+            .map(|(slot, mut w)| {
+                thread::spawn(move || {
+                    for i in 0..100 {
+                        w.write(slot * 1000 + i);
+                    }
+                    w.close();
+                })
+            })
+            .collect();
+        for producer in threads {
+            producer.join().unwrap();
+        }
+
+        let mut last = vec![None; r.slots()];
+        for (slot, value) in r.iter() {
+            last[slot] = Some(*value);
+        }
+        assert_eq!(last, vec![Some(99), Some(1099), Some(2099), Some(3099)]);
+        for slot in 0..r.slots() {
+            assert_eq!(r.read(slot).err(), Some(TryReadError::Closed));
+        }
+    }
+}
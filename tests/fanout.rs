@@ -0,0 +1,85 @@
+#[cfg(test)]
+mod tests {
+
+    use std::thread;
+
+    use rustedrazors::fanout;
+    use rustedrazors::{Reader, TryReadError, WriteError, Writer};
+
+    #[test]
+    fn test_basics() {
+        // Test that every reader gets every value, independently of the others
+
+        let (mut readers, mut w) = fanout::new::<String>(3);
+        assert_eq!(readers.len(), 3);
+        for r in &mut readers {
+            assert!(r.read().is_err(), "Read should have failed");
+        }
+
+        w.write(String::from("a"));
+        assert_eq!(readers[0].read().as_deref().map(String::as_str), Ok("a"));
+        w.write(String::from("b"));
+        for r in &mut readers {
+            assert_eq!(
+                r.read().as_deref().map(String::as_str),
+                Ok("b"),
+                "Every reader should have the newest value"
+            );
+            assert!(r.read().is_err(), "Read should have failed");
+        }
+
+        w.write_with(|value| value.push('c'));
+        assert_eq!(
+            readers[2].read_with(|value| value.clone()).as_deref(),
+            Ok("c")
+        );
+        assert!(format!("{w:?}").contains("readers_alive: 3"));
+    }
+
+    #[test]
+    fn test_threads() {
+        // Test readers on their own threads, each seeing the last value before the close
+
+        let (readers, mut w) = fanout::new::<usize>(4);
+        let threads: Vec<_> = readers
+            .into_iter()
+            .map(|mut r| {
+                thread::spawn(move || {
+                    let mut last = None;
+                    loop {
+                        match r.read() {
+                            Ok(value) => last = Some(*value),
+                            Err(TryReadError::Closed) => return last,
+                            Err(_) => thread::yield_now(),
+                        }
+                    }
+                })
+            })
+            .collect();
+        for i in 0..1000 {
+            w.write(i);
+        }
+        w.close();
+        for reader in threads {
+            assert_eq!(reader.join().unwrap(), Some(999));
+        }
+    }
+
+    #[test]
+    fn test_disconnect() {
+        // Test a writer skipping the readers that are gone, until none is left
+
+        let (mut readers, mut w) = fanout::new::<i32>(2);
+        let mut r = readers.pop().unwrap();
+        drop(readers);
+        assert_eq!(w.readers_alive(), 1);
+        assert!(w.is_reader_alive(), "A reader should still be alive");
+        assert_eq!(w.try_write(1), Ok(()));
+        assert_eq!(r.read().as_deref(), Ok(&1));
+
+        drop(r);
+        assert!(!w.is_reader_alive(), "Readers should have been dropped");
+        assert_eq!(w.try_write(2), Err(WriteError::Disconnected(2)));
+        w.write(3);
+    }
+}
@@ -0,0 +1,79 @@
+#[cfg(all(test, feature = "ffi"))]
+mod tests {
+
+    use std::thread;
+
+    use rustedrazors::ffi::{rr_channel_new, rr_free, rr_try_read, rr_write, Channel, Status};
+    use rustedrazors::Reader;
+
+    #[test]
+    fn test_basics() {
+        // Test basic API
+
+        let channel = rr_channel_new(4);
+        let mut out = [0u8; 4];
+
+        assert_eq!(
+            unsafe { rr_try_read(channel, out.as_mut_ptr()) },
+            Status::Empty
+        );
+
+        unsafe { rr_write(channel, [1, 2, 3, 4].as_ptr()) };
+        assert_eq!(
+            unsafe { rr_try_read(channel, out.as_mut_ptr()) },
+            Status::Ok
+        );
+        assert_eq!(out, [1, 2, 3, 4]);
+        assert_eq!(
+            unsafe { rr_try_read(channel, out.as_mut_ptr()) },
+            Status::Empty
+        );
+        assert_eq!(out, [1, 2, 3, 4], "Failed read should not have touched out");
+
+        unsafe { rr_write(channel, [5, 6, 7, 8].as_ptr()) };
+        unsafe { rr_write(channel, [9, 10, 11, 12].as_ptr()) };
+        let value = unsafe { Channel::reader(channel) }.read();
+        assert_eq!(
+            value.as_deref().map(Vec::as_slice),
+            Ok(&[9, 10, 11, 12][..])
+        );
+
+        unsafe { rr_free(channel) };
+        unsafe { rr_free(std::ptr::null_mut()) };
+    }
+
+    #[test]
+    fn test_threads() {
+        // Test that values written from another thread are read in order, without tearing
+
+        struct Ptr(*mut Channel);
+        unsafe impl Send for Ptr {}
+
+        let channel = Ptr(rr_channel_new(64));
+        let writer = Ptr(channel.0);
+
+        thread::scope(|s| {
+            s.spawn(move || {
+                let writer = writer;
+                for i in 1..=10_000u64 {
+                    unsafe { rr_write(writer.0, [i; 8].as_ptr().cast()) };
+                }
+            });
+
+            let mut last = 0;
+            let mut out = [0u64; 8];
+            while last < 10_000 {
+                match unsafe { rr_try_read(channel.0, out.as_mut_ptr().cast()) } {
+                    Status::Ok => {
+                        assert!(out.iter().all(|&v| v == out[0]), "Value should not be torn");
+                        assert!(out[0] > last, "Values should have been read in order");
+                        last = out[0];
+                    }
+                    status => assert_eq!(status, Status::Empty),
+                }
+            }
+        });
+
+        unsafe { rr_free(channel.0) };
+    }
+}
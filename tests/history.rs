@@ -0,0 +1,133 @@
+#[cfg(test)]
+mod tests {
+
+    use std::thread;
+
+    use rustedrazors::history;
+    use rustedrazors::{TryReadError, Writer};
+
+    #[test]
+    fn test_basics() {
+        // Test basic API
+
+        let (mut r, mut w) = history::new::<i32>(4);
+        assert_eq!(r.capacity(), 4);
+
+        assert_eq!(r.drain().count(), 0, "Nothing should have been written");
+        assert!(!r.has_pending());
+
+        w.write(1);
+        w.write(2);
+        assert!(r.has_pending());
+        assert_eq!(r.drain().collect::<Vec<_>>(), [1, 2]);
+
+        assert!(!r.has_pending());
+        assert_eq!(r.drain().count(), 0, "Every value should have been drained");
+
+        w.write(3);
+        assert_eq!(r.drain().collect::<Vec<_>>(), [3]);
+    }
+
+    #[test]
+    fn test_eviction() {
+        // Test that only the last `capacity` values are retained
+
+        let (mut r, mut w) = history::new::<i32>(3);
+
+        for i in 0..5 {
+            w.write(i);
+        }
+        assert_eq!(w.overwritten(), 2);
+        assert_eq!(r.drain().collect::<Vec<_>>(), [2, 3, 4]);
+
+        w.write(5);
+        assert_eq!(
+            r.overwritten(),
+            2,
+            "Drained values should not count as overwritten"
+        );
+        assert_eq!(r.drain().collect::<Vec<_>>(), [5]);
+    }
+
+    #[test]
+    fn test_write_with() {
+        // Test that write_with reuses evicted values
+
+        let (mut r, mut w) = history::new::<Vec<i32>>(1);
+
+        w.write_with(|v| v.push(1));
+        w.write_with(|v| v.push(2));
+        assert_eq!(
+            r.drain().collect::<Vec<_>>(),
+            [vec![1, 2]],
+            "Evicted value should have been handed to write_with"
+        );
+    }
+
+    #[test]
+    fn test_disconnected() {
+        // Test that pending values are drained before reporting the disconnection
+
+        let (mut r, mut w) = history::new::<i32>(4);
+        assert!(matches!(r.try_drain(), Err(TryReadError::Empty)));
+
+        w.write(1);
+        drop(w);
+        assert_eq!(r.try_drain().unwrap().collect::<Vec<_>>(), [1]);
+        assert!(matches!(r.try_drain(), Err(TryReadError::Disconnected)));
+
+        let (mut r, w) = history::new::<i32>(4);
+        w.close();
+        assert!(matches!(r.try_drain(), Err(TryReadError::Closed)));
+
+        let (r, w) = history::new::<i32>(4);
+        assert!(w.is_reader_alive());
+        drop(r);
+        assert!(!w.is_reader_alive(), "Reader should have been dropped");
+    }
+
+    #[test]
+    fn test_threading() {
+        // Test history across threads.
+        // Drained values must be increasing, and every value must be drained or overwritten.
+
+        const WRITES: u64 = 100_000;
+
+        let (mut r, mut w) = history::new::<u64>(16);
+
+        let read_res = thread::spawn(move || {
+            let mut last = None;
+            let mut drained = 0;
+            loop {
+                match r.try_drain() {
+                    Ok(drain) => {
+                        for value in drain {
+                            assert!(last < Some(value), "Values should be drained in order");
+                            last = Some(value);
+                            drained += 1;
+                        }
+                    }
+                    Err(TryReadError::Empty) => thread::yield_now(),
+                    Err(_) => break,
+                }
+            }
+            (last, drained + r.overwritten())
+        });
+        let write_res = thread::spawn(move || {
+            for i in 0..WRITES {
+                w.write(i);
+            }
+            w.close();
+        })
+        .join();
+        assert!(
+            write_res.is_ok(),
+            "Writer thread should have ended peacefully"
+        );
+        assert_eq!(
+            read_res.join().unwrap(),
+            (Some(WRITES - 1), WRITES),
+            "Every value should have been drained or overwritten"
+        );
+    }
+}
@@ -0,0 +1,99 @@
+#[cfg(test)]
+mod tests {
+
+    use std::thread;
+
+    use rustedrazors::isr_spsc::Storage;
+    use rustedrazors::{Reader, TryReadError, Writer};
+
+    #[test]
+    fn test_basics() {
+        // Test basic API
+
+        static STORAGE: Storage<i32> = Storage::new();
+        let (mut r, mut w) = STORAGE.split().unwrap();
+        assert!(STORAGE.split().is_none(), "Handles should have been taken");
+
+        assert!(r.read().is_err(), "Read should have failed");
+        assert!(!r.has_changed());
+
+        w.write(22);
+        assert!(r.has_changed());
+        assert_eq!(r.read().as_deref(), Ok(&22));
+        assert!(r.read().is_err(), "Read should have failed");
+
+        w.write(1);
+        w.write_with(|v| *v += 2);
+        assert_eq!(r.read().map(|v| v.into_inner()), Ok(2));
+        assert_eq!(r.overwritten(), 1);
+        assert_eq!(w.overwritten(), 1);
+    }
+
+    #[test]
+    fn test_guards() {
+        // Test that guards kept alive by the reader never starve the writer
+
+        static STORAGE: Storage<[u8; 16]> = Storage::new();
+        let (mut r, mut w) = STORAGE.split().unwrap();
+
+        let mut guards = Vec::new();
+        for i in 0..10 {
+            w.write([i; 16]);
+            guards.push(r.read().unwrap());
+            w.write([i + 100; 16]);
+        }
+        assert!(guards.iter().enumerate().all(|(i, g)| **g == [i as u8; 16]));
+        assert_eq!(r.read().as_deref(), Ok(&[109; 16]));
+    }
+
+    #[test]
+    fn test_disconnected() {
+        // Test that readers can tell a dead writer apart from a silent one
+
+        static CLOSED: Storage<i32> = Storage::new();
+        let (mut r, mut w) = CLOSED.split().unwrap();
+        assert_eq!(r.read().err(), Some(TryReadError::Empty));
+        w.write(42);
+        w.close();
+        assert_eq!(r.read().as_deref(), Ok(&42));
+        assert_eq!(r.read().err(), Some(TryReadError::Closed));
+
+        static DROPPED: Storage<i32> = Storage::new();
+        let (r, w) = DROPPED.split().unwrap();
+        assert!(w.is_reader_alive());
+        drop(r);
+        assert!(!w.is_reader_alive());
+        drop(w);
+    }
+
+    #[test]
+    fn test_threads() {
+        // Test that values are read in order, without tearing, with the writer standing in for an
+        // interrupt handler
+
+        static STORAGE: Storage<[u64; 8]> = Storage::new();
+        let (mut r, mut w) = STORAGE.split().unwrap();
+
+        thread::spawn(move || {
+            for i in 1..=10_000 {
+                w.write([i; 8]);
+            }
+        });
+        let mut last = 0;
+        loop {
+            match r.read() {
+                Ok(value) => {
+                    assert!(value[0] > last, "Values should have been read in order");
+                    assert!(value.iter().all(|&v| v == value[0]), "Value was torn");
+                    last = value[0];
+                }
+                Err(TryReadError::Empty) => thread::yield_now(),
+                Err(err) => {
+                    assert_eq!(err, TryReadError::Disconnected);
+                    break;
+                }
+            }
+        }
+        assert_eq!(last, 10_000, "Last value should have been read");
+    }
+}
@@ -0,0 +1,126 @@
+#[cfg(test)]
+mod tests {
+
+    use std::thread;
+
+    use rustedrazors::left_right;
+    use rustedrazors::{Reader, TryReadError, Writer};
+
+    #[test]
+    fn test_basics() {
+        // Test basic API
+
+        let (mut r, mut w) = left_right::new::<i32>();
+
+        for _ in 0..5 {
+            let res = r.read();
+            assert!(res.is_err(), "Read should have failed");
+        }
+
+        w.write(22);
+
+        let res = r.read();
+        assert_eq!(
+            res.as_deref(),
+            Ok(&22),
+            "Read should have returned the value previously written"
+        );
+        drop(res);
+
+        for _ in 0..5 {
+            let res = r.read();
+            assert!(res.is_err(), "Read should have failed");
+        }
+    }
+
+    #[test]
+    fn test_apply() {
+        // Test that operations end up applied once to both copies
+
+        let (mut r, mut w) = left_right::new::<Vec<i32>>();
+
+        for i in 0..5 {
+            w.apply(move |v| v.push(i));
+            assert_eq!(
+                r.read().as_deref(),
+                Ok(&(0..=i).collect::<Vec<_>>()),
+                "Every operation should have been applied exactly once"
+            );
+        }
+
+        w.write_with(|v| v.retain(|i| i % 2 == 0));
+        assert_eq!(r.read().as_deref(), Ok(&vec![0, 2, 4]));
+        w.apply(|v| v.push(6));
+        assert_eq!(r.read().as_deref(), Ok(&vec![0, 2, 4, 6]));
+    }
+
+    #[test]
+    fn test_read_lease() {
+        // Test that held copies are not modified by later writes
+
+        let (mut r, mut w) = left_right::new::<i32>();
+
+        w.write(22);
+        let guard = r.read().unwrap();
+        w.write(42);
+        assert_eq!(*guard, 22);
+        drop(guard);
+        assert_eq!(r.read().as_deref(), Ok(&42));
+    }
+
+    #[test]
+    fn test_disconnected() {
+        // Test that readers can tell a dead writer apart from a silent one
+
+        let (mut r, mut w) = left_right::new::<i32>();
+
+        assert_eq!(r.read().err(), Some(TryReadError::Empty));
+        w.write(22);
+        drop(w);
+
+        assert_eq!(
+            r.read().as_deref().ok(),
+            Some(&22),
+            "Pending value should still be readable after the writer is gone"
+        );
+        assert_eq!(r.read().err(), Some(TryReadError::Disconnected));
+    }
+
+    #[test]
+    fn test_threading() {
+        // Test left_right with a counter across threads with multiple iterations.
+        // Values must be read in the order they were written, and replays must not be missed.
+
+        let (mut r, mut w) = left_right::new::<i32>();
+
+        let read_res = thread::spawn(move || {
+            let mut last = 0;
+            loop {
+                match r.read() {
+                    Ok(value) => {
+                        assert!(*value > last, "Values should have been read in order");
+                        last = *value;
+                    }
+                    Err(TryReadError::Empty) => {}
+                    Err(_) => break,
+                }
+            }
+            last
+        });
+        let write_res = thread::spawn(move || {
+            for _ in 0..10000 {
+                w.apply(|value| *value += 1);
+            }
+        })
+        .join();
+        assert!(
+            write_res.is_ok(),
+            "Writer thread should have ended peacefully"
+        );
+        assert_eq!(
+            read_res.join().ok(),
+            Some(10000),
+            "Last value should have been read"
+        );
+    }
+}
@@ -0,0 +1,331 @@
+//! Short read/write histories of every latest-value channel, meant to be run under Miri with
+//! `MIRIFLAGS="-Zmiri-strict-provenance" cargo +nightly miri test --test miri`.
+//!
+//! The rest of the suite passes under Miri too, but takes the better part of an hour. Here every
+//! loop is bounded and the reader yields instead of spinning while nothing is written, so a run
+//! takes seconds and can be repeated under other schedules with `-Zmiri-many-seeds`.
+//!
+//! `epoch_spsc` is left out: `crossbeam-epoch` tags its pointers through integer casts, which
+//! strict provenance rejects.
+
+#[cfg(test)]
+mod tests {
+
+    use std::cell::Cell;
+    use std::marker::PhantomData;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::thread;
+
+    #[cfg(any(target_os = "linux", windows))]
+    use rustedrazors::futex_spsc;
+    use rustedrazors::{
+        arc_spsc, atomic_spsc, blocking_spsc, box_spsc, double_buffer, isr_spsc, left_right,
+        mpmc_latest, mpsc_latest, mutex_spsc, recycle_spsc, seqlock_spsc, slot_pool, ticket_spsc,
+        triple_buffer, watch,
+    };
+    use rustedrazors::{Reader, TryReadError, Writer};
+
+    const WRITES: u64 = 20;
+
+    /// Sequence number repeated over the whole value, so that a torn read shows.
+    type Payload = [u64; 4];
+
+    /// Writes `WRITES` sequence-numbered values, half of them in place, while the reader keeps
+    /// its lease across a yield before checking each value.
+    fn check_latest<R, W>(handles: (R, W))
+    where
+        R: Reader<Item = Payload>,
+        W: Writer<Item = Payload> + Send,
+    {
+        check_reads(handles, |w: &mut W, seq| {
+            if seq % 2 == 0 {
+                w.write([seq; 4]);
+            } else {
+                w.write_with(|value| *value = [seq; 4]);
+            }
+        });
+    }
+
+    /// Writes `WRITES` sequence-numbered values through `write` while the reader keeps its lease
+    /// across a yield before checking each value.
+    fn check_reads<R, W>((mut r, mut w): (R, W), write: impl Fn(&mut W, u64) + Send)
+    where
+        R: Reader<Item = Payload>,
+        W: Writer<Item = Payload> + Send,
+    {
+        thread::scope(|s| {
+            s.spawn(move || {
+                for seq in 1..=WRITES {
+                    write(&mut w, seq);
+                }
+            });
+
+            let mut last = 0;
+            loop {
+                match r.read() {
+                    Ok(value) => {
+                        thread::yield_now();
+                        assert!(value[0] > last, "Values should have been read in order");
+                        assert!(value.iter().all(|&v| v == value[0]), "Value was torn");
+                        last = value[0];
+                    }
+                    Err(TryReadError::Empty) => thread::yield_now(),
+                    Err(err) => {
+                        assert_eq!(err, TryReadError::Disconnected);
+                        break;
+                    }
+                }
+            }
+            assert_eq!(last, WRITES, "Last value should have been read");
+        });
+    }
+
+    /// Mixes the writes that read the last value back with reads holding on to it.
+    macro_rules! check_compare {
+        ($module:ident) => {
+            check_reads(
+                $module::new(),
+                |w: &mut $module::WriteHandle<Payload>, seq| {
+                    if seq % 2 == 0 {
+                        // the second write compares against the value the reader may be holding
+                        w.write_if_changed([seq; 4]);
+                        w.write_if_changed([seq; 4]);
+                    } else {
+                        w.update(|value| *value = [seq; 4]);
+                    }
+                },
+            )
+        };
+    }
+
+    /// Whether `T` is `Sync`: the inherent constant only exists if it is, and shadows the one of
+    /// `NotSync` otherwise.
+    struct IsSync<T: ?Sized>(PhantomData<T>);
+
+    trait NotSync {
+        const SYNC: bool = false;
+    }
+
+    impl<T: ?Sized> NotSync for IsSync<T> {}
+
+    impl<T: ?Sized + Sync> IsSync<T> {
+        const SYNC: bool = true;
+    }
+
+    /// Checks that the guards of `$module` are `Sync` only for `Sync` values, then shares a read
+    /// guard with another thread while the writer publishes the next value.
+    macro_rules! check_sync {
+        ($module:ident, $guard:ident, $write_guard:ident) => {{
+            assert!(IsSync::<$module::$guard<'static, Payload>>::SYNC);
+            assert!(!IsSync::<$module::$guard<'static, Cell<u64>>>::SYNC);
+            assert!(IsSync::<$module::$write_guard<'static, Payload>>::SYNC);
+            assert!(!IsSync::<$module::$write_guard<'static, Cell<u64>>>::SYNC);
+
+            let (mut r, mut w) = $module::new::<Payload>();
+            w.write([1; 4]);
+            let guard = r.read().unwrap();
+            thread::scope(|s| {
+                s.spawn(|| assert_eq!(*guard, [1; 4]));
+                s.spawn(|| w.write([2; 4]));
+                assert_eq!(*guard, [1; 4]);
+            });
+            drop(guard);
+            assert_eq!(r.read().as_deref(), Ok(&[2; 4]));
+        }};
+    }
+
+    /// Reads from inside `write_with`, then panics in it, on a channel of `Vec` so that a value
+    /// leaked or dropped twice by either path is reported.
+    macro_rules! check_write_with {
+        ($module:ident) => {{
+            let (mut r, mut w) = $module::new::<Vec<u64>>();
+            w.write(vec![1]);
+            w.write_with(|value| {
+                // the slot being written is neither the one read nor the one waiting
+                assert_eq!(r.read().as_deref(), Ok(&vec![1]));
+                value.clear();
+                value.push(2);
+            });
+            assert_eq!(r.read().as_deref(), Ok(&vec![2]));
+
+            let res = panic::catch_unwind(AssertUnwindSafe(|| {
+                w.write_with(|value| {
+                    value.push(3);
+                    panic!("write aborted");
+                })
+            }));
+            assert!(res.is_err());
+            assert!(w.is_poisoned());
+            assert_eq!(r.read().as_deref(), Err(&TryReadError::Poisoned));
+        }};
+    }
+
+    /// Mixes the writes that read the last value back with reads swapping it out.
+    macro_rules! check_swap {
+        ($module:ident) => {{
+            let (mut r, mut w) = $module::new::<Payload>();
+            thread::scope(|s| {
+                s.spawn(move || {
+                    for seq in 1..=WRITES {
+                        if seq % 2 == 0 {
+                            w.write_if_changed([seq; 4]);
+                        } else {
+                            w.update(|value| *value = [seq; 4]);
+                        }
+                    }
+                });
+
+                let mut scratch = [0; 4];
+                let mut last = 0;
+                while last < WRITES {
+                    if r.read_swap(&mut scratch).is_ok() {
+                        assert!(scratch[0] > last, "Values should have been read in order");
+                        assert!(scratch.iter().all(|&v| v == scratch[0]), "Value was torn");
+                        last = scratch[0];
+                    } else {
+                        thread::yield_now();
+                    }
+                }
+            });
+        }};
+    }
+
+    #[test]
+    fn test_pools() {
+        // Test the channels handing out leases on a pool of slots
+
+        check_latest(atomic_spsc::new());
+        check_latest(blocking_spsc::new());
+        #[cfg(any(target_os = "linux", windows))]
+        check_latest(futex_spsc::new());
+        check_latest(triple_buffer::new());
+        check_latest(double_buffer::new());
+        check_latest(left_right::new());
+    }
+
+    #[test]
+    fn test_read_swap() {
+        // Test swapping values out while the writer reads the last one back
+
+        check_swap!(atomic_spsc);
+        check_swap!(blocking_spsc);
+        #[cfg(any(target_os = "linux", windows))]
+        check_swap!(futex_spsc);
+    }
+
+    #[test]
+    fn test_compare_held() {
+        // Test the writes reading the last value back while the reader holds it
+
+        check_compare!(atomic_spsc);
+        check_compare!(blocking_spsc);
+        #[cfg(any(target_os = "linux", windows))]
+        check_compare!(futex_spsc);
+    }
+
+    #[test]
+    fn test_guard_sync() {
+        // Test guards shared between threads, which only compiles for values that are `Sync`
+
+        check_sync!(atomic_spsc, AtomicGuard, AtomicWriteGuard);
+        check_sync!(blocking_spsc, BlockingGuard, BlockingWriteGuard);
+        #[cfg(any(target_os = "linux", windows))]
+        check_sync!(futex_spsc, FutexGuard, FutexWriteGuard);
+    }
+
+    #[test]
+    fn test_write_with() {
+        // Test reading from inside a write in place, and a write in place that panics
+
+        check_write_with!(atomic_spsc);
+        check_write_with!(blocking_spsc);
+        #[cfg(any(target_os = "linux", windows))]
+        check_write_with!(futex_spsc);
+    }
+
+    #[test]
+    fn test_copies() {
+        // Test the channels copying values out
+
+        check_latest(seqlock_spsc::new());
+        check_latest(ticket_spsc::new([0; 4]));
+        check_latest(mutex_spsc::new([0; 4]));
+        // leaked storage would be reported by Miri
+        static STORAGE: isr_spsc::Storage<Payload> = isr_spsc::Storage::new();
+        check_latest(STORAGE.split().unwrap());
+    }
+
+    #[test]
+    fn test_into_inner() {
+        // Test taking the last value back out, which must be neither leaked nor dropped twice
+
+        let (mut r, mut w) = atomic_spsc::new();
+        w.write(vec![1]);
+        assert!(r.read().is_ok());
+        w.write(vec![2]);
+        w.write(vec![3]);
+        assert_eq!(atomic_spsc::into_inner(r, w), Some(vec![3]));
+        let (mut r, mut w) = atomic_spsc::new();
+        w.write(vec![1]);
+        assert!(r.read().is_ok());
+        assert_eq!(atomic_spsc::into_inner(r, w), Some(vec![1]));
+    }
+
+    #[test]
+    fn test_raw() {
+        // Test handles turned into raw pointers and back, whether reference counted or borrowed
+
+        let (r, w) = atomic_spsc::new::<Vec<i32>>();
+        let mut r = unsafe { atomic_spsc::ReadHandle::<Vec<i32>>::from_raw(r.into_raw()) };
+        let mut w = unsafe { atomic_spsc::WriteHandle::<Vec<i32>>::from_raw(w.into_raw()) };
+        w.write(vec![1]);
+        assert_eq!(r.read().as_deref(), Ok(&vec![1]));
+
+        let storage = atomic_spsc::Storage::new();
+        let (r, mut w) = storage.split().unwrap();
+        let mut r = unsafe { atomic_spsc::ReadHandle::<Vec<i32>>::from_raw(r.into_raw()) };
+        w.write(vec![2]);
+        assert_eq!(r.read().as_deref(), Ok(&vec![2]));
+    }
+
+    #[test]
+    fn test_pointers() {
+        // Test the channels handing values over behind a pointer
+
+        check_latest(arc_spsc::new());
+        check_latest(box_spsc::new());
+        check_latest(mpsc_latest::new());
+        check_latest(mpmc_latest::new());
+        check_latest(recycle_spsc::new());
+        check_latest(watch::new());
+    }
+
+    #[test]
+    fn test_slot_pool() {
+        // Test slots filled by one thread and handed back from another
+
+        let pool = slot_pool::Pool::<Payload, 2>::new();
+        thread::scope(|s| {
+            let (tx, rx) = std::sync::mpsc::channel();
+            let pool = &pool;
+            s.spawn(move || {
+                for seq in 1..=WRITES {
+                    let mut slot = loop {
+                        match pool.acquire() {
+                            Some(slot) => break slot,
+                            None => thread::yield_now(),
+                        }
+                    };
+                    *slot = [seq; 4];
+                    tx.send(slot).unwrap();
+                }
+            });
+            for seq in 1..=WRITES {
+                let slot = rx.recv().unwrap();
+                thread::yield_now();
+                assert_eq!(*slot, [seq; 4]);
+            }
+        });
+        assert_eq!(pool.available(), 2);
+    }
+}
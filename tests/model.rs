@@ -0,0 +1,112 @@
+#[cfg(test)]
+mod tests {
+
+    use proptest::prelude::*;
+
+    use rustedrazors::{
+        arc_spsc, atomic_cell_spsc, atomic_spsc, blocking_spsc, box_spsc, double_buffer, isr_spsc,
+        left_right, mpmc_latest, mpsc_latest, mutex_spsc, seqlock_spsc, ticket_spsc, triple_buffer,
+        watch,
+    };
+    use rustedrazors::{Reader, TryReadError, Writer};
+
+    #[derive(Debug, Clone, Copy)]
+    enum Op {
+        Write,
+        WriteWith,
+        Read,
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        prop_oneof![Just(Op::Write), Just(Op::WriteWith), Just(Op::Read),]
+    }
+
+    /// Trivially correct single-threaded latest-value channel.
+    #[derive(Default)]
+    struct Model {
+        // number of values written so far, each value being its own sequence number
+        written: u32,
+        unread: Option<u32>,
+    }
+
+    impl Model {
+        fn write(&mut self) -> u32 {
+            self.written += 1;
+            self.unread = Some(self.written);
+            self.written
+        }
+
+        fn read(&mut self) -> Option<u32> {
+            self.unread.take()
+        }
+    }
+
+    /// Runs `ops` against a variant and the model, checking that every read returns what the
+    /// model does, which is only ever a written value, and never one older than a value already
+    /// read.
+    fn check<R, W>((mut r, mut w): (R, W), ops: &[Op]) -> Result<(), TestCaseError>
+    where
+        R: Reader<Item = u32>,
+        W: Writer<Item = u32>,
+    {
+        let mut model = Model::default();
+        let mut last = 0;
+        for &op in ops {
+            let read = match op {
+                Op::Write => {
+                    w.write(model.write());
+                    continue;
+                }
+                Op::WriteWith => {
+                    let value = model.write();
+                    w.write_with(|v| *v = value);
+                    continue;
+                }
+                Op::Read => match r.read() {
+                    Ok(value) => Some(*value),
+                    Err(err) => {
+                        prop_assert_eq!(err, TryReadError::Empty);
+                        None
+                    }
+                },
+            };
+            if let Some(value) = read {
+                prop_assert!(
+                    value >= 1 && value <= model.written,
+                    "{value} was never written"
+                );
+                prop_assert!(value > last, "{value} read after {last}");
+                last = value;
+            }
+            prop_assert_eq!(read, model.read());
+        }
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        #[cfg_attr(miri, ignore = "proptest needs the file system and runs too many cases")]
+        fn test_model(ops in prop::collection::vec(op(), 0..64)) {
+            // Every latest-value variant must behave like the model
+
+            check(arc_spsc::new(), &ops)?;
+            check(atomic_cell_spsc::new(), &ops)?;
+            check(atomic_spsc::new(), &ops)?;
+            check(blocking_spsc::new(), &ops)?;
+            check(box_spsc::new(), &ops)?;
+            check(double_buffer::new(), &ops)?;
+            #[cfg(any(target_os = "linux", windows))]
+            check(rustedrazors::futex_spsc::new(), &ops)?;
+            check(left_right::new(), &ops)?;
+            check(mpmc_latest::new(), &ops)?;
+            check(mpsc_latest::new(), &ops)?;
+            check(mutex_spsc::new(0), &ops)?;
+            check(seqlock_spsc::new(), &ops)?;
+            check(ticket_spsc::new(0), &ops)?;
+            check(triple_buffer::new(), &ops)?;
+            check(watch::new(), &ops)?;
+            let storage = Box::leak(Box::new(isr_spsc::Storage::new()));
+            check(storage.split().unwrap(), &ops)?;
+        }
+    }
+}
@@ -0,0 +1,320 @@
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+    use std::thread;
+
+    use rustedrazors::mpmc_latest;
+    use rustedrazors::{Reader, TryReadError, WriteError, Writer};
+
+    #[test]
+    fn test_basics() {
+        // Test basic API
+
+        let (mut r, mut w) = mpmc_latest::new::<i32>();
+        let mut r2 = r.clone();
+        let mut w2 = w.clone();
+        assert_eq!(w.reader_count(), 2);
+        assert_eq!(w.writer_count(), 2);
+
+        for _ in 0..5 {
+            let res = r.read();
+            assert!(res.is_err(), "Read should have failed");
+        }
+        assert!(!r.has_changed());
+
+        w.write(22);
+        w2.write(42);
+        assert!(r.has_changed());
+        for r in [&mut r, &mut r2] {
+            let res = r.read();
+            assert_eq!(
+                res.as_deref(),
+                Ok(&42),
+                "Every reader should have read the last value written by any writer"
+            );
+            assert_eq!(res.unwrap().version(), 2);
+            assert!(r.read().is_err(), "Read should have failed");
+        }
+        assert_eq!(
+            r.latest().as_deref(),
+            Some(&42),
+            "Latest value should still be available"
+        );
+    }
+
+    #[test]
+    fn test_read_lease() {
+        // Test that held values are not overwritten while other readers and writers go on
+
+        let (mut r, mut w) = mpmc_latest::new::<i32>();
+        let mut r2 = r.clone();
+
+        w.write(22);
+        let guard = r.read().unwrap();
+        let guard2 = r2.read().unwrap();
+        for i in 0..100 {
+            w.write(i);
+        }
+        assert_eq!(*guard, 22);
+        assert_eq!(*guard2, 22);
+        drop(guard);
+        drop(guard2);
+        assert_eq!(r.read().as_deref(), Ok(&99));
+    }
+
+    #[test]
+    fn test_try_write() {
+        // Test that writes only wait once guards hold every slot, and that try_write fails instead
+
+        const POOL_SIZE: usize = 8;
+
+        let (mut r, mut w) = mpmc_latest::new::<usize>();
+        let mut readers = vec![r.clone(); POOL_SIZE];
+        let mut guards = Vec::new();
+        for (i, reader) in readers.iter_mut().enumerate() {
+            w.write(i);
+            guards.push(reader.read().unwrap());
+        }
+        assert_eq!(
+            w.try_write(100),
+            Err(WriteError::WouldBlock(100)),
+            "Write should have found no free slot"
+        );
+        assert!(
+            guards.iter().enumerate().all(|(i, guard)| **guard == i),
+            "Leased values should not have been overwritten"
+        );
+
+        guards.remove(0);
+        assert_eq!(
+            w.try_write(100),
+            Ok(()),
+            "Write should have found a free slot"
+        );
+        assert_eq!(r.read().as_deref(), Ok(&100));
+        drop(guards);
+        drop(readers);
+        drop(r);
+        assert_eq!(w.try_write(101), Err(WriteError::Disconnected(101)));
+    }
+
+    #[test]
+    fn test_uninit() {
+        // Test that slots are only dropped if they were written
+
+        struct Token {
+            _alive: Arc<()>,
+        }
+
+        let alive = Arc::new(());
+        let (r, mut w) = mpmc_latest::new::<Token>();
+        for _ in 0..3 {
+            w.write(Token {
+                _alive: Arc::clone(&alive),
+            });
+        }
+        drop(r);
+        drop(w);
+        assert_eq!(
+            Arc::strong_count(&alive),
+            1,
+            "Every token should have been dropped"
+        );
+    }
+
+    #[test]
+    fn test_drop() {
+        // Test that a value replaced before being read is dropped right away, and that every
+        // written value is dropped exactly once
+
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static DROPS: [AtomicUsize; 5] = [const { AtomicUsize::new(0) }; 5];
+
+        struct Token(usize);
+
+        impl Drop for Token {
+            fn drop(&mut self) {
+                DROPS[self.0].fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let dropped = || {
+            DROPS
+                .iter()
+                .map(|d| d.load(Ordering::Relaxed))
+                .collect::<Vec<_>>()
+        };
+        let (mut r, mut w) = mpmc_latest::new();
+        let mut w2 = w.clone();
+
+        w.write(Token(0));
+        w2.write(Token(1));
+        assert_eq!(
+            dropped(),
+            [1, 0, 0, 0, 0],
+            "Value replaced by another writer should have been dropped"
+        );
+
+        let guard = r.read().unwrap();
+        assert_eq!(guard.0, 1);
+        w.write(Token(2));
+        assert_eq!(
+            dropped(),
+            [1, 0, 0, 0, 0],
+            "Replaced value should have been kept for the guard"
+        );
+        w2.write(Token(3));
+        assert_eq!(
+            dropped(),
+            [1, 0, 1, 0, 0],
+            "Replaced value should have been dropped"
+        );
+        drop(guard);
+        w.write(Token(4));
+        assert_eq!(
+            dropped(),
+            [1, 0, 1, 1, 0],
+            "Replaced value should have been dropped"
+        );
+
+        drop(r);
+        drop(w);
+        drop(w2);
+        assert_eq!(
+            dropped(),
+            [1; 5],
+            "Remaining values should have gone with the channel"
+        );
+    }
+
+    #[test]
+    fn test_write_panic() {
+        // Test that a write panicking halfway through drops its value and frees its slot instead
+        // of leaking it, so that later writes still find one
+
+        use std::panic::{self, AssertUnwindSafe};
+
+        let alive = Arc::new(());
+        let (mut r, mut w) = mpmc_latest::new::<Vec<Arc<()>>>();
+        w.write(vec![Arc::clone(&alive)]);
+        // more panics than slots in the pool
+        for _ in 0..20 {
+            let res = panic::catch_unwind(AssertUnwindSafe(|| {
+                w.write_with(|value| {
+                    value.push(Arc::clone(&alive));
+                    panic!("write failed");
+                })
+            }));
+            assert!(res.is_err());
+        }
+        assert_eq!(
+            Arc::strong_count(&alive),
+            2,
+            "Values of the panicked writes should have been dropped"
+        );
+        assert_eq!(
+            r.read().map(|value| value.len()),
+            Ok(1),
+            "Value published before the panics should have been read"
+        );
+        assert!(
+            r.read().is_err(),
+            "Panicked writes should not have been published"
+        );
+
+        w.write_with(|value| {
+            value.clear();
+            value.push(Arc::clone(&alive));
+        });
+        assert_eq!(
+            r.read().map(|value| value.len()),
+            Ok(1),
+            "Write after the panics should have been published"
+        );
+    }
+
+    #[test]
+    fn test_disconnected() {
+        // Test that readers only report a dead channel once every writer is gone
+
+        let (mut r, mut w) = mpmc_latest::new::<i32>();
+        let mut w2 = w.clone();
+
+        w.write(22);
+        drop(w);
+        assert_eq!(r.read().as_deref().ok(), Some(&22));
+        assert_eq!(
+            r.read().err(),
+            Some(TryReadError::Empty),
+            "Remaining writer should keep the channel alive"
+        );
+
+        w2.write(42);
+        w2.close();
+        assert_eq!(r.read().as_deref().ok(), Some(&42));
+        assert_eq!(r.read().err(), Some(TryReadError::Closed));
+    }
+
+    #[test]
+    fn test_threading() {
+        // Test mpmc_latest with several writer and reader threads with multiple iterations.
+        // Every reader must see increasing versions, and values of each writer in the order it
+        // wrote them.
+
+        const WRITERS: i32 = 3;
+
+        let (r, w) = mpmc_latest::new::<(i32, i32)>();
+
+        let readers: Vec<_> = (0..3)
+            .map(|_| {
+                let mut r = r.clone();
+                thread::spawn(move || {
+                    let mut version = 0;
+                    let mut last = [-1; WRITERS as usize];
+                    loop {
+                        match r.read() {
+                            Ok(value) => {
+                                assert!(value.version() > version, "Versions should increase");
+                                version = value.version();
+                                let (writer, i) = *value;
+                                assert!(
+                                    i > last[writer as usize],
+                                    "Values should have been read in order"
+                                );
+                                last[writer as usize] = i;
+                            }
+                            Err(TryReadError::Empty) => {}
+                            Err(_) => break,
+                        }
+                    }
+                })
+            })
+            .collect();
+        drop(r);
+        let writers: Vec<_> = (0..WRITERS)
+            .map(|writer| {
+                let mut w = w.clone();
+                thread::spawn(move || {
+                    for i in 0..10000 {
+                        w.write((writer, i));
+                    }
+                })
+            })
+            .collect();
+        drop(w);
+        for write_res in writers {
+            assert!(
+                write_res.join().is_ok(),
+                "Writer thread should have ended peacefully"
+            );
+        }
+        for read_res in readers {
+            assert!(
+                read_res.join().is_ok(),
+                "Reader thread should have ended peacefully"
+            );
+        }
+    }
+}
@@ -0,0 +1,304 @@
+#[cfg(test)]
+mod tests {
+
+    use std::sync::{Arc, Barrier};
+    use std::thread;
+
+    use rustedrazors::mpsc_latest;
+    use rustedrazors::{Reader, TryReadError, WriteError, Writer};
+
+    #[test]
+    fn test_basics() {
+        // Test basic API
+
+        let (mut r, mut w) = mpsc_latest::new::<i32>();
+        let mut w2 = w.clone();
+        assert_eq!(w.writer_count(), 2);
+
+        for _ in 0..5 {
+            let res = r.read();
+            assert!(res.is_err(), "Read should have failed");
+        }
+
+        w.write(22);
+        w2.write(42);
+        assert_eq!(
+            r.read().as_deref(),
+            Ok(&42),
+            "Read should have returned the last value written by any writer"
+        );
+        assert_eq!(r.overwritten(), 1);
+
+        for _ in 0..5 {
+            let res = r.read();
+            assert!(res.is_err(), "Read should have failed");
+        }
+    }
+
+    #[test]
+    fn test_read_lease() {
+        // Test that held values are not overwritten by any writer
+
+        let (mut r, mut w) = mpsc_latest::new::<i32>();
+        let mut writers: Vec<_> = (0..4).map(|_| w.clone()).collect();
+
+        w.write(22);
+        let guard = r.read().unwrap();
+        for (i, w) in writers.iter_mut().enumerate() {
+            for j in 0..10 {
+                w.write(i as i32 * 10 + j);
+            }
+        }
+        assert_eq!(*guard, 22);
+        drop(guard);
+        assert_eq!(r.read().as_deref(), Ok(&39));
+    }
+
+    #[test]
+    fn test_try_write() {
+        // Test that writes only wait once the guard, the waiting value and concurrent writes hold
+        // every slot, and that try_write fails instead
+
+        const WRITING: usize = 6;
+
+        let (mut r, mut w) = mpsc_latest::new::<i32>();
+        w.write(1);
+        let guard = r.read().unwrap();
+        w.write(2);
+
+        let holding = Barrier::new(WRITING + 1);
+        let release = Barrier::new(WRITING + 1);
+        thread::scope(|s| {
+            for i in 0..WRITING {
+                let mut w = w.clone();
+                let (holding, release) = (&holding, &release);
+                s.spawn(move || {
+                    w.write_with(|value| {
+                        holding.wait();
+                        release.wait();
+                        *value = 10 + i as i32;
+                    })
+                });
+            }
+            holding.wait();
+            assert_eq!(
+                w.try_write(3),
+                Err(WriteError::WouldBlock(3)),
+                "Write should have found no free slot"
+            );
+            assert_eq!(*guard, 1, "Leased value should not have been overwritten");
+            release.wait();
+        });
+
+        assert_eq!(
+            w.try_write(3),
+            Ok(()),
+            "Write should have found a free slot"
+        );
+        drop(guard);
+        assert_eq!(r.read().as_deref(), Ok(&3));
+        assert_eq!(r.overwritten(), 1 + WRITING as u64);
+    }
+
+    #[test]
+    fn test_uninit() {
+        // Test that slots are only dropped if they were written
+
+        struct Token {
+            _alive: Arc<()>,
+        }
+
+        let alive = Arc::new(());
+        let (r, mut w) = mpsc_latest::new::<Token>();
+        for _ in 0..3 {
+            w.write(Token {
+                _alive: Arc::clone(&alive),
+            });
+        }
+        drop(r);
+        drop(w);
+        assert_eq!(
+            Arc::strong_count(&alive),
+            1,
+            "Every token should have been dropped"
+        );
+    }
+
+    #[test]
+    fn test_drop() {
+        // Test that a value replaced before being read is dropped right away, and that every
+        // written value is dropped exactly once
+
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static DROPS: [AtomicUsize; 5] = [const { AtomicUsize::new(0) }; 5];
+
+        struct Token(usize);
+
+        impl Drop for Token {
+            fn drop(&mut self) {
+                DROPS[self.0].fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let dropped = || {
+            DROPS
+                .iter()
+                .map(|d| d.load(Ordering::Relaxed))
+                .collect::<Vec<_>>()
+        };
+        let (mut r, mut w) = mpsc_latest::new();
+        let mut w2 = w.clone();
+
+        w.write(Token(0));
+        w2.write(Token(1));
+        assert_eq!(
+            dropped(),
+            [1, 0, 0, 0, 0],
+            "Value replaced by another writer should have been dropped"
+        );
+
+        let guard = r.read().unwrap();
+        assert_eq!(guard.0, 1);
+        w.write(Token(2));
+        w2.write(Token(3));
+        assert_eq!(
+            dropped(),
+            [1, 0, 1, 0, 0],
+            "Replaced value should have been dropped"
+        );
+        drop(guard);
+        w.write(Token(4));
+        assert_eq!(
+            dropped(),
+            [1, 0, 1, 1, 0],
+            "Replaced value should have been dropped"
+        );
+
+        drop(r);
+        drop(w);
+        drop(w2);
+        assert_eq!(
+            dropped(),
+            [1; 5],
+            "Remaining values should have gone with the channel"
+        );
+    }
+
+    #[test]
+    fn test_write_panic() {
+        // Test that a write panicking halfway through drops its value and frees its slot instead
+        // of leaking it, so that later writes still find one
+
+        use std::panic::{self, AssertUnwindSafe};
+
+        let alive = Arc::new(());
+        let (mut r, mut w) = mpsc_latest::new::<Vec<Arc<()>>>();
+        w.write(vec![Arc::clone(&alive)]);
+        // more panics than slots in the pool
+        for _ in 0..20 {
+            let res = panic::catch_unwind(AssertUnwindSafe(|| {
+                w.write_with(|value| {
+                    value.push(Arc::clone(&alive));
+                    panic!("write failed");
+                })
+            }));
+            assert!(res.is_err());
+        }
+        assert_eq!(
+            Arc::strong_count(&alive),
+            2,
+            "Values of the panicked writes should have been dropped"
+        );
+        assert_eq!(
+            r.read().map(|value| value.len()),
+            Ok(1),
+            "Value published before the panics should have been read"
+        );
+        assert!(
+            r.read().is_err(),
+            "Panicked writes should not have been published"
+        );
+
+        w.write_with(|value| {
+            value.clear();
+            value.push(Arc::clone(&alive));
+        });
+        assert_eq!(
+            r.read().map(|value| value.len()),
+            Ok(1),
+            "Write after the panics should have been published"
+        );
+    }
+
+    #[test]
+    fn test_disconnected() {
+        // Test that the reader only reports a dead channel once every writer is gone
+
+        let (mut r, mut w) = mpsc_latest::new::<i32>();
+        let mut w2 = w.clone();
+
+        w.write(22);
+        drop(w);
+        assert_eq!(r.read().as_deref().ok(), Some(&22));
+        assert_eq!(
+            r.read().err(),
+            Some(TryReadError::Empty),
+            "Remaining writer should keep the channel alive"
+        );
+
+        w2.write(42);
+        w2.close();
+        assert_eq!(r.read().as_deref().ok(), Some(&42));
+        assert_eq!(r.read().err(), Some(TryReadError::Closed));
+    }
+
+    #[test]
+    fn test_threading() {
+        // Test mpsc_latest with several writer threads with multiple iterations.
+        // Values of each writer must be read in the order it wrote them.
+
+        const WRITERS: i32 = 4;
+
+        let (mut r, w) = mpsc_latest::new::<(i32, i32)>();
+
+        let read_res = thread::spawn(move || {
+            let mut last = [-1; WRITERS as usize];
+            loop {
+                match r.read() {
+                    Ok(value) => {
+                        let (writer, i) = *value;
+                        assert!(
+                            i > last[writer as usize],
+                            "Values should have been read in order"
+                        );
+                        last[writer as usize] = i;
+                    }
+                    Err(TryReadError::Empty) => {}
+                    Err(_) => break,
+                }
+            }
+        });
+        let writers: Vec<_> = (0..WRITERS)
+            .map(|writer| {
+                let mut w = w.clone();
+                thread::spawn(move || {
+                    for i in 0..10000 {
+                        w.write((writer, i));
+                    }
+                })
+            })
+            .collect();
+        drop(w);
+        for write_res in writers {
+            assert!(
+                write_res.join().is_ok(),
+                "Writer thread should have ended peacefully"
+            );
+        }
+        assert!(
+            read_res.join().is_ok(),
+            "Reader thread should have ended peacefully"
+        );
+    }
+}
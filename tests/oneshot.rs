@@ -0,0 +1,110 @@
+#[cfg(test)]
+mod tests {
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use std::time::Duration;
+
+    use rustedrazors::oneshot;
+    use rustedrazors::TryReadError;
+
+    #[test]
+    fn test_basics() {
+        // Test basic API
+
+        let (mut r, w) = oneshot::new::<String>();
+
+        assert_eq!(r.try_recv(), Err(TryReadError::Empty));
+        assert!(!r.is_ready());
+
+        w.send("hello".to_string()).unwrap();
+        assert!(r.is_ready());
+        assert_eq!(r.try_recv().as_deref(), Ok("hello"));
+        assert_eq!(
+            r.try_recv(),
+            Err(TryReadError::Closed),
+            "Value should have been received already"
+        );
+    }
+
+    #[test]
+    fn test_timeout() {
+        // Test that a timed out receive can be retried
+
+        let (mut r, w) = oneshot::new::<i32>();
+
+        assert_eq!(
+            r.recv_timeout(Duration::from_millis(10)),
+            Err(TryReadError::Empty)
+        );
+        w.send(22).unwrap();
+        assert_eq!(r.recv_timeout(Duration::from_millis(10)), Ok(22));
+    }
+
+    #[test]
+    fn test_disconnected() {
+        // Test that dropping either handle is reported to the other one
+
+        let (r, w) = oneshot::new::<i32>();
+        drop(w);
+        assert_eq!(r.recv(), Err(TryReadError::Disconnected));
+
+        let (r, w) = oneshot::new::<i32>();
+        assert!(w.is_reader_alive());
+        drop(r);
+        assert!(!w.is_reader_alive(), "Reader should have been dropped");
+        assert_eq!(w.send(42), Err(42), "Value should have been handed back");
+    }
+
+    #[test]
+    fn test_drop() {
+        // Test that a sent value is dropped exactly once, received or not
+
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        struct Counted;
+
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                DROPS.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let (r, w) = oneshot::new::<Counted>();
+        assert!(w.send(Counted).is_ok());
+        assert_eq!(DROPS.load(Ordering::Relaxed), 0);
+        drop(r);
+        assert_eq!(DROPS.load(Ordering::Relaxed), 1);
+
+        let (r, w) = oneshot::new::<Counted>();
+        assert!(w.send(Counted).is_ok());
+        drop(r.recv());
+        assert_eq!(DROPS.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn test_threading() {
+        // Test oneshot across threads.
+        // Every worker must hand its result back through its own channel.
+
+        let workers: Vec<_> = (0..8)
+            .map(|i| {
+                let (r, w) = oneshot::new::<u64>();
+                thread::spawn(move || {
+                    let sum = (0..=i * 1000).sum();
+                    w.send(sum).unwrap();
+                });
+                r
+            })
+            .collect();
+
+        for (i, r) in workers.into_iter().enumerate() {
+            let i = i as u64 * 1000;
+            assert_eq!(
+                r.recv(),
+                Ok(i * (i + 1) / 2),
+                "Worker should have sent its result"
+            );
+        }
+    }
+}
@@ -0,0 +1,94 @@
+#[cfg(test)]
+mod tests {
+
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use rustedrazors::pipeline::Pipeline;
+    use rustedrazors::{atomic_spsc, mutex_spsc, TryReadError, Writer};
+
+    #[test]
+    fn test_stages() {
+        // Test values going through every stage, then the close of the source reaching the sink
+
+        let (r, mut w) = atomic_spsc::new::<i32>();
+        let (mut out, sink) = mutex_spsc::new(String::new());
+        let running = Pipeline::source(r)
+            .map(|value| value * 2)
+            .filter(|value| *value > 10)
+            .map(|value| format!("<{value}>"))
+            .sink(sink);
+        assert!(format!("{running:?}").contains("stages: 4"));
+
+        w.write(3);
+        w.write(21);
+        assert_eq!(
+            out.read_timeout(Duration::from_secs(5)).as_deref().cloned(),
+            Ok(String::from("<42>")),
+            "Only the value passing the filter should have reached the sink"
+        );
+
+        w.close();
+        running.join().unwrap();
+        assert_eq!(out.read_blocking().err(), Some(TryReadError::Closed));
+    }
+
+    #[test]
+    fn test_into_reader() {
+        // Test a consumer reading the last stage itself, and a source dropped without closing
+
+        let (r, mut w) = atomic_spsc::new::<i32>();
+        let (mut out, running) = Pipeline::source(r).map(|value| value + 1).into_reader();
+
+        w.write(1);
+        assert_eq!(out.read_timeout(Duration::from_secs(5)).as_deref(), Ok(&2));
+
+        drop(w);
+        assert_eq!(
+            out.read_blocking().err(),
+            Some(TryReadError::Disconnected),
+            "The source should have been seen dropped"
+        );
+        running.join().unwrap();
+    }
+
+    #[test]
+    fn test_upstream_shutdown() {
+        // Test a dropped sink reader shutting down every stage up to the source
+
+        let (r, w) = atomic_spsc::new::<i32>();
+        let (out, sink) = atomic_spsc::new::<i32>();
+        let running = Pipeline::source(r)
+            .map(|value| value + 1)
+            .filter(|_| true)
+            .sink(sink);
+
+        drop(out);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while w.is_reader_alive() {
+            assert!(
+                Instant::now() < deadline,
+                "The source should have been released"
+            );
+            thread::sleep(Duration::from_millis(1));
+        }
+        running.join().unwrap();
+    }
+
+    #[test]
+    fn test_panic() {
+        // Test a panicking stage, reported by join and seen downstream as a dropped writer
+
+        let (r, mut w) = atomic_spsc::new::<i32>();
+        let (mut out, running) = Pipeline::source(r)
+            .map(|_: &i32| -> i32 { panic!("stage failed") })
+            .into_reader();
+
+        w.write(1);
+        assert_eq!(out.read_blocking().err(), Some(TryReadError::Disconnected));
+        assert!(
+            running.join().is_err(),
+            "The panic should have been reported"
+        );
+    }
+}
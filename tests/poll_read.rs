@@ -0,0 +1,67 @@
+#[cfg(all(test, feature = "async"))]
+mod tests {
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake, Waker};
+
+    use rustedrazors::{atomic_spsc, blocking_spsc, ticket_spsc};
+    use rustedrazors::{TryReadError, Writer};
+
+    /// Waker counting how many times it was woken.
+    struct Counter(AtomicUsize);
+
+    impl Wake for Counter {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    macro_rules! check_poll_read {
+        ($module:ident $(, $init:expr)?) => {{
+            let counter = Arc::new(Counter(AtomicUsize::new(0)));
+            let waker = Waker::from(Arc::clone(&counter));
+            let mut cx = Context::from_waker(&waker);
+            let (mut r, mut w) = $module::new($($init)?);
+
+            assert!(r.poll_read(&mut cx).is_pending());
+            assert_eq!(counter.0.load(Ordering::Relaxed), 0);
+
+            w.write(1);
+            assert_eq!(
+                counter.0.load(Ordering::Relaxed),
+                1,
+                "Task should have been woken up"
+            );
+            match r.poll_read(&mut cx) {
+                Poll::Ready(Ok(guard)) => assert_eq!(*guard, 1),
+                _ => panic!("Value should have been read"),
+            }
+
+            w.write(2);
+            assert_eq!(
+                counter.0.load(Ordering::Relaxed),
+                1,
+                "Task should only be woken up after a pending poll"
+            );
+            assert!(r.poll_read(&mut cx).is_ready());
+
+            assert!(r.poll_read(&mut cx).is_pending());
+            drop(w);
+            assert_eq!(counter.0.load(Ordering::Relaxed), 2);
+            assert!(matches!(
+                r.poll_read(&mut cx),
+                Poll::Ready(Err(TryReadError::Disconnected))
+            ));
+        }};
+    }
+
+    #[test]
+    fn test_poll_read() {
+        // Test that poll_read registers the task and is woken by writes and disconnection
+
+        check_poll_read!(atomic_spsc);
+        check_poll_read!(blocking_spsc);
+        check_poll_read!(ticket_spsc, 0);
+    }
+}
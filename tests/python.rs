@@ -0,0 +1,174 @@
+#[cfg(all(test, feature = "pyo3"))]
+mod tests {
+
+    use std::ffi::CStr;
+    use std::thread;
+    use std::time::Duration;
+
+    use pyo3::prelude::*;
+    use pyo3::types::{PyDict, PyModule};
+
+    use rustedrazors::atomic_spsc;
+    use rustedrazors::python::{self, PyReader};
+    use rustedrazors::Writer;
+
+    /// Runs `code` with the bindings imported as `rr`, and `reader` bound if given.
+    fn run(code: &CStr, reader: Option<PyReader>) {
+        Python::initialize();
+        Python::attach(|py| {
+            let module = PyModule::new(py, "rustedrazors").unwrap();
+            python::register(&module).unwrap();
+            let globals = PyDict::new(py);
+            globals.set_item("rr", module).unwrap();
+            if let Some(reader) = reader {
+                globals
+                    .set_item("reader", Py::new(py, reader).unwrap())
+                    .unwrap();
+            }
+            if let Err(err) = py.run(code, Some(&globals), None) {
+                panic!("{err}");
+            }
+        });
+    }
+
+    #[test]
+    fn test_basics() {
+        // Test basic API
+
+        run(
+            cr#"
+r, w = rr.channel()
+assert r.read() is None
+w.write(b"frame 1")
+w.write(b"frame 2")
+assert r.read() == b"frame 2"
+assert r.read() is None
+assert r.overwritten() == 1
+assert w.is_reader_alive()
+assert r.read_blocking(timeout=0.01) is None
+w.write(b"frame 3")
+assert r.read_blocking(timeout=0.01) == b"frame 3"
+w.close()
+try:
+    r.read()
+    assert False, "read should have raised"
+except rr.ClosedError:
+    pass
+try:
+    w.write(b"frame 4")
+    assert False, "write should have raised"
+except rr.ClosedError:
+    pass
+"#,
+            None,
+        );
+    }
+
+    #[test]
+    fn test_disconnected() {
+        // Test that dropping the writer without closing raises a distinct error
+
+        let (r, mut w) = atomic_spsc::new::<Vec<u8>>();
+        w.write(b"last".to_vec());
+        drop(w);
+        run(
+            cr#"
+assert reader.read() == b"last"
+try:
+    reader.read()
+    assert False, "read should have raised"
+except rr.DisconnectedError:
+    pass
+except rr.ClosedError:
+    assert False, "read should have raised DisconnectedError"
+assert isinstance(rr.DisconnectedError(), EOFError)
+"#,
+            Some(r.into()),
+        );
+    }
+
+    #[test]
+    fn test_rust_producer() {
+        // Test that a Python thread can iterate over frames written by Rust
+
+        let (r, mut w) = atomic_spsc::new::<Vec<u8>>();
+        let producer = thread::spawn(move || {
+            for i in 1..=100u8 {
+                w.write(vec![i; 16]);
+                thread::sleep(Duration::from_micros(100));
+            }
+            w.close();
+        });
+        run(
+            cr#"
+last = 0
+for frame in reader:
+    assert len(frame) == 16 and len(set(frame)) == 1
+    assert frame[0] > last
+    last = frame[0]
+assert last == 100
+"#,
+            Some(r.into()),
+        );
+        producer.join().unwrap();
+    }
+
+    #[test]
+    fn test_release_gil() {
+        // Test that blocking reads let other Python threads run
+
+        run(
+            cr#"
+import threading
+import time
+
+def write():
+    time.sleep(0.05)
+    w.write(b"frame")
+
+r, w = rr.channel()
+# the writer thread needs the GIL back after sleeping, so the read can only succeed if it was
+# released
+writer = threading.Thread(target=write)
+writer.start()
+assert r.read_blocking(timeout=1.0) == b"frame"
+writer.join()
+"#,
+            None,
+        );
+    }
+
+    #[test]
+    fn test_python_producer() {
+        // Test that reads do not keep a Python writer from publishing while another read waits
+
+        run(
+            cr#"
+import threading
+import time
+
+def read():
+    frames.append(r.read_blocking(timeout=5.0))
+
+def write():
+    time.sleep(0.1)
+    w.write(b"frame")
+
+r, w = rr.channel()
+frames = []
+reader = threading.Thread(target=read)
+reader.start()
+time.sleep(0.05)
+writer = threading.Thread(target=write)
+writer.start()
+# waits for the blocked reader to let go of the handle, which takes the writer getting the GIL
+assert r.overwritten() == 0
+assert r.read() is None
+reader.join()
+writer.join()
+assert frames == [b"frame"]
+"#,
+            None,
+        );
+    }
+}
@@ -0,0 +1,188 @@
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+    use std::thread;
+
+    use rustedrazors::recycle_spsc;
+    use rustedrazors::{Reader, TryReadError, Writer};
+
+    #[test]
+    fn test_basics() {
+        // Test basic API
+
+        let (mut r, mut w) = recycle_spsc::new::<i32>();
+
+        for _ in 0..5 {
+            let res = r.read();
+            assert!(res.is_err(), "Read should have failed");
+        }
+
+        w.write(22);
+
+        let res = r.read();
+        assert_eq!(
+            res.as_deref(),
+            Ok(&22),
+            "Read should have returned the value previously written"
+        );
+        drop(res);
+
+        for _ in 0..5 {
+            let res = r.read();
+            assert!(res.is_err(), "Read should have failed");
+        }
+    }
+
+    #[test]
+    fn test_recycle() {
+        // Test that the boxes read are handed back to the writer and written into again
+
+        let (mut r, mut w) = recycle_spsc::new::<Vec<u8>>();
+
+        w.write_with(|frame| frame.extend_from_slice(b"first"));
+        let frame = r.read().unwrap();
+        let addr: *const Vec<u8> = &*frame;
+        let data = frame.as_ptr();
+        assert_eq!(frame.as_slice(), b"first");
+        drop(frame);
+
+        w.write_with(|frame| {
+            assert_eq!(frame.as_slice(), b"first", "Frame should have been reused");
+            frame.clear();
+            frame.extend_from_slice(b"again");
+        });
+        let frame = r.read().unwrap();
+        assert!(std::ptr::eq(&*frame, addr), "Box should have been reused");
+        assert_eq!(frame.as_ptr(), data, "Buffer should have been reused");
+        assert_eq!(frame.as_slice(), b"again");
+        assert_eq!(w.allocations(), 1);
+    }
+
+    #[test]
+    fn test_steady() {
+        // Test that a steady flow of values stops allocating, whether values are read or not
+
+        let (mut r, mut w) = recycle_spsc::new::<Vec<u8>>();
+
+        for i in 0..100u8 {
+            w.write_with(|frame| {
+                frame.clear();
+                frame.push(i);
+            });
+            if i % 3 != 0 {
+                let frame = r.read().unwrap();
+                assert_eq!(frame.as_slice(), &[i]);
+            }
+        }
+        assert!(
+            w.allocations() <= 2,
+            "Writer allocated {} boxes",
+            w.allocations()
+        );
+        assert_eq!(w.overwritten(), r.overwritten());
+        assert_eq!(r.overwritten(), 33);
+    }
+
+    #[test]
+    fn test_into_box() {
+        // Test that a box kept by the reader is not handed back
+
+        let (mut r, mut w) = recycle_spsc::new::<i32>();
+
+        w.write(22);
+        let value = r.read().unwrap().into_box();
+        assert_eq!(*value, 22);
+        w.write(42);
+        assert_eq!(w.allocations(), 2, "Kept box should not have been reused");
+
+        let mut guard = r.read().unwrap();
+        *guard += 1;
+        drop(guard);
+        w.write_with(|value| assert_eq!(*value, 43, "Changes of the reader are handed back"));
+    }
+
+    #[test]
+    fn test_drop() {
+        // Test that pending, returned and spare values are dropped
+
+        let alive = Arc::new(());
+        let (mut r, mut w) = recycle_spsc::new::<Arc<()>>();
+
+        w.write(Arc::clone(&alive));
+        w.write(Arc::clone(&alive));
+        assert_eq!(
+            Arc::strong_count(&alive),
+            3,
+            "Replaced value should be kept"
+        );
+        drop(r.read());
+        assert_eq!(Arc::strong_count(&alive), 3, "Read value should be kept");
+
+        w.write(Arc::new(()));
+        assert_eq!(
+            Arc::strong_count(&alive),
+            2,
+            "Content of the reused box should have been replaced"
+        );
+        let guard = r.read();
+        drop(w);
+        assert_eq!(
+            Arc::strong_count(&alive),
+            1,
+            "Spare value should be dropped"
+        );
+        drop(guard);
+        drop(r);
+        assert_eq!(Arc::strong_count(&alive), 1);
+    }
+
+    #[test]
+    fn test_close() {
+        // Test that the last value is drained before the reader sees the channel closed
+
+        let (mut r, mut w) = recycle_spsc::new::<i32>();
+
+        w.write(1);
+        w.close();
+        assert_eq!(r.read().as_deref(), Ok(&1));
+        assert_eq!(r.read().err(), Some(TryReadError::Closed));
+        assert!(format!("{r:?}").starts_with("recycle_spsc::ReadHandle"));
+    }
+
+    #[test]
+    fn test_threads() {
+        // Test frames flowing between threads, each one whole
+
+        let (mut r, mut w) = recycle_spsc::new::<Vec<u64>>();
+
+        let writer = thread::spawn(move || {
+            for i in 1..=10_000u64 {
+                w.write_with(|frame| {
+                    frame.clear();
+                    frame.extend((0..16).map(|_| i));
+                });
+            }
+            w.close();
+        });
+
+        let mut last = 0;
+        loop {
+            match r.read() {
+                Ok(frame) => {
+                    assert_eq!(frame.len(), 16);
+                    assert!(frame.iter().all(|&value| value == frame[0]), "Torn frame");
+                    assert!(frame[0] > last, "Frames should arrive in order");
+                    last = frame[0];
+                }
+                Err(TryReadError::Empty) => thread::yield_now(),
+                Err(err) => {
+                    assert_eq!(err, TryReadError::Closed);
+                    break;
+                }
+            }
+        }
+        assert_eq!(last, 10_000);
+        writer.join().unwrap();
+    }
+}
@@ -0,0 +1,177 @@
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+    use std::thread;
+
+    use rustedrazors::ring_spsc;
+    use rustedrazors::{Reader, TryReadError, WriteError, Writer};
+
+    #[test]
+    fn test_basics() {
+        // Test basic API
+
+        let (mut r, mut w) = ring_spsc::new::<i32>(4);
+
+        for _ in 0..5 {
+            let res = r.read();
+            assert!(res.is_err(), "Read should have failed");
+        }
+
+        w.write(22);
+        w.write(42);
+        assert_eq!(r.len(), 2);
+
+        assert_eq!(
+            r.read().as_deref(),
+            Ok(&22),
+            "Read should have returned the oldest value"
+        );
+        assert_eq!(r.pop(), Some(42));
+
+        for _ in 0..5 {
+            let res = r.read();
+            assert!(res.is_err(), "Read should have failed");
+        }
+        assert!(r.is_empty());
+    }
+
+    #[test]
+    fn test_full() {
+        // Test that pushing to a full queue hands the value back
+
+        let (mut r, mut w) = ring_spsc::new::<i32>(3);
+        assert_eq!(w.capacity(), 3);
+
+        for i in 0..3 {
+            assert_eq!(w.try_push(i), Ok(()));
+        }
+        assert_eq!(w.try_push(3), Err(3), "Queue should have been full");
+
+        assert_eq!(r.pop(), Some(0));
+        assert_eq!(w.try_push(3), Ok(()), "Popping should have made room");
+        for i in 1..4 {
+            assert_eq!(r.pop(), Some(i), "Values should have been read in order");
+        }
+    }
+
+    #[test]
+    fn test_write_with() {
+        // Test that in place writes start from the default value
+
+        let (mut r, mut w) = ring_spsc::new::<Vec<i32>>(2);
+
+        w.write(vec![1, 2, 3]);
+        let _ = r.read();
+        w.write_with(|value| value.push(42));
+        assert_eq!(r.read().as_deref(), Ok(&vec![42]));
+    }
+
+    #[test]
+    fn test_disconnected() {
+        // Test that every pending value is read before reporting the dead writer
+
+        let (mut r, mut w) = ring_spsc::new::<i32>(4);
+
+        assert_eq!(r.read().err(), Some(TryReadError::Empty));
+        w.write(22);
+        w.write(42);
+        drop(w);
+
+        assert_eq!(r.read().as_deref().ok(), Some(&22));
+        assert_eq!(r.read().as_deref().ok(), Some(&42));
+        assert_eq!(r.read().err(), Some(TryReadError::Disconnected));
+    }
+
+    #[test]
+    fn test_reader_gone() {
+        // Test that a writer blocked on a full queue gives up once the reader is gone
+
+        let (r, mut w) = ring_spsc::new::<i32>(1);
+        w.write(22);
+
+        let write_res = thread::spawn(move || w.write(42));
+        drop(r);
+        assert!(
+            write_res.join().is_ok(),
+            "Writer thread should have ended peacefully"
+        );
+    }
+
+    #[test]
+    fn test_drop() {
+        // Test that values never popped are dropped with the queue
+
+        let alive = Arc::new(());
+        let (mut r, mut w) = ring_spsc::new::<Arc<()>>(4);
+        for _ in 0..3 {
+            w.write(Arc::clone(&alive));
+        }
+        drop(r.pop());
+        assert_eq!(Arc::strong_count(&alive), 3);
+
+        drop(r);
+        drop(w);
+        assert_eq!(
+            Arc::strong_count(&alive),
+            1,
+            "Pending values should have been dropped"
+        );
+    }
+
+    #[test]
+    fn test_threading() {
+        // Test ring_spsc with i32 across threads with multiple iterations.
+        // Every value must be read, in the order it was written.
+
+        let (mut r, mut w) = ring_spsc::new::<i32>(16);
+
+        let read_res = thread::spawn(move || {
+            let mut next = 0;
+            loop {
+                match r.read() {
+                    Ok(value) => {
+                        assert_eq!(*value, next, "Values should have been read in order");
+                        next += 1;
+                    }
+                    Err(TryReadError::Empty) => {}
+                    Err(_) => break,
+                }
+            }
+            next
+        });
+        let write_res = thread::spawn(move || {
+            for i in 0..10000 {
+                w.write(i);
+            }
+        })
+        .join();
+        assert!(
+            write_res.is_ok(),
+            "Writer thread should have ended peacefully"
+        );
+        assert_eq!(
+            read_res.join().ok(),
+            Some(10000),
+            "Every value should have been read"
+        );
+    }
+
+    #[test]
+    fn test_try_write() {
+        // Test the writes that fail instead of waiting for room in the queue
+
+        let (mut r, mut w) = ring_spsc::new(1);
+        assert_eq!(w.try_write(1), Ok(()));
+        assert_eq!(
+            w.try_write(2),
+            Err(WriteError::WouldBlock(2)),
+            "Queue should have been full"
+        );
+        assert_eq!(r.read().as_deref(), Ok(&1));
+        assert_eq!(w.try_write(2), Ok(()));
+
+        drop(r);
+        assert_eq!(w.try_write(3), Err(WriteError::Disconnected(3)));
+    }
+}
@@ -0,0 +1,45 @@
+#[cfg(all(test, feature = "rtic"))]
+mod tests {
+
+    use std::ptr;
+    use std::thread;
+
+    use rustedrazors::rtic::{Consumer, Mailbox, Producer};
+    use rustedrazors::{Reader, TryReadError, Writer};
+
+    #[test]
+    fn test_mailbox() {
+        // Test a mailbox split the way RTIC hands out the local resources of `init`
+
+        // RTIC keeps local resources in statics, which unlike a leaked box Miri does not report
+        static mut MAILBOX: Mailbox<(u32, u32)> = Mailbox::new();
+        // Safety: the mailbox is only borrowed here, like `init` does once
+        let mailbox: &'static mut Mailbox<(u32, u32)> = unsafe { &mut *ptr::addr_of_mut!(MAILBOX) };
+        let (mut consumer, mut producer): (Consumer<_>, Producer<_>) = mailbox.split();
+
+        // the hardware task preempts `idle` at will
+        let task = thread::spawn(move || {
+            for i in 1..=1_000 {
+                producer.write((i, i * 2));
+            }
+            producer.close();
+        });
+        let mut last = 0;
+        loop {
+            match consumer.read() {
+                Ok(value) => {
+                    assert!(value.0 > last, "Values should have been read in order");
+                    assert_eq!(value.1, value.0 * 2);
+                    last = value.0;
+                }
+                Err(TryReadError::Empty) => thread::yield_now(),
+                Err(err) => {
+                    assert_eq!(err, TryReadError::Closed);
+                    break;
+                }
+            }
+        }
+        assert_eq!(last, 1_000, "Last value should have been read");
+        task.join().unwrap();
+    }
+}
@@ -0,0 +1,98 @@
+#[cfg(test)]
+mod tests {
+
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use rustedrazors::sampler::Sampler;
+    use rustedrazors::{atomic_spsc, Reader, TryReadError, Writer};
+
+    #[test]
+    fn test_interval() {
+        // Test samples published right away, then once per interval
+
+        let (mut r, w) = atomic_spsc::new::<Instant>();
+        let start = Instant::now();
+        let sampler = Sampler::spawn(w, Duration::from_millis(20), Instant::now);
+
+        let first = *r.read_timeout(Duration::from_secs(5)).unwrap();
+        assert!(
+            first - start < Duration::from_millis(20),
+            "The first sample should have been published right away"
+        );
+        let mut last = first;
+        for _ in 0..3 {
+            let sample = *r.read_timeout(Duration::from_secs(5)).unwrap();
+            assert!(
+                sample - last >= Duration::from_millis(15),
+                "Samples should be an interval apart"
+            );
+            last = sample;
+        }
+        assert!(format!("{sampler:?}").contains("finished: false"));
+    }
+
+    #[test]
+    fn test_drop() {
+        // Test that dropping the sampler stops it right away and drops the writer
+
+        let (mut r, w) = atomic_spsc::new::<u32>();
+        let sampler = Sampler::spawn(w, Duration::from_secs(60), || 1);
+        assert_eq!(r.read_timeout(Duration::from_secs(5)).as_deref(), Ok(&1));
+
+        let start = Instant::now();
+        drop(sampler);
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "The thread should not have waited for the next sample"
+        );
+        assert_eq!(r.read().err(), Some(TryReadError::Disconnected));
+    }
+
+    #[test]
+    fn test_stop() {
+        // Test the writer handed back by stop, then closed for the reader
+
+        let (mut r, w) = atomic_spsc::new::<u32>();
+        let mut count = 0;
+        let sampler = Sampler::spawn(w, Duration::from_millis(1), move || {
+            count += 1;
+            count
+        });
+        assert!(r.read_timeout(Duration::from_secs(5)).is_ok());
+
+        let w = sampler.stop().unwrap();
+        w.close();
+        while r.read().is_ok() {}
+        assert_eq!(r.read().err(), Some(TryReadError::Closed));
+    }
+
+    #[test]
+    fn test_reader_dropped() {
+        // Test that the sampler stops on its own once the reader is gone
+
+        let (r, w) = atomic_spsc::new::<u32>();
+        let sampler = Sampler::spawn(w, Duration::from_millis(1), || 0);
+        drop(r);
+
+        let start = Instant::now();
+        while !sampler.is_finished() {
+            assert!(
+                start.elapsed() < Duration::from_secs(5),
+                "Sampler should have stopped"
+            );
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert!(sampler.stop().is_ok());
+    }
+
+    #[test]
+    fn test_panic() {
+        // Test a panicking sample reported by stop
+
+        let (_r, w) = atomic_spsc::new::<u32>();
+        let sampler = Sampler::spawn(w, Duration::from_millis(1), || panic!("sensor failure"));
+        let err = sampler.stop().unwrap_err();
+        assert_eq!(err.downcast_ref::<&str>(), Some(&"sensor failure"));
+    }
+}
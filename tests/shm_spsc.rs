@@ -0,0 +1,211 @@
+#[cfg(all(test, target_has_atomic = "64"))]
+mod tests {
+
+    use std::thread;
+
+    use rustedrazors::shm_spsc::{self, RegionError};
+    use rustedrazors::{Reader, TryReadError, Writer};
+
+    #[derive(Debug, Clone, Copy, Default, PartialEq)]
+    struct Pose {
+        x: f64,
+        y: f64,
+        seq: u64,
+    }
+
+    /// Zeroed, suitably aligned stand-in for a mapped region.
+    fn region<T>() -> Vec<u64> {
+        assert!(shm_spsc::region_align::<T>() <= 8);
+        vec![0; shm_spsc::region_size::<T>().div_ceil(8)]
+    }
+
+    #[test]
+    fn test_basics() {
+        // Test basic API
+
+        let mut region = region::<Pose>();
+        let (ptr, len) = (region.as_mut_ptr().cast::<u8>(), region.len() * 8);
+        let mut w = unsafe { shm_spsc::create::<Pose>(ptr, len) }.unwrap();
+        let mut r = unsafe { shm_spsc::open::<Pose>(ptr, len) }.unwrap();
+
+        assert!(r.read().is_err(), "Read should have failed");
+        assert_eq!(*r.front_buffer(), Pose::default());
+
+        let pose = Pose {
+            x: 1.0,
+            y: 2.0,
+            seq: 1,
+        };
+        w.write(pose);
+        assert_eq!(r.read().as_deref(), Ok(&pose));
+        assert!(r.read().is_err(), "Read should have failed");
+
+        w.write_with(|p| p.seq = 2);
+        w.write_with(|p| p.seq = 3);
+        assert_eq!(r.read().map(|p| p.seq), Ok(3));
+        assert_eq!(r.overwritten(), 1);
+        assert_eq!(w.overwritten(), 1);
+    }
+
+    #[test]
+    fn test_handshake() {
+        // Test that a region must be created, and fit the type, before it can be opened
+
+        let mut region = region::<Pose>();
+        let (ptr, len) = (region.as_mut_ptr().cast::<u8>(), region.len() * 8);
+
+        let res = unsafe { shm_spsc::open::<Pose>(ptr, len) };
+        assert_eq!(res.err(), Some(RegionError::Uninitialized));
+        let res = unsafe { shm_spsc::create::<Pose>(ptr, len - 8) };
+        assert_eq!(res.err(), Some(RegionError::TooSmall));
+        let res = unsafe { shm_spsc::create::<Pose>(ptr.add(4), len - 8) };
+        assert_eq!(res.err(), Some(RegionError::Misaligned));
+
+        let mut w = unsafe { shm_spsc::create::<Pose>(ptr, len) }.unwrap();
+        let res = unsafe { shm_spsc::open::<[u32; 2]>(ptr, len) };
+        assert_eq!(res.err(), Some(RegionError::LayoutMismatch));
+
+        let r = unsafe { shm_spsc::open::<Pose>(ptr, len) }.unwrap();
+        assert!(w.is_reader_alive());
+        let res = unsafe { shm_spsc::open::<Pose>(ptr, len) };
+        assert_eq!(res.err(), Some(RegionError::AlreadyOpen));
+
+        // a new reader takes over where the previous one left
+        w.write_with(|p| p.seq = 1);
+        drop(r);
+        assert!(!w.is_reader_alive());
+        let mut r = unsafe { shm_spsc::open::<Pose>(ptr, len) }.unwrap();
+        assert_eq!(r.read().map(|p| p.seq), Ok(1));
+        assert!(r.read().is_err(), "Read should have failed");
+    }
+
+    #[test]
+    fn test_version() {
+        // Test that a region created with another layout version cannot be opened
+
+        let mut region = region::<u64>();
+        let (ptr, len) = (region.as_mut_ptr().cast::<u8>(), region.len() * 8);
+        drop(unsafe { shm_spsc::create::<u64>(ptr, len) }.unwrap());
+
+        // the version sits right after the magic, it is tampered with while no handle borrows the
+        // region
+        let version = unsafe { ptr.add(8).cast::<u32>() };
+        assert_eq!(unsafe { version.read() }, shm_spsc::LAYOUT_VERSION);
+        unsafe { version.write(shm_spsc::LAYOUT_VERSION + 1) };
+        let res = unsafe { shm_spsc::open::<u64>(ptr, len) };
+        assert_eq!(res.err(), Some(RegionError::VersionMismatch));
+        unsafe { version.write(shm_spsc::LAYOUT_VERSION) };
+        assert!(unsafe { shm_spsc::open::<u64>(ptr, len) }.is_ok());
+    }
+
+    #[test]
+    fn test_close() {
+        // Test that the last value can still be read once the writer is closed
+
+        let mut region = region::<u64>();
+        let (ptr, len) = (region.as_mut_ptr().cast::<u8>(), region.len() * 8);
+        let mut w = unsafe { shm_spsc::create::<u64>(ptr, len) }.unwrap();
+        let mut r = unsafe { shm_spsc::open::<u64>(ptr, len) }.unwrap();
+
+        assert_eq!(r.read().err(), Some(TryReadError::Empty));
+        w.write(42);
+        w.close();
+        assert_eq!(r.read().as_deref(), Ok(&42));
+        assert_eq!(r.read().err(), Some(TryReadError::Closed));
+    }
+
+    #[test]
+    fn test_threads() {
+        // Test that values are read in order, without tearing
+
+        let mut region = region::<Pose>();
+        let (ptr, len) = (region.as_mut_ptr().cast::<u8>(), region.len() * 8);
+        let mut w = unsafe { shm_spsc::create::<Pose>(ptr, len) }.unwrap();
+        let mut r = unsafe { shm_spsc::open::<Pose>(ptr, len) }.unwrap();
+
+        thread::scope(|s| {
+            s.spawn(move || {
+                for seq in 1..=10_000 {
+                    let x = seq as f64;
+                    w.write(Pose { x, y: -x, seq });
+                }
+            });
+
+            let mut last = 0;
+            loop {
+                match r.read() {
+                    Ok(pose) => {
+                        assert!(pose.seq > last, "Values should have been read in order");
+                        assert_eq!(pose.x, pose.seq as f64);
+                        assert_eq!(pose.y, -pose.x);
+                        last = pose.seq;
+                    }
+                    Err(TryReadError::Empty) => thread::yield_now(),
+                    Err(_) => break,
+                }
+            }
+            assert_eq!(last, 10_000, "Last value should have been read");
+        });
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    #[cfg_attr(
+        miri,
+        ignore = "Miri only supports private anonymous mappings and no fork"
+    )]
+    fn test_fork() {
+        // Test exchanging values with a child process through a shared mapping
+
+        let len = shm_spsc::region_size::<u64>();
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        assert_ne!(ptr, libc::MAP_FAILED);
+        let ptr = ptr.cast::<u8>();
+
+        let pid = unsafe { libc::fork() };
+        assert!(pid >= 0);
+        if pid == 0 {
+            // the child opens the region before or after it is created
+            let mut r = loop {
+                match unsafe { shm_spsc::open::<u64>(ptr, len) } {
+                    Ok(r) => break r,
+                    Err(RegionError::Uninitialized) => std::hint::spin_loop(),
+                    Err(_) => unsafe { libc::_exit(1) },
+                }
+            };
+            let mut last = 0;
+            loop {
+                match r.read() {
+                    Ok(value) if *value < last => unsafe { libc::_exit(1) },
+                    Ok(value) => last = *value,
+                    Err(TryReadError::Empty) => std::hint::spin_loop(),
+                    Err(_) => break,
+                }
+            }
+            unsafe { libc::_exit(if last == 1_000 { 0 } else { 1 }) };
+        }
+
+        let mut w = unsafe { shm_spsc::create::<u64>(ptr, len) }.unwrap();
+        while !w.is_reader_alive() {
+            thread::yield_now();
+        }
+        for value in 1..=1_000 {
+            w.write(value);
+        }
+        w.close();
+
+        let mut status = 0;
+        assert_eq!(unsafe { libc::waitpid(pid, &mut status, 0) }, pid);
+        assert!(libc::WIFEXITED(status) && libc::WEXITSTATUS(status) == 0);
+        unsafe { libc::munmap(ptr.cast(), len) };
+    }
+}
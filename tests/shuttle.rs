@@ -0,0 +1,168 @@
+//! Randomized schedules of long read/write histories, run with
+//! `RUSTFLAGS="--cfg shuttle" cargo test --release --test shuttle`.
+//!
+//! `shuttle` runs the threads one at a time and switches between them at every atomic access, so
+//! unlike an exhaustive model checker it can afford histories of a thousand writes. It only
+//! explores sequentially consistent executions: weak memory orderings are not covered.
+
+#[cfg(all(test, shuttle))]
+mod tests {
+
+    use shuttle::thread;
+
+    use rustedrazors::{
+        atomic_spsc, isr_spsc, mpmc_latest, seqlock_spsc, ticket_spsc, triple_buffer,
+    };
+    use rustedrazors::{Reader, TryReadError, Writer};
+
+    const ITERATIONS: usize = 1_000;
+    const WRITES: u64 = 1_000;
+
+    /// Sequence number repeated over the whole value, so that a torn read shows.
+    type Payload = [u64; 4];
+
+    /// Writes `WRITES` sequence-numbered values while the reader keeps reading, checking that
+    /// reads never go back in time nor tear, that the last value is always read, and that every
+    /// value was either read or counted as overwritten.
+    fn check_latest<R, W>(new: fn() -> (R, W), overwritten: fn(&R) -> u64)
+    where
+        R: Reader<Item = Payload> + Send + 'static,
+        W: Writer<Item = Payload> + Send + 'static,
+    {
+        shuttle::check_random(
+            move || {
+                let (mut r, mut w) = new();
+                let writer = thread::spawn(move || {
+                    for seq in 1..=WRITES {
+                        w.write([seq; 4]);
+                    }
+                });
+
+                let mut last = 0;
+                let mut reads = 0;
+                loop {
+                    match r.read() {
+                        Ok(value) => {
+                            assert!(value[0] > last, "Values should have been read in order");
+                            assert!(value.iter().all(|&v| v == value[0]), "Value was torn");
+                            last = value[0];
+                            reads += 1;
+                        }
+                        Err(TryReadError::Empty) => thread::yield_now(),
+                        Err(err) => {
+                            assert_eq!(err, TryReadError::Disconnected);
+                            break;
+                        }
+                    }
+                }
+                writer.join().unwrap();
+                assert_eq!(last, WRITES, "Last value should have been read");
+                assert_eq!(reads + overwritten(&r), WRITES);
+            },
+            ITERATIONS,
+        );
+    }
+
+    /// Value that clears its sequence number when dropped, so that reading it afterwards shows.
+    struct Canary(u64);
+
+    impl Drop for Canary {
+        fn drop(&mut self) {
+            self.0 = 0;
+        }
+    }
+
+    /// Has two writers publish `WRITES` sequence-numbered values each while the reader keeps
+    /// reading, checking that the reader never sees a value that was dropped, and that versions
+    /// only go forward.
+    fn check_multi_writer_drops() {
+        shuttle::check_random(
+            || {
+                let (mut r, w) = mpmc_latest::new::<Canary>();
+                let writers: Vec<_> = (0..2)
+                    .map(|_| {
+                        let mut w = w.clone();
+                        thread::spawn(move || {
+                            for seq in 1..=WRITES {
+                                w.write(Canary(seq));
+                            }
+                        })
+                    })
+                    .collect();
+                drop(w);
+
+                let mut last = 0;
+                loop {
+                    match r.read() {
+                        Ok(value) => {
+                            assert_ne!(value.0, 0, "read a dropped value");
+                            assert!(value.version() > last, "Versions should only go forward");
+                            last = value.version();
+                        }
+                        Err(TryReadError::Empty) => thread::yield_now(),
+                        Err(err) => {
+                            assert_eq!(err, TryReadError::Disconnected);
+                            break;
+                        }
+                    }
+                }
+                for writer in writers {
+                    writer.join().unwrap();
+                }
+                assert_eq!(last, 2 * WRITES, "Last value should have been read");
+            },
+            ITERATIONS,
+        );
+    }
+
+    #[test]
+    fn test_atomic_spsc() {
+        // Test the latest-value invariant of atomic_spsc
+
+        check_latest(atomic_spsc::new, atomic_spsc::ReadHandle::overwritten);
+    }
+
+    #[test]
+    fn test_isr_spsc() {
+        // Test the latest-value invariant of isr_spsc
+
+        check_latest(
+            || {
+                let storage = Box::leak(Box::new(isr_spsc::Storage::new()));
+                storage.split().unwrap()
+            },
+            isr_spsc::ReadHandle::overwritten,
+        );
+    }
+
+    #[test]
+    fn test_seqlock_spsc() {
+        // Test the latest-value invariant of seqlock_spsc
+
+        check_latest(seqlock_spsc::new, seqlock_spsc::ReadHandle::overwritten);
+    }
+
+    #[test]
+    fn test_ticket_spsc() {
+        // Test the latest-value invariant of ticket_spsc
+
+        check_latest(
+            || ticket_spsc::new([0; 4]),
+            ticket_spsc::ReadHandle::overwritten,
+        );
+    }
+
+    #[test]
+    fn test_triple_buffer() {
+        // Test the latest-value invariant of triple_buffer
+
+        check_latest(triple_buffer::new, triple_buffer::ReadHandle::overwritten);
+    }
+
+    #[test]
+    fn test_mpmc_latest() {
+        // Test that mpmc_latest never hands out a value dropped by a concurrent writer
+
+        check_multi_writer_drops();
+    }
+}
@@ -0,0 +1,107 @@
+#[cfg(test)]
+mod tests {
+
+    use std::sync::mpsc;
+    use std::thread;
+
+    use rustedrazors::slot_pool::{Pool, SlotGuard};
+
+    #[test]
+    fn test_basics() {
+        // Test basic API
+
+        let pool = Pool::<i32, 3>::from_fn(|idx| idx as i32 * 10);
+        assert_eq!(pool.capacity(), 3);
+        assert_eq!(pool.available(), 3);
+
+        let mut a = pool.acquire().unwrap();
+        let b = pool.acquire().unwrap();
+        assert_eq!((a.index(), *a), (0, 0));
+        assert_eq!((b.index(), *b), (1, 10));
+        *a += 1;
+        assert_eq!(pool.available(), 1);
+        assert_eq!(format!("{a:?}"), "SlotGuard { index: 0, value: 1 }");
+        assert_eq!(
+            format!("{pool:?}"),
+            "slot_pool::Pool { capacity: 3, available: 1 }"
+        );
+
+        drop(a);
+        let a = pool.acquire().unwrap();
+        assert_eq!(
+            (a.index(), *a),
+            (0, 1),
+            "Value should have been kept between leases"
+        );
+        drop((a, b));
+
+        let mut pool = pool;
+        pool.get_mut().for_each(|value| *value += 2);
+        assert_eq!(pool.into_inner(), [3, 12, 22]);
+    }
+
+    #[test]
+    fn test_exhausted() {
+        // Test that acquire fails once every slot is leased, until one is freed
+
+        let pool = Pool::<Vec<u8>, 2>::default();
+        let a = pool.acquire().unwrap();
+        let b = pool.acquire().unwrap();
+        assert!(pool.acquire().is_none(), "Every slot should be leased");
+        assert_eq!(pool.available(), 0);
+
+        drop(b);
+        let c = pool.acquire().expect("A slot should have been freed");
+        assert_eq!(c.index(), 1);
+        drop((a, c));
+        assert_eq!(pool.available(), 2);
+    }
+
+    #[test]
+    fn test_index() {
+        // Test a lease given up as an index and resumed later
+
+        let pool = Pool::<String, 4>::new();
+        let mut guard = pool.acquire().unwrap();
+        guard.push_str("kept");
+        let idx = guard.into_index();
+        assert_eq!(pool.available(), 3, "Slot should still be leased");
+
+        let guard = unsafe { SlotGuard::from_index(&pool, idx) };
+        assert_eq!(guard.as_str(), "kept");
+        drop(guard);
+        assert_eq!(pool.available(), 4);
+    }
+
+    #[test]
+    fn test_threads() {
+        // Test a producer filling slots and a consumer handing them back from another thread
+
+        let pool = Pool::<Vec<u64>, 4>::new();
+        let (tx, rx) = mpsc::sync_channel(4);
+
+        thread::scope(|s| {
+            let pool = &pool;
+            s.spawn(move || {
+                for i in 0..1000u64 {
+                    let mut frame = loop {
+                        match pool.acquire() {
+                            Some(frame) => break frame,
+                            None => thread::yield_now(),
+                        }
+                    };
+                    frame.clear();
+                    frame.extend((0..8).map(|_| i));
+                    tx.send(frame).unwrap();
+                }
+            });
+            s.spawn(move || {
+                for i in 0..1000u64 {
+                    let frame = rx.recv().unwrap();
+                    assert_eq!(frame.as_slice(), &[i; 8]);
+                }
+            });
+        });
+        assert_eq!(pool.available(), 4);
+    }
+}
@@ -0,0 +1,68 @@
+#[cfg(test)]
+mod tests {
+
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use rustedrazors::{mutex_spsc, stamped};
+    use rustedrazors::{Reader, TryReadError, Writer};
+
+    #[test]
+    fn test_basics() {
+        // Test that every value is read along with the instant it was written
+
+        let (mut r, mut w) = stamped::new();
+        assert!(r.read().is_err(), "Read should have failed");
+
+        let before = Instant::now();
+        w.write(1);
+        let after = Instant::now();
+        {
+            let guard = r.read().unwrap();
+            let (at, value) = &*guard;
+            assert_eq!(*value, 1);
+            assert!(
+                before <= *at && *at <= after,
+                "The value should be stamped at write time"
+            );
+        }
+        assert!(r.read().is_err(), "Read should have failed");
+
+        w.write_with(|value| *value = 2);
+        assert_eq!(r.read_with(|(_, value)| *value), Ok(2));
+    }
+
+    #[test]
+    fn test_read_latency() {
+        // Test the time elapsed between a write and the read of its value
+
+        let (mut r, mut w) = stamped::new();
+        w.write(1);
+        thread::sleep(Duration::from_millis(10));
+        let (latency, guard) = r.read_latency().unwrap();
+        assert_eq!(guard.1, 1);
+        assert!(
+            latency >= Duration::from_millis(10),
+            "The latency should span the sleep"
+        );
+        drop(guard);
+        assert!(r.read_latency().is_err(), "Read should have failed");
+    }
+
+    #[test]
+    fn test_wrap() {
+        // Test a stamped channel on top of another variant, which is handed back on unwrapping
+
+        let (r, w) = mutex_spsc::new((Instant::now(), 0));
+        let (mut r, mut w) = stamped::wrap(r, w);
+        w.write(1);
+        assert_eq!(r.read().map(|guard| guard.1), Ok(1));
+
+        assert!(w.is_reader_alive(), "Reader should still be alive");
+        w.close();
+        assert_eq!(r.read().err(), Some(TryReadError::Closed));
+
+        let mut r = r.into_inner();
+        assert!(r.read().is_err(), "Read should have failed");
+    }
+}
@@ -0,0 +1,78 @@
+#[cfg(all(test, feature = "futures"))]
+mod tests {
+
+    use std::thread;
+
+    use futures::executor::block_on;
+    use futures::{Stream, StreamExt};
+
+    use rustedrazors::{atomic_spsc, blocking_spsc, ticket_spsc};
+    use rustedrazors::{Reader, Writer};
+
+    fn check_basics<R, W>(mut r: R, mut w: W)
+    where
+        R: Reader<Item = i32> + Stream<Item = i32> + Unpin,
+        W: Writer<Item = i32>,
+    {
+        w.write(1);
+        w.write(2);
+        assert_eq!(
+            block_on(r.next()),
+            Some(2),
+            "Only the last value should have been yielded"
+        );
+        w.write(3);
+        w.close();
+        assert_eq!(block_on(r.next()), Some(3));
+        assert_eq!(block_on(r.next()), None, "Stream should have ended");
+    }
+
+    fn check_threading<R, W>(r: R, mut w: W)
+    where
+        R: Stream<Item = u64> + Unpin + Send + 'static,
+        W: Writer<Item = u64> + Send + 'static,
+    {
+        let read_res = thread::spawn(move || {
+            block_on(r.fold(None, |last, value| async move {
+                assert!(last < Some(value), "Values should only increase");
+                Some(value)
+            }))
+        });
+        thread::spawn(move || {
+            for i in 0..100_000 {
+                w.write(i);
+            }
+        })
+        .join()
+        .unwrap();
+        assert_eq!(
+            read_res.join().unwrap(),
+            Some(99_999),
+            "Last value should have been yielded"
+        );
+    }
+
+    #[test]
+    fn test_basics() {
+        // Test that streams yield conflated values and end with the writer
+
+        let (r, w) = atomic_spsc::new::<i32>();
+        check_basics(r, w);
+        let (r, w) = blocking_spsc::new::<i32>();
+        check_basics(r, w);
+        let (r, w) = ticket_spsc::new(0);
+        check_basics(r, w);
+    }
+
+    #[test]
+    fn test_threading() {
+        // Test that a task waiting on a stream is woken up by the writer
+
+        let (r, w) = atomic_spsc::new::<u64>();
+        check_threading(r, w);
+        let (r, w) = blocking_spsc::new::<u64>();
+        check_threading(r, w);
+        let (r, w) = ticket_spsc::new(0);
+        check_threading(r, w);
+    }
+}
@@ -0,0 +1,70 @@
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+    use std::thread;
+
+    use rustedrazors::ticket_mutex::TicketMutex;
+
+    #[test]
+    fn test_basics() {
+        // Test basic API
+
+        let mut mutex = TicketMutex::new(1);
+        *mutex.lock() += 1;
+        assert_eq!(*mutex.lock(), 2);
+
+        *mutex.get_mut() += 1;
+        assert_eq!(format!("{mutex:?}"), "TicketMutex { data: 3, .. }");
+        assert_eq!(mutex.into_inner(), 3);
+
+        let mutex = TicketMutex::<Vec<i32>>::default();
+        assert!(mutex.lock().is_empty());
+    }
+
+    #[test]
+    fn test_try_lock() {
+        // Test that try_lock fails, without queuing, while the lock is held
+
+        let mutex = TicketMutex::new(0);
+        {
+            let guard = mutex.try_lock().expect("Lock should have been free");
+            assert!(mutex.try_lock().is_none(), "Lock should have been held");
+            assert_eq!(format!("{mutex:?}"), "TicketMutex { data: <locked>, .. }");
+            drop(guard);
+        }
+        // a failed try_lock must not leave a ticket behind
+        *mutex.try_lock().expect("Lock should have been free") = 1;
+        assert_eq!(*mutex.lock(), 1);
+    }
+
+    #[test]
+    fn test_threading() {
+        // Test that concurrent increments are never lost
+
+        let mutex = Arc::new(TicketMutex::new(0u64));
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let mutex = Arc::clone(&mutex);
+                thread::spawn(move || {
+                    for i in 0..10_000 {
+                        if i % 2 == 0 {
+                            *mutex.lock() += 1;
+                        } else {
+                            loop {
+                                if let Some(mut guard) = mutex.try_lock() {
+                                    *guard += 1;
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(*mutex.lock(), 40_000);
+    }
+}
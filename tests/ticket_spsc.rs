@@ -1,10 +1,12 @@
 #[cfg(test)]
 mod tests {
 
+    use std::sync::mpsc;
     use std::thread;
+    use std::time::Duration;
 
     use rustedrazors::ticket_spsc;
-    use rustedrazors::{Reader, Writer};
+    use rustedrazors::{Reader, TryReadError, WriteError, Writer};
 
     #[derive(Clone)]
     struct ClonePayload {
@@ -34,7 +36,7 @@ mod tests {
         // Allow creation and usage across threads of ticket_spsc with either clonable and copayable
         // types
 
-        let (clone_r, clone_w) = ticket_spsc::new::<ClonePayload>(ClonePayload::default());
+        let (mut clone_r, mut clone_w) = ticket_spsc::new::<ClonePayload>(ClonePayload::default());
 
         let _ = thread::spawn(move || {
             let _ = clone_r.read();
@@ -46,7 +48,7 @@ mod tests {
         })
         .join();
 
-        let (copy_r, copy_w) = ticket_spsc::new::<CopyPayload>(CopyPayload::default());
+        let (mut copy_r, mut copy_w) = ticket_spsc::new::<CopyPayload>(CopyPayload::default());
         let _ = thread::spawn(move || {
             let _ = copy_r.read();
         })
@@ -62,11 +64,11 @@ mod tests {
     fn test_basics() {
         // Test basic API
 
-        let (r, w) = ticket_spsc::new::<i32>(0);
+        let (mut r, mut w) = ticket_spsc::new::<i32>(0);
 
         for _ in 0..5 {
             let res = r.read();
-            assert!(res.is_none(), "Read should have failed");
+            assert!(res.is_err(), "Read should have failed");
         }
 
         w.write(22);
@@ -75,7 +77,7 @@ mod tests {
             let res = r.read();
             assert_eq!(
                 res.as_deref(),
-                Some(&22),
+                Ok(&22),
                 "Read should have returned the value previously written"
             );
             // drop the guard
@@ -83,7 +85,7 @@ mod tests {
 
         {
             let res = r.read();
-            assert!(res.is_none(), "Read should have failed");
+            assert!(res.is_err(), "Read should have failed");
             // drop the guard
         }
 
@@ -94,19 +96,213 @@ mod tests {
             let res = r.read();
             assert_eq!(
                 res.as_deref(),
-                Some(&62),
+                Ok(&62),
                 "Read should have returned the value previously written"
             );
             // drop the guard
         }
     }
 
+    #[test]
+    fn test_write_with() {
+        // Test that write_with updates the last written value in place
+
+        let (mut r, mut w) = ticket_spsc::new::<i32>(0);
+
+        w.write(20);
+        w.write_with(|value| *value += 2);
+
+        let res = r.read();
+        assert_eq!(
+            res.as_deref(),
+            Ok(&22),
+            "Read should have returned the value updated in place"
+        );
+    }
+
+    #[test]
+    fn test_busy_writer() {
+        // Test that reads do not wait behind a writer holding the lock
+
+        let (mut r, mut w) = ticket_spsc::new::<i32>(0);
+        let (locked_tx, locked_rx) = mpsc::channel();
+        let (done_tx, done_rx) = mpsc::channel::<()>();
+
+        w.write(20);
+        let writer = thread::spawn(move || {
+            w.write_with(|value| {
+                locked_tx.send(()).unwrap();
+                done_rx.recv().unwrap();
+                *value = 42;
+            });
+            w
+        });
+
+        locked_rx.recv().unwrap();
+        assert!(r.read().is_err(), "Read should not have waited");
+        assert_eq!(r.read().err(), Some(TryReadError::Empty));
+        done_tx.send(()).unwrap();
+
+        let _w = writer.join().unwrap();
+        assert_eq!(r.read().as_deref(), Ok(&42));
+    }
+
+    #[test]
+    fn test_read_timeout() {
+        // Test that a timed read gives up without a writer and is woken up by one
+
+        let (mut r, mut w) = ticket_spsc::new::<i32>(0);
+
+        let res = r.read_timeout(Duration::from_millis(10));
+        assert_eq!(
+            res.err(),
+            Some(TryReadError::Empty),
+            "Read should have timed out"
+        );
+
+        let write_res = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            w.write(42);
+        });
+        {
+            let res = r.read_timeout(Duration::from_secs(10));
+            assert_eq!(
+                res.as_deref().ok(),
+                Some(&42),
+                "Read should have returned the value written by the other thread"
+            );
+            // drop the guard
+        }
+        assert!(
+            write_res.join().is_ok(),
+            "Writer thread should have ended peacefully"
+        );
+    }
+
+    #[test]
+    fn test_read_swap() {
+        // Test exchanging buffers with the channel instead of cloning them
+
+        let (mut r, mut w) = ticket_spsc::new::<Vec<i32>>(Vec::new());
+        let mut scratch = Vec::with_capacity(8);
+
+        assert!(
+            r.read_swap(&mut scratch).is_err(),
+            "Read should have failed"
+        );
+
+        w.write(vec![1, 2]);
+        assert!(
+            r.read_swap(&mut scratch).is_ok(),
+            "Read should have succeeded"
+        );
+        assert_eq!(scratch, [1, 2]);
+        assert!(
+            r.read_swap(&mut scratch).is_err(),
+            "Read should have failed"
+        );
+        assert_eq!(scratch, [1, 2], "Failed read should not touch the buffer");
+    }
+
+    #[test]
+    fn test_update() {
+        // Test that updates always start from the last written value
+
+        let (mut r, mut w) = ticket_spsc::new::<Vec<i32>>(vec![1]);
+
+        w.update(|value| value.push(2));
+        assert_eq!(r.read().as_deref(), Ok(&vec![1, 2]));
+
+        w.write(vec![3]);
+        w.update(|value| value.push(4));
+        w.update(|value| value.push(5));
+        assert_eq!(r.read().as_deref(), Ok(&vec![3, 4, 5]));
+    }
+
+    #[test]
+    fn test_write_if_changed() {
+        // Test that values equal to the last written one are not published
+
+        let (mut r, mut w) = ticket_spsc::new::<i32>(0);
+
+        assert!(
+            !w.write_if_changed(0),
+            "Initial value should count as written"
+        );
+        assert!(r.read().is_err(), "Read should have failed");
+
+        assert!(w.write_if_changed(22));
+        assert!(!w.write_if_changed(22));
+        assert_eq!(r.read().as_deref(), Ok(&22));
+
+        assert!(
+            !w.write_if_changed(22),
+            "Read values should still be compared"
+        );
+        assert!(r.read().is_err(), "Read should have failed");
+    }
+
+    #[test]
+    fn test_overwritten() {
+        // Test that only values replaced before being read are counted
+
+        let (mut r, mut w) = ticket_spsc::new::<i32>(0);
+
+        w.write(22);
+        let _ = r.read();
+        assert_eq!(w.overwritten(), 0, "Read values should not be counted");
+
+        w.write(42);
+        w.write(62);
+        w.write(82);
+        assert_eq!(w.overwritten(), 2);
+        assert_eq!(r.overwritten(), 2);
+    }
+
+    #[test]
+    fn test_disconnected() {
+        // Test that readers can tell a dead writer apart from a silent one
+
+        let (mut r, mut w) = ticket_spsc::new::<i32>(0);
+
+        assert_eq!(r.read().err(), Some(TryReadError::Empty));
+        w.write(22);
+        drop(w);
+
+        {
+            let res = r.read();
+            assert_eq!(
+                res.as_deref().ok(),
+                Some(&22),
+                "Pending value should still be readable after the writer is gone"
+            );
+            // drop the guard
+        }
+        assert_eq!(r.read().err(), Some(TryReadError::Disconnected));
+        assert_eq!(r.read_blocking().err(), Some(TryReadError::Disconnected));
+
+        let (mut r, w) = ticket_spsc::new::<i32>(0);
+        let write_res = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            drop(w);
+        });
+        assert_eq!(
+            r.read_blocking().err(),
+            Some(TryReadError::Disconnected),
+            "Blocked reader should have been woken up by the writer going away"
+        );
+        assert!(
+            write_res.join().is_ok(),
+            "Writer thread should have ended peacefully"
+        );
+    }
+
     #[test]
     fn test_threading() {
         // Test ticket_spsc with i32 across threads with multiple iterations.
         // Maybe find a way to enable thread sanitizers?
 
-        let (r, w) = ticket_spsc::new::<i32>(0);
+        let (mut r, mut w) = ticket_spsc::new::<i32>(0);
 
         let read_res = thread::spawn(move || {
             for _ in 0..1000 {
@@ -129,4 +325,274 @@ mod tests {
             "Writer thread should have ended peacefully"
         );
     }
+
+    #[cfg(feature = "stats")]
+    #[test]
+    fn test_stats() {
+        // Test the counters shared by both handles and the writer waiting for the lock
+
+        let (mut r, mut w) = ticket_spsc::new(0);
+        w.write(1);
+        w.write(2);
+        assert_eq!(r.read().as_deref(), Ok(&2));
+        assert!(r.read().is_err());
+        let stats = r.stats();
+        assert_eq!(
+            (
+                stats.reads,
+                stats.read_misses,
+                stats.writes,
+                stats.overwrites
+            ),
+            (1, 1, 2, 1)
+        );
+        assert_eq!(stats, w.stats(), "Both handles should share the counters");
+        assert_eq!(
+            stats.max_writer_stall,
+            Duration::ZERO,
+            "The writer should not have waited"
+        );
+
+        // the writer waits for the reader to unlock the value
+        w.write(3);
+        let guard = r.read();
+        let writer = thread::spawn(move || {
+            w.write(4);
+            w
+        });
+        thread::sleep(Duration::from_millis(20));
+        drop(guard);
+        let w = writer.join().unwrap();
+        assert!(
+            w.stats().max_writer_stall >= Duration::from_millis(20),
+            "The writer should have waited for the reader"
+        );
+    }
+
+    #[cfg(feature = "hooks")]
+    #[test]
+    fn test_hooks() {
+        // Test the callbacks installed at construction, called where the counters are bumped
+
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        use rustedrazors::hooks::Hooks;
+
+        // writes, read hits, read misses, overwrites
+        struct Recorder(Arc<[AtomicUsize; 4]>);
+
+        impl Hooks for Recorder {
+            fn on_write(&self) {
+                self.0[0].fetch_add(1, Ordering::Relaxed);
+            }
+            fn on_read_hit(&self) {
+                self.0[1].fetch_add(1, Ordering::Relaxed);
+            }
+            fn on_read_miss(&self) {
+                self.0[2].fetch_add(1, Ordering::Relaxed);
+            }
+            fn on_overwrite(&self) {
+                self.0[3].fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let counts = Arc::new([const { AtomicUsize::new(0) }; 4]);
+        let (mut r, mut w) = ticket_spsc::with_hooks(Recorder(Arc::clone(&counts)), 0);
+        w.write(1);
+        w.write(2);
+        assert_eq!(r.read().as_deref(), Ok(&2));
+        assert!(r.read().is_err());
+        assert_eq!(
+            counts.each_ref().map(|count| count.load(Ordering::Relaxed)),
+            [2, 1, 1, 1]
+        );
+
+        drop((r, w));
+        assert_eq!(
+            Arc::strong_count(&counts),
+            1,
+            "Hooks should be dropped with the channel"
+        );
+    }
+
+    #[test]
+    fn test_named() {
+        // Test the label given to a channel, shown by both handles
+
+        let (r, w) = ticket_spsc::named("quotes", 0);
+        assert_eq!(r.name(), Some("quotes"));
+        assert_eq!(w.name(), Some("quotes"));
+        assert!(
+            format!("{r:?}").contains("name: \"quotes\""),
+            "The label should show up in Debug"
+        );
+        #[cfg(feature = "stats")]
+        assert_eq!(w.stats().name, Some("quotes"));
+
+        let (r, _w) = ticket_spsc::new(0);
+        assert_eq!(r.name(), None, "Channels are unlabelled by default");
+    }
+
+    #[cfg(feature = "age")]
+    #[test]
+    fn test_age() {
+        // Test the instant of the last publish, kept whether the value was read or not
+
+        let (mut r, mut w) = ticket_spsc::new(0);
+        assert_eq!(r.age(), None, "Nothing was published yet");
+        w.write(1);
+        let first = r.last_update().unwrap();
+        assert!(r.read().is_ok());
+        thread::sleep(Duration::from_millis(10));
+        assert!(
+            r.age().unwrap() >= Duration::from_millis(10),
+            "Reading should not reset the age"
+        );
+        w.write(2);
+        assert!(r.last_update().unwrap() > first);
+    }
+
+    #[test]
+    fn test_try_write() {
+        // Test the writes that fail instead of waiting for the reader to unlock the value
+
+        let (mut r, mut w) = ticket_spsc::new(0);
+        assert_eq!(w.try_write(1), Ok(()));
+        {
+            let guard = r.read().unwrap();
+            assert_eq!(*guard, 1);
+            assert_eq!(
+                w.try_write(2),
+                Err(WriteError::WouldBlock(2)),
+                "The reader holds the lock"
+            );
+        }
+        assert_eq!(w.try_write(2), Ok(()));
+        assert_eq!(r.read().as_deref(), Ok(&2));
+
+        drop(r);
+        assert_eq!(w.try_write(3), Err(WriteError::Disconnected(3)));
+    }
+
+    #[test]
+    fn test_poisoned() {
+        // Test that a write panicking while holding the lock poisons the channel instead of
+        // publishing the value it left halfway through
+
+        use std::panic::{self, AssertUnwindSafe};
+
+        let (mut r, mut w) = ticket_spsc::new(vec![0]);
+        w.write(vec![1]);
+        let res = panic::catch_unwind(AssertUnwindSafe(|| {
+            w.write_with(|value| {
+                value.push(2);
+                panic!("write failed");
+            })
+        }));
+        assert!(res.is_err());
+        assert_eq!(
+            r.read().err(),
+            Some(TryReadError::Poisoned),
+            "Value left halfway through should not have been read"
+        );
+        assert_eq!(
+            r.read_timeout(Duration::from_millis(10)).err(),
+            Some(TryReadError::Poisoned)
+        );
+        assert_eq!(
+            r.monitor().peek(),
+            vec![1, 2],
+            "Monitor should have seen the value as left by the panic"
+        );
+        assert_eq!(w.try_write(vec![3]), Err(WriteError::Poisoned(vec![3])));
+        let res = panic::catch_unwind(AssertUnwindSafe(|| w.write(vec![3])));
+        assert!(res.is_err(), "Write should have panicked");
+    }
+
+    #[test]
+    fn test_into_inner() {
+        // Test taking the value back out of the channel
+
+        let (r, w) = ticket_spsc::new(String::from("init"));
+        assert_eq!(ticket_spsc::into_inner(r, w).as_deref(), Some("init"));
+
+        let (mut r, mut w) = ticket_spsc::new(String::new());
+        w.write(String::from("a"));
+        assert_eq!(r.read().as_deref().map(String::as_str), Ok("a"));
+        assert_eq!(ticket_spsc::into_inner(r, w).as_deref(), Some("a"));
+
+        let storage = ticket_spsc::Storage::new(String::new());
+        let (r, w) = storage.split().unwrap();
+        assert_eq!(
+            ticket_spsc::into_inner(r, w),
+            None,
+            "The storage should have kept its value"
+        );
+    }
+
+    #[test]
+    fn test_raw() {
+        // Test handles turned into raw pointers and back, which must stay alive meanwhile
+
+        let (r, mut w) = ticket_spsc::new::<i32>(0);
+        let r = r.into_raw();
+        assert!(w.is_reader_alive(), "Reader should still be alive");
+        w.write(1);
+        let mut r = unsafe { ticket_spsc::ReadHandle::<i32>::from_raw(r) };
+        assert_eq!(r.read().as_deref(), Ok(&1));
+
+        let w = w.into_raw();
+        drop(r);
+        let w = unsafe { ticket_spsc::WriteHandle::<i32>::from_raw(w) };
+        assert!(!w.is_reader_alive(), "Reader should have been dropped");
+
+        let storage = ticket_spsc::Storage::new(0);
+        let (mut r, w) = storage.split().unwrap();
+        let mut w = unsafe { ticket_spsc::WriteHandle::<i32>::from_raw(w.into_raw()) };
+        w.write(2);
+        assert_eq!(r.read().as_deref(), Ok(&2));
+    }
+
+    #[test]
+    fn test_monitor() {
+        // Test a third thread peeking at the channel, which must not consume the value
+
+        let (mut r, mut w) = ticket_spsc::new(0);
+        let monitor = r.monitor();
+        assert_eq!(
+            monitor.peek(),
+            0,
+            "Monitor should have seen the initial value"
+        );
+
+        w.write(1);
+        assert_eq!(monitor.peek(), 1);
+        assert_eq!(
+            monitor.peek(),
+            1,
+            "Monitor should have seen the value again"
+        );
+        assert_eq!(
+            r.read().as_deref(),
+            Ok(&1),
+            "Peeks should have left the value to the reader"
+        );
+        assert!(r.read().is_err(), "Read should have failed");
+
+        let peeker = {
+            let monitor = w.monitor();
+            thread::spawn(move || monitor.peek_with(|value| value * 2))
+        };
+        assert_eq!(peeker.join().unwrap(), 2);
+
+        drop(r);
+        drop(w);
+        assert_eq!(
+            monitor.clone().peek(),
+            1,
+            "Monitor should have kept the channel alive"
+        );
+        assert!(format!("{monitor:?}").starts_with("ticket_spsc::MonitorHandle"));
+    }
 }
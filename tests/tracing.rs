@@ -0,0 +1,161 @@
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+
+    use std::fmt::Debug;
+    use std::sync::{mpsc, Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    use rustedrazors::{blocking_spsc, ticket_spsc};
+    use rustedrazors::{Reader, Writer};
+
+    /// Subscriber keeping a line per span (`span <name> <channel>`) and event
+    /// (`<message> <channel>`), from every thread it is installed on.
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    #[derive(Default)]
+    struct Fields {
+        message: String,
+        channel: String,
+    }
+
+    impl Visit for Fields {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            if field.name() == "channel" {
+                self.channel = value.to_owned();
+            }
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            if field.name() == "message" {
+                self.message = format!("{value:?}");
+            }
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut fields = Fields::default();
+            span.record(&mut fields);
+            let line = format!("span {} {}", span.metadata().name(), fields.channel);
+            self.0.lock().unwrap().push(line);
+            Id::from_u64(1)
+        }
+
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut fields = Fields::default();
+            event.record(&mut fields);
+            let line = format!("{} {}", fields.message, fields.channel);
+            self.0.lock().unwrap().push(line);
+        }
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    impl Recorder {
+        fn count(&self, line: &str) -> usize {
+            self.0.lock().unwrap().iter().filter(|l| *l == line).count()
+        }
+    }
+
+    #[test]
+    fn test_slot_wait() {
+        // Test the events of a blocking_spsc channel, whose writer runs out of slots while the
+        // reader holds its lease
+
+        let recorder = Recorder::default();
+        let (mut r, mut w) = blocking_spsc::named("prices");
+        let (tx, rx) = mpsc::channel();
+        tracing::subscriber::with_default(recorder.clone(), || {
+            w.write(1);
+            thread::scope(|s| {
+                let reader = recorder.clone();
+                s.spawn(move || {
+                    tracing::subscriber::with_default(reader, || {
+                        let guard = r.read();
+                        assert_eq!(guard.as_deref(), Ok(&1));
+                        tx.send(()).unwrap();
+                        thread::sleep(Duration::from_millis(20));
+                    })
+                });
+                rx.recv().unwrap();
+                w.write(2);
+                // both slots are taken, by the lease and by the unread value
+                w.write(3);
+            });
+        });
+
+        assert_eq!(
+            recorder.count("write prices"),
+            3,
+            "Every write should be traced"
+        );
+        assert_eq!(
+            recorder.count("read prices"),
+            1,
+            "The read should be traced"
+        );
+        assert_eq!(
+            recorder.count("span slot_wait prices"),
+            1,
+            "The writer should have waited for a slot once"
+        );
+    }
+
+    #[test]
+    fn test_lock_wait() {
+        // Test the events of a ticket_spsc channel, whose writer waits while the reader holds the
+        // lock
+
+        let recorder = Recorder::default();
+        let (mut r, mut w) = ticket_spsc::named("orders", 0);
+        let (tx, rx) = mpsc::channel();
+        tracing::subscriber::with_default(recorder.clone(), || {
+            w.write(1);
+            thread::scope(|s| {
+                let reader = recorder.clone();
+                s.spawn(move || {
+                    tracing::subscriber::with_default(reader, || {
+                        let guard = r.read();
+                        assert_eq!(guard.as_deref(), Ok(&1));
+                        tx.send(()).unwrap();
+                        thread::sleep(Duration::from_millis(20));
+                    })
+                });
+                rx.recv().unwrap();
+                w.write(2);
+            });
+        });
+
+        assert_eq!(
+            recorder.count("write orders"),
+            2,
+            "Every write should be traced"
+        );
+        assert_eq!(
+            recorder.count("read orders"),
+            1,
+            "The read should be traced"
+        );
+        assert_eq!(
+            recorder.count("span lock_wait orders"),
+            1,
+            "The writer should have waited for the lock once"
+        );
+    }
+}
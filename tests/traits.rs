@@ -0,0 +1,381 @@
+#[cfg(test)]
+mod tests {
+
+    use std::fmt::Debug;
+
+    use rustedrazors::{
+        arc_spsc, atomic_cell_spsc, atomic_spsc, blocking_spsc, box_spsc, disruptor, double_buffer,
+        left_right, mpmc_latest, mpsc_latest, mutex_spsc, recycle_spsc, ring_spsc, seqlock_spsc,
+        ticket_spsc, triple_buffer, watch,
+    };
+    use rustedrazors::{Channel, Reader, TryReadError, WriteError, Writer};
+
+    fn roundtrip<R, W>(mut r: R, mut w: W)
+    where
+        R: Reader<Item = i32>,
+        W: Writer<Item = i32>,
+    {
+        assert!(r.read().is_err(), "Read should have failed");
+
+        w.write(22);
+        {
+            let res = r.read();
+            assert_eq!(
+                res.as_deref(),
+                Ok(&22),
+                "Read should have returned the value previously written"
+            );
+            // drop the guard
+        }
+
+        assert!(r.read().is_err(), "Read should have failed");
+
+        w.write(7);
+        assert_eq!(
+            r.read_with(|value| value * 6),
+            Ok(42),
+            "Read should have handed the value previously written to the closure"
+        );
+        assert_eq!(
+            r.read_with(|value| value * 6),
+            Err(TryReadError::Empty),
+            "Read should have failed"
+        );
+
+        w.write_with(|value| *value = 42);
+        {
+            let res = r.read();
+            assert_eq!(
+                res.as_deref(),
+                Ok(&42),
+                "Read should have returned the value written in place"
+            );
+            // drop the guard
+        }
+
+        assert!(w.is_reader_alive(), "Reader should still be alive");
+        drop(r);
+        assert!(!w.is_reader_alive(), "Reader should have been dropped");
+        assert_eq!(
+            w.try_write(1),
+            Err(WriteError::Disconnected(1)),
+            "Write should have been refused without a reader"
+        );
+    }
+
+    fn close<R, W>(mut r: R, mut w: W)
+    where
+        R: Reader<Item = i32>,
+        W: Writer<Item = i32>,
+    {
+        w.write(22);
+        w.close();
+
+        {
+            let res = r.read();
+            assert_eq!(
+                res.as_deref().ok(),
+                Some(&22),
+                "Pending value should still be readable after closing"
+            );
+            // drop the guard
+        }
+        for _ in 0..5 {
+            assert_eq!(r.read().err(), Some(TryReadError::Closed));
+        }
+    }
+
+    fn debug<R, W>(variant: &str, mut r: R, mut w: W)
+    where
+        R: Reader<Item = i32> + Debug,
+        W: Writer<Item = i32> + Debug,
+    {
+        let read = format!("{r:?}");
+        assert!(
+            read.starts_with(&format!("{variant}::ReadHandle {{")),
+            "Read handle should have named its variant: {read}"
+        );
+        assert!(
+            read.contains("pending: false"),
+            "Nothing should have been pending: {read}"
+        );
+
+        w.write(1);
+        let write = format!("{w:?}");
+        assert!(
+            write.starts_with(&format!("{variant}::WriteHandle {{")),
+            "Write handle should have named its variant: {write}"
+        );
+        let read = format!("{r:?}");
+        assert!(
+            read.contains("pending: true"),
+            "Written value should have been pending: {read}"
+        );
+
+        assert!(r.read().is_ok(), "Read should have succeeded");
+        let read = format!("{r:?}");
+        assert!(
+            read.contains("pending: false"),
+            "Read value should not have been pending anymore: {read}"
+        );
+    }
+
+    fn create<C: Channel<i32>>() {
+        let (r, w) = C::create();
+        roundtrip(r, w);
+    }
+
+    #[test]
+    fn test_channel() {
+        // Every module with a plain constructor must be usable through the Channel trait alone
+
+        create::<arc_spsc::Channel>();
+        create::<atomic_cell_spsc::Channel>();
+        create::<atomic_spsc::Channel>();
+        create::<blocking_spsc::Channel>();
+        create::<box_spsc::Channel>();
+        #[cfg(feature = "critical-section")]
+        create::<rustedrazors::cs_spsc::Channel>();
+        create::<disruptor::Channel<4>>();
+        create::<double_buffer::Channel>();
+        #[cfg(feature = "epoch")]
+        create::<rustedrazors::epoch_spsc::Channel>();
+        #[cfg(any(target_os = "linux", windows))]
+        create::<rustedrazors::futex_spsc::Channel>();
+        create::<left_right::Channel>();
+        create::<mpmc_latest::Channel>();
+        create::<mpsc_latest::Channel>();
+        create::<mutex_spsc::Channel>();
+        create::<recycle_spsc::Channel>();
+        create::<ring_spsc::Channel<4>>();
+        create::<seqlock_spsc::Channel>();
+        create::<ticket_spsc::Channel>();
+        create::<triple_buffer::Channel>();
+        create::<watch::Channel>();
+    }
+
+    #[test]
+    fn test_generic() {
+        // Every variant must be usable through the Reader/Writer traits alone
+
+        let (r, w) = arc_spsc::new::<i32>();
+        roundtrip(r, w);
+        let (r, w) = atomic_cell_spsc::new::<i32>();
+        roundtrip(r, w);
+        let (r, w) = atomic_spsc::new::<i32>();
+        roundtrip(r, w);
+        let (r, w) = blocking_spsc::new::<i32>();
+        roundtrip(r, w);
+        let (r, w) = box_spsc::new::<i32>();
+        roundtrip(r, w);
+        #[cfg(any(target_os = "linux", windows))]
+        {
+            let (r, w) = rustedrazors::futex_spsc::new::<i32>();
+            roundtrip(r, w);
+        }
+        let (r, w) = left_right::new::<i32>();
+        roundtrip(r, w);
+        let (r, w) = mpmc_latest::new::<i32>();
+        roundtrip(r, w);
+        let (r, w) = mpsc_latest::new::<i32>();
+        roundtrip(r, w);
+        let (r, w) = mutex_spsc::new::<i32>(0);
+        roundtrip(r, w);
+        let (r, w) = recycle_spsc::new::<i32>();
+        roundtrip(r, w);
+        let (r, w) = ring_spsc::new::<i32>(4);
+        roundtrip(r, w);
+        let (r, w) = seqlock_spsc::new::<i32>();
+        roundtrip(r, w);
+        let (r, w) = ticket_spsc::new::<i32>(0);
+        roundtrip(r, w);
+        let (r, w) = triple_buffer::new::<i32>();
+        roundtrip(r, w);
+        let (r, w) = disruptor::new::<i32>(4);
+        roundtrip(r, w);
+        let (r, w) = double_buffer::new::<i32>();
+        roundtrip(r, w);
+        #[cfg(feature = "epoch")]
+        {
+            let (r, w) = rustedrazors::epoch_spsc::new::<i32>();
+            roundtrip(r, w);
+        }
+        let (r, w) = watch::new::<i32>();
+        roundtrip(r, w);
+    }
+
+    #[test]
+    fn test_debug() {
+        // Test that every latest-value handle shows its variant and whether a value is pending
+
+        let (r, w) = arc_spsc::new::<i32>();
+        debug("arc_spsc", r, w);
+        let (r, w) = atomic_cell_spsc::new::<i32>();
+        debug("atomic_cell_spsc", r, w);
+        let (r, w) = atomic_spsc::new::<i32>();
+        debug("atomic_spsc", r, w);
+        let (r, w) = blocking_spsc::new::<i32>();
+        debug("blocking_spsc", r, w);
+        let (r, w) = box_spsc::new::<i32>();
+        debug("box_spsc", r, w);
+        #[cfg(any(target_os = "linux", windows))]
+        {
+            let (r, w) = rustedrazors::futex_spsc::new::<i32>();
+            debug("futex_spsc", r, w);
+        }
+        let (r, w) = left_right::new::<i32>();
+        debug("left_right", r, w);
+        let (r, w) = mpmc_latest::new::<i32>();
+        debug("mpmc_latest", r, w);
+        let (r, w) = mpsc_latest::new::<i32>();
+        debug("mpsc_latest", r, w);
+        let (r, w) = mutex_spsc::new::<i32>(0);
+        debug("mutex_spsc", r, w);
+        let (r, w) = recycle_spsc::new::<i32>();
+        debug("recycle_spsc", r, w);
+        let (r, w) = seqlock_spsc::new::<i32>();
+        debug("seqlock_spsc", r, w);
+        let (r, w) = ticket_spsc::new::<i32>(0);
+        debug("ticket_spsc", r, w);
+        let (r, w) = triple_buffer::new::<i32>();
+        debug("triple_buffer", r, w);
+        let (r, w) = double_buffer::new::<i32>();
+        debug("double_buffer", r, w);
+        #[cfg(feature = "epoch")]
+        {
+            let (r, w) = rustedrazors::epoch_spsc::new::<i32>();
+            debug("epoch_spsc", r, w);
+        }
+        let (r, w) = watch::new::<i32>();
+        debug("watch", r, w);
+    }
+
+    #[test]
+    fn test_scoped() {
+        // Every variant must work with handles borrowed from a storage instead of an Arc
+
+        let storage = arc_spsc::Storage::<i32>::new();
+        let (r, w) = storage.split().unwrap();
+        roundtrip(r, w);
+        let storage = atomic_cell_spsc::Storage::<i32>::new();
+        let (r, w) = storage.split().unwrap();
+        roundtrip(r, w);
+        let storage = atomic_spsc::Storage::<i32>::new();
+        let (r, w) = storage.split().unwrap();
+        assert!(storage.split().is_none(), "Handles should have been taken");
+        roundtrip(r, w);
+        let storage = blocking_spsc::Storage::<i32>::new();
+        let (r, w) = storage.split().unwrap();
+        roundtrip(r, w);
+        let storage = box_spsc::Storage::<i32>::new();
+        let (r, w) = storage.split().unwrap();
+        roundtrip(r, w);
+        #[cfg(any(target_os = "linux", windows))]
+        {
+            let storage = rustedrazors::futex_spsc::Storage::<i32>::new();
+            let (r, w) = storage.split().unwrap();
+            roundtrip(r, w);
+        }
+        let storage = mpmc_latest::Storage::<i32>::new();
+        let (r, w) = storage.split().unwrap();
+        roundtrip(r, w);
+        let storage = mpsc_latest::Storage::<i32>::new();
+        let (r, w) = storage.split().unwrap();
+        roundtrip(r, w);
+        let storage = mutex_spsc::Storage::<i32>::new(0);
+        let (r, w) = storage.split().unwrap();
+        roundtrip(r, w);
+        let storage = seqlock_spsc::Storage::<i32>::new();
+        let (r, w) = storage.split().unwrap();
+        roundtrip(r, w);
+        let storage = ticket_spsc::Storage::<i32>::new(0);
+        let (r, w) = storage.split().unwrap();
+        roundtrip(r, w);
+    }
+
+    #[test]
+    fn test_close() {
+        // Every variant must drain the last value and then report Closed
+
+        let (r, w) = arc_spsc::new::<i32>();
+        close(r, w);
+        let (r, w) = atomic_cell_spsc::new::<i32>();
+        close(r, w);
+        let (r, w) = atomic_spsc::new::<i32>();
+        close(r, w);
+        let (r, w) = blocking_spsc::new::<i32>();
+        close(r, w);
+        let (r, w) = box_spsc::new::<i32>();
+        close(r, w);
+        #[cfg(any(target_os = "linux", windows))]
+        {
+            let (r, w) = rustedrazors::futex_spsc::new::<i32>();
+            close(r, w);
+        }
+        let (r, w) = left_right::new::<i32>();
+        close(r, w);
+        let (r, w) = mpmc_latest::new::<i32>();
+        close(r, w);
+        let (r, w) = mpsc_latest::new::<i32>();
+        close(r, w);
+        let (r, w) = mutex_spsc::new::<i32>(0);
+        close(r, w);
+        let (r, w) = recycle_spsc::new::<i32>();
+        close(r, w);
+        let (r, w) = ring_spsc::new::<i32>(4);
+        close(r, w);
+        let (r, w) = seqlock_spsc::new::<i32>();
+        close(r, w);
+        let (r, w) = ticket_spsc::new::<i32>(0);
+        close(r, w);
+        let (r, w) = triple_buffer::new::<i32>();
+        close(r, w);
+        let (r, w) = disruptor::new::<i32>(4);
+        close(r, w);
+        let (r, w) = double_buffer::new::<i32>();
+        close(r, w);
+        #[cfg(feature = "epoch")]
+        {
+            let (r, w) = rustedrazors::epoch_spsc::new::<i32>();
+            close(r, w);
+        }
+        let (r, w) = watch::new::<i32>();
+        close(r, w);
+    }
+
+    #[test]
+    fn test_default_channel() {
+        // Test the crate-root constructor, which builds the recommended variant
+
+        let (r, w) = rustedrazors::channel::<i32>();
+        assert!(format!("{w:?}").starts_with("atomic_spsc::WriteHandle"));
+        roundtrip(r, w);
+    }
+
+    #[test]
+    fn test_forwarding() {
+        // Test handles lent or boxed to generic code, which must behave as the handles themselves
+
+        fn latest<R: Reader<Item = i32>>(mut r: R) -> Result<i32, TryReadError> {
+            r.read_with(|value| *value)
+        }
+
+        let (mut r, mut w) = atomic_spsc::new::<i32>();
+        w.write(1);
+        assert_eq!(latest(&mut r), Ok(1));
+        w.write(2);
+        assert_eq!(latest(Box::new(&mut r)), Ok(2));
+        assert_eq!(
+            latest(&mut r),
+            Err(TryReadError::Empty),
+            "Read should have failed"
+        );
+
+        let (r, w) = mutex_spsc::new::<i32>(0);
+        roundtrip(Box::new(r), Box::new(w));
+        let (r, w) = ring_spsc::new::<i32>(4);
+        roundtrip(Box::new(r), Box::new(w));
+        let (r, w) = ticket_spsc::new::<i32>(0);
+        close(Box::new(r), Box::new(w));
+    }
+}
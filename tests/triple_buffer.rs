@@ -0,0 +1,137 @@
+#[cfg(test)]
+mod tests {
+
+    use std::thread;
+
+    use rustedrazors::triple_buffer;
+    use rustedrazors::{Reader, TryReadError, Writer};
+
+    #[test]
+    fn test_basics() {
+        // Test basic API
+
+        let (mut r, mut w) = triple_buffer::new::<i32>();
+
+        for _ in 0..5 {
+            let res = r.read();
+            assert!(res.is_err(), "Read should have failed");
+        }
+
+        w.write(22);
+
+        {
+            let res = r.read();
+            assert_eq!(
+                res.as_deref(),
+                Ok(&22),
+                "Read should have returned the value previously written"
+            );
+            // drop the guard
+        }
+
+        for _ in 0..5 {
+            let res = r.read();
+            assert!(res.is_err(), "Read should have failed");
+        }
+    }
+
+    #[test]
+    fn test_back_buffer() {
+        // Test that the writer can build values incrementally in its back buffer
+
+        let (mut r, mut w) = triple_buffer::new::<Vec<i32>>();
+
+        assert!(!r.update(), "Update should have failed");
+        assert_eq!(*r.front_buffer(), Vec::<i32>::new());
+
+        w.back_buffer().push(1);
+        w.back_buffer().push(2);
+        w.publish();
+
+        assert!(r.update(), "Update should have taken the published buffer");
+        assert_eq!(*r.front_buffer(), vec![1, 2]);
+        assert!(!r.update(), "Update should have failed");
+        assert_eq!(
+            *r.front_buffer(),
+            vec![1, 2],
+            "Front buffer should have been kept"
+        );
+
+        w.back_buffer().clear();
+        w.back_buffer().push(3);
+        w.publish();
+        assert!(r.update());
+        assert_eq!(*r.front_buffer(), vec![3]);
+    }
+
+    #[test]
+    fn test_read_lease() {
+        // Test that a held guard keeps its value while writes go on
+
+        let (mut r, mut w) = triple_buffer::new::<i32>();
+
+        w.write(22);
+        let guard = r.read().expect("Read should have succeeded");
+        w.write(42);
+        w.write(62);
+        assert_eq!(*guard, 22, "Leased value should not have changed");
+
+        assert_eq!(r.read().as_deref(), Ok(&62));
+        assert_eq!(r.overwritten(), 1);
+    }
+
+    #[test]
+    fn test_disconnected() {
+        // Test that readers can tell a dead writer apart from a silent one
+
+        let (mut r, mut w) = triple_buffer::new::<i32>();
+
+        assert_eq!(r.read().err(), Some(TryReadError::Empty));
+        w.write(22);
+        drop(w);
+
+        {
+            let res = r.read();
+            assert_eq!(
+                res.as_deref().ok(),
+                Some(&22),
+                "Pending value should still be readable after the writer is gone"
+            );
+            // drop the guard
+        }
+        assert_eq!(r.read().err(), Some(TryReadError::Disconnected));
+    }
+
+    #[test]
+    fn test_threading() {
+        // Test triple_buffer with arrays across threads, values must never be torn
+
+        let (mut r, mut w) = triple_buffer::new::<[u64; 32]>();
+
+        let read_res = thread::spawn(move || loop {
+            match r.read() {
+                Ok(value) => assert!(
+                    value.iter().all(|&v| v == value[0]),
+                    "Read should not have observed a partial write"
+                ),
+                Err(TryReadError::Empty) => {}
+                Err(_) => break,
+            }
+        });
+        let write_res = thread::spawn(move || {
+            for i in 0..10000 {
+                w.back_buffer().fill(i);
+                w.publish();
+            }
+        })
+        .join();
+        assert!(
+            write_res.is_ok(),
+            "Writer thread should have ended peacefully"
+        );
+        assert!(
+            read_res.join().is_ok(),
+            "Reader thread should have ended peacefully"
+        );
+    }
+}
@@ -0,0 +1,151 @@
+#[cfg(test)]
+mod tests {
+
+    use std::thread;
+
+    use rustedrazors::watch;
+    use rustedrazors::{Reader, TryReadError, Writer};
+
+    #[test]
+    fn test_basics() {
+        // Test basic API
+
+        let (mut r, mut w) = watch::new::<i32>();
+
+        for _ in 0..5 {
+            let res = r.read();
+            assert!(res.is_err(), "Read should have failed");
+        }
+        assert!(r.latest().is_none(), "Nothing should have been written");
+
+        w.write(22);
+        assert!(r.has_changed());
+        assert_eq!(r.read().as_deref(), Ok(&22));
+        assert!(!r.has_changed());
+
+        for _ in 0..5 {
+            let res = r.read();
+            assert!(res.is_err(), "Read should have failed");
+        }
+        assert_eq!(
+            r.latest().as_deref(),
+            Some(&22),
+            "Latest value should still be available"
+        );
+    }
+
+    #[test]
+    fn test_readers() {
+        // Test that every reader observes values independently
+
+        let (mut r1, mut w) = watch::new::<i32>();
+        let mut r2 = r1.clone();
+        assert_eq!(w.reader_count(), 2);
+
+        w.write(22);
+        assert_eq!(r1.read().as_deref(), Ok(&22));
+        assert_eq!(
+            r2.read().as_deref(),
+            Ok(&22),
+            "Other readers should still see the value"
+        );
+
+        w.write(42);
+        let mut r3 = r1.clone();
+        assert_eq!(r1.read().as_deref(), Ok(&42));
+        assert!(r1.read().is_err(), "Read should have failed");
+        assert_eq!(
+            r3.read().as_deref(),
+            Ok(&42),
+            "Clones should have read what their source had read"
+        );
+
+        drop(r1);
+        drop(r2);
+        assert!(w.is_reader_alive());
+        drop(r3);
+        assert!(
+            !w.is_reader_alive(),
+            "Every reader should have been dropped"
+        );
+    }
+
+    #[test]
+    fn test_guard_outlives_write() {
+        // Test that held values are not affected by newer writes
+
+        let (mut r, mut w) = watch::new::<String>();
+        w.write("old".to_string());
+        let guard = r.read().unwrap();
+        w.write("new".to_string());
+        assert_eq!(&*guard, "old");
+        assert_eq!(r.read().as_deref().map(String::as_str), Ok("new"));
+    }
+
+    #[test]
+    fn test_disconnected() {
+        // Test that readers can tell a dead writer apart from a silent one
+
+        let (mut r1, mut w) = watch::new::<i32>();
+        let r2 = r1.clone();
+
+        assert_eq!(r1.read().err(), Some(TryReadError::Empty));
+        w.write(22);
+        drop(w);
+
+        for mut r in [r1, r2] {
+            assert_eq!(
+                r.read().as_deref().ok(),
+                Some(&22),
+                "Pending value should still be readable after the writer is gone"
+            );
+            assert_eq!(r.read().err(), Some(TryReadError::Disconnected));
+        }
+    }
+
+    #[test]
+    fn test_threading() {
+        // Test watch with i32 across several reader threads with multiple iterations.
+        // Every reader must see values in the order they were written, and the last one.
+
+        let (r, mut w) = watch::new::<i32>();
+
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let mut r = r.clone();
+                thread::spawn(move || {
+                    let mut last = -1;
+                    loop {
+                        match r.read() {
+                            Ok(value) => {
+                                assert!(*value > last, "Values should have been read in order");
+                                last = *value;
+                            }
+                            Err(TryReadError::Empty) => {}
+                            Err(_) => break,
+                        }
+                    }
+                    last
+                })
+            })
+            .collect();
+        drop(r);
+        let write_res = thread::spawn(move || {
+            for i in 0..10000 {
+                w.write(i);
+            }
+        })
+        .join();
+        assert!(
+            write_res.is_ok(),
+            "Writer thread should have ended peacefully"
+        );
+        for reader in readers {
+            assert_eq!(
+                reader.join().ok(),
+                Some(9999),
+                "Every reader should have read the last value"
+            );
+        }
+    }
+}
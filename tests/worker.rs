@@ -0,0 +1,114 @@
+#[cfg(test)]
+mod tests {
+
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use rustedrazors::worker::Worker;
+    use rustedrazors::{Reader, TryReadError};
+
+    #[test]
+    fn test_steps() {
+        // Test the initial state published right away, then the state after every step
+
+        let (mut r, worker) = Worker::spawn(vec![0u32], |history| {
+            let last = *history.last().unwrap();
+            history.push(last + 1);
+            thread::sleep(Duration::from_millis(1));
+        });
+
+        let mut last = 0;
+        for _ in 0..5 {
+            let history = r.read_timeout(Duration::from_secs(5)).unwrap();
+            assert!(
+                history.iter().copied().eq(0..history.len() as u32),
+                "Every published state should be whole"
+            );
+            assert!(history.len() > last || last == 0);
+            last = history.len();
+        }
+
+        let history = worker.stop().unwrap();
+        assert!(history.len() >= last);
+        while r.read().is_ok() {}
+        assert_eq!(r.read().err(), Some(TryReadError::Closed));
+    }
+
+    #[test]
+    fn test_pause() {
+        // Test that a paused worker publishes nothing until resumed
+
+        let (mut r, worker) = Worker::spawn(0u64, |count| {
+            *count += 1;
+            thread::sleep(Duration::from_millis(1));
+        });
+        assert!(r.read_timeout(Duration::from_secs(5)).is_ok());
+
+        worker.pause();
+        assert!(worker.is_paused());
+        // the step under way when pausing may still publish
+        thread::sleep(Duration::from_millis(20));
+        let paused_at = r.read().map(|count| *count).ok();
+        thread::sleep(Duration::from_millis(20));
+        assert!(r.read().is_err(), "A paused worker should not publish");
+        assert!(format!("{worker:?}").contains("paused: true"));
+
+        worker.resume();
+        assert!(!worker.is_paused());
+        let count = *r.read_timeout(Duration::from_secs(5)).unwrap();
+        assert!(paused_at.is_none_or(|paused_at| count > paused_at));
+    }
+
+    #[test]
+    fn test_stop_paused() {
+        // Test that a paused worker can still be stopped, returning its state
+
+        let (mut r, worker) = Worker::spawn(String::from("idle"), |_| {
+            thread::sleep(Duration::from_millis(1));
+        });
+        worker.pause();
+        assert_eq!(worker.stop().unwrap(), "idle");
+        while r.read().is_ok() {}
+        assert_eq!(r.read().err(), Some(TryReadError::Closed));
+    }
+
+    #[test]
+    fn test_drop() {
+        // Test that dropping the control handle stops the thread and closes the channel
+
+        let (mut r, worker) = Worker::spawn(0u8, |_| thread::sleep(Duration::from_millis(1)));
+        drop(worker);
+        while r.read().is_ok() {}
+        assert_eq!(r.read().err(), Some(TryReadError::Closed));
+    }
+
+    #[test]
+    fn test_reader_dropped() {
+        // Test that the worker stops on its own once the reader is gone
+
+        let (r, worker) = Worker::spawn(0u8, |_| thread::sleep(Duration::from_millis(1)));
+        drop(r);
+
+        let start = Instant::now();
+        while !worker.is_finished() {
+            assert!(
+                start.elapsed() < Duration::from_secs(5),
+                "Worker should have stopped"
+            );
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert!(worker.stop().is_ok());
+    }
+
+    #[test]
+    fn test_panic() {
+        // Test a panicking step reported by stop
+
+        let (_r, worker) = Worker::spawn(0u8, |_| panic!("step failure"));
+        while !worker.is_finished() {
+            thread::sleep(Duration::from_millis(1));
+        }
+        let err = worker.stop().unwrap_err();
+        assert_eq!(err.downcast_ref::<&str>(), Some(&"step failure"));
+    }
+}